@@ -0,0 +1,22 @@
+// Measures the password hashing cost, e.g. `PASSWORD_HASH_COST=4 cargo bench --bench hasher`.
+use conduit::utils::hasher::{self, HashConfig};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn hash_and_verify(c: &mut Criterion) {
+    let config = HashConfig::from_env().expect("Invalid password hashing config");
+    let hash = hasher::hash_password(&config, "password123").unwrap();
+
+    let mut group = c.benchmark_group(format!("argon2id cost {}", config.cost));
+    // NOTE: each iteration takes tens of milliseconds, so the default sample count is too slow.
+    group.sample_size(20);
+    group.bench_function("hash_password", |b| {
+        b.iter(|| hasher::hash_password(&config, black_box("password123")).unwrap())
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| hasher::verify(black_box("password123"), black_box(&hash)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, hash_and_verify);
+criterion_main!(benches);
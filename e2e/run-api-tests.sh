@@ -6,7 +6,7 @@ SCRIPTDIR="$( cd "$( dirname "${BASH_SOURCE[0]}" )" >/dev/null && pwd )"
 APIURL=${APIURL:-https://api.realworld.io/api}
 USERNAME=${USERNAME:-u`date +%s`}
 EMAIL=${EMAIL:-$USERNAME@mail.com}
-PASSWORD=${PASSWORD:-password}
+PASSWORD=${PASSWORD:-conduit-e2e-2021}
 
 npx newman run $SCRIPTDIR/e2e/Conduit.postman_collection.json \
   --delay-request 500 \
@@ -0,0 +1,5 @@
+DROP INDEX users_username_lower_key;
+
+DROP INDEX users_email_lower_key;
+
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
\ No newline at end of file
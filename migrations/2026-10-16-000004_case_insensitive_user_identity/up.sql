@@ -0,0 +1,22 @@
+-- Accounts that differ only in case cannot be merged automatically, so stop and name one.
+DO $$
+DECLARE
+  duplicate TEXT;
+BEGIN
+  SELECT lower(email) INTO duplicate FROM users GROUP BY lower(email) HAVING count(*) > 1 LIMIT 1;
+  IF duplicate IS NOT NULL THEN
+    RAISE EXCEPTION 'several users have the email % in different cases; merge them before migrating', duplicate;
+  END IF;
+  SELECT lower(username) INTO duplicate FROM users GROUP BY lower(username) HAVING count(*) > 1 LIMIT 1;
+  IF duplicate IS NOT NULL THEN
+    RAISE EXCEPTION 'several users have the username % in different cases; rename them before migrating', duplicate;
+  END IF;
+END $$;
+
+ALTER TABLE users DROP CONSTRAINT users_email_key;
+
+UPDATE users SET email = lower(email);
+
+CREATE UNIQUE INDEX users_email_lower_key ON users (lower(email));
+
+CREATE UNIQUE INDEX users_username_lower_key ON users (lower(username));
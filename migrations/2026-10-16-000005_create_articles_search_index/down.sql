@@ -0,0 +1 @@
+DROP INDEX articles_search_idx;
\ No newline at end of file
@@ -0,0 +1,2 @@
+CREATE INDEX articles_search_idx ON articles
+  USING GIN (to_tsvector('english', title || ' ' || description || ' ' || body));
@@ -0,0 +1 @@
+ALTER TABLE articles DROP COLUMN published;
\ No newline at end of file
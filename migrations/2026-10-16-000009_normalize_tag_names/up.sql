@@ -0,0 +1,7 @@
+UPDATE tags SET name = lower(regexp_replace(btrim(name), '\s+', ' ', 'g'));
+
+DELETE FROM tags duplicate
+USING tags original
+WHERE duplicate.article_id = original.article_id
+  AND duplicate.name = original.name
+  AND (duplicate.created_at, duplicate.id) > (original.created_at, original.id);
@@ -0,0 +1 @@
+DROP INDEX tags_article_id_name_key;
\ No newline at end of file
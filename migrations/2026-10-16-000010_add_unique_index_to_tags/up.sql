@@ -0,0 +1 @@
+CREATE UNIQUE INDEX tags_article_id_name_key ON tags (article_id, name);
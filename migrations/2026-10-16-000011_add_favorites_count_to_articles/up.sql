@@ -0,0 +1,10 @@
+ALTER TABLE articles ADD COLUMN favorites_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE articles
+SET favorites_count = counts.favorites_count
+FROM (
+  SELECT article_id, COUNT(*) AS favorites_count
+  FROM favorites
+  GROUP BY article_id
+) counts
+WHERE articles.id = counts.article_id;
@@ -0,0 +1,7 @@
+SELECT diesel_manage_updated_at('users');
+
+-- Favoriting only moves the counter; it is not an edit of the article.
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON articles
+  FOR EACH ROW
+  WHEN (OLD.favorites_count IS NOT DISTINCT FROM NEW.favorites_count)
+  EXECUTE PROCEDURE diesel_set_updated_at();
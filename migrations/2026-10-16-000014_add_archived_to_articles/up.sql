@@ -0,0 +1 @@
+ALTER TABLE articles ADD COLUMN archived BOOLEAN NOT NULL DEFAULT FALSE;
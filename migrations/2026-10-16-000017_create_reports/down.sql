@@ -0,0 +1,3 @@
+DROP TABLE reports;
+ALTER TABLE comments DROP COLUMN hidden;
+ALTER TABLE articles DROP COLUMN hidden;
@@ -0,0 +1,15 @@
+ALTER TABLE articles ADD COLUMN hidden BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE comments ADD COLUMN hidden BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE reports (
+  id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  reporter_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  article_id UUID REFERENCES articles (id) ON DELETE CASCADE,
+  comment_id UUID REFERENCES comments (id) ON DELETE CASCADE,
+  reason TEXT NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  -- NOTE: a report targets exactly one article or one comment.
+  CHECK ((article_id IS NULL) <> (comment_id IS NULL))
+);
+
+CREATE INDEX reports_created_at_idx ON reports (created_at);
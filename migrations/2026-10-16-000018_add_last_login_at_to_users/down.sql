@@ -0,0 +1,4 @@
+DROP TRIGGER set_updated_at ON users;
+SELECT diesel_manage_updated_at('users');
+
+ALTER TABLE users DROP COLUMN last_login_at;
@@ -0,0 +1,8 @@
+ALTER TABLE users ADD COLUMN last_login_at TIMESTAMP;
+
+-- Signing in only records the time; it is not an edit of the profile.
+DROP TRIGGER set_updated_at ON users;
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON users
+  FOR EACH ROW
+  WHEN (OLD.last_login_at IS NOT DISTINCT FROM NEW.last_login_at)
+  EXECUTE PROCEDURE diesel_set_updated_at();
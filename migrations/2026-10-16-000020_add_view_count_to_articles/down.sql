@@ -0,0 +1,7 @@
+DROP TRIGGER set_updated_at ON articles;
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON articles
+  FOR EACH ROW
+  WHEN (OLD.favorites_count IS NOT DISTINCT FROM NEW.favorites_count)
+  EXECUTE PROCEDURE diesel_set_updated_at();
+
+ALTER TABLE articles DROP COLUMN view_count;
@@ -0,0 +1,9 @@
+ALTER TABLE articles ADD COLUMN view_count INTEGER NOT NULL DEFAULT 0;
+
+-- Views only move a counter too; they are not an edit of the article.
+DROP TRIGGER set_updated_at ON articles;
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON articles
+  FOR EACH ROW
+  WHEN (OLD.favorites_count IS NOT DISTINCT FROM NEW.favorites_count
+    AND OLD.view_count IS NOT DISTINCT FROM NEW.view_count)
+  EXECUTE PROCEDURE diesel_set_updated_at();
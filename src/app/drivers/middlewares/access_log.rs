@@ -0,0 +1,278 @@
+use super::request_id;
+use crate::app::features::user::entities::User;
+use crate::utils::logger::{LogFormat, ACCESS_LOG_TARGET};
+use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use actix_web::HttpMessage;
+use actix_web::{
+    dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    web::Bytes,
+    Error,
+};
+use futures::future::{ok, Ready};
+use futures::Future;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Instant;
+use tracing::Instrument;
+use uuid::Uuid;
+
+// NOTE: bigger bodies (e.g. avatar uploads) are never buffered just to be logged.
+const MAX_LOGGED_BODY_BYTES: usize = 16 * 1024;
+
+const REDACTED: &str = "[REDACTED]";
+const SENSITIVE_KEYS: [&str; 3] = ["password", "token", "secret"];
+
+// Logs method, path, status, latency and the authenticated user of every request.
+pub struct AccessLog;
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AccessLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AccessLogMiddleware {
+            service: Rc::new(service),
+            format: LogFormat::from_env(),
+        })
+    }
+}
+
+pub struct AccessLogMiddleware<S> {
+    service: Rc<S>,
+    format: LogFormat,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let format = self.format;
+        let request_id = request_id::get_request_id(req.request()).map(|id| id.to_string());
+        // NOTE: the root span of the request; usecase and repository spans nest under it.
+        let span = tracing::debug_span!(
+            "request",
+            method = %req.method(),
+            path = %req.path(),
+            request_id = ?request_id
+        );
+        Box::pin(
+            async move {
+                let started_at = Instant::now();
+                let body = if has_loggable_body(&req) {
+                    let bytes = req.extract::<Bytes>().await?;
+                    req.set_payload(Payload::from(bytes.clone()));
+                    serde_json::from_slice::<JsonValue>(&bytes).ok()
+                } else {
+                    None
+                };
+                let mut entry = AccessLogEntry {
+                    method: req.method().to_string(),
+                    path: req.path().to_owned(),
+                    query: Some(redact_query(req.query_string())).filter(|query| !query.is_empty()),
+                    status: 0,
+                    latency_ms: 0.0,
+                    user_id: None,
+                    request_id,
+                    body: body.map(redact),
+                };
+
+                let res = service.call(req).await?;
+
+                // NOTE: read after the call, since the auth middleware runs inside this one.
+                entry.user_id = res.request().extensions().get::<User>().map(|user| user.id);
+                entry.status = res.status().as_u16();
+                entry.latency_ms = started_at.elapsed().as_secs_f64() * 1000.0;
+                info!(target: ACCESS_LOG_TARGET, "{}", entry.to_line(format));
+                Ok(res)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AccessLogEntry {
+    method: String,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+    status: u16,
+    latency_ms: f64,
+    user_id: Option<Uuid>,
+    request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<JsonValue>,
+}
+
+impl AccessLogEntry {
+    fn to_line(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+            LogFormat::Pretty => {
+                let target = match &self.query {
+                    Some(query) => format!("{}?{}", self.path, query),
+                    None => self.path.to_owned(),
+                };
+                let user = match &self.user_id {
+                    Some(user_id) => user_id.to_string(),
+                    None => "-".to_owned(),
+                };
+                format!(
+                    "{} {} {} {:.1}ms user={} request_id={}",
+                    self.method,
+                    target,
+                    self.status,
+                    self.latency_ms,
+                    user,
+                    self.request_id.as_deref().unwrap_or("-")
+                )
+            }
+        }
+    }
+}
+
+fn has_loggable_body(req: &ServiceRequest) -> bool {
+    let is_json = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let is_small = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok())
+        .is_some_and(|length| length <= MAX_LOGGED_BODY_BYTES);
+    is_json && is_small
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS
+        .iter()
+        .any(|sensitive| key.contains(sensitive))
+}
+
+fn redact(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let value = if is_sensitive(&key) {
+                    JsonValue::from(REDACTED)
+                } else {
+                    redact(value)
+                };
+                (key, value)
+            })
+            .collect(),
+        JsonValue::Array(list) => list.into_iter().map(redact).collect(),
+        value => value,
+    }
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_sensitive(key) => format!("{}={}", key, REDACTED),
+            _ => pair.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_and_read_body_json, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use serde_json::json;
+
+    #[test]
+    fn redact_hides_signin_password() {
+        let body = json!({"user": {"email": "jake@jake.jake", "password": "jakejake"}});
+        assert_eq!(
+            json!({"user": {"email": "jake@jake.jake", "password": REDACTED}}),
+            redact(body)
+        );
+    }
+
+    #[test]
+    fn redact_hides_nested_tokens() {
+        let body = json!({"token": "abc", "list": [{"newPassword": "x", "bio": "hi"}]});
+        assert_eq!(
+            json!({"token": REDACTED, "list": [{"newPassword": REDACTED, "bio": "hi"}]}),
+            redact(body)
+        );
+    }
+
+    #[test]
+    fn redact_query_hides_token() {
+        assert_eq!(
+            "token=[REDACTED]&limit=10",
+            redact_query("token=abc.def&limit=10")
+        );
+        assert_eq!("", redact_query(""));
+    }
+
+    #[test]
+    fn pretty_line_has_method_path_status_and_user() {
+        let entry = AccessLogEntry {
+            method: "GET".to_owned(),
+            path: "/api/articles".to_owned(),
+            query: Some("limit=10".to_owned()),
+            status: 200,
+            latency_ms: 1.26,
+            user_id: None,
+            request_id: Some("abc-123".to_owned()),
+            body: None,
+        };
+        assert_eq!(
+            "GET /api/articles?limit=10 200 1.3ms user=- request_id=abc-123",
+            entry.to_line(LogFormat::Pretty)
+        );
+        let line: JsonValue = serde_json::from_str(&entry.to_line(LogFormat::Json)).unwrap();
+        assert_eq!(200, line["status"]);
+        assert!(line["user_id"].is_null());
+        assert_eq!("abc-123", line["request_id"]);
+    }
+
+    #[actix_web::test]
+    async fn buffered_body_still_reaches_handler() {
+        let app = init_service(App::new().wrap(AccessLog).route(
+            "/api/users/login",
+            web::post().to(|body: web::Json<JsonValue>| async move {
+                HttpResponse::Ok().json(body.into_inner())
+            }),
+        ))
+        .await;
+        let body = json!({"user": {"email": "jake@jake.jake", "password": "jakejake"}});
+        let req = TestRequest::post()
+            .uri("/api/users/login")
+            .set_json(&body)
+            .to_request();
+        let res: JsonValue = call_and_read_body_json(&app, req).await;
+        assert_eq!(body, res);
+    }
+}
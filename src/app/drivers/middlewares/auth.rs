@@ -7,15 +7,13 @@ use actix_web::HttpMessage;
 use actix_web::{
     body::EitherBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    http::Method,
+    http::{header::HeaderMap, Method},
     web::Data,
-    Error, HttpRequest, HttpResponse,
+    Error, HttpRequest,
 };
 use futures::future::{ok, Ready};
 use futures::Future;
-use serde_json::json;
 use std::pin::Pin;
-use uuid::Uuid;
 
 // There are two steps in middleware processing.
 // 1. Middleware initialization, middleware factory gets called with
@@ -60,24 +58,26 @@ where
     actix_web::dev::forward_ready!(service);
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        let is_verified = if should_skip_auth(&req) {
-            true
+        let verified = if should_skip_auth(&req) {
+            set_optional_auth_user(&mut req);
+            Ok(())
         } else {
             set_auth_user(&mut req)
         };
-        if is_verified {
-            let fut = self.service.call(req);
-            Box::pin(async move {
-                let res = fut.await?.map_into_left_body();
-                Ok(res)
-            })
-        } else {
-            Box::pin(async move {
+        match verified {
+            Ok(()) => {
+                let fut = self.service.call(req);
+                Box::pin(async move {
+                    let res = fut.await?.map_into_left_body();
+                    Ok(res)
+                })
+            }
+            Err(err) => Box::pin(async move {
                 let (req, _res) = req.into_parts();
-                let res = HttpResponse::Unauthorized().finish().map_into_right_body();
+                let res = err.to_response().map_into_right_body();
                 let srv = ServiceResponse::new(req, res);
                 Ok(srv)
-            })
+            }),
         }
     }
 }
@@ -95,28 +95,44 @@ fn should_skip_auth(req: &ServiceRequest) -> bool {
 
 const TOKEN_IDENTIFIER: &str = "Token";
 
-fn set_auth_user(req: &mut ServiceRequest) -> bool {
+fn set_auth_user(req: &mut ServiceRequest) -> Result<(), AppError> {
     match fetch_user(req) {
         Ok(user) => {
             req.extensions_mut().insert(user);
-            true
+            Ok(())
         }
-        Err(err_msg) => {
-            info!("Cannot fetch user {}", err_msg);
-            false
+        Err(err) => {
+            info!("Cannot fetch user {}", err);
+            Err(err)
         }
     }
 }
 
-fn fetch_user(req: &ServiceRequest) -> Result<User, &str> {
-    let user_id = get_user_id_from_header(req)?;
-    req.app_data::<Data<AppState>>()
-        .ok_or("Cannot get app state.")
-        .and_then(|state| state.di_container.user_usecase.find_auth_user(user_id))
+// NOTE: public routes still see who is calling when a valid token is sent, but never fail on it.
+fn set_optional_auth_user(req: &mut ServiceRequest) {
+    if get_token(req.request()).is_err() {
+        return;
+    }
+    if let Ok(user) = fetch_user(req) {
+        req.extensions_mut().insert(user);
+    }
+}
+
+fn fetch_user(req: &ServiceRequest) -> Result<User, AppError> {
+    let token = get_token(req.request())?;
+    let state = req.app_data::<Data<AppState>>().ok_or(AppError::Internal)?;
+    let claims = token::verify(&state.config.jwt, &token)?.claims;
+    let user_usecase = &state.di_container.user_usecase;
+    if user_usecase.is_revoked_token(&claims.jti)? {
+        return Err(unauthorized("Token has been revoked"));
+    }
+    user_usecase
+        .find_auth_user(claims.user_id)
+        .map_err(unauthorized)
 }
 
-fn get_user_id_from_header(req: &ServiceRequest) -> Result<Uuid, &str> {
-    req.headers()
+fn get_token_from_header(headers: &HeaderMap) -> Result<&str, AppError> {
+    headers
         .get(constants::AUTHORIZATION)
         .ok_or("Cannot find authrization key-value in req header")
         .and_then(|auth_header| auth_header.to_str().map_err(|_err| "Cannot stringify"))
@@ -128,8 +144,27 @@ fn get_user_id_from_header(req: &ServiceRequest) -> Result<Uuid, &str> {
             }
         })
         .map(|auth_str| auth_str[6..auth_str.len()].trim())
-        .and_then(|token| token::decode(token).map_err(|_err| "Cannot decode token."))
-        .map(|token| token.claims.user_id)
+        .map_err(unauthorized)
+}
+
+fn get_token_from_cookie(req: &HttpRequest) -> Result<String, AppError> {
+    req.cookie(constants::TOKEN_COOKIE)
+        .map(|cookie| cookie.value().to_owned())
+        .ok_or_else(|| unauthorized("Cannot find auth token in req header nor cookie"))
+}
+
+// NOTE: Authorization header takes precedence over the cookie.
+pub fn get_token(req: &HttpRequest) -> Result<String, AppError> {
+    if req.headers().contains_key(constants::AUTHORIZATION) {
+        let token = get_token_from_header(req.headers())?;
+        Ok(token.to_owned())
+    } else {
+        get_token_from_cookie(req)
+    }
+}
+
+fn unauthorized(msg: &str) -> AppError {
+    AppError::Unauthorized(msg.into())
 }
 
 pub fn get_current_user(req: &HttpRequest) -> Result<User, AppError> {
@@ -137,10 +172,23 @@ pub fn get_current_user(req: &HttpRequest) -> Result<User, AppError> {
         .get::<User>()
         .map(|user| user.to_owned())
         .ok_or_else(|| {
-            AppError::Unauthorized(json!({"error": "Unauthrized user. Need auth token on header."}))
+            AppError::Unauthorized("Unauthrized user. Need auth token on header.".into())
         })
 }
 
+pub fn get_current_user_optional(req: &HttpRequest) -> Option<User> {
+    req.extensions().get::<User>().map(|user| user.to_owned())
+}
+
+// NOTE: for maintenance and moderation routes; a signed-in user without the role gets 403, not 401.
+pub fn require_admin(req: &HttpRequest) -> Result<User, AppError> {
+    let user = get_current_user(req)?;
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required.".into()));
+    }
+    Ok(user)
+}
+
 struct SkipAuthRoute {
     path: &'static str,
     method: Method,
@@ -183,7 +231,131 @@ impl SkipAuthRoute {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::cookie::Cookie;
     use actix_web::http::Method;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    #[test]
+    fn get_token_from_header_test() {
+        let req = TestRequest::default()
+            .insert_header((constants::AUTHORIZATION, "Token header-token"))
+            .to_http_request();
+        assert_eq!("header-token", get_token(&req).unwrap());
+    }
+
+    #[test]
+    fn get_token_from_cookie_test() {
+        let req = TestRequest::default()
+            .cookie(Cookie::new(constants::TOKEN_COOKIE, "cookie-token"))
+            .to_http_request();
+        assert_eq!("cookie-token", get_token(&req).unwrap());
+    }
+
+    #[test]
+    fn get_token_prefers_header_test() {
+        let req = TestRequest::default()
+            .insert_header((constants::AUTHORIZATION, "Token header-token"))
+            .cookie(Cookie::new(constants::TOKEN_COOKIE, "cookie-token"))
+            .to_http_request();
+        assert_eq!("header-token", get_token(&req).unwrap());
+    }
+
+    #[test]
+    fn get_token_without_credentials_test() {
+        let req = TestRequest::default().to_http_request();
+        assert!(get_token(&req).is_err());
+    }
+
+    #[test]
+    fn get_current_user_without_auth_test() {
+        let req = TestRequest::default().to_http_request();
+        assert!(matches!(
+            get_current_user(&req),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn get_current_user_optional_without_auth_test() {
+        let req = TestRequest::default().to_http_request();
+        assert!(get_current_user_optional(&req).is_none());
+    }
+
+    fn request_as(role: &str) -> HttpRequest {
+        let req = TestRequest::default().to_http_request();
+        let now = chrono::NaiveDateTime::default();
+        req.extensions_mut().insert(User {
+            id: uuid::Uuid::new_v4(),
+            email: "jake@example.com".to_owned(),
+            username: "jake".to_owned(),
+            password: "hashed".to_owned(),
+            bio: None,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            email_verified: true,
+            role: role.to_owned(),
+            last_login_at: None,
+        });
+        req
+    }
+
+    #[test]
+    fn get_current_user_optional_with_auth_test() {
+        let user = get_current_user_optional(&request_as("user")).map(|user| user.username);
+        assert_eq!(Some("jake".to_owned()), user);
+    }
+
+    #[test]
+    fn require_admin_lets_admins_through_test() {
+        let user = require_admin(&request_as("admin")).unwrap();
+        assert!(user.is_admin());
+    }
+
+    #[test]
+    fn require_admin_forbids_normal_users_test() {
+        assert!(matches!(
+            require_admin(&request_as("user")),
+            Err(AppError::Forbidden(_))
+        ));
+        let req = TestRequest::default().to_http_request();
+        assert!(matches!(
+            require_admin(&req),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    async fn viewer(req: HttpRequest) -> HttpResponse {
+        let viewer = get_current_user_optional(&req).map(|user| user.username);
+        HttpResponse::Ok().body(viewer.unwrap_or_else(|| "anonymous".to_owned()))
+    }
+
+    async fn call(req: TestRequest) -> (u16, String) {
+        let app = init_service(App::new().wrap(Authentication).route(
+            "/api/articles/{article_title_slug}",
+            web::route().to(viewer),
+        ))
+        .await;
+        let res = call_service(&app, req.to_request()).await;
+        let status = res.status().as_u16();
+        let body = to_bytes(res.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn article_detail_is_readable_without_a_token_test() {
+        let req = TestRequest::get().uri("/api/articles/how-to-train-your-dragon");
+        assert_eq!((200, "anonymous".to_owned()), call(req).await);
+    }
+
+    #[actix_web::test]
+    async fn article_writes_still_require_a_token_test() {
+        let req = TestRequest::put().uri("/api/articles/how-to-train-your-dragon");
+        assert_eq!(401, call(req).await.0);
+    }
+
     #[test]
     fn is_match_path_and_method_test() {
         let route = SkipAuthRoute {
@@ -200,11 +372,15 @@ mod tests {
     }
 }
 
-const SKIP_AUTH_ROUTES: [SkipAuthRoute; 6] = [
+const SKIP_AUTH_ROUTES: [SkipAuthRoute; 20] = [
     SkipAuthRoute {
         path: "/api/healthcheck",
         method: Method::GET,
     },
+    SkipAuthRoute {
+        path: "/api/events",
+        method: Method::GET,
+    },
     SkipAuthRoute {
         path: "/api/tags",
         method: Method::GET,
@@ -217,12 +393,65 @@ const SKIP_AUTH_ROUTES: [SkipAuthRoute; 6] = [
         path: "/api/users/login",
         method: Method::POST,
     },
+    SkipAuthRoute {
+        path: "/api/users/refresh",
+        method: Method::POST,
+    },
+    SkipAuthRoute {
+        path: "/api/users/verify",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/api/users/password-reset",
+        method: Method::POST,
+    },
+    SkipAuthRoute {
+        path: "/api/users/password-reset/confirm",
+        method: Method::POST,
+    },
     SkipAuthRoute {
         path: "/api/articles",
         method: Method::GET,
     },
+    // NOTE: also matches `/api/articles/feed`, whose handler still requires a user.
+    SkipAuthRoute {
+        path: "/api/articles/{article_title_slug}",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/api/articles/{article_title_slug}/related",
+        method: Method::GET,
+    },
     SkipAuthRoute {
         path: "/api/articles/{article_title_slug}/comments",
         method: Method::GET,
     },
+    SkipAuthRoute {
+        path: "/api/articles/{article_title_slug}/comments/ws",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/api/profiles/{username}/followers",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/api/profiles/{username}/following",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/api/images/{file_name}",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/api-docs/openapi.json",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/swagger-ui",
+        method: Method::GET,
+    },
+    SkipAuthRoute {
+        path: "/swagger-ui/{file_name}",
+        method: Method::GET,
+    },
 ];
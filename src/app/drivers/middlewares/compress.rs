@@ -0,0 +1,138 @@
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::http::header::{ContentEncoding, CONTENT_ENCODING};
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures::future::{ok, Ready};
+use futures::Future;
+use std::pin::Pin;
+
+// NOTE: below this size the gzip/brotli framing costs about as much as it saves.
+pub const MIN_COMPRESS_SIZE: u64 = 1024;
+
+// Marks responses smaller than `MIN_COMPRESS_SIZE` as `identity` so an outer `Compress` leaves them alone.
+// NOTE: must be wrapped before (inside) `actix_web::middleware::Compress`.
+pub struct CompressionThreshold;
+
+impl<S, B> Transform<S, ServiceRequest> for CompressionThreshold
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CompressionThresholdMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(CompressionThresholdMiddleware { service })
+    }
+}
+
+pub struct CompressionThresholdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for CompressionThresholdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let mut res = fut.await?;
+            let small = matches!(
+                res.response().body().size(),
+                BodySize::Sized(size) if size < MIN_COMPRESS_SIZE
+            );
+            if small && !res.headers().contains_key(CONTENT_ENCODING) {
+                res.headers_mut().insert(
+                    CONTENT_ENCODING,
+                    ContentEncoding::Identity.to_header_value(),
+                );
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::ACCEPT_ENCODING;
+    use actix_web::middleware::{Compress, Condition};
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use serde_json::json;
+
+    async fn articles() -> HttpResponse {
+        let articles = (0..50)
+            .map(|i| {
+                json!({
+                    "slug": format!("how-to-train-your-dragon-{}", i),
+                    "title": "How to train your dragon",
+                    "description": "Ever wonder how?",
+                    "tagList": ["dragons", "training"],
+                })
+            })
+            .collect::<Vec<_>>();
+        HttpResponse::Ok().json(json!({ "articles": articles, "articlesCount": 50 }))
+    }
+
+    async fn tags() -> HttpResponse {
+        HttpResponse::Ok().json(json!({ "tags": ["dragons"] }))
+    }
+
+    async fn content_encoding(enabled: bool, uri: &str) -> Option<String> {
+        let app = init_service(
+            App::new()
+                .wrap(Condition::new(enabled, CompressionThreshold))
+                .wrap(Condition::new(enabled, Compress::default()))
+                .route("/api/articles", web::get().to(articles))
+                .route("/api/tags", web::get().to(tags)),
+        )
+        .await;
+        let req = TestRequest::get()
+            .uri(uri)
+            .insert_header((ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(200, res.status().as_u16());
+        res.headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    }
+
+    #[actix_web::test]
+    async fn large_list_is_gzipped_when_requested() {
+        assert_eq!(
+            Some("gzip".to_owned()),
+            content_encoding(true, "/api/articles").await
+        );
+    }
+
+    #[actix_web::test]
+    async fn small_response_is_not_compressed() {
+        assert_eq!(
+            Some("identity".to_owned()),
+            content_encoding(true, "/api/tags").await
+        );
+    }
+
+    #[actix_web::test]
+    async fn nothing_is_compressed_when_disabled() {
+        assert_eq!(None, content_encoding(false, "/api/articles").await);
+    }
+}
@@ -1,15 +1,184 @@
-use crate::constants::env_key;
+use crate::config::Lookup;
+use crate::constants::{self, env_key};
 use actix_cors::Cors;
-use actix_web::http;
-use std::env;
+use actix_web::http::{self, Method};
 
-pub fn cors() -> Cors {
-    let frontend_origin = env::var(env_key::FRONTEND_ORIGIN).unwrap_or_else(|_| "*".to_string());
+static DEFAULT_ALLOWED_METHODS: [Method; 4] =
+    [Method::GET, Method::POST, Method::PUT, Method::DELETE];
+static DEFAULT_MAX_AGE_SECS: usize = 3600;
 
-    Cors::default()
-        .allowed_origin(&frontend_origin)
-        .allowed_origin_fn(|origin, _req_head| origin.as_bytes().ends_with(b".rust-lang.org"))
+#[derive(Debug, Clone, PartialEq)]
+pub enum AllowedOrigins {
+    Any,
+    // NOTE: an empty list denies every cross-origin request.
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsConfig {
+    pub origins: AllowedOrigins,
+    pub methods: Vec<Method>,
+    pub max_age: usize,
+}
+
+impl CorsConfig {
+    pub fn load(lookup: Lookup) -> Self {
+        let origins = match lookup(env_key::CORS_ALLOWED_ORIGINS) {
+            Some(origins) if origins.trim() == "*" => AllowedOrigins::Any,
+            Some(origins) => AllowedOrigins::List(split_list(&origins)),
+            None => AllowedOrigins::List(vec![]),
+        };
+        let methods = lookup(env_key::CORS_ALLOWED_METHODS)
+            .map(|methods| {
+                split_list(&methods)
+                    .iter()
+                    .filter_map(|method| Method::from_bytes(method.to_uppercase().as_bytes()).ok())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|methods| !methods.is_empty())
+            .unwrap_or_else(|| DEFAULT_ALLOWED_METHODS.to_vec());
+        let max_age = lookup(env_key::CORS_MAX_AGE)
+            .and_then(|secs| secs.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_SECS);
+        Self {
+            origins,
+            methods,
+            max_age,
+        }
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+pub fn cors(config: &CorsConfig) -> Cors {
+    let cors = match &config.origins {
+        AllowedOrigins::Any => Cors::default().allow_any_origin(),
+        AllowedOrigins::List(origins) => origins
+            .iter()
+            .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin)),
+    };
+    cors.allowed_methods(config.methods.clone())
         .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
         .allowed_header(http::header::CONTENT_TYPE)
-        .max_age(3600)
+        .allowed_header(constants::REQUEST_ID)
+        .allowed_header(http::header::IF_NONE_MATCH)
+        .allowed_header(constants::IDEMPOTENCY_KEY)
+        .expose_headers(vec![constants::REQUEST_ID, "link", "retry-after", "etag"])
+        .max_age(config.max_age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{
+        ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE,
+        ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+    };
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    const FRONTEND: &str = "http://localhost:3000";
+
+    fn config(origins: AllowedOrigins) -> CorsConfig {
+        CorsConfig {
+            origins,
+            methods: DEFAULT_ALLOWED_METHODS.to_vec(),
+            max_age: 600,
+        }
+    }
+
+    async fn preflight(config: &CorsConfig, origin: &str) -> (u16, Option<String>, Option<String>) {
+        let app = init_service(
+            App::new()
+                .wrap(cors(config))
+                .route("/api/articles", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/api/articles")
+            .insert_header((ORIGIN, origin))
+            .insert_header((ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        (
+            res.status().as_u16(),
+            header(ACCESS_CONTROL_ALLOW_ORIGIN),
+            header(ACCESS_CONTROL_MAX_AGE),
+        )
+    }
+
+    #[actix_web::test]
+    async fn preflight_from_allowed_origin() {
+        let config = config(AllowedOrigins::List(vec![FRONTEND.to_owned()]));
+        let (status, origin, max_age) = preflight(&config, FRONTEND).await;
+        assert_eq!(200, status);
+        assert_eq!(Some(FRONTEND.to_owned()), origin);
+        assert_eq!(Some("600".to_owned()), max_age);
+    }
+
+    #[actix_web::test]
+    async fn preflight_from_disallowed_origin() {
+        let config = config(AllowedOrigins::List(vec![FRONTEND.to_owned()]));
+        let (status, origin, _) = preflight(&config, "http://evil.example").await;
+        assert_eq!(400, status);
+        assert_eq!(None, origin);
+    }
+
+    #[actix_web::test]
+    async fn empty_origin_list_denies_everyone() {
+        let (status, origin, _) = preflight(&config(AllowedOrigins::List(vec![])), FRONTEND).await;
+        assert_eq!(400, status);
+        assert_eq!(None, origin);
+    }
+
+    #[actix_web::test]
+    async fn wildcard_allows_any_origin() {
+        let (status, origin, _) = preflight(&config(AllowedOrigins::Any), FRONTEND).await;
+        assert_eq!(200, status);
+        assert!(origin.is_some());
+    }
+
+    #[actix_web::test]
+    async fn preflight_allows_the_idempotency_key_header() {
+        let config = config(AllowedOrigins::List(vec![FRONTEND.to_owned()]));
+        let app = init_service(
+            App::new()
+                .wrap(cors(&config))
+                .route("/api/articles", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/api/articles")
+            .insert_header((ORIGIN, FRONTEND))
+            .insert_header((ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .insert_header((ACCESS_CONTROL_REQUEST_HEADERS, "idempotency-key"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(200, res.status().as_u16());
+        let allowed = res.headers().get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap();
+        assert!(allowed.to_str().unwrap().contains("idempotency-key"));
+    }
+
+    #[test]
+    fn split_list_trims_and_drops_blanks() {
+        assert_eq!(
+            vec!["http://a.example", "http://b.example"],
+            split_list(" http://a.example, ,http://b.example ")
+        );
+    }
 }
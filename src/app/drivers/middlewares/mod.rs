@@ -1,4 +1,8 @@
+pub mod access_log;
 pub mod auth;
+pub mod compress;
 pub mod cors;
 pub mod error;
+pub mod rate_limit;
+pub mod request_id;
 pub mod state;
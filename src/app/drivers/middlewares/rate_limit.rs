@@ -0,0 +1,192 @@
+use crate::error::AppError;
+use crate::utils::rate_limiter::RateLimiter;
+use actix_web::http::header::{HeaderValue, RETRY_AFTER};
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    web::Data,
+    Error, HttpRequest,
+};
+use futures::future::{ok, Ready};
+use futures::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::time::Instant;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+// Answers 429 with `Retry-After` once a client IP has used up its `RateLimiter` bucket.
+// NOTE: the limiter is shared app data so every worker draws from the same buckets.
+pub struct RateLimit;
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware { service })
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let limited = req.app_data::<Data<RateLimiter>>().and_then(|limiter| {
+            let client = client_ip(req.request(), limiter.trust_forwarded_for());
+            limiter.acquire(&client, Instant::now()).err()
+        });
+        match limited {
+            None => {
+                let fut = self.service.call(req);
+                Box::pin(async move {
+                    let res = fut.await?.map_into_left_body();
+                    Ok(res)
+                })
+            }
+            Some(retry_after) => Box::pin(async move {
+                let (req, _payload) = req.into_parts();
+                let mut res =
+                    AppError::TooManyRequests("Too many requests. Try again later.".into())
+                        .to_response();
+                // NOTE: whole seconds, rounded up so a client retrying on time is let through.
+                let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+                res.headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(seconds));
+                Ok(ServiceResponse::new(req, res.map_into_right_body()))
+            }),
+        }
+    }
+}
+
+// NOTE: only trust `X-Forwarded-For` behind a proxy; its last entry is the address the proxy saw.
+pub fn client_ip(req: &HttpRequest, trust_forwarded_for: bool) -> String {
+    let forwarded_for = || {
+        req.headers()
+            .get(X_FORWARDED_FOR)?
+            .to_str()
+            .ok()?
+            .rsplit(',')
+            .next()?
+            .trim()
+            .parse::<IpAddr>()
+            .ok()
+    };
+    let ip = match trust_forwarded_for {
+        true => forwarded_for(),
+        false => None,
+    };
+    ip.or_else(|| req.peer_addr().map(|addr| addr.ip()))
+        .map(|ip| ip.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use serde_json::json;
+    use std::net::SocketAddr;
+
+    fn peer(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 40000)
+    }
+
+    #[actix_web::test]
+    async fn requests_past_the_limit_get_429_with_retry_after() {
+        let limiter = RateLimiter::new(2, 0.5, false);
+        let app = init_service(
+            App::new().app_data(Data::new(limiter)).service(
+                web::scope("/api")
+                    .wrap(RateLimit)
+                    .route("/tags", web::get().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+        let get = |ip: &str| {
+            TestRequest::get()
+                .uri("/api/tags")
+                .peer_addr(peer(ip))
+                .to_request()
+        };
+
+        for _ in 0..2 {
+            assert_eq!(200, call_service(&app, get("10.0.0.1")).await.status());
+        }
+        let res = call_service(&app, get("10.0.0.1")).await;
+        assert_eq!(429, res.status().as_u16());
+        assert_eq!("2", res.headers().get(RETRY_AFTER).unwrap());
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json!({"errors": {"body": ["Too many requests. Try again later."]}}),
+            body
+        );
+
+        assert_eq!(200, call_service(&app, get("10.0.0.2")).await.status());
+    }
+
+    #[actix_web::test]
+    async fn forwarded_for_is_used_only_when_trusted() {
+        let limiter = RateLimiter::new(1, 0.5, true);
+        let app = init_service(
+            App::new()
+                .app_data(Data::new(limiter))
+                .wrap(RateLimit)
+                .route("/api/tags", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let get = |forwarded_for: &str| {
+            TestRequest::get()
+                .uri("/api/tags")
+                .peer_addr(peer("10.0.0.254"))
+                .insert_header((X_FORWARDED_FOR, forwarded_for))
+                .to_request()
+        };
+
+        assert_eq!(
+            200,
+            call_service(&app, get("1.1.1.1, 10.0.0.1")).await.status()
+        );
+        assert_eq!(
+            200,
+            call_service(&app, get("1.1.1.1, 10.0.0.2")).await.status()
+        );
+        assert_eq!(
+            429,
+            call_service(&app, get("2.2.2.2, 10.0.0.1")).await.status()
+        );
+    }
+
+    #[test]
+    fn client_ip_ignores_forwarded_for_unless_trusted() {
+        let req = TestRequest::default()
+            .peer_addr(peer("10.0.0.254"))
+            .insert_header((X_FORWARDED_FOR, "10.0.0.1"))
+            .to_http_request();
+        assert_eq!("10.0.0.254", client_ip(&req, false));
+        assert_eq!("10.0.0.1", client_ip(&req, true));
+    }
+}
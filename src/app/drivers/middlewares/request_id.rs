@@ -0,0 +1,167 @@
+use crate::constants;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::HttpMessage;
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpRequest,
+};
+use futures::future::{ok, Ready};
+use futures::Future;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use uuid::Uuid;
+
+// NOTE: longer or non-printable ids are replaced so clients cannot flood or forge log lines.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        let is_valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LENGTH
+            && value.bytes().all(|byte| byte.is_ascii_graphic());
+        is_valid.then(|| Self(value.to_owned()))
+    }
+
+    fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub fn get_request_id(req: &HttpRequest) -> Option<RequestId> {
+    req.extensions().get::<RequestId>().cloned()
+}
+
+// Tags every request with an `X-Request-Id`, kept from the client or generated, and echoes it back.
+pub struct RequestIdentifier;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdentifier
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdentifierMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdentifierMiddleware {
+            service: Rc::new(service),
+        })
+    }
+}
+
+pub struct RequestIdentifierMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdentifierMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let request_id = req
+            .headers()
+            .get(constants::REQUEST_ID)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        req.extensions_mut().insert(request_id.clone());
+        Box::pin(async move {
+            let mut res = service.call(req).await?;
+            if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(constants::REQUEST_ID), value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    fn response_id<B>(res: &ServiceResponse<B>) -> Option<String> {
+        res.headers()
+            .get(constants::REQUEST_ID)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    }
+
+    #[actix_web::test]
+    async fn generated_id_is_echoed() {
+        let app = init_service(App::new().wrap(RequestIdentifier).route(
+            "/api/tags",
+            web::get().to(|req: HttpRequest| async move {
+                HttpResponse::Ok().body(get_request_id(&req).unwrap().to_string())
+            }),
+        ))
+        .await;
+        let res = call_service(&app, TestRequest::get().uri("/api/tags").to_request()).await;
+        let id = response_id(&res).expect("missing request id header");
+        assert!(Uuid::parse_str(&id).is_ok());
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(id.as_bytes(), &body[..]);
+    }
+
+    #[actix_web::test]
+    async fn provided_id_is_preserved_on_errors() {
+        let app = init_service(App::new().wrap(RequestIdentifier).route(
+            "/api/articles/missing",
+            web::get().to(|| async {
+                Err::<HttpResponse, _>(AppError::NotFound("article not found".into()))
+            }),
+        ))
+        .await;
+        let req = TestRequest::get()
+            .uri("/api/articles/missing")
+            .insert_header((constants::REQUEST_ID, "abc-123"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(404, res.status().as_u16());
+        assert_eq!(Some("abc-123".to_owned()), response_id(&res));
+    }
+
+    #[test]
+    fn unsafe_ids_are_replaced() {
+        assert_eq!(None, RequestId::from_header(&HeaderValue::from_static("")));
+        assert_eq!(
+            None,
+            RequestId::from_header(&HeaderValue::from_static("two words"))
+        );
+        let long = "a".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        assert_eq!(
+            None,
+            RequestId::from_header(&HeaderValue::from_str(&long).unwrap())
+        );
+    }
+}
@@ -1,14 +1,20 @@
-use crate::utils::db::DbPool;
+use crate::config::Config;
+use crate::utils::db::{DbPool, DbPools};
 use crate::utils::di::DiContainer;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub di_container: DiContainer,
+    pub config: Arc<Config>,
 }
 
 impl AppState {
-    pub fn new(pool: DbPool) -> Self {
-        let di_container = DiContainer::new(&pool);
-        Self { di_container }
+    pub fn new(config: Config, pool: DbPool, replica: Option<DbPool>) -> Self {
+        let di_container = DiContainer::new(&config, &DbPools::new(pool, replica));
+        Self {
+            di_container,
+            config: Arc::new(config),
+        }
     }
 }
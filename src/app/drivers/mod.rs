@@ -1,2 +1,4 @@
 pub mod middlewares;
+pub mod openapi;
 pub mod routes;
+pub mod seed;
@@ -0,0 +1,88 @@
+use crate::app::features::{article, favorite, profile, report, tag, user};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+pub const SPEC_PATH: &str = "/api-docs/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Conduit API", description = "RealWorld backend built with actix-web and diesel."),
+    paths(
+        user::controllers::signin,
+        user::controllers::signup,
+        user::controllers::refresh,
+        user::controllers::logout,
+        user::controllers::me,
+        user::controllers::update,
+        profile::controllers::index,
+        profile::controllers::search,
+        profile::controllers::show,
+        profile::controllers::followers,
+        profile::controllers::following,
+        profile::controllers::mutuals,
+        profile::controllers::follow,
+        profile::controllers::unfollow,
+        article::controllers::index,
+        article::controllers::feed,
+        article::controllers::show,
+        article::controllers::related,
+        article::controllers::create,
+        article::controllers::update,
+        article::controllers::delete,
+        article::controllers::restore,
+        article::controllers::archive,
+        article::controllers::unarchive,
+        favorite::controllers::favorite,
+        favorite::controllers::unfavorite,
+        favorite::controllers::favorited_by,
+        tag::controllers::index,
+        tag::controllers::consolidate,
+        report::controllers::report_article,
+        report::controllers::report_comment,
+        report::controllers::index,
+        report::controllers::hide,
+    ),
+    modifiers(&TokenAuth)
+)]
+pub struct ApiDoc;
+
+// SPEC: https://gothinkster.github.io/realworld/docs/specs/backend-specs/endpoints#authentication-header
+struct TokenAuth;
+
+impl Modify for TokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "Authorization",
+                "`Token <jwt>`",
+            ))),
+        );
+    }
+}
+
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/swagger-ui/{_:.*}").url(SPEC_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_and_read_body, init_service, TestRequest};
+    use actix_web::App;
+    use serde_json::Value as JsonValue;
+
+    #[actix_web::test]
+    async fn serves_spec_with_profile_path() {
+        let app = init_service(App::new().service(swagger_ui())).await;
+        let req = TestRequest::get().uri(SPEC_PATH).to_request();
+        let body = call_and_read_body(&app, req).await;
+        let spec: JsonValue = serde_json::from_slice(&body).unwrap();
+        let profile = &spec["paths"]["/api/profiles/{username}"];
+        assert!(profile["get"].is_object());
+        assert!(spec["components"]["schemas"]["ProfileResponse"].is_object());
+        assert!(spec["components"]["securitySchemes"]["token"].is_object());
+    }
+}
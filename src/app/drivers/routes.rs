@@ -3,14 +3,25 @@ use actix_web::web;
 use actix_web::web::{delete, get, post, put};
 
 pub fn api(cfg: &mut web::ServiceConfig) {
+    cfg.service(app::drivers::openapi::swagger_ui());
     cfg.service(
         web::scope("/api")
+            .wrap(app::drivers::middlewares::rate_limit::RateLimit)
             .service(
                 web::scope("/healthcheck")
                     .route("", get().to(app::features::healthcheck::controllers::index)),
             )
             .service(
-                web::scope("/tags").route("", get().to(app::features::tag::controllers::index)),
+                web::scope("/events")
+                    .route("", get().to(app::features::activity::controllers::index)),
+            )
+            .service(
+                web::scope("/tags")
+                    .route("", get().to(app::features::tag::controllers::index))
+                    .route(
+                        "/consolidate",
+                        post().to(app::features::tag::controllers::consolidate),
+                    ),
             )
             .service(
                 web::scope("/users")
@@ -18,19 +29,64 @@ pub fn api(cfg: &mut web::ServiceConfig) {
                         "/login",
                         post().to(app::features::user::controllers::signin),
                     )
+                    .route(
+                        "/refresh",
+                        post().to(app::features::user::controllers::refresh),
+                    )
+                    .route(
+                        "/logout",
+                        post().to(app::features::user::controllers::logout),
+                    )
+                    .route(
+                        "/verify",
+                        get().to(app::features::email_verification::controllers::verify),
+                    )
+                    .route(
+                        "/password-reset",
+                        post().to(app::features::password_reset::controllers::request),
+                    )
+                    .route(
+                        "/password-reset/confirm",
+                        post().to(app::features::password_reset::controllers::confirm),
+                    )
                     .route("", post().to(app::features::user::controllers::signup)),
             )
             .service(
                 web::scope("/user")
                     .route("", get().to(app::features::user::controllers::me))
-                    .route("", put().to(app::features::user::controllers::update)),
+                    .route("", put().to(app::features::user::controllers::update))
+                    .route(
+                        "/image",
+                        post().to(app::features::avatar::controllers::upload),
+                    ),
             )
+            .service(web::scope("/images").route(
+                "/{file_name}",
+                get().to(app::features::avatar::controllers::show),
+            ))
             .service(
                 web::scope("/profiles")
+                    .route("", get().to(app::features::profile::controllers::index))
+                    .route(
+                        "/search",
+                        get().to(app::features::profile::controllers::search),
+                    )
                     .route(
                         "/{username}",
                         get().to(app::features::profile::controllers::show),
                     )
+                    .route(
+                        "/{username}/followers",
+                        get().to(app::features::profile::controllers::followers),
+                    )
+                    .route(
+                        "/{username}/following",
+                        get().to(app::features::profile::controllers::following),
+                    )
+                    .route(
+                        "/{username}/mutuals",
+                        get().to(app::features::profile::controllers::mutuals),
+                    )
                     .route(
                         "/{username}/follow",
                         post().to(app::features::profile::controllers::follow),
@@ -50,6 +106,30 @@ pub fn api(cfg: &mut web::ServiceConfig) {
                             .route("", get().to(app::features::article::controllers::show))
                             .route("", put().to(app::features::article::controllers::update))
                             .route("", delete().to(app::features::article::controllers::delete))
+                            .route(
+                                "/restore",
+                                post().to(app::features::article::controllers::restore),
+                            )
+                            .route(
+                                "/archive",
+                                post().to(app::features::article::controllers::archive),
+                            )
+                            .route(
+                                "/unarchive",
+                                post().to(app::features::article::controllers::unarchive),
+                            )
+                            .route(
+                                "/report",
+                                post().to(app::features::report::controllers::report_article),
+                            )
+                            .route(
+                                "/related",
+                                get().to(app::features::article::controllers::related),
+                            )
+                            .route(
+                                "/favorited-by",
+                                get().to(app::features::favorite::controllers::favorited_by),
+                            )
                             .service(
                                 web::scope("/favorite")
                                     .route(
@@ -69,12 +149,29 @@ pub fn api(cfg: &mut web::ServiceConfig) {
                                         "",
                                         post().to(app::features::comment::controllers::create),
                                     )
+                                    .route(
+                                        "/ws",
+                                        get().to(app::features::comment::controllers::subscribe),
+                                    )
                                     .route(
                                         "/{comment_id}",
                                         delete().to(app::features::comment::controllers::delete),
+                                    )
+                                    .route(
+                                        "/{comment_id}/report",
+                                        post()
+                                            .to(app::features::report::controllers::report_comment),
                                     ),
                             ),
                     ),
+            )
+            .service(
+                web::scope("/reports")
+                    .route("", get().to(app::features::report::controllers::index))
+                    .route(
+                        "/{report_id}/hide",
+                        post().to(app::features::report::controllers::hide),
+                    ),
             ),
     );
 }
@@ -0,0 +1,227 @@
+use crate::app::features::article::entities::{Article, CreateArticle};
+use crate::app::features::comment::entities::{Comment, CreateComment};
+use crate::app::features::favorite::entities::{CreateFavorite, Favorite};
+use crate::app::features::follow::entities::{CreateFollow, Follow};
+use crate::app::features::tag::entities::Tag;
+use crate::app::features::user::entities::{SignupUser, User};
+use crate::error::AppError;
+use crate::schema::users;
+use crate::utils::hasher::{self, HashConfig};
+use crate::utils::{db, slug};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// NOTE: every seeded user signs in with this password, e.g. `jake@example.com`.
+pub const PASSWORD: &str = "password123";
+
+const USERNAMES: [&str; 4] = ["jake", "celeb", "anna", "kenji"];
+
+struct SeedArticle {
+    author: &'static str,
+    title: &'static str,
+    description: &'static str,
+    body: &'static str,
+    tags: &'static [&'static str],
+}
+
+const ARTICLES: [SeedArticle; 5] = [
+    SeedArticle {
+        author: "jake",
+        title: "How to train your dragon",
+        description: "Ever wonder how?",
+        body: "You have to believe in the dragon.",
+        tags: &["dragons", "training"],
+    },
+    SeedArticle {
+        author: "jake",
+        title: "Ownership in Rust",
+        description: "Moves, borrows and lifetimes",
+        body: "Every value has exactly one owner.",
+        tags: &["rust", "programming"],
+    },
+    SeedArticle {
+        author: "celeb",
+        title: "Life on the red carpet",
+        description: "It is not all glamour",
+        body: "Mostly it is standing still and smiling.",
+        tags: &["lifestyle"],
+    },
+    SeedArticle {
+        author: "anna",
+        title: "Diesel query builder tips",
+        description: "Type-safe SQL without tears",
+        body: "Lean on `QueryDsl` and let the compiler check your joins.",
+        tags: &["rust", "databases"],
+    },
+    SeedArticle {
+        author: "kenji",
+        title: "Training for a marathon",
+        description: "Sixteen weeks to the finish line",
+        body: "Build your base mileage before anything else.",
+        tags: &["training", "lifestyle"],
+    },
+];
+
+// (follower, followee)
+const FOLLOWS: [(&str, &str); 4] = [
+    ("jake", "celeb"),
+    ("jake", "anna"),
+    ("anna", "jake"),
+    ("kenji", "jake"),
+];
+
+// (user, article title)
+const FAVORITES: [(&str, &str); 4] = [
+    ("celeb", "How to train your dragon"),
+    ("anna", "Ownership in Rust"),
+    ("kenji", "Ownership in Rust"),
+    ("jake", "Diesel query builder tips"),
+];
+
+// (author, article title, body)
+const COMMENTS: [(&str, &str, &str); 3] = [
+    (
+        "celeb",
+        "How to train your dragon",
+        "Mine still will not fly.",
+    ),
+    (
+        "anna",
+        "Ownership in Rust",
+        "The borrow checker finally clicked for me.",
+    ),
+    (
+        "jake",
+        "Training for a marathon",
+        "Good luck with the race!",
+    ),
+];
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SeedSummary {
+    pub users: usize,
+    pub articles: usize,
+    pub follows: usize,
+    pub favorites: usize,
+    pub comments: usize,
+}
+
+// Fills an empty database with demo data; returns `None` when it has been seeded before.
+pub fn run(conn: &mut PgConnection, hash: &HashConfig) -> Result<Option<SeedSummary>, AppError> {
+    if User::find_id_by_username(conn, USERNAMES[0])?.is_some() {
+        return Ok(None);
+    }
+    db::with_transaction(conn, |conn| {
+        let user_ids = create_users(conn, hash)?;
+        let article_ids = create_articles(conn, &user_ids)?;
+        for (follower, followee) in FOLLOWS {
+            Follow::create(
+                conn,
+                &CreateFollow {
+                    follower_id: user_ids[follower],
+                    followee_id: user_ids[followee],
+                },
+            )?;
+        }
+        for (username, title) in FAVORITES {
+            let article_id = article_ids[title];
+            let inserted = Favorite::create(
+                conn,
+                &CreateFavorite {
+                    user_id: user_ids[username],
+                    article_id,
+                },
+            )?;
+            Article::adjust_favorites_count(conn, &article_id, inserted as i32)?;
+        }
+        for (username, title, body) in COMMENTS {
+            Comment::create(
+                conn,
+                &CreateComment {
+                    body: body.to_owned(),
+                    author_id: user_ids[username],
+                    article_id: article_ids[title],
+                    parent_id: None,
+                },
+            )?;
+        }
+        Ok(Some(SeedSummary {
+            users: user_ids.len(),
+            articles: article_ids.len(),
+            follows: FOLLOWS.len(),
+            favorites: FAVORITES.len(),
+            comments: COMMENTS.len(),
+        }))
+    })
+}
+
+fn create_users(
+    conn: &mut PgConnection,
+    hash: &HashConfig,
+) -> Result<HashMap<&'static str, Uuid>, AppError> {
+    // NOTE: hashing is deliberately slow, so all users share one hash of the same password.
+    let hashed_password = hasher::hash_password(hash, PASSWORD)?;
+    let mut user_ids = HashMap::new();
+    for username in USERNAMES {
+        let email = format!("{}@example.com", username);
+        let user = diesel::insert_into(users::table)
+            .values(SignupUser {
+                email: &email,
+                username,
+                password: &hashed_password,
+            })
+            .get_result::<User>(conn)?;
+        // NOTE: verified up front so seeded users can publish right away.
+        User::mark_email_verified(conn, &user.id)?;
+        user_ids.insert(username, user.id);
+    }
+    Ok(user_ids)
+}
+
+fn create_articles(
+    conn: &mut PgConnection,
+    user_ids: &HashMap<&'static str, Uuid>,
+) -> Result<HashMap<&'static str, Uuid>, AppError> {
+    let mut article_ids = HashMap::new();
+    for article in ARTICLES.iter() {
+        let record = CreateArticle {
+            author_id: user_ids[article.author],
+            slug: slug::generate(article.title, conn)?,
+            title: article.title.to_owned(),
+            description: article.description.to_owned(),
+            body: article.body.to_owned(),
+            published: true,
+        };
+        let created = Article::create(conn, &record)?;
+        let tags = article
+            .tags
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect::<Vec<_>>();
+        Tag::create_list(conn, &created.id, &tags)?;
+        article_ids.insert(article.title, created.id);
+    }
+    Ok(article_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_reference_seeded_users_and_articles() {
+        let titles = ARTICLES.iter().map(|a| a.title).collect::<Vec<_>>();
+        assert!(ARTICLES.iter().all(|a| USERNAMES.contains(&a.author)));
+        assert!(FOLLOWS
+            .iter()
+            .all(|(a, b)| USERNAMES.contains(a) && USERNAMES.contains(b)));
+        assert!(FAVORITES
+            .iter()
+            .all(|(user, title)| USERNAMES.contains(user) && titles.contains(title)));
+        assert!(COMMENTS
+            .iter()
+            .all(|(user, title, _)| USERNAMES.contains(user) && titles.contains(title)));
+    }
+}
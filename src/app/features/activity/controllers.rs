@@ -0,0 +1,27 @@
+use crate::app::drivers::middlewares::state::AppState;
+use crate::constants;
+use crate::utils::api::ApiResponse;
+use crate::utils::sse;
+use actix_web::{web, HttpRequest};
+use futures::StreamExt;
+use std::time::Duration;
+
+const HEARTBEAT: Duration = Duration::from_secs(15);
+
+// NOTE: `EventSource` sends `Last-Event-ID` when it reconnects, so missed events are replayed.
+pub async fn index(state: web::Data<AppState>, req: HttpRequest) -> ApiResponse {
+    let last_event_id = req
+        .headers()
+        .get(constants::LAST_EVENT_ID)
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| id.trim().parse().ok());
+    let events = state
+        .di_container
+        .activity_feed
+        .subscribe(last_event_id)
+        .map(|event| {
+            let data = serde_json::to_string(&event.activity).unwrap_or_default();
+            sse::message(event.id, event.activity.name(), &data)
+        });
+    Ok(sse::stream(events, HEARTBEAT))
+}
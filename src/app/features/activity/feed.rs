@@ -0,0 +1,198 @@
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+// NOTE: a subscriber that falls this far behind skips ahead instead of holding up the others.
+const CAPACITY: usize = 64;
+// NOTE: how many recent events a reconnecting client can catch up on with `Last-Event-ID`.
+const HISTORY: usize = 128;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Activity {
+    Article {
+        slug: String,
+        title: String,
+        author: String,
+    },
+    Favorite {
+        slug: String,
+        username: String,
+    },
+    Follow {
+        follower: String,
+        followee: String,
+    },
+}
+
+impl Activity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Article { .. } => "article",
+            Self::Favorite { .. } => "favorite",
+            Self::Follow { .. } => "follow",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub id: u64,
+    pub activity: Activity,
+}
+
+struct History {
+    next_id: u64,
+    events: VecDeque<Event>,
+}
+
+// Site-wide activity for dashboards: new articles, favorites and follows.
+// NOTE: ids count up from 1 and restart with the process.
+#[derive(Clone)]
+pub struct ActivityFeed {
+    sender: broadcast::Sender<Event>,
+    history: Arc<Mutex<History>>,
+}
+
+impl Default for ActivityFeed {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+            history: Arc::new(Mutex::new(History {
+                next_id: 1,
+                events: VecDeque::with_capacity(HISTORY),
+            })),
+        }
+    }
+}
+
+impl ActivityFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&self, activity: Activity) {
+        let mut history = self.history.lock().unwrap();
+        let event = Event {
+            id: history.next_id,
+            activity,
+        };
+        history.next_id += 1;
+        if history.events.len() == HISTORY {
+            history.events.pop_front();
+        }
+        history.events.push_back(event.clone());
+        // NOTE: sent under the lock so `subscribe` never sees an event both replayed and received.
+        // Fails only when nobody listens, which is fine to ignore.
+        let _ = self.sender.send(event);
+    }
+
+    // Events published after `last_event_id` that are still remembered, then every new one.
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> impl Stream<Item = Event> + Unpin {
+        let (missed, receiver) = {
+            let history = self.history.lock().unwrap();
+            // NOTE: an id this process never handed out was seen before a restart; replay it all.
+            let after = last_event_id
+                .filter(|id| *id < history.next_id)
+                .unwrap_or(0);
+            let missed = match last_event_id {
+                Some(_) => history
+                    .events
+                    .iter()
+                    .filter(|event| event.id > after)
+                    .cloned()
+                    .collect(),
+                None => vec![],
+            };
+            (missed, self.sender.subscribe())
+        };
+        let live = stream::unfold(receiver, |mut receiver| async {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Activity feed subscriber skipped {} events", skipped)
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        Box::pin(stream::iter(missed).chain(live))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    fn follow(followee: &str) -> Activity {
+        Activity::Follow {
+            follower: "jake".to_owned(),
+            followee: followee.to_owned(),
+        }
+    }
+
+    // NOTE: every event here is published before it is polled for, so none of these wait.
+    fn ready_events(events: &mut (impl Stream<Item = Event> + Unpin)) -> Vec<u64> {
+        let mut ids = vec![];
+        while let Some(Some(event)) = events.next().now_or_never() {
+            ids.push(event.id);
+        }
+        ids
+    }
+
+    #[test]
+    fn new_subscribers_only_receive_new_events() {
+        let feed = ActivityFeed::new();
+        feed.publish(follow("anna"));
+        let mut events = feed.subscribe(None);
+        feed.publish(follow("celeb"));
+
+        let event = events.next().now_or_never().flatten().unwrap();
+        assert_eq!(2, event.id);
+        assert_eq!(follow("celeb"), event.activity);
+        assert!(ready_events(&mut events).is_empty());
+    }
+
+    #[test]
+    fn resuming_replays_missed_events_once() {
+        let feed = ActivityFeed::new();
+        for followee in ["anna", "celeb", "ben"] {
+            feed.publish(follow(followee));
+        }
+        let mut events = feed.subscribe(Some(1));
+        feed.publish(follow("carl"));
+        assert_eq!(vec![2, 3, 4], ready_events(&mut events));
+
+        // NOTE: ids from before a restart are unknown, so everything remembered is replayed.
+        let mut events = feed.subscribe(Some(99));
+        assert_eq!(vec![1, 2, 3, 4], ready_events(&mut events));
+    }
+
+    #[test]
+    fn history_keeps_the_latest_events_only() {
+        let feed = ActivityFeed::new();
+        for _ in 0..HISTORY + 2 {
+            feed.publish(follow("anna"));
+        }
+        let mut events = feed.subscribe(Some(0));
+        let ids = ready_events(&mut events);
+        assert_eq!(HISTORY, ids.len());
+        assert_eq!(Some(&3), ids.first());
+    }
+
+    #[test]
+    fn activities_serialize_without_a_tag() {
+        assert_eq!(
+            r#"{"slug":"how-to-train-your-dragon","username":"anna"}"#,
+            serde_json::to_string(&Activity::Favorite {
+                slug: "how-to-train-your-dragon".to_owned(),
+                username: "anna".to_owned(),
+            })
+            .unwrap()
+        );
+    }
+}
@@ -1,79 +1,350 @@
 use super::{
+    presenters::{ArticleFormat, MultipleArticlesResponse, SingleArticleResponse},
+    repositories::ArticleOrder,
     requests,
     usecases::{
-        CreateArticleUsecaseInput, DeleteArticleUsecaseInput, FetchArticlesUsecaseInput,
-        UpdateArticleUsecaseInput,
+        ArchiveArticleUsecaseInput, CreateArticleUsecaseInput, DeleteArticleUsecaseInput,
+        FetchArticlesUsecaseInput, RestoreArticleUsecaseInput, UpdateArticleUsecaseInput,
     },
+    views::Viewer,
 };
 use crate::app::drivers::middlewares::auth;
+use crate::app::drivers::middlewares::rate_limit;
 use crate::app::drivers::middlewares::state::AppState;
-use crate::utils::api::ApiResponse;
+use crate::constants;
+use crate::error::AppError;
+use crate::utils::api::{self, ApiResponse};
+use crate::utils::pagination::{Cursor, Pagination};
+use crate::utils::rate_limiter::RateLimiter;
+use crate::utils::validation;
+use actix_web::http::header::{Accept, Header, USER_AGENT};
 use actix_web::{web, HttpRequest};
+use chrono::{DateTime, NaiveDateTime};
 use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
 
 type ArticleTitleSlug = String;
 
-#[derive(Deserialize)]
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+const CRAWLER_MARKERS: [&str; 4] = ["bot", "crawler", "spider", "slurp"];
+const DEFAULT_RELATED_LIMIT: i64 = 5;
+const MAX_RELATED_LIMIT: i64 = 20;
+
+// NOTE: `?status=draft` lists the current user's own drafts; anyone else only sees published ones.
+fn drafts_of(req: &HttpRequest, status: Option<&str>) -> Result<Option<Uuid>, AppError> {
+    match status {
+        None | Some("published") => Ok(None),
+        Some("draft") => auth::get_current_user(req).map(|user| Some(user.id)),
+        Some(_) => Err(AppError::invalid("status", "is invalid")),
+    }
+}
+
+// NOTE: any RFC 3339 offset is accepted and compared in UTC, e.g. the newest `createdAt` already seen.
+fn since(value: Option<&str>) -> Result<Option<NaiveDateTime>, AppError> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|since| since.naive_utc())
+                .map_err(|_| AppError::invalid("since", "is invalid"))
+        })
+        .transpose()
+}
+
+fn order(value: Option<&str>) -> Result<ArticleOrder, AppError> {
+    match value {
+        None => Ok(ArticleOrder::default()),
+        Some(value) => {
+            ArticleOrder::parse(value).ok_or_else(|| AppError::invalid("order", "is invalid"))
+        }
+    }
+}
+
+fn after(
+    value: Option<&str>,
+    order: ArticleOrder,
+    query: Option<&str>,
+) -> Result<Option<Cursor>, AppError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match Cursor::parse(value) {
+        Some(cursor) if order.pages_by_cursor(query.is_some()) => Ok(Some(cursor)),
+        _ => Err(AppError::invalid("after", "is invalid")),
+    }
+}
+
+// NOTE: `?format` wins over `Accept`, which only picks HTML when `text/html` is the top preference.
+fn format(req: &HttpRequest, value: Option<&str>) -> Result<ArticleFormat, AppError> {
+    if let Some(value) = value {
+        return ArticleFormat::parse(value)
+            .ok_or_else(|| AppError::invalid("format", "is invalid"));
+    }
+    let html =
+        Accept::parse(req).is_ok_and(|accept| accept.preference().essence_str() == "text/html");
+    Ok(if html {
+        ArticleFormat::Html
+    } else {
+        ArticleFormat::Json
+    })
+}
+
+// NOTE: keys are opaque to the server; the length cap only keeps the table's primary key small.
+fn idempotency_key(req: &HttpRequest) -> Result<Option<String>, AppError> {
+    let Some(value) = req.headers().get(constants::IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+    match value.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH => {
+            Ok(Some(key.to_owned()))
+        }
+        _ => Err(AppError::invalid("idempotencyKey", "is invalid")),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ArticlesListQueryParameter {
+    /// Full-text search over title, description and body.
+    query: Option<String>,
     tag: Option<String>,
     author: Option<String>,
     favorited: Option<String>,
+    /// `published` (default) or `draft` for the current user's own drafts.
+    status: Option<String>,
+    /// Only articles created after this RFC 3339 timestamp, e.g. `2016-02-18T03:22:56.637Z`.
+    since: Option<String>,
+    /// `recent` (default), `popular` (most favorited first) or `oldest`.
+    order: Option<String>,
     limit: Option<i64>,
     offset: Option<i64>,
+    /// `nextCursor` of the previous page; replaces `offset`. Not available with `popular` or `query`.
+    after: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/articles",
+    tag = "article",
+    params(ArticlesListQueryParameter),
+    responses(
+        (status = 200, body = MultipleArticlesResponse),
+        (status = 422, description = "Invalid status filter, since, order or after"),
+    )
+)]
 pub async fn index(
     state: web::Data<AppState>,
+    req: HttpRequest,
     params: web::Query<ArticlesListQueryParameter>,
 ) -> ApiResponse {
-    let offset = std::cmp::min(params.offset.to_owned().unwrap_or(0), 100);
-    let limit = params.limit.unwrap_or(20);
+    let drafts_of = drafts_of(&req, params.status.as_deref())?;
+    let since = since(params.since.as_deref())?;
+    let order = order(params.order.as_deref())?;
+    let viewer_id = auth::get_current_user_optional(&req).map(|user| user.id);
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    let query = params
+        .query
+        .as_deref()
+        .map(str::trim)
+        .filter(|query| !query.is_empty())
+        .map(str::to_owned);
+    let after = after(params.after.as_deref(), order, query.as_deref())?;
     state
         .di_container
         .article_usecase
         .fetch_articles(FetchArticlesUsecaseInput {
+            query,
             tag: params.tag.clone(),
             author: params.author.clone(),
             favorited: params.favorited.clone(),
-            offset,
-            limit,
+            drafts_of,
+            since,
+            order,
+            viewer_id,
+            pagination,
+            after,
+            page_url: api::page_url(&req),
         })
+        .await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FeedQueryParameter {
+    /// Only articles created after this RFC 3339 timestamp, e.g. `2016-02-18T03:22:56.637Z`.
+    since: Option<String>,
     limit: Option<i64>,
     offset: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/articles/feed",
+    tag = "article",
+    params(FeedQueryParameter),
+    responses(
+        (status = 200, body = MultipleArticlesResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 422, description = "Invalid since"),
+    ),
+    security(("token" = []))
+)]
 pub async fn feed(
     state: web::Data<AppState>,
     req: HttpRequest,
     params: web::Query<FeedQueryParameter>,
 ) -> ApiResponse {
     let current_user = auth::get_current_user(&req)?;
-    let offset = std::cmp::min(params.offset.to_owned().unwrap_or(0), 100);
-    let limit = params.limit.unwrap_or(20);
+    let since = since(params.since.as_deref())?;
+    let pagination = Pagination::from_query(params.limit, params.offset);
     state
         .di_container
         .article_usecase
-        .fetch_following_articles(current_user, offset, limit)
+        .fetch_following_articles(current_user, since, pagination, api::page_url(&req))
+        .await
+}
+
+// NOTE: crawlers don't count as readers. Everyone else is counted by user when signed in, by IP otherwise.
+fn viewer(req: &HttpRequest, viewer_id: Option<Uuid>) -> Option<Viewer> {
+    let user_agent = req
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|user_agent| user_agent.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    if CRAWLER_MARKERS
+        .iter()
+        .any(|marker| user_agent.contains(marker))
+    {
+        return None;
+    }
+    let viewer = match viewer_id {
+        Some(user_id) => Viewer::User(user_id),
+        None => {
+            let trust_forwarded_for = req
+                .app_data::<web::Data<RateLimiter>>()
+                .is_some_and(|limiter| limiter.trust_forwarded_for());
+            Viewer::Anonymous(rate_limit::client_ip(req, trust_forwarded_for))
+        }
+    };
+    Some(viewer)
 }
 
-pub async fn show(state: web::Data<AppState>, path: web::Path<ArticleTitleSlug>) -> ApiResponse {
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArticleQueryParameter {
+    /// `json` (default) or `html` for the body rendered from markdown; overrides `Accept`.
+    format: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/articles/{article_title_slug}",
+    tag = "article",
+    params(
+        ("article_title_slug" = String, Path, description = "Article slug or id"),
+        ArticleQueryParameter,
+    ),
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 200, description = "Sanitized HTML of the body, for `Accept: text/html` or `?format=html`", content_type = "text/html", body = String),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+        (status = 404, description = "Article not found"),
+        (status = 422, description = "Invalid format"),
+    )
+)]
+pub async fn show(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleTitleSlug>,
+    params: web::Query<ArticleQueryParameter>,
+) -> ApiResponse {
+    let format = format(&req, params.format.as_deref())?;
+    let viewer_id = auth::get_current_user_optional(&req).map(|user| user.id);
     let article_title_slug = path.into_inner();
     state
         .di_container
         .article_usecase
-        .fetch_article_by_slug(article_title_slug)
+        .fetch_article_by_slug(
+            article_title_slug,
+            viewer_id,
+            viewer(&req, viewer_id),
+            format,
+            api::if_none_match(&req),
+        )
+        .await
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RelatedArticlesQueryParameter {
+    /// At most this many articles, 5 by default and 20 at most.
+    limit: Option<i64>,
+    /// Leave out other articles by the same author.
+    #[serde(rename = "excludeAuthor")]
+    exclude_author: Option<bool>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/articles/{article_title_slug}/related",
+    tag = "article",
+    params(
+        ("article_title_slug" = String, Path, description = "Article slug or id"),
+        RelatedArticlesQueryParameter,
+    ),
+    responses(
+        (status = 200, description = "Articles sharing the most tags first", body = MultipleArticlesResponse),
+        (status = 404, description = "Article not found"),
+    )
+)]
+pub async fn related(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleTitleSlug>,
+    params: web::Query<RelatedArticlesQueryParameter>,
+) -> ApiResponse {
+    let viewer_id = auth::get_current_user_optional(&req).map(|user| user.id);
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_RELATED_LIMIT)
+        .clamp(0, MAX_RELATED_LIMIT);
+    state
+        .di_container
+        .article_usecase
+        .fetch_related_articles(
+            path.into_inner(),
+            viewer_id,
+            params.exclude_author.unwrap_or(false),
+            limit,
+        )
+        .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles",
+    tag = "article",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key within 24 hours return the article the first request created"),
+    ),
+    request_body = requests::CreateArticleRequest,
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Email is not verified"),
+        (status = 422, description = "Invalid fields or `Idempotency-Key`"),
+    ),
+    security(("token" = []))
+)]
 pub async fn create(
     state: web::Data<AppState>,
     req: HttpRequest,
     form: web::Json<requests::CreateArticleRequest>,
 ) -> ApiResponse {
+    validation::validate(&*form)?;
     let current_user = auth::get_current_user(&req)?;
+    let idempotency_key = idempotency_key(&req)?;
     state
         .di_container
         .article_usecase
@@ -82,21 +353,44 @@ pub async fn create(
             description: form.article.description.clone(),
             body: form.article.body.clone(),
             tag_name_list: form.article.tag_list.to_owned(),
+            published: form.article.published,
             current_user,
+            idempotency_key,
         })
+        .await
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/articles/{article_title_slug}",
+    tag = "article",
+    params(("article_title_slug" = String, Path)),
+    request_body = requests::UpdateArticleRequest,
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article not found"),
+        (status = 409, description = "The article was changed since `version` was read"),
+        (status = 422, description = "Missing `version`"),
+    ),
+    security(("token" = []))
+)]
 pub async fn update(
     state: web::Data<AppState>,
     req: HttpRequest,
     path: web::Path<ArticleTitleSlug>,
     form: web::Json<requests::UpdateArticleRequest>,
 ) -> ApiResponse {
+    validation::validate(&*form)?;
     let current_user = auth::get_current_user(&req)?;
     let article_title_slug = path.into_inner();
     let title = form.article.title.clone();
     let description = form.article.description.clone();
     let body = form.article.body.clone();
+    let published = form.article.published;
+    let tag_name_list = form.article.tag_list.clone();
+    // NOTE: present, since validation rejects a missing version.
+    let version = form.article.version.unwrap_or_default();
     state
         .di_container
         .article_usecase
@@ -106,9 +400,25 @@ pub async fn update(
             title,
             description,
             body,
+            published,
+            tag_name_list,
+            version,
         })
+        .await
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/articles/{article_title_slug}",
+    tag = "article",
+    params(("article_title_slug" = String, Path)),
+    responses(
+        (status = 200, description = "The article has been deleted"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article not found"),
+    ),
+    security(("token" = []))
+)]
 pub async fn delete(
     state: web::Data<AppState>,
     req: HttpRequest,
@@ -123,4 +433,264 @@ pub async fn delete(
             author_id: current_user.id,
             slug: article_title_slug,
         })
+        .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles/{article_title_slug}/restore",
+    tag = "article",
+    params(("article_title_slug" = String, Path)),
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article not found"),
+    ),
+    security(("token" = []))
+)]
+pub async fn restore(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleTitleSlug>,
+) -> ApiResponse {
+    let current_user = auth::get_current_user(&req)?;
+    let article_title_slug = path.into_inner();
+    state
+        .di_container
+        .article_usecase
+        .restore_article(RestoreArticleUsecaseInput {
+            slug: article_title_slug,
+            current_user,
+        })
+        .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles/{article_title_slug}/archive",
+    tag = "article",
+    params(("article_title_slug" = String, Path)),
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Not the author"),
+        (status = 404, description = "Article not found"),
+    ),
+    security(("token" = []))
+)]
+pub async fn archive(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleTitleSlug>,
+) -> ApiResponse {
+    set_archived(state, req, path, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles/{article_title_slug}/unarchive",
+    tag = "article",
+    params(("article_title_slug" = String, Path)),
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Not the author"),
+        (status = 404, description = "Article not found"),
+    ),
+    security(("token" = []))
+)]
+pub async fn unarchive(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleTitleSlug>,
+) -> ApiResponse {
+    set_archived(state, req, path, false).await
+}
+
+async fn set_archived(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleTitleSlug>,
+    archived: bool,
+) -> ApiResponse {
+    let current_user = auth::get_current_user(&req)?;
+    let article_title_slug = path.into_inner();
+    state
+        .di_container
+        .article_usecase
+        .set_archived(ArchiveArticleUsecaseInput {
+            slug: article_title_slug,
+            archived,
+            current_user,
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use serde_json::json;
+
+    #[test]
+    fn drafts_of_defaults_to_published() {
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(None, drafts_of(&req, None).unwrap());
+        assert_eq!(None, drafts_of(&req, Some("published")).unwrap());
+    }
+
+    #[test]
+    fn drafts_of_requires_auth() {
+        let req = TestRequest::default().to_http_request();
+        assert!(matches!(
+            drafts_of(&req, Some("draft")),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn drafts_of_rejects_unknown_status() {
+        let req = TestRequest::default().to_http_request();
+        assert!(matches!(
+            drafts_of(&req, Some("archived")),
+            Err(AppError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn viewers_are_users_or_client_ips_but_never_crawlers() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:4242".parse().unwrap())
+            .insert_header((USER_AGENT, "Mozilla/5.0 (X11; Linux x86_64)"))
+            .to_http_request();
+        let user_id = Uuid::new_v4();
+        assert_eq!(Some(Viewer::User(user_id)), viewer(&req, Some(user_id)));
+        assert_eq!(
+            Some(Viewer::Anonymous("10.0.0.1".to_owned())),
+            viewer(&req, None)
+        );
+
+        let req = TestRequest::default()
+            .insert_header((USER_AGENT, "Mozilla/5.0 (compatible; Googlebot/2.1)"))
+            .to_http_request();
+        assert_eq!(None, viewer(&req, Some(user_id)));
+    }
+
+    #[test]
+    fn order_defaults_to_recent() {
+        assert_eq!(ArticleOrder::Recent, order(None).unwrap());
+        assert_eq!(ArticleOrder::Popular, order(Some("popular")).unwrap());
+    }
+
+    #[test]
+    fn order_rejects_unknown_value() {
+        let err = order(Some("random")).unwrap_err();
+        match err {
+            err @ AppError::UnprocessableEntity(_) => {
+                assert_eq!(json!({"errors": {"order": ["is invalid"]}}), err.body())
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn format_prefers_the_query_over_accept() {
+        let browser = TestRequest::default()
+            .insert_header(("Accept", "text/html,application/xhtml+xml,*/*;q=0.8"))
+            .to_http_request();
+        assert_eq!(ArticleFormat::Html, format(&browser, None).unwrap());
+        assert_eq!(ArticleFormat::Json, format(&browser, Some("json")).unwrap());
+        let client = TestRequest::default().to_http_request();
+        assert_eq!(ArticleFormat::Json, format(&client, None).unwrap());
+        assert_eq!(ArticleFormat::Html, format(&client, Some("html")).unwrap());
+        let json_first = TestRequest::default()
+            .insert_header(("Accept", "application/json, text/html;q=0.5"))
+            .to_http_request();
+        assert_eq!(ArticleFormat::Json, format(&json_first, None).unwrap());
+    }
+
+    #[test]
+    fn format_rejects_unknown_value() {
+        let req = TestRequest::default().to_http_request();
+        match format(&req, Some("xml")) {
+            Err(err @ AppError::UnprocessableEntity(_)) => {
+                assert_eq!(json!({"errors": {"format": ["is invalid"]}}), err.body())
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn since_is_read_as_utc() {
+        assert_eq!(None, since(None).unwrap());
+        let expected = chrono::NaiveDate::from_ymd_opt(2016, 2, 18)
+            .unwrap()
+            .and_hms_milli_opt(3, 22, 56, 637)
+            .unwrap();
+        assert_eq!(
+            Some(expected),
+            since(Some("2016-02-18T03:22:56.637Z")).unwrap()
+        );
+        assert_eq!(
+            Some(expected),
+            since(Some("2016-02-18T05:22:56.637+02:00")).unwrap()
+        );
+    }
+
+    #[test]
+    fn since_rejects_garbage() {
+        for value in ["yesterday", "2016-02-18", "2016-02-18T03:22:56", ""] {
+            match since(Some(value)) {
+                Err(err @ AppError::UnprocessableEntity(_)) => {
+                    assert_eq!(json!({"errors": {"since": ["is invalid"]}}), err.body())
+                }
+                other => panic!("{:?} was accepted: {:?}", value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn after_accepts_a_cursor_for_time_ordered_lists() {
+        let cursor = "1455765776637123_00000000-0000-0000-0000-000000000000";
+        assert_eq!(None, after(None, ArticleOrder::Popular, None).unwrap());
+        assert_eq!(
+            Cursor::parse(cursor),
+            after(Some(cursor), ArticleOrder::Oldest, None).unwrap()
+        );
+        for (value, order, query) in [
+            ("page-2", ArticleOrder::Recent, None),
+            (cursor, ArticleOrder::Popular, None),
+            (cursor, ArticleOrder::Recent, Some("dragons")),
+        ] {
+            match after(Some(value), order, query) {
+                Err(err @ AppError::UnprocessableEntity(_)) => {
+                    assert_eq!(json!({"errors": {"after": ["is invalid"]}}), err.body())
+                }
+                other => panic!("{:?} was accepted: {:?}", value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn idempotency_key_is_optional() {
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(None, idempotency_key(&req).unwrap());
+        let req = TestRequest::default()
+            .insert_header((constants::IDEMPOTENCY_KEY, " retry-1 "))
+            .to_http_request();
+        assert_eq!(Some("retry-1".to_owned()), idempotency_key(&req).unwrap());
+    }
+
+    #[test]
+    fn idempotency_key_rejects_blank_and_oversized_keys() {
+        for key in [" ".to_owned(), "k".repeat(MAX_IDEMPOTENCY_KEY_LENGTH + 1)] {
+            let req = TestRequest::default()
+                .insert_header((constants::IDEMPOTENCY_KEY, key))
+                .to_http_request();
+            assert!(matches!(
+                idempotency_key(&req),
+                Err(AppError::UnprocessableEntity(_))
+            ));
+        }
+    }
 }
@@ -2,9 +2,8 @@ use crate::app::features::favorite::entities::Favorite;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
 use crate::schema::articles;
-use crate::utils::converter;
-use chrono::NaiveDateTime;
-use diesel::dsl::Eq;
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::{Add, And, Eq, Filter, IsNotNull, IsNull, Nullable, Or, Update};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::Insertable;
@@ -23,11 +22,48 @@ pub struct Article {
     pub body: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub published: bool,
+    pub favorites_count: i32,
+    pub version: i32,
+    pub archived: bool,
+    pub hidden: bool,
+    pub view_count: i32,
 }
 
+// An article path segment: anything that parses as a UUID is an id, everything else a slug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlugOrId<'a> {
+    Id(Uuid),
+    Slug(&'a str),
+}
+
+impl<'a> SlugOrId<'a> {
+    pub fn parse(segment: &'a str) -> Self {
+        Uuid::parse_str(segment)
+            .map(Self::Id)
+            .unwrap_or(Self::Slug(segment))
+    }
+}
+
+type Following = bool;
+type Favorited = bool;
 type WithAuthorId<T> = Eq<articles::author_id, T>;
 type WithSlug<T> = Eq<articles::slug, T>;
 type WithId<T> = Eq<articles::id, T>;
+type NotDeleted = IsNull<articles::deleted_at>;
+type Listed =
+    And<And<articles::published, Eq<articles::archived, bool>>, Eq<articles::hidden, bool>>;
+type VisibleTo<'a> = Or<Listed, Eq<Nullable<articles::author_id>, Option<&'a Uuid>>>;
+type Deleted = IsNotNull<articles::deleted_at>;
+type AdjustFavoritesCount<'a> = Update<
+    Filter<articles::table, WithId<&'a Uuid>>,
+    Eq<articles::favorites_count, Add<articles::favorites_count, i32>>,
+>;
+type RecordView<'a> = Update<
+    Filter<articles::table, WithId<&'a Uuid>>,
+    Eq<articles::view_count, Add<articles::view_count, i32>>,
+>;
 
 impl Article {
     fn with_author_id(author_id: &Uuid) -> WithAuthorId<&Uuid> {
@@ -41,6 +77,32 @@ impl Article {
     fn with_id(id: &Uuid) -> WithId<&Uuid> {
         articles::id.eq(id)
     }
+
+    // NOTE: soft-deleted articles must be hidden from every list and read.
+    pub fn not_deleted() -> NotDeleted {
+        articles::deleted_at.is_null()
+    }
+
+    // NOTE: what shows up in public lists: published, not archived and not hidden by a moderator.
+    pub fn listed() -> Listed {
+        articles::published
+            .and(articles::archived.eq(false))
+            .and(articles::hidden.eq(false))
+    }
+
+    // NOTE: `listed()` for an article already loaded.
+    pub fn is_listed(&self) -> bool {
+        self.published && !self.archived && !self.hidden
+    }
+
+    // NOTE: drafts, archived and hidden articles are only visible to their author; everyone else gets a 404.
+    pub fn visible_to(viewer_id: Option<&Uuid>) -> VisibleTo<'_> {
+        Self::listed().or(articles::author_id.nullable().eq(viewer_id))
+    }
+
+    fn deleted() -> Deleted {
+        articles::deleted_at.is_not_null()
+    }
 }
 
 impl Article {
@@ -52,64 +114,97 @@ impl Article {
         Ok(article)
     }
 
+    // NOTE: optimistic locking; the write only lands if nobody bumped `version` since it was read.
     pub fn update(
         conn: &mut PgConnection,
         article_title_slug: &str,
         author_id: &Uuid,
+        expected_version: i32,
         record: &UpdateArticle,
     ) -> Result<Self, AppError> {
         let t = articles::table
             .filter(Self::with_slug(article_title_slug))
-            .filter(Self::with_author_id(author_id));
-        let article = diesel::update(t).set(record).get_result::<Article>(conn)?;
-        Ok(article)
+            .filter(Self::with_author_id(author_id))
+            .filter(Self::not_deleted());
+        let article = diesel::update(t.filter(articles::version.eq(expected_version)))
+            .set((record, articles::version.eq(articles::version + 1)))
+            .get_result::<Article>(conn)
+            .optional()?;
+        match article {
+            Some(article) => Ok(article),
+            None => {
+                // NOTE: tells a stale version apart from an article that isn't there at all.
+                t.select(articles::id).first::<Uuid>(conn)?;
+                Err(Self::stale_version())
+            }
+        }
     }
 
-    pub fn convert_title_to_slug(title: &str) -> String {
-        converter::to_kebab(title)
-    }
-
-    pub fn fetch_by_slug_and_author_id(
-        conn: &mut PgConnection,
-        params: &FetchBySlugAndAuthorId,
-    ) -> Result<Self, AppError> {
-        let t = articles::table
-            .filter(Self::with_slug(&params.slug))
-            .filter(Self::with_author_id(&params.author_id));
-        let item = t.first::<Self>(conn)?;
-        Ok(item)
+    fn stale_version() -> AppError {
+        AppError::Conflict(
+            "Article has been modified since it was read. Reload it and try again.".into(),
+        )
     }
 
     pub fn fetch_by_slug_with_author(
         conn: &mut PgConnection,
         slug: &str,
+        viewer_id: Option<&Uuid>,
     ) -> Result<(Self, User), AppError> {
         use crate::schema::users;
         let t = articles::table
             .inner_join(users::table)
-            .filter(Self::with_slug(slug));
+            .filter(Self::with_slug(slug))
+            .filter(Self::not_deleted())
+            .filter(Self::visible_to(viewer_id));
         let result = t.get_result::<(Self, User)>(conn)?;
         Ok(result)
     }
 
-    pub fn fetch_ids_by_author_name(
+    // NOTE: the viewer's follow and favorite rows ride along as left joins; both are unique per viewer.
+    pub fn fetch_with_viewer_state(
         conn: &mut PgConnection,
-        name: &str,
-    ) -> Result<Vec<Uuid>, AppError> {
-        use crate::schema::users;
-        let t = users::table
-            .inner_join(articles::table)
-            .filter(User::with_username(name))
-            .select(articles::id);
-        let ids = t.load::<Uuid>(conn)?;
-        Ok(ids)
+        key: SlugOrId<'_>,
+        viewer_id: Option<&Uuid>,
+    ) -> Result<(Self, User, Following, Favorited), AppError> {
+        use crate::schema::{favorites, follows, users};
+        // NOTE: values bound in a join's ON clause must be owned.
+        let viewer = viewer_id.copied();
+        let t = articles::table
+            .inner_join(users::table)
+            .left_join(
+                follows::table.on(follows::followee_id
+                    .eq(articles::author_id)
+                    .and(follows::follower_id.nullable().eq(viewer))),
+            )
+            .left_join(
+                favorites::table.on(favorites::article_id
+                    .eq(articles::id)
+                    .and(favorites::user_id.nullable().eq(viewer))),
+            )
+            .filter(Self::not_deleted())
+            .filter(Self::visible_to(viewer_id))
+            .select((
+                articles::all_columns,
+                users::all_columns,
+                follows::follower_id.nullable().is_not_null(),
+                favorites::id.nullable().is_not_null(),
+            ))
+            .into_boxed();
+        let t = match key {
+            SlugOrId::Id(id) => t.filter(articles::id.eq(id)),
+            SlugOrId::Slug(slug) => t.filter(Self::with_slug(slug)),
+        };
+        let result = t.get_result::<(Self, User, Following, Favorited)>(conn)?;
+        Ok(result)
     }
 
     pub fn find_with_author(conn: &mut PgConnection, id: &Uuid) -> Result<(Self, User), AppError> {
         use crate::schema::users;
         let t = articles::table
             .inner_join(users::table)
-            .filter(Self::with_id(id));
+            .filter(Self::with_id(id))
+            .filter(Self::not_deleted());
         let result = t.get_result::<(Article, User)>(conn)?;
         Ok(result)
     }
@@ -117,12 +212,58 @@ impl Article {
     pub fn delete(conn: &mut PgConnection, params: &DeleteArticle) -> Result<(), AppError> {
         let t = articles::table
             .filter(Self::with_slug(&params.slug))
-            .filter(Self::with_author_id(&params.author_id));
-        diesel::delete(t).execute(conn)?;
-        // NOTE: references tag rows are deleted automatically by DELETE CASCADE
-
+            .filter(Self::with_author_id(&params.author_id))
+            .filter(Self::not_deleted());
+        // NOTE: soft delete keeps comments and favorites attached for a later restore.
+        let deleted = diesel::update(t)
+            .set(articles::deleted_at.eq(Utc::now().naive_utc()))
+            .execute(conn)?;
+        if deleted == 0 {
+            return Err(diesel::result::Error::NotFound.into());
+        }
         Ok(())
     }
+
+    pub fn restore(conn: &mut PgConnection, params: &RestoreArticle) -> Result<Self, AppError> {
+        let t = articles::table
+            .filter(Self::with_slug(&params.slug))
+            .filter(Self::with_author_id(&params.author_id))
+            .filter(Self::deleted());
+        let article = diesel::update(t)
+            .set(articles::deleted_at.eq(None::<NaiveDateTime>))
+            .get_result::<Self>(conn)?;
+        Ok(article)
+    }
+}
+
+impl Article {
+    pub fn set_archived(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+        archived: bool,
+    ) -> Result<Self, AppError> {
+        let t = articles::table
+            .filter(Self::with_id(article_id))
+            .filter(Self::not_deleted());
+        let article = diesel::update(t)
+            .set(articles::archived.eq(archived))
+            .get_result::<Self>(conn)?;
+        Ok(article)
+    }
+
+    pub fn set_hidden(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+        hidden: bool,
+    ) -> Result<Self, AppError> {
+        let t = articles::table
+            .filter(Self::with_id(article_id))
+            .filter(Self::not_deleted());
+        let article = diesel::update(t)
+            .set(articles::hidden.eq(hidden))
+            .get_result::<Self>(conn)?;
+        Ok(article)
+    }
 }
 
 impl Article {
@@ -140,13 +281,32 @@ impl Article {
         Ok(count >= 1)
     }
 
-    pub fn fetch_favorites_count(&self, conn: &mut PgConnection) -> Result<i64, AppError> {
-        use crate::schema::favorites;
-        let t = favorites::table
-            .filter(Favorite::with_article_id(&self.id))
-            .select(diesel::dsl::count(favorites::created_at));
-        let favorites_count = t.first::<i64>(conn)?;
-        Ok(favorites_count)
+    // NOTE: relative update, so concurrent favorites can't overwrite each other's count.
+    pub fn adjust_favorites_count(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+        delta: i32,
+    ) -> Result<Self, AppError> {
+        let article = Self::adjust_favorites_count_query(article_id, delta).get_result(conn)?;
+        Ok(article)
+    }
+
+    fn adjust_favorites_count_query(article_id: &Uuid, delta: i32) -> AdjustFavoritesCount<'_> {
+        diesel::update(articles::table.filter(Self::with_id(article_id)))
+            .set(articles::favorites_count.eq(articles::favorites_count + delta))
+    }
+
+    // NOTE: relative as well; returns the count including this view.
+    pub fn record_view(conn: &mut PgConnection, article_id: &Uuid) -> Result<i32, AppError> {
+        let view_count = Self::record_view_query(article_id)
+            .returning(articles::view_count)
+            .get_result(conn)?;
+        Ok(view_count)
+    }
+
+    fn record_view_query(article_id: &Uuid) -> RecordView<'_> {
+        diesel::update(articles::table.filter(Self::with_id(article_id)))
+            .set(articles::view_count.eq(articles::view_count + 1))
     }
 }
 
@@ -158,6 +318,7 @@ pub struct CreateArticle {
     pub title: String,
     pub description: String,
     pub body: String,
+    pub published: bool,
 }
 
 #[derive(AsChangeset)]
@@ -167,14 +328,60 @@ pub struct UpdateArticle {
     pub title: Option<String>,
     pub description: Option<String>,
     pub body: Option<String>,
+    pub published: Option<bool>,
 }
 
-pub struct FetchBySlugAndAuthorId {
+pub struct DeleteArticle {
     pub slug: String,
     pub author_id: Uuid,
 }
 
-pub struct DeleteArticle {
+pub struct RestoreArticle {
     pub slug: String,
     pub author_id: Uuid,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+
+    #[test]
+    fn adjust_favorites_count_updates_relatively() {
+        let article_id = Uuid::nil();
+        let query = Article::adjust_favorites_count_query(&article_id, 1);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"SET "favorites_count" = ("articles"."favorites_count" + $1) WHERE ("articles"."id" = $2)"#
+        ));
+        assert!(sql.contains("binds: [1, 00000000-0000-0000-0000-000000000000]"));
+
+        let query = Article::adjust_favorites_count_query(&article_id, -1);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains("binds: [-1, 00000000-0000-0000-0000-000000000000]"));
+    }
+
+    #[test]
+    fn record_view_increments_in_place() {
+        let article_id = Uuid::nil();
+        let query = Article::record_view_query(&article_id);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"SET "view_count" = ("articles"."view_count" + $1) WHERE ("articles"."id" = $2)"#
+        ));
+    }
+
+    #[test]
+    fn slug_or_id_prefers_uuid() {
+        let id = "6f1c2e4a-3b5d-4c7e-9f80-1a2b3c4d5e6f";
+        assert_eq!(
+            SlugOrId::Id(Uuid::parse_str(id).unwrap()),
+            SlugOrId::parse(id)
+        );
+        assert_eq!(
+            SlugOrId::Slug("how-to-train-your-dragon"),
+            SlugOrId::parse("how-to-train-your-dragon")
+        );
+    }
+}
@@ -4,3 +4,4 @@ pub mod presenters;
 pub mod repositories;
 pub mod requests;
 pub mod usecases;
+pub mod views;
@@ -1,15 +1,42 @@
-use super::{entities::Article, repositories::ArticlesList};
+use super::{
+    entities::Article,
+    repositories::{ArticlesPage, TagNamesByArticle},
+};
 use crate::app::features::favorite::entities::FavoriteInfo;
 use crate::app::features::profile::entities::Profile;
 use crate::app::features::tag::entities::Tag;
 use crate::utils::date::Iso8601;
+use crate::utils::markdown;
+use crate::utils::pagination::Cursor;
+use actix_web::http::header::ContentType;
 use actix_web::HttpResponse;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::convert::From;
+use utoipa::ToSchema;
+use uuid::Uuid;
 
 type ArticleCount = i64;
 
-#[derive(Deserialize, Serialize)]
+// NOTE: bodies are stored as markdown; `Html` renders the body for clients that can't.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ArticleFormat {
+    #[default]
+    Json,
+    Html,
+}
+
+impl ArticleFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct SingleArticleResponse {
     pub article: ArticleContent,
 }
@@ -18,90 +45,83 @@ impl From<(Article, Profile, FavoriteInfo, Vec<Tag>)> for SingleArticleResponse
     fn from(
         (article, profile, favorite_info, tag_list): (Article, Profile, FavoriteInfo, Vec<Tag>),
     ) -> Self {
+        let tag_list = tag_list.into_iter().map(|tag| tag.name).collect();
         Self {
-            article: ArticleContent {
-                slug: article.slug,
-                title: article.title,
-                description: article.description,
-                body: article.body,
-                tag_list: tag_list
-                    .iter()
-                    .map(move |tag| tag.name.to_owned())
-                    .collect(),
-                created_at: Iso8601(article.created_at),
-                updated_at: Iso8601(article.updated_at),
-                favorited: favorite_info.is_favorited.to_owned(),
-                favorites_count: favorite_info.favorites_count.to_owned(),
-                author: AuthorContent {
-                    username: profile.username,
-                    bio: profile.bio,
-                    image: profile.image,
-                    following: profile.following,
-                },
-            },
+            article: ArticleContent::from((article, profile, favorite_info, tag_list)),
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MultipleArticlesResponse {
     pub articles: Vec<ArticleContent>,
     pub articles_count: ArticleCount,
+    // NOTE: pass back as `?after=` for the next page; absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub next_cursor: Option<String>,
 }
 
 type ArticlesCount = i64;
-type Inner = ((Article, Profile, FavoriteInfo), Vec<Tag>);
-type Item = (ArticlesList, ArticlesCount);
+type TagNames = Vec<String>;
+type Inner = (Article, Profile, FavoriteInfo, TagNames);
+type Item = (Vec<Inner>, ArticlesCount);
 impl From<Item> for MultipleArticlesResponse {
-    fn from((list, articles_count): (Vec<Inner>, ArticleCount)) -> Self {
-        let articles = list
-            .iter()
-            .map(|((article, profile, favorite_info), tags_list)| {
-                ArticleContent::from((
-                    article.to_owned(),
-                    profile.to_owned(),
-                    favorite_info.to_owned(),
-                    tags_list.to_owned(),
-                ))
-            })
-            .collect();
+    fn from((list, articles_count): Item) -> Self {
+        let articles = list.into_iter().map(ArticleContent::from).collect();
         Self {
             articles_count,
             articles,
+            next_cursor: None,
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ArticleContent {
     pub slug: String,
     pub title: String,
     pub description: String,
     pub body: String,
+    pub reading_time: i64,
     pub tag_list: Vec<String>,
+    #[schema(value_type = String, format = DateTime)]
     pub created_at: Iso8601,
+    #[schema(value_type = String, format = DateTime)]
     pub updated_at: Iso8601,
+    pub published: bool,
+    pub archived: bool,
     pub favorited: bool,
     pub favorites_count: i64,
+    pub view_count: i64,
+    // NOTE: send it back on update; a stale version is rejected with 409.
+    pub version: i32,
     pub author: AuthorContent,
 }
 
-impl From<(Article, Profile, FavoriteInfo, Vec<Tag>)> for ArticleContent {
+impl From<(Article, Profile, FavoriteInfo, TagNames)> for ArticleContent {
     fn from(
-        (article, profile, favorite_info, tag_list): (Article, Profile, FavoriteInfo, Vec<Tag>),
+        (article, profile, favorite_info, mut tag_list): (Article, Profile, FavoriteInfo, TagNames),
     ) -> Self {
+        // NOTE: sorted and deduplicated so clients see the same list whatever the insert order.
+        tag_list.sort_unstable();
+        tag_list.dedup();
         Self {
             slug: article.slug,
             title: article.title,
             description: article.description,
+            reading_time: reading_time(&article.body),
             body: article.body,
-            tag_list: tag_list.iter().map(move |tag| tag.name.clone()).collect(),
+            tag_list,
             created_at: Iso8601(article.created_at),
             updated_at: Iso8601(article.updated_at),
+            published: article.published,
+            archived: article.archived,
             favorited: favorite_info.is_favorited.to_owned(),
-            favorites_count: favorite_info.favorites_count.to_owned(),
+            favorites_count: article.favorites_count.into(),
+            view_count: article.view_count.into(),
+            version: article.version,
             author: AuthorContent {
                 username: profile.username,
                 bio: profile.bio,
@@ -112,7 +132,46 @@ impl From<(Article, Profile, FavoriteInfo, Vec<Tag>)> for ArticleContent {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+const WORDS_PER_MINUTE: usize = 200;
+
+// NOTE: counts visible words only, so HTML tags and markdown link targets don't inflate it.
+fn reading_time(body: &str) -> i64 {
+    let words = strip_markup(body)
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+    let minutes = words.div_ceil(WORDS_PER_MINUTE);
+    minutes.max(1) as i64
+}
+
+fn strip_markup(body: &str) -> String {
+    let mut text = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                for c in chars.by_ref() {
+                    if c == '>' {
+                        break;
+                    }
+                }
+                text.push(' ');
+            }
+            ']' if chars.peek() == Some(&'(') => {
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        break;
+                    }
+                }
+                text.push(' ');
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct AuthorContent {
     pub username: String,
     pub bio: Option<String>,
@@ -121,12 +180,20 @@ pub struct AuthorContent {
 }
 
 pub trait ArticlePresenter: Send + Sync + 'static {
-    fn to_multi_json(&self, list: ArticlesList, count: i64) -> HttpResponse;
+    fn to_multi_json(
+        &self,
+        page: ArticlesPage,
+        favorited_article_ids: &HashSet<Uuid>,
+        followed_author_ids: &HashSet<Uuid>,
+        tag_names: &TagNamesByArticle,
+        next_cursor: Option<Cursor>,
+    ) -> HttpResponse;
     fn to_single_json(&self, item: (Article, Profile, FavoriteInfo, Vec<Tag>)) -> HttpResponse;
+    fn to_html(&self, article: Article) -> HttpResponse;
     fn to_http_res(&self) -> HttpResponse;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ArticlePresenterImpl {}
 impl ArticlePresenterImpl {
     pub fn new() -> Self {
@@ -134,15 +201,184 @@ impl ArticlePresenterImpl {
     }
 }
 impl ArticlePresenter for ArticlePresenterImpl {
-    fn to_multi_json(&self, list: ArticlesList, count: i64) -> HttpResponse {
-        let res = MultipleArticlesResponse::from((list, count));
+    fn to_multi_json(
+        &self,
+        page: ArticlesPage,
+        favorited_article_ids: &HashSet<Uuid>,
+        followed_author_ids: &HashSet<Uuid>,
+        tag_names: &TagNamesByArticle,
+        next_cursor: Option<Cursor>,
+    ) -> HttpResponse {
+        let list = page
+            .items
+            .into_iter()
+            .map(|(article, profile, _)| {
+                let profile = Profile {
+                    following: followed_author_ids.contains(&article.author_id),
+                    ..profile
+                };
+                let favorite_info = FavoriteInfo {
+                    is_favorited: favorited_article_ids.contains(&article.id),
+                };
+                let tag_list = tag_names.get(&article.id).cloned().unwrap_or_default();
+                (article, profile, favorite_info, tag_list)
+            })
+            .collect();
+        let res = MultipleArticlesResponse {
+            next_cursor: next_cursor.map(|cursor| cursor.to_string()),
+            ..MultipleArticlesResponse::from((list, page.total))
+        };
         HttpResponse::Ok().json(res)
     }
     fn to_single_json(&self, item: (Article, Profile, FavoriteInfo, Vec<Tag>)) -> HttpResponse {
         let res = SingleArticleResponse::from(item);
         HttpResponse::Ok().json(res)
     }
+    fn to_html(&self, article: Article) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .body(markdown::to_html(&article.body))
+    }
     fn to_http_res(&self) -> HttpResponse {
         HttpResponse::Ok().json(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::pagination::Page;
+
+    #[test]
+    fn reading_time_short_body() {
+        assert_eq!(1, reading_time("It takes a Jacobian"));
+    }
+
+    #[test]
+    fn reading_time_empty_body() {
+        assert_eq!(1, reading_time(""));
+        assert_eq!(1, reading_time("  <p></p> \n ## "));
+    }
+
+    #[test]
+    fn reading_time_long_body() {
+        assert_eq!(2, reading_time(&"word ".repeat(400)));
+        assert_eq!(3, reading_time(&"word ".repeat(401)));
+    }
+
+    #[test]
+    fn reading_time_ignores_markup() {
+        let body = format!(
+            "# Title\n<p class=\"lead\">{}</p> [link](https://example.com/a b c)",
+            "word ".repeat(198)
+        );
+        assert_eq!(1, reading_time(&body));
+        assert_eq!(
+            "hello  world  [there ",
+            strip_markup("hello <b>world</b> [there](https://example.com)")
+        );
+    }
+
+    fn article(slug: &str) -> Article {
+        let now = chrono::NaiveDateTime::default();
+        Article {
+            id: Uuid::new_v4(),
+            author_id: Uuid::nil(),
+            slug: slug.to_owned(),
+            title: slug.to_owned(),
+            description: String::new(),
+            body: String::new(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            published: true,
+            favorites_count: 1,
+            version: 1,
+            archived: false,
+            hidden: false,
+            view_count: 0,
+        }
+    }
+
+    fn profile() -> Profile {
+        Profile {
+            username: "jake".to_owned(),
+            bio: None,
+            image: None,
+            following: false,
+        }
+    }
+
+    #[test]
+    fn tag_list_is_sorted_and_deduplicated() {
+        let tag_list = ["rust", "dragons", "rust", "angular", "dragons"]
+            .map(str::to_owned)
+            .to_vec();
+        let favorite_info = FavoriteInfo {
+            is_favorited: false,
+        };
+        let content = ArticleContent::from((article("a"), profile(), favorite_info, tag_list));
+        assert_eq!(vec!["angular", "dragons", "rust"], content.tag_list);
+    }
+
+    #[actix_web::test]
+    async fn to_multi_json_marks_favorited_articles() {
+        let item = |article: Article| {
+            let profile = profile();
+            let favorite_info = FavoriteInfo {
+                is_favorited: false,
+            };
+            (article, profile, favorite_info)
+        };
+        let (liked, other) = (article("liked"), article("other"));
+        let favorited = [liked.id].into_iter().collect::<HashSet<_>>();
+        let tag_names = [(liked.id, vec!["react".to_owned(), "rust".to_owned()])]
+            .into_iter()
+            .collect::<TagNamesByArticle>();
+        let page = Page {
+            items: vec![item(liked), item(other)],
+            total: 2,
+        };
+        let res = ArticlePresenterImpl::new().to_multi_json(
+            page,
+            &favorited,
+            &HashSet::new(),
+            &tag_names,
+            None,
+        );
+
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(true, body["articles"][0]["favorited"]);
+        assert_eq!(false, body["articles"][1]["favorited"]);
+        assert_eq!(
+            serde_json::json!(["react", "rust"]),
+            body["articles"][0]["tagList"]
+        );
+        assert_eq!(serde_json::json!([]), body["articles"][1]["tagList"]);
+        assert!(body.get("nextCursor").is_none());
+    }
+
+    #[actix_web::test]
+    async fn timestamps_are_utc_with_milliseconds() {
+        let created_at = chrono::NaiveDate::from_ymd_opt(2016, 2, 18)
+            .unwrap()
+            .and_hms_milli_opt(3, 22, 56, 637)
+            .unwrap();
+        let article = Article {
+            created_at,
+            updated_at: created_at,
+            ..article("dragons")
+        };
+        let favorite_info = FavoriteInfo {
+            is_favorited: false,
+        };
+        let res =
+            ArticlePresenterImpl::new().to_single_json((article, profile(), favorite_info, vec![]));
+
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("2016-02-18T03:22:56.637Z", body["article"]["createdAt"]);
+        assert_eq!("2016-02-18T03:22:56.637Z", body["article"]["updatedAt"]);
+    }
+}
@@ -1,31 +1,59 @@
-use super::entities::{Article, CreateArticle, DeleteArticle, UpdateArticle};
-use crate::app::features::favorite::entities::FavoriteInfo;
+use super::entities::{
+    Article, CreateArticle, DeleteArticle, RestoreArticle, SlugOrId, UpdateArticle,
+};
+use crate::app::features::favorite::entities::{Favorite, FavoriteInfo};
+use crate::app::features::idempotency_key::entities::{CreateIdempotencyKey, IdempotencyKey};
 use crate::app::features::profile::entities::Profile;
-use crate::app::features::tag::entities::{CreateTag, Tag};
+use crate::app::features::tag::entities::Tag;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
-use crate::utils::db::DbPool;
+use crate::schema::{articles, users};
+use crate::utils::converter;
+use crate::utils::db::{self, DbPools};
+use crate::utils::gravatar;
+use crate::utils::pagination::{Cursor, Page, Pagination};
+use crate::utils::slug;
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::{sql, AsExprOf, InnerJoin, IntoBoxed};
+use diesel::expression::{SqlLiteral, UncheckedBind};
+use diesel::pg::Pg;
+use diesel::sql_types::{Array, BigInt, Bool, Float, Text};
 use diesel::PgConnection;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 pub trait ArticleRepository: Send + Sync + 'static {
     fn fetch_articles(
         &self,
         params: FetchArticlesRepositoryInput,
-    ) -> Result<(ArticlesList, ArticlesCount), AppError>;
+    ) -> Result<ArticlesPage, AppError>;
 
-    fn fetch_article_by_slug(
+    // NOTE: the article detail with its author, the viewer's follow / favorite state and its tags.
+    // The segment is looked up as an id when it parses as a UUID, otherwise as a slug.
+    fn find_by_slug_or_id(
         &self,
-        article_title_slug: String,
+        slug_or_id: &str,
+        viewer_id: Option<Uuid>,
     ) -> Result<FetchArticleBySlugOutput, AppError>;
 
+    // NOTE: `Inserted` is false when the idempotency key replayed an article created earlier.
     fn create_article(
         &self,
         params: CreateArticleRepositoryInput,
-    ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError>;
+    ) -> Result<(FetchArticleBySlugOutput, Inserted), AppError>;
 
     fn delete_article(&self, input: DeleteArticleRepositoryInput) -> Result<(), AppError>;
 
+    fn restore(
+        &self,
+        input: RestoreArticleRepositoryInput,
+    ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError>;
+
+    fn set_archived(
+        &self,
+        input: ArchiveArticleRepositoryInput,
+    ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError>;
+
     fn update_article(
         &self,
         input: UpdateArticleRepositoryInput,
@@ -39,16 +67,204 @@ pub trait ArticleRepository: Send + Sync + 'static {
     fn fetch_following_articles(
         &self,
         params: &FetchFollowingArticlesRepositoryInput,
-    ) -> Result<(ArticlesList, ArticlesCount), AppError>;
+    ) -> Result<ArticlesPage, AppError>;
+
+    fn tags_for_articles(&self, article_ids: &[Uuid]) -> Result<TagNamesByArticle, AppError>;
+
+    // NOTE: returns the new count.
+    fn record_view(&self, article_id: &Uuid) -> Result<i32, AppError>;
+
+    // NOTE: listed articles sharing at least one tag with the given one, most shared tags first.
+    fn fetch_related_articles(
+        &self,
+        params: &FetchRelatedArticlesRepositoryInput,
+    ) -> Result<ArticlesList, AppError>;
 }
 #[derive(Clone)]
 pub struct ArticleRepositoryImpl {
-    pool: DbPool,
+    pools: DbPools,
 }
 
 impl ArticleRepositoryImpl {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
+    }
+
+    fn articles_query<'a>(
+        params: &'a FetchArticlesRepositoryInput,
+        favoriter_id: Option<&'a Uuid>,
+    ) -> ArticlesQuery<'a> {
+        use diesel::prelude::*;
+
+        let mut query = articles::table
+            .inner_join(users::table)
+            .filter(Article::not_deleted())
+            .into_boxed();
+
+        query = match &params.drafts_of {
+            Some(author_id) => query
+                .filter(articles::published.eq(false))
+                .filter(articles::author_id.eq(author_id)),
+            None => query.filter(Article::listed()),
+        };
+
+        if let Some(since) = &params.since {
+            query = query.filter(articles::created_at.gt(since));
+        }
+
+        if let Some(tag_name) = &params.tag {
+            query = query.filter(articles::id.eq_any(Tag::article_ids_by_name(tag_name)));
+        }
+
+        if let Some(author_name) = &params.author {
+            query = query.filter(User::with_username(author_name));
+        }
+
+        if let Some(user_id) = favoriter_id {
+            query = query.filter(articles::id.eq_any(Favorite::article_ids_by_user_id(user_id)));
+        }
+
+        if let Some(search) = &params.query {
+            let pattern = converter::to_like_pattern(search);
+            let matches_document = sql::<Bool>(&format!(
+                "{} @@ plainto_tsquery('english', ",
+                SEARCH_DOCUMENT
+            ))
+            .bind::<Text, _>(search)
+            .sql(")");
+            query = query.filter(
+                matches_document
+                    .or(articles::title.ilike(pattern.clone()))
+                    .or(articles::description.ilike(pattern.clone()))
+                    .or(articles::body.ilike(pattern)),
+            );
+        }
+
+        query
+    }
+
+    // NOTE: the id comes last so articles sharing a timestamp still page deterministically.
+    fn order_by(query: ArticlesQuery<'_>, order: ArticleOrder) -> ArticlesQuery<'_> {
+        use diesel::prelude::*;
+
+        match order {
+            ArticleOrder::Recent => query
+                .then_order_by(articles::created_at.desc())
+                .then_order_by(articles::id.desc()),
+            ArticleOrder::Popular => query
+                .then_order_by(articles::favorites_count.desc())
+                .then_order_by(articles::created_at.desc())
+                .then_order_by(articles::id.desc()),
+            ArticleOrder::Oldest => query
+                .then_order_by(articles::created_at.asc())
+                .then_order_by(articles::id.asc()),
+        }
+    }
+
+    // NOTE: rows strictly past `cursor` in `order`; `popular` has no `(created_at, id)` key and is rejected upstream.
+    fn after<'a>(
+        query: ArticlesQuery<'a>,
+        cursor: &'a Cursor,
+        order: ArticleOrder,
+    ) -> ArticlesQuery<'a> {
+        use diesel::prelude::*;
+
+        let same_instant = articles::created_at.eq(&cursor.created_at);
+        match order {
+            ArticleOrder::Oldest => query.filter(
+                articles::created_at
+                    .gt(&cursor.created_at)
+                    .or(same_instant.and(articles::id.gt(&cursor.id))),
+            ),
+            ArticleOrder::Recent | ArticleOrder::Popular => query.filter(
+                articles::created_at
+                    .lt(&cursor.created_at)
+                    .or(same_instant.and(articles::id.lt(&cursor.id))),
+            ),
+        }
+    }
+
+    // NOTE: substring-only (ILIKE) matches rank 0 and fall back to the requested order.
+    fn search_rank(search: &str) -> SearchRank<'_> {
+        sql::<Float>(&format!(
+            "ts_rank({}, plainto_tsquery('english', ",
+            SEARCH_DOCUMENT
+        ))
+        .bind::<Text, _>(search)
+        .sql("))")
+    }
+
+    fn feed_query<'a>(
+        follower_id: &'a Uuid,
+        since: Option<&'a NaiveDateTime>,
+    ) -> ArticlesQuery<'a> {
+        use crate::app::features::follow::entities::Follow;
+        use diesel::prelude::*;
+
+        let query = articles::table
+            .inner_join(users::table)
+            .filter(articles::author_id.eq_any(Follow::followee_ids_by_follower_id(follower_id)))
+            .filter(Article::not_deleted())
+            .filter(Article::listed())
+            .into_boxed();
+        match since {
+            Some(since) => query.filter(articles::created_at.gt(since)),
+            None => query,
+        }
+    }
+
+    fn related_query<'a>(
+        params: &'a FetchRelatedArticlesRepositoryInput,
+        tag_names: &'a [String],
+    ) -> ArticlesQuery<'a> {
+        use crate::schema::tags;
+        use diesel::prelude::*;
+
+        let sharing_ids = tags::table
+            .filter(tags::name.eq_any(tag_names))
+            .select(tags::article_id);
+        let query = articles::table
+            .inner_join(users::table)
+            .filter(articles::id.eq_any(sharing_ids))
+            .filter(articles::id.ne(&params.article_id))
+            .filter(Article::not_deleted())
+            .filter(Article::listed())
+            .into_boxed();
+        let query = match &params.exclude_author_id {
+            Some(author_id) => query.filter(articles::author_id.ne(author_id)),
+            None => query,
+        };
+        // NOTE: newest first among equally related articles.
+        query
+            .order(Self::shared_tag_count(tag_names).desc())
+            .then_order_by(articles::created_at.desc())
+            .then_order_by(articles::id.desc())
+            .limit(params.limit)
+    }
+
+    // NOTE: distinct names, since nothing stops an article from carrying the same tag twice.
+    fn shared_tag_count(tag_names: &[String]) -> SharedTagCount<'_> {
+        sql::<BigInt>(
+            "(SELECT count(DISTINCT shared.name) FROM tags shared \
+             WHERE shared.article_id = articles.id AND shared.name = ANY(",
+        )
+        .bind::<Array<Text>, _>(tag_names)
+        .sql("))")
+    }
+
+    fn list_item((article, user): (Article, User)) -> (Article, Profile, FavoriteInfo) {
+        (
+            article,
+            Profile {
+                username: user.username,
+                bio: user.bio,
+                image: Some(gravatar::image_or_default(user.image, &user.email)),
+                following: false, // NOTE: resolved in one batch by the usecase
+            },
+            FavoriteInfo {
+                is_favorited: false, // NOTE: resolved in one batch by the usecase
+            },
+        )
     }
 
     fn create_tag_list(
@@ -58,181 +274,137 @@ impl ArticleRepositoryImpl {
     ) -> Result<Vec<Tag>, AppError> {
         let list = tag_name_list
             .as_ref()
-            .map(|tag_name_list| {
-                let records = tag_name_list
-                    .iter()
-                    .map(|name| CreateTag { name, article_id })
-                    .collect();
-                Tag::create_list(conn, records)
-            })
+            .map(|tag_name_list| Tag::create_list(conn, article_id, tag_name_list))
             .unwrap_or_else(|| Ok(vec![]));
         list
     }
 }
 
 impl ArticleRepository for ArticleRepositoryImpl {
+    #[tracing::instrument(level = "debug", skip_all, fields(tag = ?params.tag, author = ?params.author))]
     fn fetch_articles(
         &self,
         params: FetchArticlesRepositoryInput,
-    ) -> Result<(ArticlesList, ArticlesCount), AppError> {
-        use crate::app::features::favorite::entities::Favorite;
-        use crate::schema::{articles, tags, users};
+    ) -> Result<ArticlesPage, AppError> {
+        use crate::schema::articles;
         use diesel::prelude::*;
         // ====
-        let conn = &mut self.pool.get()?;
-
-        let query = {
-            let mut query = articles::table.inner_join(users::table).into_boxed();
-
-            if let Some(tag_name) = &params.tag {
-                let ids = Tag::fetch_article_ids_by_name(conn, tag_name)
-                    .expect("could not fetch tagged article ids."); // TODO: us e ? or error handling
-                query = query.filter(articles::id.eq_any(ids));
-            }
-
-            if let Some(author_name) = &params.author {
-                let ids = Article::fetch_ids_by_author_name(conn, author_name)
-                    .expect("could not fetch authors id."); // TODO: use ? or error handling
-                query = query.filter(articles::id.eq_any(ids));
-            }
-
-            if let Some(username) = &params.favorited {
-                let ids = Favorite::fetch_favorited_article_ids_by_username(conn, username)
-                    .expect("could not fetch favorited articles id."); // TODO: use ? or error handling
+        let conn = &mut self.pools.read_conn()?;
 
-                query = query.filter(articles::id.eq_any(ids));
-            }
-
-            query
+        let favoriter_id = match &params.favorited {
+            Some(username) => match User::find_id_by_username(conn, username)? {
+                Some(user_id) => Some(user_id),
+                // NOTE: an unknown user has no favorites, so nothing can match.
+                None => return Ok(Page::empty()),
+            },
+            None => None,
         };
-        let articles_count = query
+
+        let articles_count = Self::articles_query(&params, favoriter_id.as_ref())
             .select(diesel::dsl::count(articles::id))
             .first::<i64>(conn)?;
 
         let result = {
-            let query = {
-                let mut query = articles::table.inner_join(users::table).into_boxed();
-
-                if let Some(tag_name) = &params.tag {
-                    let ids = Tag::fetch_article_ids_by_name(conn, tag_name)
-                        .expect("could not fetch tagged article ids."); // TODO: use ? or error handling
-                    query = query.filter(articles::id.eq_any(ids));
-                }
-
-                if let Some(author_name) = &params.author {
-                    let ids = Article::fetch_ids_by_author_name(conn, author_name)
-                        .expect("could not fetch authors id."); // TODO: use ? or error handling
-                    query = query.filter(articles::id.eq_any(ids));
-                }
-
-                if let Some(username) = &params.favorited {
-                    let ids = Favorite::fetch_favorited_article_ids_by_username(conn, username)
-                        .expect("could not fetch favorited articles id."); // TODO: use ? or error handling
-
-                    query = query.filter(articles::id.eq_any(ids));
-                }
-
-                query
-            };
-            let article_and_user_list =
-                query
-                    .offset(params.offset)
-                    .limit(params.limit)
-                    .load::<(Article, User)>(conn)?;
-
-            let tags_list = {
-                let articles_list = article_and_user_list
-                    .clone()
-                    .into_iter()
-                    .map(|(article, _)| article)
-                    .collect::<Vec<_>>();
-                let tags_list = Tag::belonging_to(&articles_list)
-                    .order(tags::name.asc())
-                    .load::<Tag>(conn)?;
-                let tags_list: Vec<Vec<Tag>> = tags_list.grouped_by(&articles_list);
-                tags_list
-            };
-
-            let favorites_count_list = {
-                let list: Result<Vec<_>, _> = article_and_user_list
-                    .clone()
-                    .into_iter()
-                    .map(|(article, _)| article.fetch_favorites_count(conn))
-                    .collect();
-
-                list?
+            let mut query = Self::articles_query(&params, favoriter_id.as_ref());
+            if let Some(search) = &params.query {
+                query = query.order(Self::search_rank(search).desc());
+            }
+            // NOTE: a stable order keeps pages from overlapping.
+            let query = Self::order_by(query, params.order);
+            // NOTE: a keyset page starts right after its cursor, so the offset no longer applies.
+            let query = match &params.after {
+                Some(cursor) => Self::after(query, cursor, params.order),
+                None => query.offset(params.pagination.offset),
             };
+            let article_and_user_list = query
+                .limit(params.pagination.limit)
+                .load::<(Article, User)>(conn)?;
 
             article_and_user_list
                 .into_iter()
-                .zip(favorites_count_list)
-                .map(|((article, user), favorites_count)| {
-                    (
-                        article,
-                        Profile {
-                            username: user.username,
-                            bio: user.bio,
-                            image: user.image,
-                            following: false, // NOTE: because not authz
-                        },
-                        FavoriteInfo {
-                            is_favorited: false, // NOTE: because not authz
-                            favorites_count,
-                        },
-                    )
-                })
-                .zip(tags_list)
+                .map(Self::list_item)
                 .collect::<Vec<_>>()
         };
 
-        Ok((result, articles_count))
+        Ok(Page {
+            items: result,
+            total: articles_count,
+        })
     }
 
-    fn fetch_article_by_slug(
+    #[tracing::instrument(level = "debug", skip_all, fields(slug_or_id = %slug_or_id, viewer_id = ?viewer_id))]
+    fn find_by_slug_or_id(
         &self,
-        article_title_slug: String,
+        slug_or_id: &str,
+        viewer_id: Option<Uuid>,
     ) -> Result<FetchArticleBySlugOutput, AppError> {
-        let conn = &mut self.pool.get()?;
-
-        let (article, author) = Article::fetch_by_slug_with_author(conn, &article_title_slug)?;
-
-        let profile = author.fetch_profile(conn, &author.id)?;
-
-        let tags_list = {
-            use diesel::prelude::*;
-            Tag::belonging_to(&article).load::<Tag>(conn)?
+        let conn = &mut self.pools.read_conn()?;
+
+        let viewer = viewer_id.as_ref();
+        let found = match SlugOrId::parse(slug_or_id) {
+            // NOTE: a title can slugify to something UUID-shaped, so a miss by id retries it as a slug.
+            key @ SlugOrId::Id(_) => match Article::fetch_with_viewer_state(conn, key, viewer) {
+                Err(AppError::NotFound(_)) => {
+                    Article::fetch_with_viewer_state(conn, SlugOrId::Slug(slug_or_id), viewer)
+                }
+                found => found,
+            },
+            key => Article::fetch_with_viewer_state(conn, key, viewer),
         };
-
-        let favorite_info = {
-            let is_favorited = article.is_favorited_by_user_id(conn, &author.id)?;
-            let favorites_count = article.fetch_favorites_count(conn)?;
-            FavoriteInfo {
-                is_favorited,
-                favorites_count,
-            }
+        let (article, author, following, is_favorited) = found?;
+        let tags_list = Tag::fetch_by_article_id(conn, &article.id)?;
+
+        let profile = Profile {
+            username: author.username,
+            bio: author.bio,
+            image: Some(gravatar::image_or_default(author.image, &author.email)),
+            following,
         };
-
-        Ok((article, profile, favorite_info, tags_list))
+        Ok((article, profile, FavoriteInfo { is_favorited }, tags_list))
     }
 
+    #[tracing::instrument(level = "debug", skip_all, fields(user_id = %params.current_user.id))]
     fn create_article(
         &self,
         params: CreateArticleRepositoryInput,
-    ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError> {
-        let conn = &mut self.pool.get()?;
-
-        let article = Article::create(
-            conn,
-            &CreateArticle {
-                author_id: params.current_user.id,
-                slug: params.slug.clone(),
-                title: params.title.clone(),
-                description: params.description.clone(),
-                body: params.body.clone(),
-            },
-        )?;
-
-        let tag_list = Self::create_tag_list(conn, &params.tag_name_list, &article.id)?;
+    ) -> Result<(FetchArticleBySlugOutput, Inserted), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+
+        let (article, tag_list, inserted) = db::with_transaction(conn, |conn| {
+            let user_id = params.current_user.id;
+            if let Some(key) = &params.idempotency_key {
+                IdempotencyKey::purge_expired(conn, Utc::now().naive_utc())?;
+                if let Some(article_id) = IdempotencyKey::find_article_id(conn, &user_id, key)? {
+                    let (article, _author) = Article::find_with_author(conn, &article_id)?;
+                    let tag_list = Tag::fetch_by_article_id(conn, &article.id)?;
+                    return Ok((article, tag_list, false));
+                }
+            }
+            let slug = slug::generate(&params.title, conn)?;
+            let article = Article::create(
+                conn,
+                &CreateArticle {
+                    author_id: user_id,
+                    slug,
+                    title: params.title.clone(),
+                    description: params.description.clone(),
+                    body: params.body.clone(),
+                    published: params.published,
+                },
+            )?;
+            let tag_list = Self::create_tag_list(conn, &params.tag_name_list, &article.id)?;
+            if let Some(key) = &params.idempotency_key {
+                IdempotencyKey::create(
+                    conn,
+                    &CreateIdempotencyKey {
+                        user_id,
+                        key: key.clone(),
+                        article_id: article.id,
+                    },
+                )?;
+            }
+            Ok((article, tag_list, true))
+        })?;
 
         let profile = params
             .current_user
@@ -240,18 +412,15 @@ impl ArticleRepository for ArticleRepositoryImpl {
 
         let favorite_info = {
             let is_favorited = article.is_favorited_by_user_id(conn, &params.current_user.id)?;
-            let favorites_count = article.fetch_favorites_count(conn)?;
-            FavoriteInfo {
-                is_favorited,
-                favorites_count,
-            }
+            FavoriteInfo { is_favorited }
         };
 
-        Ok((article, profile, favorite_info, tag_list))
+        Ok(((article, profile, favorite_info, tag_list), inserted))
     }
 
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.slug, user_id = %input.author_id))]
     fn delete_article(&self, input: DeleteArticleRepositoryInput) -> Result<(), AppError> {
-        let conn = &mut self.pool.get()?;
+        let conn = &mut self.pools.write_conn()?;
         Article::delete(
             conn,
             &DeleteArticle {
@@ -261,21 +430,18 @@ impl ArticleRepository for ArticleRepositoryImpl {
         )
     }
 
-    fn update_article(
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.slug, user_id = %input.current_user.id))]
+    fn restore(
         &self,
-        input: UpdateArticleRepositoryInput,
+        input: RestoreArticleRepositoryInput,
     ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError> {
-        let conn = &mut self.pool.get()?;
+        let conn = &mut self.pools.write_conn()?;
 
-        let article = Article::update(
+        let article = Article::restore(
             conn,
-            &input.article_title_slug,
-            &input.current_user.id,
-            &UpdateArticle {
-                slug: input.slug.to_owned(),
-                title: input.title.to_owned(),
-                description: input.description.to_owned(),
-                body: input.body.to_owned(),
+            &RestoreArticle {
+                slug: input.slug,
+                author_id: input.current_user.id,
             },
         )?;
 
@@ -285,32 +451,91 @@ impl ArticleRepository for ArticleRepositoryImpl {
 
         let favorite_info = {
             let is_favorited = article.is_favorited_by_user_id(conn, &input.current_user.id)?;
-            let favorites_count = article.fetch_favorites_count(conn)?;
-            FavoriteInfo {
-                is_favorited,
-                favorites_count,
-            }
+            FavoriteInfo { is_favorited }
+        };
+
+        Ok((article, profile, favorite_info, tag_list))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(article_id = %input.article_id, user_id = %input.current_user.id))]
+    fn set_archived(
+        &self,
+        input: ArchiveArticleRepositoryInput,
+    ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+
+        let article = Article::set_archived(conn, &input.article_id, input.archived)?;
+
+        let tag_list = Tag::fetch_by_article_id(conn, &article.id)?;
+
+        let profile = input.current_user.fetch_profile(conn, &article.author_id)?;
+
+        let favorite_info = {
+            let is_favorited = article.is_favorited_by_user_id(conn, &input.current_user.id)?;
+            FavoriteInfo { is_favorited }
+        };
+
+        Ok((article, profile, favorite_info, tag_list))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.article_title_slug, user_id = %input.current_user.id))]
+    fn update_article(
+        &self,
+        input: UpdateArticleRepositoryInput,
+    ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+
+        let new_slug = input
+            .title
+            .as_deref()
+            .map(|title| slug::generate_except(title, conn, Some(&input.article_title_slug)))
+            .transpose()?;
+
+        let (article, tag_list) = db::with_transaction(conn, |conn| {
+            let article = Article::update(
+                conn,
+                &input.article_title_slug,
+                &input.current_user.id,
+                input.version,
+                &UpdateArticle {
+                    slug: new_slug,
+                    title: input.title.to_owned(),
+                    description: input.description.to_owned(),
+                    body: input.body.to_owned(),
+                    published: input.published,
+                },
+            )?;
+            // NOTE: no `tagList` keeps the tags as they are; an empty one removes them all.
+            let tag_list = match &input.tag_name_list {
+                Some(tag_name_list) => Tag::replace_list(conn, &article.id, tag_name_list)?,
+                None => Tag::fetch_by_article_id(conn, &article.id)?,
+            };
+            Ok((article, tag_list))
+        })?;
+
+        let profile = input.current_user.fetch_profile(conn, &article.author_id)?;
+
+        let favorite_info = {
+            let is_favorited = article.is_favorited_by_user_id(conn, &input.current_user.id)?;
+            FavoriteInfo { is_favorited }
         };
 
         Ok((article, profile, favorite_info, tag_list))
     }
 
+    #[tracing::instrument(level = "debug", skip_all, fields(article_id = %input.article_id))]
     fn fetch_article(
         &self,
         input: &FetchArticleRepositoryInput,
     ) -> Result<(Article, Profile, FavoriteInfo, Vec<Tag>), AppError> {
-        let conn = &mut self.pool.get()?;
+        let conn = &mut self.pools.read_conn()?;
         let (article, author) = Article::find_with_author(conn, &input.article_id)?;
 
         let profile = input.current_user.fetch_profile(conn, &author.id)?;
 
         let favorite_info = {
             let is_favorited = article.is_favorited_by_user_id(conn, &input.current_user.id)?;
-            let favorites_count = article.fetch_favorites_count(conn)?;
-            FavoriteInfo {
-                is_favorited,
-                favorites_count,
-            }
+            FavoriteInfo { is_favorited }
         };
 
         let tags_list = {
@@ -321,116 +546,83 @@ impl ArticleRepository for ArticleRepositoryImpl {
         Ok((article, profile, favorite_info, tags_list))
     }
 
+    #[tracing::instrument(level = "debug", skip_all, fields(user_id = %params.current_user.id))]
     fn fetch_following_articles(
         &self,
         params: &FetchFollowingArticlesRepositoryInput,
-    ) -> Result<(ArticlesList, ArticlesCount), AppError> {
-        use crate::app::features::follow::entities::Follow;
-        use crate::schema::articles::dsl::*;
-        use crate::schema::follows;
-        use crate::schema::{articles, users};
+    ) -> Result<ArticlesPage, AppError> {
         use diesel::prelude::*;
 
-        let conn = &mut self.pool.get()?;
-
-        let create_query = {
-            let ids = Follow::fetch_folowee_ids_by_follower_id(conn, &params.current_user.id)?;
-            articles.filter(articles::author_id.eq_any(ids))
-        };
+        let conn = &mut self.pools.read_conn()?;
 
         let articles_list = {
-            let article_and_user_list = create_query
-                .to_owned()
-                .inner_join(users::table)
-                .limit(params.limit)
-                .offset(params.offset)
-                .order(articles::created_at.desc())
-                .get_results::<(Article, User)>(conn)?;
-
-            let tags_list = {
-                let articles_list = article_and_user_list
-                    .clone() // TODO: avoid clone
-                    .into_iter()
-                    .map(|(article, _)| article)
-                    .collect::<Vec<_>>();
-
-                let tags_list = Tag::belonging_to(&articles_list).load::<Tag>(conn)?;
-                let tags_list: Vec<Vec<Tag>> = tags_list.grouped_by(&articles_list);
-                tags_list
-            };
-
-            let follows_list = {
-                let user_ids_list = article_and_user_list
-                    .clone() // TODO: avoid clone
-                    .into_iter()
-                    .map(|(_, user)| user.id)
-                    .collect::<Vec<_>>();
-
-                let list = follows::table
-                    .filter(Follow::with_follower(&params.current_user.id))
-                    .filter(follows::followee_id.eq_any(user_ids_list))
-                    .get_results::<Follow>(conn)?;
-
-                list.into_iter()
-            };
-
-            let favorites_count_list = {
-                let list: Result<Vec<_>, _> = article_and_user_list
-                    .clone()
-                    .into_iter()
-                    .map(|(article, _)| article.fetch_favorites_count(conn))
-                    .collect();
-
-                list?
-            };
-
-            let favorited_article_ids = params.current_user.fetch_favorited_article_ids(conn)?;
-            let is_favorited_by_me = |article: &Article| {
-                favorited_article_ids
-                    .iter()
-                    .copied()
-                    .any(|_id| _id == article.id)
-            };
+            let article_and_user_list =
+                Self::feed_query(&params.current_user.id, params.since.as_ref())
+                    .order((articles::created_at.desc(), articles::id.desc()))
+                    .limit(params.pagination.limit)
+                    .offset(params.pagination.offset)
+                    .get_results::<(Article, User)>(conn)?;
 
             article_and_user_list
                 .into_iter()
-                .zip(favorites_count_list)
-                .map(|((article, user), favorites_count)| {
-                    let following = follows_list.clone().any(|item| item.followee_id == user.id);
-                    let is_favorited = is_favorited_by_me(&article);
-                    (
-                        article,
-                        Profile {
-                            username: user.username,
-                            bio: user.bio,
-                            image: user.image,
-                            following: following.to_owned(),
-                        },
-                        FavoriteInfo {
-                            is_favorited,
-                            favorites_count,
-                        },
-                    )
-                })
-                .zip(tags_list)
+                .map(Self::list_item)
                 .collect::<Vec<_>>()
         };
 
-        let articles_count = create_query
+        let articles_count = Self::feed_query(&params.current_user.id, params.since.as_ref())
             .select(diesel::dsl::count(articles::id))
             .first::<i64>(conn)?;
 
-        Ok((articles_list, articles_count))
+        Ok(Page {
+            items: articles_list,
+            total: articles_count,
+        })
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(articles = article_ids.len()))]
+    fn tags_for_articles(&self, article_ids: &[Uuid]) -> Result<TagNamesByArticle, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        Tag::fetch_names_by_article_ids(conn, article_ids)
+    }
+
+    fn record_view(&self, article_id: &Uuid) -> Result<i32, AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        Article::record_view(conn, article_id)
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(article_id = %params.article_id))]
+    fn fetch_related_articles(
+        &self,
+        params: &FetchRelatedArticlesRepositoryInput,
+    ) -> Result<ArticlesList, AppError> {
+        use diesel::prelude::*;
+
+        let conn = &mut self.pools.read_conn()?;
+        let tag_names = Tag::fetch_by_article_id(conn, &params.article_id)?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect::<Vec<_>>();
+        if tag_names.is_empty() {
+            return Ok(vec![]);
+        }
+        let article_and_user_list =
+            Self::related_query(params, &tag_names).load::<(Article, User)>(conn)?;
+        Ok(article_and_user_list
+            .into_iter()
+            .map(Self::list_item)
+            .collect())
     }
 }
 
 pub struct CreateArticleRepositoryInput {
-    pub slug: String,
     pub title: String,
     pub description: String,
     pub body: String,
     pub tag_name_list: Option<Vec<String>>,
+    pub published: bool,
     pub current_user: User,
+    // NOTE: a retry carrying the same key gets the article the first request created.
+    pub idempotency_key: Option<String>,
 }
 
 pub struct DeleteArticleRepositoryInput {
@@ -438,23 +630,71 @@ pub struct DeleteArticleRepositoryInput {
     pub author_id: Uuid,
 }
 
+pub struct RestoreArticleRepositoryInput {
+    pub slug: String,
+    pub current_user: User,
+}
+
+pub struct ArchiveArticleRepositoryInput {
+    pub article_id: Uuid,
+    pub archived: bool,
+    pub current_user: User,
+}
+
 pub struct UpdateArticleRepositoryInput {
     pub current_user: User,
     pub article_title_slug: String,
-    pub slug: Option<String>,
     pub title: Option<String>,
     pub description: Option<String>,
     pub body: Option<String>,
+    pub published: Option<bool>,
+    pub tag_name_list: Option<Vec<String>>,
+    // NOTE: the version the client read; the update is rejected if it has moved on.
+    pub version: i32,
 }
 
 pub type FetchArticleBySlugOutput = (Article, Profile, FavoriteInfo, Vec<Tag>);
+pub type Inserted = bool;
 
 pub struct FetchArticlesRepositoryInput {
+    pub query: Option<String>,
     pub tag: Option<String>,
     pub author: Option<String>,
     pub favorited: Option<String>,
-    pub offset: i64,
-    pub limit: i64,
+    // NOTE: `Some(author_id)` lists that author's drafts instead of published articles.
+    pub drafts_of: Option<Uuid>,
+    // NOTE: only articles created strictly after this instant (UTC).
+    pub since: Option<NaiveDateTime>,
+    pub order: ArticleOrder,
+    pub pagination: Pagination,
+    // NOTE: keyset pagination; replaces `pagination.offset` and leaves the total count unchanged.
+    pub after: Option<Cursor>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArticleOrder {
+    // NOTE: newest first.
+    #[default]
+    Recent,
+    // NOTE: most favorited first, newest first among equals.
+    Popular,
+    Oldest,
+}
+
+impl ArticleOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "recent" => Some(Self::Recent),
+            "popular" => Some(Self::Popular),
+            "oldest" => Some(Self::Oldest),
+            _ => None,
+        }
+    }
+
+    // NOTE: keyset pages follow `(created_at, id)`, which neither `popular` nor search ranking order by.
+    pub fn pages_by_cursor(self, searching: bool) -> bool {
+        self != Self::Popular && !searching
+    }
 }
 
 pub struct FetchArticleRepositoryInput {
@@ -464,10 +704,221 @@ pub struct FetchArticleRepositoryInput {
 
 pub struct FetchFollowingArticlesRepositoryInput {
     pub current_user: User,
-    pub offset: i64,
+    // NOTE: only articles created strictly after this instant (UTC).
+    pub since: Option<NaiveDateTime>,
+    pub pagination: Pagination,
+}
+
+pub struct FetchRelatedArticlesRepositoryInput {
+    pub article_id: Uuid,
+    // NOTE: `Some(author_id)` leaves out that author's articles, typically the given one's author.
+    pub exclude_author_id: Option<Uuid>,
     pub limit: i64,
 }
 
-type ArticlesCount = i64;
-type ArticlesListInner = (Article, Profile, FavoriteInfo);
-pub type ArticlesList = Vec<(ArticlesListInner, Vec<Tag>)>;
+// NOTE: must match the expression of `articles_search_idx` so the GIN index is used.
+const SEARCH_DOCUMENT: &str =
+    "to_tsvector('english', articles.title || ' ' || articles.description || ' ' || articles.body)";
+
+type SearchRank<'a> = SqlLiteral<Float, UncheckedBind<SqlLiteral<Float>, AsExprOf<&'a str, Text>>>;
+type SharedTagCount<'a> =
+    SqlLiteral<BigInt, UncheckedBind<SqlLiteral<BigInt>, AsExprOf<&'a [String], Array<Text>>>>;
+type ArticlesQuery<'a> = IntoBoxed<'a, InnerJoin<articles::table, users::table>, Pg>;
+// NOTE: tags are not included; the usecase loads them for the whole page via `tags_for_articles`.
+pub type ArticlesList = Vec<(Article, Profile, FavoriteInfo)>;
+pub type ArticlesPage = Page<(Article, Profile, FavoriteInfo)>;
+pub type TagNamesByArticle = HashMap<Uuid, Vec<String>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+
+    fn params(tag: Option<&str>, author: Option<&str>) -> FetchArticlesRepositoryInput {
+        FetchArticlesRepositoryInput {
+            query: None,
+            tag: tag.map(str::to_owned),
+            author: author.map(str::to_owned),
+            favorited: None,
+            drafts_of: None,
+            since: None,
+            order: ArticleOrder::default(),
+            pagination: Pagination::default(),
+            after: None,
+        }
+    }
+
+    fn to_sql(params: &FetchArticlesRepositoryInput, favoriter_id: Option<&Uuid>) -> String {
+        let query = ArticleRepositoryImpl::articles_query(params, favoriter_id);
+        debug_query::<Pg, _>(&query).to_string()
+    }
+
+    #[test]
+    fn articles_query_filters_by_tag() {
+        let sql = to_sql(&params(Some("dragons"), None), None);
+        assert!(sql.contains(
+            r#"= ANY(SELECT "tags"."article_id" FROM "tags" WHERE ("tags"."name" = $3))"#
+        ));
+        assert!(sql.contains(r#"binds: [false, false, "dragons"]"#));
+    }
+
+    #[test]
+    fn articles_query_hides_deleted_articles() {
+        let sql = to_sql(&params(None, None), None);
+        assert!(sql.contains(r#"WHERE (("articles"."deleted_at" IS NULL) AND"#));
+    }
+
+    #[test]
+    fn articles_query_hides_drafts_archived_and_hidden_articles() {
+        let sql = to_sql(&params(None, None), None);
+        assert!(sql.contains(
+            r#"AND (("articles"."published" AND ("articles"."archived" = $1)) AND ("articles"."hidden" = $2))"#
+        ));
+        assert!(sql.contains("binds: [false, false]"));
+    }
+
+    #[test]
+    fn articles_query_lists_own_drafts() {
+        let author_id = Uuid::nil();
+        let params = FetchArticlesRepositoryInput {
+            drafts_of: Some(author_id),
+            ..params(None, None)
+        };
+        let sql = to_sql(&params, None);
+        assert!(sql.contains(r#"("articles"."published" = $1)) AND ("articles"."author_id" = $2)"#));
+        assert!(sql.contains("binds: [false, 00000000-0000-0000-0000-000000000000]"));
+    }
+
+    #[test]
+    fn articles_query_without_tag_skips_tags() {
+        let sql = to_sql(&params(None, None), None);
+        assert!(!sql.contains("tags"));
+    }
+
+    #[test]
+    fn articles_query_filters_by_author() {
+        let sql = to_sql(&params(None, Some("jake")), None);
+        assert!(sql.contains(r#"AND (lower("users"."username") = lower($3))"#));
+        assert!(sql.contains(r#"binds: [false, false, "jake"]"#));
+    }
+
+    #[test]
+    fn articles_query_combines_author_and_tag() {
+        let sql = to_sql(&params(Some("dragons"), Some("jake")), None);
+        assert!(sql.contains(r#"("tags"."name" = $3)"#));
+        assert!(sql.contains(r#"lower("users"."username") = lower($4)"#));
+        assert!(sql.contains(r#"binds: [false, false, "dragons", "jake"]"#));
+    }
+
+    #[test]
+    fn articles_query_filters_by_favorited() {
+        let user_id = Uuid::nil();
+        let sql = to_sql(&params(None, None), Some(&user_id));
+        assert!(sql.contains(
+            r#"= ANY(SELECT "favorites"."article_id" FROM "favorites" WHERE ("favorites"."user_id" = $3))"#
+        ));
+    }
+
+    #[test]
+    fn articles_query_combines_all_filters() {
+        let user_id = Uuid::nil();
+        let sql = to_sql(&params(Some("dragons"), Some("jake")), Some(&user_id));
+        assert!(sql.contains(
+            r#"binds: [false, false, "dragons", "jake", 00000000-0000-0000-0000-000000000000]"#
+        ));
+    }
+
+    fn order_sql(order: ArticleOrder) -> String {
+        let params = params(None, None);
+        let query = ArticleRepositoryImpl::order_by(
+            ArticleRepositoryImpl::articles_query(&params, None),
+            order,
+        );
+        debug_query::<Pg, _>(&query).to_string()
+    }
+
+    #[test]
+    fn articles_query_orders_recent_first_by_default() {
+        assert!(order_sql(ArticleOrder::default())
+            .contains(r#"ORDER BY "articles"."created_at" DESC, "articles"."id" DESC"#));
+    }
+
+    #[test]
+    fn articles_query_orders_popular_by_favorites_count() {
+        assert!(order_sql(ArticleOrder::Popular).contains(
+            r#"ORDER BY "articles"."favorites_count" DESC, "articles"."created_at" DESC, "articles"."id" DESC"#
+        ));
+    }
+
+    #[test]
+    fn articles_query_orders_oldest_first() {
+        assert!(order_sql(ArticleOrder::Oldest)
+            .contains(r#"ORDER BY "articles"."created_at" ASC, "articles"."id" ASC"#));
+    }
+
+    #[test]
+    fn article_order_parses_known_values_only() {
+        assert_eq!(Some(ArticleOrder::Popular), ArticleOrder::parse("popular"));
+        assert_eq!(Some(ArticleOrder::Recent), ArticleOrder::parse("recent"));
+        assert_eq!(Some(ArticleOrder::Oldest), ArticleOrder::parse("oldest"));
+        assert_eq!(None, ArticleOrder::parse("Popular"));
+        assert_eq!(None, ArticleOrder::parse("random"));
+    }
+
+    #[test]
+    fn articles_query_filters_by_since() {
+        let params = FetchArticlesRepositoryInput {
+            since: Some(NaiveDateTime::default()),
+            ..params(Some("dragons"), None)
+        };
+        let sql = to_sql(&params, None);
+        assert!(sql.contains(r#"AND ("articles"."created_at" > $3)) AND"#));
+        assert!(sql.contains(r#"binds: [false, false, 1970-01-01T00:00:00, "dragons"]"#));
+    }
+
+    #[test]
+    fn feed_query_filters_by_since() {
+        let (follower_id, since) = (Uuid::nil(), NaiveDateTime::default());
+        let query = ArticleRepositoryImpl::feed_query(&follower_id, Some(&since));
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#"AND ("articles"."created_at" > $4)"#));
+    }
+
+    #[test]
+    fn feed_query_filters_by_followees() {
+        let follower_id = Uuid::nil();
+        let query = ArticleRepositoryImpl::feed_query(&follower_id, None);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"WHERE ((("articles"."author_id" = ANY(SELECT "follows"."followee_id" FROM "follows" WHERE ("follows"."follower_id" = $1))) AND ("articles"."deleted_at" IS NULL)) AND (("articles"."published" AND ("articles"."archived" = $2)) AND ("articles"."hidden" = $3)))"#
+        ));
+    }
+
+    #[test]
+    fn articles_query_searches_documents() {
+        let params = FetchArticlesRepositoryInput {
+            query: Some("rust web".to_owned()),
+            ..params(None, None)
+        };
+        let sql = to_sql(&params, None);
+        assert!(sql.contains(&format!(
+            "{} @@ plainto_tsquery('english', $3)",
+            SEARCH_DOCUMENT
+        )));
+        assert!(sql.contains(r#""articles"."title" ILIKE $4"#));
+        assert!(sql.contains(r#""articles"."body" ILIKE $6"#));
+        assert!(sql.contains(
+            r#"binds: [false, false, "rust web", "%rust web%", "%rust web%", "%rust web%"]"#
+        ));
+    }
+
+    #[test]
+    fn articles_query_escapes_search_wildcards() {
+        let params = FetchArticlesRepositoryInput {
+            query: Some("100%_off".to_owned()),
+            ..params(None, None)
+        };
+        let sql = to_sql(&params, None);
+        assert!(sql.contains(r#""%100\\%\\_off%""#));
+    }
+}
@@ -1,27 +1,41 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
 pub struct CreateArticleRequest {
+    #[validate(nested)]
     pub article: CreateArticleInner,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateArticleInner {
+    #[validate(length(min = 1, message = "can't be blank"))]
     pub title: String,
+    #[validate(length(min = 1, message = "can't be blank"))]
     pub description: String,
+    #[validate(length(min = 1, message = "can't be blank"))]
     pub body: String,
     pub tag_list: Option<Vec<String>>,
+    pub published: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
 pub struct UpdateArticleRequest {
+    #[validate(nested)]
     pub article: UpdateArticleInner,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateArticleInner {
     pub title: Option<String>,
     pub description: Option<String>,
     pub body: Option<String>,
+    pub published: Option<bool>,
+    pub tag_list: Option<Vec<String>>,
+    // NOTE: the `version` of the article as the client last read it.
+    #[validate(required(message = "can't be blank"))]
+    pub version: Option<i32>,
 }
@@ -1,13 +1,29 @@
 use super::entities::Article;
-use super::presenters::ArticlePresenter;
+use super::presenters::{ArticleFormat, ArticlePresenter};
 use super::repositories::{
-    ArticleRepository, CreateArticleRepositoryInput, DeleteArticleRepositoryInput,
-    FetchArticlesRepositoryInput, FetchFollowingArticlesRepositoryInput,
-    UpdateArticleRepositoryInput,
+    ArchiveArticleRepositoryInput, ArticleOrder, ArticleRepository, ArticlesList,
+    CreateArticleRepositoryInput, DeleteArticleRepositoryInput, FetchArticlesRepositoryInput,
+    FetchFollowingArticlesRepositoryInput, FetchRelatedArticlesRepositoryInput,
+    RestoreArticleRepositoryInput, TagNamesByArticle, UpdateArticleRepositoryInput,
 };
+use super::views::{ViewTracker, Viewer};
+use crate::app::features::activity::feed::{Activity, ActivityFeed};
+use crate::app::features::favorite::entities::FavoriteInfo;
+use crate::app::features::favorite::repositories::FavoriteRepository;
+use crate::app::features::profile::entities::Profile;
+use crate::app::features::profile::repositories::ProfileRepository;
+use crate::app::features::tag::entities::Tag;
 use crate::app::features::user::entities::User;
+use crate::app::features::webhook::dispatcher::{ArticlePublished, WebhookDispatcher};
+use crate::config::ArticleConfig;
 use crate::error::AppError;
+use crate::utils::article_limits::ArticleLimits;
+use crate::utils::pagination::{Cursor, Page, Pagination};
+use crate::utils::{api, db};
+use actix_web::http::header::{HeaderValue, VARY};
 use actix_web::HttpResponse;
+use chrono::NaiveDateTime;
+use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -15,118 +31,395 @@ use uuid::Uuid;
 pub struct ArticleUsecase {
     article_repository: Arc<dyn ArticleRepository>,
     article_presenter: Arc<dyn ArticlePresenter>,
+    favorite_repository: Arc<dyn FavoriteRepository>,
+    profile_repository: Arc<dyn ProfileRepository>,
+    webhook_dispatcher: Arc<dyn WebhookDispatcher>,
+    activity_feed: ActivityFeed,
+    view_tracker: ViewTracker,
+    limits: ArticleLimits,
+    require_verified_email: bool,
 }
 
 impl ArticleUsecase {
     pub fn new(
         article_repository: Arc<dyn ArticleRepository>,
         article_presenter: Arc<dyn ArticlePresenter>,
+        favorite_repository: Arc<dyn FavoriteRepository>,
+        profile_repository: Arc<dyn ProfileRepository>,
+        webhook_dispatcher: Arc<dyn WebhookDispatcher>,
+        activity_feed: ActivityFeed,
+        config: ArticleConfig,
     ) -> Self {
         Self {
             article_repository,
             article_presenter,
+            favorite_repository,
+            profile_repository,
+            webhook_dispatcher,
+            activity_feed,
+            view_tracker: ViewTracker::new(config.view_window),
+            limits: config.limits,
+            require_verified_email: config.require_verified_email,
         }
     }
 
-    pub fn fetch_articles(
+    async fn favorited_article_ids(
+        &self,
+        viewer_id: Option<Uuid>,
+        list: &ArticlesList,
+    ) -> Result<HashSet<Uuid>, AppError> {
+        match viewer_id {
+            Some(viewer_id) => {
+                let article_ids = Self::article_ids(list);
+                let favorite_repository = self.favorite_repository.clone();
+                db::blocking(move || {
+                    favorite_repository.favorited_article_ids(&viewer_id, &article_ids)
+                })
+                .await
+            }
+            None => Ok(HashSet::new()),
+        }
+    }
+
+    // NOTE: one query for the whole page instead of one per author.
+    async fn followed_author_ids(
+        &self,
+        viewer_id: Option<Uuid>,
+        list: &ArticlesList,
+    ) -> Result<HashSet<Uuid>, AppError> {
+        match viewer_id {
+            Some(viewer_id) => {
+                let mut author_ids = list
+                    .iter()
+                    .map(|(article, _, _)| article.author_id)
+                    .collect::<Vec<_>>();
+                author_ids.sort();
+                author_ids.dedup();
+                let profile_repository = self.profile_repository.clone();
+                db::blocking(move || profile_repository.following_map(&viewer_id, &author_ids))
+                    .await
+            }
+            None => Ok(HashSet::new()),
+        }
+    }
+
+    // NOTE: one query for the whole page instead of one per article.
+    async fn tags_for_articles(&self, list: &ArticlesList) -> Result<TagNamesByArticle, AppError> {
+        let article_ids = Self::article_ids(list);
+        let article_repository = self.article_repository.clone();
+        db::blocking(move || article_repository.tags_for_articles(&article_ids)).await
+    }
+
+    fn article_ids(list: &ArticlesList) -> Vec<Uuid> {
+        list.iter().map(|(article, _, _)| article.id).collect()
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(tag = ?params.tag, author = ?params.author, viewer_id = ?params.viewer_id))]
+    pub async fn fetch_articles(
         &self,
         params: FetchArticlesUsecaseInput,
     ) -> Result<HttpResponse, AppError> {
-        let (list, count) =
-            self.article_repository
-                .fetch_articles(FetchArticlesRepositoryInput {
-                    tag: params.tag.clone(),
-                    author: params.author.clone(),
-                    favorited: params.favorited.clone(),
-                    offset: params.offset,
-                    limit: params.limit,
-                })?;
-        let res = self.article_presenter.to_multi_json(list, count);
+        let article_repository = self.article_repository.clone();
+        let (page_url, pagination) = (params.page_url.clone(), params.pagination);
+        let input = FetchArticlesRepositoryInput {
+            query: params.query.clone(),
+            tag: params.tag.as_deref().map(Tag::normalize_name),
+            author: params.author.clone(),
+            favorited: params.favorited.clone(),
+            drafts_of: params.drafts_of,
+            since: params.since,
+            order: params.order,
+            pagination,
+            after: params.after,
+        };
+        let page = db::blocking(move || article_repository.fetch_articles(input)).await?;
+        // NOTE: a short page is the last one, so there is nothing to continue from; neither is
+        // there for orders `after` is refused with.
+        let next_cursor = (params.order.pages_by_cursor(params.query.is_some())
+            && pagination.limit > 0
+            && page.items.len() as i64 == pagination.limit)
+            .then(|| page.items.last())
+            .flatten()
+            .map(|(article, _, _)| Cursor {
+                created_at: article.created_at,
+                id: article.id,
+            });
+        let favorited = self
+            .favorited_article_ids(params.viewer_id, &page.items)
+            .await?;
+        let followed = self
+            .followed_author_ids(params.viewer_id, &page.items)
+            .await?;
+        let tag_names = self.tags_for_articles(&page.items).await?;
+        let total = page.total;
+        let mut res = self.article_presenter.to_multi_json(
+            page,
+            &favorited,
+            &followed,
+            &tag_names,
+            next_cursor,
+        );
+        // NOTE: offset-based links would point back to the first pages; keyset clients follow `nextCursor`.
+        if params.after.is_none() {
+            api::set_link_header(
+                &mut res,
+                &page_url,
+                pagination.limit,
+                pagination.offset,
+                total,
+            );
+        }
         Ok(res)
     }
 
-    pub fn fetch_article_by_slug(
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %article_title_slug, viewer_id = ?viewer_id))]
+    pub async fn fetch_article_by_slug(
         &self,
         article_title_slug: String,
+        viewer_id: Option<Uuid>,
+        viewer: Option<Viewer>,
+        format: ArticleFormat,
+        if_none_match: Option<String>,
     ) -> Result<HttpResponse, AppError> {
-        let result = self
-            .article_repository
-            .fetch_article_by_slug(article_title_slug)?;
-        let res = self.article_presenter.to_single_json(result);
-        Ok(res)
+        let article_repository = self.article_repository.clone();
+        let view_tracker = self.view_tracker.clone();
+        let result = db::blocking(move || {
+            let mut result =
+                article_repository.find_by_slug_or_id(&article_title_slug, viewer_id)?;
+            let article = &mut result.0;
+            if viewer.is_some_and(|viewer| view_tracker.record(article.id, viewer)) {
+                article.view_count = article_repository.record_view(&article.id)?;
+            }
+            Ok(result)
+        })
+        .await?;
+        let (article, profile, favorite_info, _) = &result;
+        // NOTE: besides id + updated_at, the counter and the viewer's own flags change the body too.
+        // Views are left out on purpose, or every view by someone else would defeat revalidation.
+        let etag = api::weak_etag(&(
+            article.id,
+            article.updated_at,
+            article.favorites_count,
+            favorite_info.is_favorited,
+            profile.following,
+            format,
+        ));
+        let mut res = match format {
+            ArticleFormat::Json => self.article_presenter.to_single_json(result),
+            ArticleFormat::Html => self.article_presenter.to_html(result.0),
+        };
+        // NOTE: the representation follows `Accept` when `?format` is absent, so caches must key on it.
+        res.headers_mut()
+            .insert(VARY, HeaderValue::from_static("Accept"));
+        Ok(api::conditional(if_none_match.as_deref(), &etag, res))
     }
 
-    pub fn fetch_following_articles(
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %article_title_slug, viewer_id = ?viewer_id))]
+    pub async fn fetch_related_articles(
         &self,
-        user: User,
-        offset: i64,
+        article_title_slug: String,
+        viewer_id: Option<Uuid>,
+        exclude_author: bool,
         limit: i64,
     ) -> Result<HttpResponse, AppError> {
-        let (list, count) = self.article_repository.fetch_following_articles(
-            &FetchFollowingArticlesRepositoryInput {
-                current_user: user,
-                offset,
+        let article_repository = self.article_repository.clone();
+        let items = db::blocking(move || {
+            // NOTE: a draft has related articles only for its author, who is the only one to see it.
+            let (article, _, _, _) =
+                article_repository.find_by_slug_or_id(&article_title_slug, viewer_id)?;
+            article_repository.fetch_related_articles(&FetchRelatedArticlesRepositoryInput {
+                article_id: article.id,
+                exclude_author_id: exclude_author.then_some(article.author_id),
                 limit,
-            },
-        )?;
-        let res = self.article_presenter.to_multi_json(list, count);
+            })
+        })
+        .await?;
+        let favorited = self.favorited_article_ids(viewer_id, &items).await?;
+        let followed = self.followed_author_ids(viewer_id, &items).await?;
+        let tag_names = self.tags_for_articles(&items).await?;
+        let total = items.len() as i64;
+        Ok(self.article_presenter.to_multi_json(
+            Page { items, total },
+            &favorited,
+            &followed,
+            &tag_names,
+            None,
+        ))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(user_id = %user.id))]
+    pub async fn fetch_following_articles(
+        &self,
+        user: User,
+        since: Option<NaiveDateTime>,
+        pagination: Pagination,
+        page_url: String,
+    ) -> Result<HttpResponse, AppError> {
+        let viewer_id = user.id;
+        let article_repository = self.article_repository.clone();
+        let page = db::blocking(move || {
+            article_repository.fetch_following_articles(&FetchFollowingArticlesRepositoryInput {
+                current_user: user,
+                since,
+                pagination,
+            })
+        })
+        .await?;
+        let favorited = self
+            .favorited_article_ids(Some(viewer_id), &page.items)
+            .await?;
+        let followed = self
+            .followed_author_ids(Some(viewer_id), &page.items)
+            .await?;
+        let tag_names = self.tags_for_articles(&page.items).await?;
+        let total = page.total;
+        let mut res = self
+            .article_presenter
+            .to_multi_json(page, &favorited, &followed, &tag_names, None);
+        api::set_link_header(
+            &mut res,
+            &page_url,
+            pagination.limit,
+            pagination.offset,
+            total,
+        );
         Ok(res)
     }
 
-    pub fn create_article(
+    #[tracing::instrument(level = "debug", skip_all, fields(user_id = %params.current_user.id))]
+    pub async fn create_article(
         &self,
         params: CreateArticleUsecaseInput,
     ) -> Result<HttpResponse, AppError> {
-        let slug = Article::convert_title_to_slug(&params.title);
-        let result = self
-            .article_repository
-            .create_article(CreateArticleRepositoryInput {
-                body: params.body,
-                current_user: params.current_user,
-                description: params.description,
-                tag_name_list: params.tag_name_list,
-                title: params.title,
-                slug,
-            })?;
+        if self.require_verified_email && !params.current_user.email_verified {
+            return Err(AppError::Forbidden(
+                "Verify your email before publishing articles.".into(),
+            ));
+        }
+        self.limits.check(Some(&params.title), Some(&params.body))?;
+        let article_repository = self.article_repository.clone();
+        let input = CreateArticleRepositoryInput {
+            body: params.body,
+            current_user: params.current_user,
+            description: params.description,
+            tag_name_list: params.tag_name_list,
+            title: params.title,
+            published: params.published.unwrap_or(true),
+            idempotency_key: params.idempotency_key,
+        };
+        let (result, inserted) =
+            db::blocking(move || article_repository.create_article(input)).await?;
+        // NOTE: a replayed idempotency key was announced when the article was first created.
+        if inserted && result.0.published {
+            self.announce(&result);
+        }
         let res = self.article_presenter.to_single_json(result);
         Ok(res)
     }
 
-    pub fn delete_article(
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.slug, user_id = %input.author_id))]
+    pub async fn delete_article(
         &self,
         input: DeleteArticleUsecaseInput,
     ) -> Result<HttpResponse, AppError> {
-        self.article_repository
-            .delete_article(DeleteArticleRepositoryInput {
-                slug: input.slug,
-                author_id: input.author_id,
-            })?;
+        let article_repository = self.article_repository.clone();
+        let input = DeleteArticleRepositoryInput {
+            slug: input.slug,
+            author_id: input.author_id,
+        };
+        db::blocking(move || article_repository.delete_article(input)).await?;
         let res = self.article_presenter.to_http_res();
         Ok(res)
     }
 
-    pub fn update_article(
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.slug, user_id = %input.current_user.id))]
+    pub async fn restore_article(
+        &self,
+        input: RestoreArticleUsecaseInput,
+    ) -> Result<HttpResponse, AppError> {
+        let article_repository = self.article_repository.clone();
+        let input = RestoreArticleRepositoryInput {
+            slug: input.slug,
+            current_user: input.current_user,
+        };
+        let result = db::blocking(move || article_repository.restore(input)).await?;
+        let res = self.article_presenter.to_single_json(result);
+        Ok(res)
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.slug, user_id = %input.current_user.id, archived = input.archived))]
+    pub async fn set_archived(
+        &self,
+        input: ArchiveArticleUsecaseInput,
+    ) -> Result<HttpResponse, AppError> {
+        let article_repository = self.article_repository.clone();
+        let result = db::blocking(move || {
+            let current_user = input.current_user;
+            let (article, _, _, _) =
+                article_repository.find_by_slug_or_id(&input.slug, Some(current_user.id))?;
+            if article.author_id != current_user.id {
+                return Err(AppError::Forbidden(
+                    "Only the author can archive this article.".into(),
+                ));
+            }
+            article_repository.set_archived(ArchiveArticleRepositoryInput {
+                article_id: article.id,
+                archived: input.archived,
+                current_user,
+            })
+        })
+        .await?;
+        let res = self.article_presenter.to_single_json(result);
+        Ok(res)
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(slug = %input.article_title_slug, user_id = %input.current_user.id))]
+    pub async fn update_article(
         &self,
         input: UpdateArticleUsecaseInput,
     ) -> Result<HttpResponse, AppError> {
-        let article_slug = &input
-            .title
-            .as_ref()
-            .map(|_title| Article::convert_title_to_slug(_title));
-        let slug = article_slug.to_owned();
-        let result = self
-            .article_repository
-            .update_article(UpdateArticleRepositoryInput {
-                current_user: input.current_user,
-                article_title_slug: input.article_title_slug,
-                slug,
-                title: input.title,
-                description: input.description,
-                body: input.body,
-            })?;
+        self.limits
+            .check(input.title.as_deref(), input.body.as_deref())?;
+        let article_repository = self.article_repository.clone();
+        let publishing = input.published == Some(true);
+        let input = UpdateArticleRepositoryInput {
+            current_user: input.current_user,
+            article_title_slug: input.article_title_slug,
+            title: input.title,
+            description: input.description,
+            body: input.body,
+            published: input.published,
+            tag_name_list: input.tag_name_list,
+            version: input.version,
+        };
+        let (result, was_published) = db::blocking(move || {
+            // NOTE: only the draft-to-published transition is an event, not every save of a published article.
+            let was_published = publishing
+                && article_repository
+                    .find_by_slug_or_id(&input.article_title_slug, Some(input.current_user.id))?
+                    .0
+                    .published;
+            let result = article_repository.update_article(input)?;
+            Ok((result, was_published))
+        })
+        .await?;
+        if publishing && !was_published && result.0.published {
+            self.announce(&result);
+        }
         let res = self.article_presenter.to_single_json(result);
         Ok(res)
     }
+
+    fn announce(&self, result: &(Article, Profile, FavoriteInfo, Vec<Tag>)) {
+        let (article, author, _, _) = result;
+        self.activity_feed.publish(Activity::Article {
+            slug: article.slug.clone(),
+            title: article.title.clone(),
+            author: author.username.clone(),
+        });
+        self.webhook_dispatcher
+            .article_published(ArticlePublished::from(result));
+    }
 }
 
 pub struct CreateArticleUsecaseInput {
@@ -134,7 +427,9 @@ pub struct CreateArticleUsecaseInput {
     pub description: String,
     pub body: String,
     pub tag_name_list: Option<Vec<String>>,
+    pub published: Option<bool>,
     pub current_user: User,
+    pub idempotency_key: Option<String>,
 }
 
 pub struct DeleteArticleUsecaseInput {
@@ -142,18 +437,1088 @@ pub struct DeleteArticleUsecaseInput {
     pub author_id: Uuid,
 }
 
+pub struct RestoreArticleUsecaseInput {
+    pub slug: String,
+    pub current_user: User,
+}
+
+pub struct ArchiveArticleUsecaseInput {
+    pub slug: String,
+    pub archived: bool,
+    pub current_user: User,
+}
+
 pub struct UpdateArticleUsecaseInput {
     pub current_user: User,
     pub article_title_slug: String,
     pub title: Option<String>,
     pub description: Option<String>,
     pub body: Option<String>,
+    pub published: Option<bool>,
+    pub tag_name_list: Option<Vec<String>>,
+    pub version: i32,
 }
 
 pub struct FetchArticlesUsecaseInput {
+    pub query: Option<String>,
     pub tag: Option<String>,
     pub author: Option<String>,
     pub favorited: Option<String>,
-    pub offset: i64,
-    pub limit: i64,
+    pub drafts_of: Option<Uuid>,
+    pub since: Option<NaiveDateTime>,
+    pub order: ArticleOrder,
+    pub viewer_id: Option<Uuid>,
+    pub pagination: Pagination,
+    pub after: Option<Cursor>,
+    pub page_url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::features::article::presenters::ArticlePresenterImpl;
+    use crate::app::features::article::repositories::ArticlesPage;
+    use crate::app::features::article::repositories::{
+        FetchArticleBySlugOutput, FetchArticleRepositoryInput, Inserted,
+    };
+    use crate::app::features::profile::entities::FollowCounts;
+    use actix_web::body::to_bytes;
+    use actix_web::http::header::{CONTENT_TYPE, ETAG, LINK};
+    use actix_web::ResponseError;
+    use chrono::NaiveDateTime;
+    use futures::{FutureExt, StreamExt};
+    use serde_json::json;
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::instrument::WithSubscriber;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    type TagRow = (Article, Profile, FavoriteInfo, Vec<Tag>);
+
+    struct FakeArticleRepository {
+        list: ArticlesList,
+        tags: TagNamesByArticle,
+        tag_queries: Mutex<Vec<Vec<Uuid>>>,
+        views: Mutex<Vec<Uuid>>,
+        idempotency_keys: Mutex<HashSet<String>>,
+    }
+
+    impl ArticleRepository for FakeArticleRepository {
+        // NOTE: filters by tag and author in memory like the SQL would, but never pages.
+        fn fetch_articles(
+            &self,
+            params: FetchArticlesRepositoryInput,
+        ) -> Result<ArticlesPage, AppError> {
+            let items = self
+                .list
+                .iter()
+                .filter(|(article, profile, _)| {
+                    let tagged = params.tag.as_ref().is_none_or(|tag| {
+                        self.tags
+                            .get(&article.id)
+                            .is_some_and(|names| names.contains(tag))
+                    });
+                    let by_author = params
+                        .author
+                        .as_ref()
+                        .is_none_or(|author| profile.username == *author);
+                    tagged && by_author
+                })
+                .cloned()
+                .collect::<ArticlesList>();
+            let total = items.len() as i64;
+            Ok(Page { items, total })
+        }
+        fn find_by_slug_or_id(
+            &self,
+            slug_or_id: &str,
+            _viewer_id: Option<Uuid>,
+        ) -> Result<FetchArticleBySlugOutput, AppError> {
+            self.list
+                .iter()
+                .find(|(article, _, _)| {
+                    article.slug == slug_or_id || article.id.to_string() == slug_or_id
+                })
+                .map(|(article, profile, favorite_info)| {
+                    let article = Article {
+                        view_count: self.view_count(&article.id),
+                        ..article.clone()
+                    };
+                    (article, profile.clone(), favorite_info.clone(), vec![])
+                })
+                .ok_or_else(|| diesel::result::Error::NotFound.into())
+        }
+        fn create_article(
+            &self,
+            params: CreateArticleRepositoryInput,
+        ) -> Result<(TagRow, Inserted), AppError> {
+            let (article, profile, favorite_info) = item(&params.title);
+            let article = Article {
+                published: params.published,
+                ..article
+            };
+            let inserted = match params.idempotency_key {
+                Some(key) => self.idempotency_keys.lock().unwrap().insert(key),
+                None => true,
+            };
+            Ok(((article, profile, favorite_info, vec![]), inserted))
+        }
+        fn delete_article(&self, _input: DeleteArticleRepositoryInput) -> Result<(), AppError> {
+            Err(AppError::Internal)
+        }
+        fn restore(&self, _input: RestoreArticleRepositoryInput) -> Result<TagRow, AppError> {
+            Err(AppError::Internal)
+        }
+        fn set_archived(&self, input: ArchiveArticleRepositoryInput) -> Result<TagRow, AppError> {
+            let (article, profile, favorite_info) = self
+                .list
+                .iter()
+                .find(|(article, _, _)| article.id == input.article_id)
+                .cloned()
+                .ok_or(diesel::result::Error::NotFound)?;
+            let article = Article {
+                archived: input.archived,
+                ..article
+            };
+            Ok((article, profile, favorite_info, vec![]))
+        }
+        fn update_article(&self, input: UpdateArticleRepositoryInput) -> Result<TagRow, AppError> {
+            let (article, profile, favorite_info, tags) =
+                self.find_by_slug_or_id(&input.article_title_slug, None)?;
+            let article = Article {
+                published: input.published.unwrap_or(article.published),
+                ..article
+            };
+            Ok((article, profile, favorite_info, tags))
+        }
+        fn fetch_article(&self, _input: &FetchArticleRepositoryInput) -> Result<TagRow, AppError> {
+            Err(AppError::Internal)
+        }
+        fn fetch_following_articles(
+            &self,
+            _params: &FetchFollowingArticlesRepositoryInput,
+        ) -> Result<ArticlesPage, AppError> {
+            Err(AppError::Internal)
+        }
+        fn tags_for_articles(&self, article_ids: &[Uuid]) -> Result<TagNamesByArticle, AppError> {
+            self.tag_queries.lock().unwrap().push(article_ids.to_vec());
+            Ok(self.tags.clone())
+        }
+        fn record_view(&self, article_id: &Uuid) -> Result<i32, AppError> {
+            self.views.lock().unwrap().push(*article_id);
+            Ok(self.view_count(article_id))
+        }
+        // NOTE: ranks by shared tags in memory like the SQL would; ties keep the list order.
+        fn fetch_related_articles(
+            &self,
+            params: &FetchRelatedArticlesRepositoryInput,
+        ) -> Result<ArticlesList, AppError> {
+            let names = |article_id: &Uuid| self.tags.get(article_id).cloned().unwrap_or_default();
+            let source = names(&params.article_id);
+            let mut ranked = self
+                .list
+                .iter()
+                .filter(|(article, _, _)| article.id != params.article_id)
+                .filter(|(article, _, _)| Some(article.author_id) != params.exclude_author_id)
+                .map(|item| {
+                    let shared = names(&item.0.id)
+                        .iter()
+                        .filter(|name| source.contains(name))
+                        .count();
+                    (shared, item.clone())
+                })
+                .filter(|(shared, _)| *shared > 0)
+                .collect::<Vec<_>>();
+            ranked.sort_by_key(|(shared, _)| std::cmp::Reverse(*shared));
+            Ok(ranked
+                .into_iter()
+                .map(|(_, item)| item)
+                .take(params.limit as usize)
+                .collect())
+        }
+    }
+
+    impl FakeArticleRepository {
+        fn view_count(&self, article_id: &Uuid) -> i32 {
+            let views = self.views.lock().unwrap();
+            views.iter().filter(|id| *id == article_id).count() as i32
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct SpanFields(Vec<(String, String)>);
+
+    // Records every span opened while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<Mutex<Vec<(&'static str, SpanFields)>>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    impl Visit for SpanFields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .push((field.name().to_owned(), format!("{:?}", value)));
+        }
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push((field.name().to_owned(), value.to_owned()));
+        }
+    }
+
+    impl Subscriber for SpanRecorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = SpanFields::default();
+            span.record(&mut fields);
+            self.spans
+                .lock()
+                .unwrap()
+                .push((span.metadata().name(), fields));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+        fn event(&self, _event: &Event<'_>) {}
+        fn enter(&self, _span: &Id) {}
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[derive(Default)]
+    struct FakeWebhookDispatcher {
+        published: Mutex<Vec<ArticlePublished>>,
+    }
+
+    impl WebhookDispatcher for FakeWebhookDispatcher {
+        fn article_published(&self, event: ArticlePublished) {
+            self.published.lock().unwrap().push(event);
+        }
+    }
+
+    struct FakeFavoriteRepository;
+
+    impl FavoriteRepository for FakeFavoriteRepository {
+        fn favorite_article(
+            &self,
+            _user: User,
+            _slug: String,
+        ) -> Result<(Article, bool), AppError> {
+            Err(AppError::Internal)
+        }
+        fn unfavorite_article(&self, _user: User, _slug: String) -> Result<Article, AppError> {
+            Err(AppError::Internal)
+        }
+        fn favorited_article_ids(
+            &self,
+            _user_id: &Uuid,
+            _article_ids: &[Uuid],
+        ) -> Result<HashSet<Uuid>, AppError> {
+            Ok(HashSet::new())
+        }
+        fn users_for_article(
+            &self,
+            _article_id: &Uuid,
+            _viewer_id: Option<&Uuid>,
+            _pagination: Pagination,
+        ) -> Result<Page<Profile>, AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeProfileRepository {
+        followed: HashSet<Uuid>,
+        following_queries: Mutex<Vec<Vec<Uuid>>>,
+    }
+
+    impl ProfileRepository for FakeProfileRepository {
+        fn fetch_profile_by_name(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<Profile, AppError> {
+            Err(AppError::Internal)
+        }
+        fn follow_counts(&self, _username: &str) -> Result<FollowCounts, AppError> {
+            Err(AppError::Internal)
+        }
+        fn following_map(
+            &self,
+            _current_user_id: &Uuid,
+            user_ids: &[Uuid],
+        ) -> Result<HashSet<Uuid>, AppError> {
+            self.following_queries
+                .lock()
+                .unwrap()
+                .push(user_ids.to_vec());
+            Ok(self.followed.clone())
+        }
+        fn profiles_by_usernames(
+            &self,
+            _current_user: &Option<User>,
+            _usernames: &[String],
+        ) -> Result<Vec<Profile>, AppError> {
+            Err(AppError::Internal)
+        }
+        fn search(
+            &self,
+            _current_user: &Option<User>,
+            _query: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn mutuals(
+            &self,
+            _current_user: &User,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn followers(
+            &self,
+            _current_user: &Option<User>,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn following(
+            &self,
+            _current_user: &Option<User>,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    fn item(slug: &str) -> (Article, Profile, FavoriteInfo) {
+        let now = NaiveDateTime::default();
+        let article = Article {
+            id: Uuid::new_v4(),
+            author_id: Uuid::nil(),
+            slug: slug.to_owned(),
+            title: slug.to_owned(),
+            description: String::new(),
+            body: String::new(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            published: true,
+            favorites_count: 0,
+            version: 1,
+            archived: false,
+            hidden: false,
+            view_count: 0,
+        };
+        let profile = Profile {
+            username: "jake".to_owned(),
+            bio: None,
+            image: None,
+            following: false,
+        };
+        (
+            article,
+            profile,
+            FavoriteInfo {
+                is_favorited: false,
+            },
+        )
+    }
+
+    fn input() -> FetchArticlesUsecaseInput {
+        FetchArticlesUsecaseInput {
+            query: None,
+            tag: None,
+            author: None,
+            favorited: None,
+            drafts_of: None,
+            since: None,
+            order: ArticleOrder::default(),
+            viewer_id: None,
+            pagination: Pagination::default(),
+            after: None,
+            page_url: "http://localhost:8080/api/articles".to_owned(),
+        }
+    }
+
+    fn article_repository(
+        list: ArticlesList,
+        tags: TagNamesByArticle,
+    ) -> Arc<FakeArticleRepository> {
+        Arc::new(FakeArticleRepository {
+            list,
+            tags,
+            tag_queries: Mutex::new(vec![]),
+            views: Mutex::new(vec![]),
+            idempotency_keys: Mutex::new(HashSet::new()),
+        })
+    }
+
+    // The fakes an `ArticleUsecase` is wired to; tests override the ones they look at.
+    struct Fakes {
+        article_repository: Arc<FakeArticleRepository>,
+        profile_repository: Arc<FakeProfileRepository>,
+        webhook_dispatcher: Arc<FakeWebhookDispatcher>,
+        activity_feed: ActivityFeed,
+    }
+
+    impl Default for Fakes {
+        fn default() -> Self {
+            Self::new(vec![])
+        }
+    }
+
+    impl Fakes {
+        fn new(list: ArticlesList) -> Self {
+            Self {
+                article_repository: article_repository(list, TagNamesByArticle::new()),
+                profile_repository: Arc::new(FakeProfileRepository::default()),
+                webhook_dispatcher: Arc::new(FakeWebhookDispatcher::default()),
+                activity_feed: ActivityFeed::new(),
+            }
+        }
+
+        fn usecase(&self) -> ArticleUsecase {
+            ArticleUsecase::new(
+                self.article_repository.clone(),
+                Arc::new(ArticlePresenterImpl::new()),
+                Arc::new(FakeFavoriteRepository),
+                self.profile_repository.clone(),
+                self.webhook_dispatcher.clone(),
+                self.activity_feed.clone(),
+                ArticleConfig::default(),
+            )
+        }
+    }
+
+    #[actix_web::test]
+    async fn fetch_articles_loads_tags_in_one_query() {
+        let list = (0..25)
+            .map(|n| item(&format!("article-{}", n)))
+            .collect::<ArticlesList>();
+        let tags = list
+            .iter()
+            .map(|(article, _, _)| (article.id, vec![article.slug.clone()]))
+            .collect::<TagNamesByArticle>();
+        let repository = article_repository(list, tags);
+        let usecase = Fakes {
+            article_repository: repository.clone(),
+            ..Fakes::default()
+        }
+        .usecase();
+        let res = usecase.fetch_articles(input()).await.unwrap();
+
+        let tag_queries = repository.tag_queries.lock().unwrap().clone();
+        assert_eq!(1, tag_queries.len());
+        assert_eq!(25, tag_queries[0].len());
+
+        let links = res.headers().get(LINK).unwrap().to_str().unwrap();
+        assert!(
+            links.contains("<http://localhost:8080/api/articles?limit=20&offset=20>; rel=\"next\"")
+        );
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!(["article-0"]), body["articles"][0]["tagList"]);
+        assert_eq!(json!(["article-24"]), body["articles"][24]["tagList"]);
+    }
+
+    #[actix_web::test]
+    async fn fetch_articles_filters_by_tag_and_author() {
+        let by = |slug: &str, author: &str| {
+            let (article, profile, favorite_info) = item(slug);
+            let profile = Profile {
+                username: author.to_owned(),
+                ..profile
+            };
+            (article, profile, favorite_info)
+        };
+        let list = vec![
+            by("dragons-by-celeb", "celeb"),
+            by("dragons-by-jake", "jake"),
+            by("riding-by-celeb", "celeb"),
+        ];
+        let tags = list
+            .iter()
+            .map(|(article, _, _)| {
+                let tag = article.slug.split('-').next().unwrap().to_owned();
+                (article.id, vec![tag])
+            })
+            .collect::<TagNamesByArticle>();
+        let usecase = Fakes {
+            article_repository: article_repository(list, tags),
+            ..Fakes::default()
+        }
+        .usecase();
+        // NOTE: the tag is normalized before it reaches the repository.
+        let params = FetchArticlesUsecaseInput {
+            tag: Some(" Dragons ".to_owned()),
+            author: Some("celeb".to_owned()),
+            ..input()
+        };
+        let res = usecase.fetch_articles(params).await.unwrap();
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body["articlesCount"]);
+        assert_eq!("dragons-by-celeb", body["articles"][0]["slug"]);
+        assert_eq!(json!(["dragons"]), body["articles"][0]["tagList"]);
+        assert!(body.get("nextCursor").is_none());
+    }
+
+    #[actix_web::test]
+    async fn fetch_articles_by_cursor_returns_the_next_cursor() {
+        let list = (0..20)
+            .map(|n| item(&format!("article-{}", n)))
+            .collect::<ArticlesList>();
+        let (last, _, _) = list.last().cloned().unwrap();
+        let usecase = Fakes::new(list).usecase();
+        let params = FetchArticlesUsecaseInput {
+            after: Some(Cursor {
+                created_at: NaiveDateTime::default(),
+                id: Uuid::nil(),
+            }),
+            ..input()
+        };
+        let res = usecase.fetch_articles(params).await.unwrap();
+
+        assert!(res.headers().get(LINK).is_none());
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let next = Cursor {
+            created_at: last.created_at,
+            id: last.id,
+        };
+        assert_eq!(next.to_string(), body["nextCursor"]);
+    }
+
+    #[actix_web::test]
+    async fn popular_and_search_pages_have_no_next_cursor() {
+        let list = (0..20)
+            .map(|n| item(&format!("article-{}", n)))
+            .collect::<ArticlesList>();
+        let usecase = Fakes::new(list).usecase();
+        for params in [
+            FetchArticlesUsecaseInput {
+                order: ArticleOrder::Popular,
+                ..input()
+            },
+            FetchArticlesUsecaseInput {
+                query: Some("dragons".to_owned()),
+                ..input()
+            },
+        ] {
+            let res = usecase.fetch_articles(params).await.unwrap();
+            let body = to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(20, body["articles"].as_array().unwrap().len());
+            assert!(body.get("nextCursor").is_none());
+        }
+    }
+
+    #[actix_web::test]
+    async fn fetch_articles_resolves_following_in_one_query() {
+        let author_ids = (0..5).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        let list = (0..25)
+            .map(|n| {
+                let (article, profile, favorite_info) = item(&format!("article-{}", n));
+                let article = Article {
+                    author_id: author_ids[n % 5],
+                    ..article
+                };
+                (article, profile, favorite_info)
+            })
+            .collect::<ArticlesList>();
+        let profile_repository = Arc::new(FakeProfileRepository {
+            followed: [author_ids[0]].into_iter().collect(),
+            following_queries: Mutex::new(vec![]),
+        });
+        let usecase = Fakes {
+            profile_repository: profile_repository.clone(),
+            ..Fakes::new(list)
+        }
+        .usecase();
+        let params = FetchArticlesUsecaseInput {
+            viewer_id: Some(Uuid::new_v4()),
+            ..input()
+        };
+        let res = usecase.fetch_articles(params).await.unwrap();
+
+        let following_queries = profile_repository.following_queries.lock().unwrap().clone();
+        assert_eq!(1, following_queries.len());
+        assert_eq!(5, following_queries[0].len());
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(true, body["articles"][0]["author"]["following"]);
+        assert_eq!(false, body["articles"][1]["author"]["following"]);
+        assert_eq!(true, body["articles"][5]["author"]["following"]);
+    }
+
+    #[actix_web::test]
+    async fn fetch_articles_for_anonymous_viewer_skips_viewer_state() {
+        let (article, profile, favorite_info) = item("how-to-train-your-dragon");
+        let profile_repository = Arc::new(FakeProfileRepository {
+            followed: [article.author_id].into_iter().collect(),
+            following_queries: Mutex::new(vec![]),
+        });
+        let usecase = Fakes {
+            profile_repository: profile_repository.clone(),
+            ..Fakes::new(vec![(article, profile, favorite_info)])
+        }
+        .usecase();
+        let res = usecase.fetch_articles(input()).await.unwrap();
+
+        assert!(profile_repository
+            .following_queries
+            .lock()
+            .unwrap()
+            .is_empty());
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(false, body["articles"][0]["favorited"]);
+        assert_eq!(false, body["articles"][0]["author"]["following"]);
+    }
+
+    #[actix_web::test]
+    async fn fetch_article_by_slug_emits_span_with_slug() {
+        let usecase = Fakes::default().usecase();
+        let recorder = SpanRecorder::default();
+        let result = usecase
+            .fetch_article_by_slug(
+                "how-to-train-your-dragon".to_owned(),
+                None,
+                None,
+                ArticleFormat::default(),
+                None,
+            )
+            .with_subscriber(recorder.clone())
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        let spans = recorder.spans.lock().unwrap().clone();
+        let (_, fields) = spans
+            .iter()
+            .find(|(name, _)| *name == "fetch_article_by_slug")
+            .expect("span was not emitted");
+        assert!(fields
+            .0
+            .contains(&("slug".to_owned(), "how-to-train-your-dragon".to_owned())));
+    }
+
+    #[actix_web::test]
+    async fn missing_slug_is_not_found() {
+        let usecase = Fakes::default().usecase();
+        let err = usecase
+            .fetch_article_by_slug(
+                "no-such-article".to_owned(),
+                None,
+                None,
+                ArticleFormat::default(),
+                None,
+            )
+            .await
+            .err()
+            .unwrap();
+        let res = err.error_response();
+        assert_eq!(404, res.status().as_u16());
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!({"errors": {"body": ["not found"]}}), body);
+    }
+
+    #[actix_web::test]
+    async fn fetch_article_by_slug_answers_304_for_a_matching_etag() {
+        let usecase = Fakes::new(vec![item("how-to-train-your-dragon")]).usecase();
+        let fetch = |if_none_match: Option<String>| {
+            usecase.fetch_article_by_slug(
+                "how-to-train-your-dragon".to_owned(),
+                None,
+                None,
+                ArticleFormat::default(),
+                if_none_match,
+            )
+        };
+
+        let res = fetch(None).await.unwrap();
+        assert_eq!(200, res.status().as_u16());
+        let etag = res
+            .headers()
+            .get(ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(etag.starts_with("W/"));
+
+        let res = fetch(Some(etag.clone())).await.unwrap();
+        assert_eq!(304, res.status().as_u16());
+        assert_eq!(etag, res.headers().get(ETAG).unwrap().to_str().unwrap());
+        assert!(to_bytes(res.into_body()).await.unwrap().is_empty());
+
+        let res = fetch(Some("W/\"stale\"".to_owned())).await.unwrap();
+        assert_eq!(200, res.status().as_u16());
+    }
+
+    #[actix_web::test]
+    async fn related_articles_are_ranked_by_shared_tags() {
+        let source = item("dragons");
+        let mut by_anna = item("dragon-riding");
+        by_anna.0.author_id = Uuid::new_v4();
+        let (one_tag, two_tags, none) = (item("one-tag"), item("two-tags"), item("unrelated"));
+        let tags = [
+            (&source, vec!["dragons", "fantasy", "vikings"]),
+            (&one_tag, vec!["vikings"]),
+            (&two_tags, vec!["dragons", "fantasy", "cooking"]),
+            (&none, vec!["cooking"]),
+            (&by_anna, vec!["dragons", "fantasy", "vikings"]),
+        ]
+        .into_iter()
+        .map(|((article, _, _), names)| {
+            let names = names.into_iter().map(str::to_owned).collect();
+            (article.id, names)
+        })
+        .collect::<TagNamesByArticle>();
+        let usecase = Fakes {
+            article_repository: article_repository(
+                vec![source, one_tag, two_tags, none, by_anna],
+                tags,
+            ),
+            ..Fakes::default()
+        }
+        .usecase();
+        let usecase = &usecase;
+        let related = |exclude_author: bool, limit: i64| async move {
+            let res = usecase
+                .fetch_related_articles("dragons".to_owned(), None, exclude_author, limit)
+                .await
+                .unwrap();
+            let body = to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            body["articles"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|article| article["slug"].as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["dragon-riding", "two-tags", "one-tag"],
+            related(false, 5).await
+        );
+        assert_eq!(vec!["dragon-riding"], related(false, 1).await);
+        // NOTE: everything else is by the same author as the article itself.
+        assert_eq!(vec!["dragon-riding"], related(true, 5).await);
+    }
+
+    #[actix_web::test]
+    async fn fetch_article_by_slug_counts_each_viewer_once_per_window() {
+        let fakes = Fakes::new(vec![item("how-to-train-your-dragon")]);
+        let usecase = fakes.usecase();
+        let fetch = |viewer: Option<Viewer>| async {
+            let res = usecase
+                .fetch_article_by_slug(
+                    "how-to-train-your-dragon".to_owned(),
+                    None,
+                    viewer,
+                    ArticleFormat::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+            let body = to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            body["article"]["viewCount"].clone()
+        };
+        let jake = Viewer::User(Uuid::new_v4());
+        let anonymous = Viewer::Anonymous("10.0.0.1".to_owned());
+
+        assert_eq!(json!(1), fetch(Some(jake.clone())).await);
+        assert_eq!(json!(2), fetch(Some(anonymous.clone())).await);
+        assert_eq!(json!(2), fetch(Some(jake)).await);
+        assert_eq!(json!(2), fetch(Some(anonymous)).await);
+        // NOTE: crawlers are shown the count but never add to it.
+        assert_eq!(json!(2), fetch(None).await);
+        assert_eq!(2, fakes.article_repository.views.lock().unwrap().len());
+    }
+
+    #[actix_web::test]
+    async fn fetch_article_by_slug_renders_html_on_request() {
+        let (mut article, profile, favorite_info) = item("how-to-train-your-dragon");
+        article.body = "# Dragons\n<script>alert(1)</script>".to_owned();
+        let usecase = Fakes::new(vec![(article, profile, favorite_info)]).usecase();
+        let fetch = |format: ArticleFormat| {
+            usecase.fetch_article_by_slug(
+                "how-to-train-your-dragon".to_owned(),
+                None,
+                None,
+                format,
+                None,
+            )
+        };
+
+        let json = fetch(ArticleFormat::Json).await.unwrap();
+        let res = fetch(ArticleFormat::Html).await.unwrap();
+        assert_eq!("Accept", res.headers().get(VARY).unwrap());
+        assert_ne!(json.headers().get(ETAG), res.headers().get(ETAG));
+        assert_eq!(
+            "text/html; charset=utf-8",
+            res.headers().get(CONTENT_TYPE).unwrap()
+        );
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            "<h1>Dragons</h1>\n<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>\n",
+            std::str::from_utf8(&body).unwrap()
+        );
+        let body = to_bytes(json.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            "# Dragons\n<script>alert(1)</script>",
+            body["article"]["body"]
+        );
+    }
+
+    fn user(id: Uuid, username: &str) -> User {
+        let now = NaiveDateTime::default();
+        User {
+            id,
+            email: format!("{}@example.com", username),
+            username: username.to_owned(),
+            password: "hashed".to_owned(),
+            bio: None,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            email_verified: true,
+            role: "user".to_owned(),
+            last_login_at: None,
+        }
+    }
+
+    fn single_article_usecase() -> ArticleUsecase {
+        Fakes::new(vec![item("how-to-train-your-dragon")]).usecase()
+    }
+
+    #[actix_web::test]
+    async fn only_the_author_can_archive() {
+        let res = single_article_usecase()
+            .set_archived(ArchiveArticleUsecaseInput {
+                slug: "how-to-train-your-dragon".to_owned(),
+                archived: true,
+                current_user: user(Uuid::new_v4(), "celeb"),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::Forbidden(_))));
+    }
+
+    #[actix_web::test]
+    async fn author_archives_and_unarchives() {
+        let usecase = single_article_usecase();
+        for archived in [true, false] {
+            let res = usecase
+                .set_archived(ArchiveArticleUsecaseInput {
+                    slug: "how-to-train-your-dragon".to_owned(),
+                    archived,
+                    current_user: user(Uuid::nil(), "jake"),
+                })
+                .await
+                .unwrap();
+            let body = to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(archived, body["article"]["archived"]);
+        }
+    }
+
+    #[actix_web::test]
+    async fn update_with_an_oversized_body_is_rejected() {
+        let input = UpdateArticleUsecaseInput {
+            current_user: user(Uuid::nil(), "jake"),
+            article_title_slug: "how-to-train-your-dragon".to_owned(),
+            title: Some("t".repeat(256)),
+            description: None,
+            body: Some("b".repeat(100_001)),
+            published: None,
+            tag_name_list: None,
+            version: 1,
+        };
+        let err = single_article_usecase()
+            .update_article(input)
+            .await
+            .unwrap_err();
+        let res = err.error_response();
+        assert_eq!(422, res.status().as_u16());
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json!(["is too long (maximum is 255 characters)"]),
+            body["errors"]["title"]
+        );
+        assert_eq!(
+            json!(["is too long (maximum is 100000 characters)"]),
+            body["errors"]["body"]
+        );
+    }
+
+    fn usecase_with_webhooks(list: ArticlesList) -> (ArticleUsecase, Arc<FakeWebhookDispatcher>) {
+        let fakes = Fakes::new(list);
+        (fakes.usecase(), fakes.webhook_dispatcher)
+    }
+
+    #[actix_web::test]
+    async fn creating_a_published_article_dispatches_a_webhook() {
+        let (usecase, webhook_dispatcher) = usecase_with_webhooks(vec![]);
+        for published in [Some(false), None] {
+            usecase
+                .create_article(CreateArticleUsecaseInput {
+                    title: "how-to-train-your-dragon".to_owned(),
+                    description: String::new(),
+                    body: String::new(),
+                    tag_name_list: None,
+                    published,
+                    current_user: user(Uuid::nil(), "jake"),
+                    idempotency_key: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            vec![ArticlePublished {
+                slug: "how-to-train-your-dragon".to_owned(),
+                title: "how-to-train-your-dragon".to_owned(),
+                author: "jake".to_owned(),
+                tag_list: vec![],
+            }],
+            *webhook_dispatcher.published.lock().unwrap()
+        );
+    }
+
+    #[actix_web::test]
+    async fn a_retried_create_is_announced_once() {
+        let fakes = Fakes::default();
+        let usecase = fakes.usecase();
+        let mut events = fakes.activity_feed.subscribe(None);
+        for _ in 0..2 {
+            usecase
+                .create_article(CreateArticleUsecaseInput {
+                    title: "how-to-train-your-dragon".to_owned(),
+                    description: String::new(),
+                    body: String::new(),
+                    tag_name_list: None,
+                    published: None,
+                    current_user: user(Uuid::nil(), "jake"),
+                    idempotency_key: Some("retry-1".to_owned()),
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(1, fakes.webhook_dispatcher.published.lock().unwrap().len());
+        assert!(events.next().await.is_some());
+        assert!(events.next().now_or_never().is_none());
+    }
+
+    #[actix_web::test]
+    async fn unverified_users_cannot_create_articles() {
+        let (usecase, webhook_dispatcher) = usecase_with_webhooks(vec![]);
+        let err = usecase
+            .create_article(CreateArticleUsecaseInput {
+                title: "how-to-train-your-dragon".to_owned(),
+                description: String::new(),
+                body: String::new(),
+                tag_name_list: None,
+                published: None,
+                current_user: User {
+                    email_verified: false,
+                    ..user(Uuid::nil(), "jake")
+                },
+                idempotency_key: None,
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(403, err.status().as_u16());
+        assert_eq!(
+            json!({"errors": {"body": ["Verify your email before publishing articles."]}}),
+            err.body()
+        );
+        assert!(webhook_dispatcher.published.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn publishing_a_draft_dispatches_a_webhook_once() {
+        let (article, profile, favorite_info) = item("how-to-train-your-dragon");
+        let draft = Article {
+            published: false,
+            ..article
+        };
+        let (usecase, webhook_dispatcher) = usecase_with_webhooks(vec![(
+            draft.clone(),
+            profile.clone(),
+            favorite_info.clone(),
+        )]);
+        let update = |published: Option<bool>| UpdateArticleUsecaseInput {
+            current_user: user(Uuid::nil(), "jake"),
+            article_title_slug: "how-to-train-your-dragon".to_owned(),
+            title: None,
+            description: None,
+            body: None,
+            published,
+            tag_name_list: None,
+            version: 1,
+        };
+        for published in [None, Some(false), Some(true)] {
+            usecase.update_article(update(published)).await.unwrap();
+        }
+        assert_eq!(1, webhook_dispatcher.published.lock().unwrap().len());
+
+        let published = Article {
+            published: true,
+            ..draft
+        };
+        let (usecase, webhook_dispatcher) =
+            usecase_with_webhooks(vec![(published, profile, favorite_info)]);
+        usecase.update_article(update(Some(true))).await.unwrap();
+        assert!(webhook_dispatcher.published.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn creating_a_published_article_is_announced_on_the_activity_feed() {
+        let fakes = Fakes::default();
+        let usecase = fakes.usecase();
+        let mut events = fakes.activity_feed.subscribe(None);
+        for published in [Some(false), None] {
+            usecase
+                .create_article(CreateArticleUsecaseInput {
+                    title: "how-to-train-your-dragon".to_owned(),
+                    description: String::new(),
+                    body: String::new(),
+                    tag_name_list: None,
+                    published,
+                    current_user: user(Uuid::nil(), "jake"),
+                    idempotency_key: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let event = events.next().await.unwrap();
+        assert_eq!(
+            Activity::Article {
+                slug: "how-to-train-your-dragon".to_owned(),
+                title: "how-to-train-your-dragon".to_owned(),
+                author: "jake".to_owned(),
+            },
+            event.activity
+        );
+        assert!(events.next().now_or_never().is_none());
+    }
 }
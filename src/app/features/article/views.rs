@@ -0,0 +1,141 @@
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// NOTE: expired views are only swept once this many are remembered, and at most once per
+// `SWEEP_INTERVAL`, so a busy tracker does not walk the whole map on every request.
+const SWEEP_AT: usize = 10_000;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+// NOTE: past this, views from new viewers are not counted until a sweep makes room; under a
+// flood of fresh viewers, under-counting beats unbounded memory or inflated counts.
+const MAX_TRACKED_VIEWS: usize = 100_000;
+
+// Who viewed an article: signed-in users by id, everyone else by client IP.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Viewer {
+    User(Uuid),
+    Anonymous(String),
+}
+
+type ViewedAt = Instant;
+
+// Counts a viewer once per article per window, however often they reload the page.
+#[derive(Clone)]
+pub struct ViewTracker {
+    views: Arc<DashMap<(Uuid, Viewer), ViewedAt>>,
+    window: Duration,
+    last_sweep: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ViewTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            views: Arc::new(DashMap::new()),
+            window,
+            last_sweep: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Whether this view should be counted; remembers it if so.
+    pub fn record(&self, article_id: Uuid, viewer: Viewer) -> bool {
+        self.sweep();
+        // NOTE: read before taking the entry, which holds its shard locked.
+        let full = self.views.len() >= MAX_TRACKED_VIEWS;
+        match self.views.entry((article_id, viewer)) {
+            // NOTE: the window runs from the counted view, so a steady reloader still counts once per window.
+            Entry::Occupied(entry) if entry.get().elapsed() < self.window => false,
+            Entry::Occupied(mut entry) => {
+                entry.insert(Instant::now());
+                true
+            }
+            Entry::Vacant(_) if full => false,
+            Entry::Vacant(entry) => {
+                entry.insert(Instant::now());
+                true
+            }
+        }
+    }
+
+    fn sweep(&self) {
+        if self.views.len() < SWEEP_AT {
+            return;
+        }
+        // NOTE: whoever holds the lock is already sweeping, so everyone else moves on.
+        let Ok(mut last_sweep) = self.last_sweep.try_lock() else {
+            return;
+        };
+        if last_sweep.is_some_and(|swept_at| swept_at.elapsed() < SWEEP_INTERVAL) {
+            return;
+        }
+        *last_sweep = Some(Instant::now());
+        self.views
+            .retain(|_, viewed_at| viewed_at.elapsed() < self.window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeat_views_within_the_window_count_once() {
+        let tracker = ViewTracker::new(Duration::from_secs(60));
+        let article_id = Uuid::new_v4();
+        let jake = Viewer::User(Uuid::new_v4());
+
+        assert!(tracker.record(article_id, jake.clone()));
+        assert!(!tracker.record(article_id, jake.clone()));
+        assert!(tracker.record(Uuid::new_v4(), jake));
+        assert!(tracker.record(article_id, Viewer::Anonymous("10.0.0.1".to_owned())));
+        assert!(!tracker.record(article_id, Viewer::Anonymous("10.0.0.1".to_owned())));
+        assert!(tracker.record(article_id, Viewer::Anonymous("10.0.0.2".to_owned())));
+    }
+
+    #[test]
+    fn views_count_again_once_the_window_passes() {
+        let tracker = ViewTracker::new(Duration::from_millis(20));
+        let (article_id, jake) = (Uuid::new_v4(), Viewer::User(Uuid::new_v4()));
+
+        assert!(tracker.record(article_id, jake.clone()));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(tracker.record(article_id, jake.clone()));
+        assert!(!tracker.record(article_id, jake));
+    }
+
+    fn remember(tracker: &ViewTracker, count: usize) {
+        for _ in 0..count {
+            tracker.views.insert(
+                (Uuid::new_v4(), Viewer::User(Uuid::new_v4())),
+                Instant::now(),
+            );
+        }
+    }
+
+    #[test]
+    fn expired_views_are_swept_at_most_once_per_interval() {
+        let tracker = ViewTracker::new(Duration::from_millis(1));
+        remember(&tracker, SWEEP_AT);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(tracker.record(Uuid::new_v4(), Viewer::User(Uuid::new_v4())));
+        assert_eq!(1, tracker.views.len());
+
+        remember(&tracker, SWEEP_AT);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(tracker.record(Uuid::new_v4(), Viewer::User(Uuid::new_v4())));
+        assert_eq!(SWEEP_AT + 2, tracker.views.len());
+    }
+
+    #[test]
+    fn new_viewers_are_not_counted_once_the_tracker_is_full() {
+        let tracker = ViewTracker::new(Duration::from_secs(60));
+        let (article_id, jake) = (Uuid::new_v4(), Viewer::User(Uuid::new_v4()));
+        assert!(tracker.record(article_id, jake.clone()));
+        remember(&tracker, MAX_TRACKED_VIEWS - 1);
+
+        assert!(!tracker.record(article_id, Viewer::User(Uuid::new_v4())));
+        assert!(!tracker.record(article_id, jake));
+        assert_eq!(MAX_TRACKED_VIEWS, tracker.views.len());
+    }
+}
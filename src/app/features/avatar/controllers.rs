@@ -0,0 +1,55 @@
+use crate::app::drivers::middlewares::auth;
+use crate::app::drivers::middlewares::state::AppState;
+use crate::error::AppError;
+use crate::utils::api::ApiResponse;
+use crate::utils::image::{self, MAX_IMAGE_BYTES};
+use crate::utils::multipart;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::{web, HttpRequest};
+use futures::StreamExt;
+
+type FileName = String;
+
+// NOTE: room for the multipart boundaries and part headers around the image itself.
+const MAX_BODY_BYTES: usize = MAX_IMAGE_BYTES + 16 * 1024;
+
+async fn read_body(mut payload: web::Payload) -> Result<Vec<u8>, AppError> {
+    let mut body = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|_err| image::invalid("could not be read"))?;
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(image::invalid("is too large (maximum is 2MB)"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+pub async fn upload(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> ApiResponse {
+    let current_user = auth::get_current_user(&req)?;
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let boundary = multipart::boundary(content_type)
+        .ok_or_else(|| image::invalid("must be sent as multipart/form-data"))?;
+    let body = read_body(payload).await?;
+    let part = multipart::parse(&body, boundary)
+        .into_iter()
+        .find(|part| part.name == Some("image"))
+        .ok_or_else(|| image::invalid("can't be blank"))?;
+    state
+        .di_container
+        .avatar_usecase
+        .upload_image(current_user.id, part.content_type, part.data)
+}
+
+pub async fn show(state: web::Data<AppState>, path: web::Path<FileName>) -> ApiResponse {
+    let file_name = path.into_inner();
+    state.di_container.avatar_usecase.fetch_image(&file_name)
+}
@@ -0,0 +1,24 @@
+use crate::utils::image::ImageKind;
+use actix_web::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use actix_web::HttpResponse;
+
+pub trait AvatarPresenter: Send + Sync + 'static {
+    fn to_image(&self, kind: ImageKind, data: Vec<u8>) -> HttpResponse;
+}
+
+#[derive(Clone, Default)]
+pub struct AvatarPresenterImpl {}
+impl AvatarPresenterImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl AvatarPresenter for AvatarPresenterImpl {
+    fn to_image(&self, kind: ImageKind, data: Vec<u8>) -> HttpResponse {
+        // NOTE: every upload gets a new file name, so a served file never changes.
+        HttpResponse::Ok()
+            .insert_header((CONTENT_TYPE, kind.content_type()))
+            .insert_header((CACHE_CONTROL, "public, max-age=31536000, immutable"))
+            .body(data)
+    }
+}
@@ -0,0 +1,103 @@
+use crate::error::AppError;
+use crate::utils::image::ImageKind;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+pub static DEFAULT_AVATAR_DIR: &str = "uploads/avatars";
+
+type FileName = String;
+
+pub trait AvatarRepository: Send + Sync + 'static {
+    fn save(&self, user_id: &Uuid, kind: ImageKind, data: &[u8]) -> Result<FileName, AppError>;
+    fn load(&self, file_name: &str) -> Result<(ImageKind, Vec<u8>), AppError>;
+}
+
+#[derive(Clone)]
+pub struct AvatarRepositoryImpl {
+    dir: PathBuf,
+}
+
+impl AvatarRepositoryImpl {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn not_found() -> AppError {
+        AppError::NotFound("Image not found".into())
+    }
+
+    // NOTE: only names this repository could have written, so a path can never escape `dir`.
+    fn kind_of(file_name: &str) -> Option<ImageKind> {
+        let (stem, extension) = file_name.rsplit_once('.')?;
+        let is_safe = !stem.is_empty() && stem.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+        if is_safe {
+            ImageKind::from_extension(extension)
+        } else {
+            None
+        }
+    }
+}
+
+impl AvatarRepository for AvatarRepositoryImpl {
+    fn save(&self, user_id: &Uuid, kind: ImageKind, data: &[u8]) -> Result<FileName, AppError> {
+        // NOTE: a fresh name per upload so caches never serve a replaced avatar.
+        let file_name = format!("{}-{}.{}", user_id, Uuid::new_v4(), kind.extension());
+        fs::create_dir_all(&self.dir).map_err(|err| {
+            error!("Cannot create avatar directory {}", err);
+            AppError::Internal
+        })?;
+        fs::write(self.dir.join(&file_name), data).map_err(|err| {
+            error!("Cannot write avatar {}", err);
+            AppError::Internal
+        })?;
+        Ok(file_name)
+    }
+
+    fn load(&self, file_name: &str) -> Result<(ImageKind, Vec<u8>), AppError> {
+        let kind = Self::kind_of(file_name).ok_or_else(Self::not_found)?;
+        match fs::read(self.dir.join(file_name)) {
+            Ok(data) => Ok((kind, data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(Self::not_found()),
+            Err(err) => {
+                error!("Cannot read avatar {}", err);
+                Err(AppError::Internal)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::image::TINY_PNG;
+    use std::env;
+
+    #[test]
+    fn save_then_load_roundtrip() {
+        let dir = env::temp_dir().join(format!("avatars-{}", Uuid::new_v4()));
+        let repository = AvatarRepositoryImpl::new(dir.clone());
+        let file_name = repository
+            .save(&Uuid::new_v4(), ImageKind::Png, TINY_PNG)
+            .unwrap();
+        assert!(file_name.ends_with(".png"));
+        let (kind, data) = repository.load(&file_name).unwrap();
+        assert_eq!(ImageKind::Png, kind);
+        assert_eq!(TINY_PNG, data.as_slice());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_unsafe_names() {
+        let repository = AvatarRepositoryImpl::new(env::temp_dir());
+        assert!(matches!(
+            repository.load("../etc/passwd.png"),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            repository.load("abc.exe"),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use super::presenters::AvatarPresenter;
+use super::repositories::AvatarRepository;
+use crate::app::features::user::entities::UpdateUser;
+use crate::app::features::user::presenters::UserPresenter;
+use crate::app::features::user::repositories::UserRepository;
+use crate::error::AppError;
+use crate::utils::image;
+use actix_web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const IMAGE_PATH: &str = "/api/images";
+
+#[derive(Clone)]
+pub struct AvatarUsecase {
+    avatar_repository: Arc<dyn AvatarRepository>,
+    user_repository: Arc<dyn UserRepository>,
+    avatar_presenter: Arc<dyn AvatarPresenter>,
+    user_presenter: Arc<dyn UserPresenter>,
+}
+
+impl AvatarUsecase {
+    pub fn new(
+        avatar_repository: Arc<dyn AvatarRepository>,
+        user_repository: Arc<dyn UserRepository>,
+        avatar_presenter: Arc<dyn AvatarPresenter>,
+        user_presenter: Arc<dyn UserPresenter>,
+    ) -> Self {
+        Self {
+            avatar_repository,
+            user_repository,
+            avatar_presenter,
+            user_presenter,
+        }
+    }
+
+    pub fn upload_image(
+        &self,
+        user_id: Uuid,
+        content_type: Option<&str>,
+        data: &[u8],
+    ) -> Result<HttpResponse, AppError> {
+        let kind = image::validate(content_type, data)?;
+        let file_name = self.avatar_repository.save(&user_id, kind, data)?;
+        let (user, token) = self.user_repository.update(
+            user_id,
+            UpdateUser {
+                email: None,
+                username: None,
+                password: None,
+                image: Some(format!("{}/{}", IMAGE_PATH, file_name)),
+                bio: None,
+            },
+        )?;
+        let res = self.user_presenter.to_json(user, token);
+        Ok(res)
+    }
+
+    pub fn fetch_image(&self, file_name: &str) -> Result<HttpResponse, AppError> {
+        let (kind, data) = self.avatar_repository.load(file_name)?;
+        let res = self.avatar_presenter.to_image(kind, data);
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::features::avatar::presenters::AvatarPresenterImpl;
+    use crate::app::features::profile::entities::Profile;
+    use crate::app::features::user::entities::User;
+    use crate::app::features::user::presenters::UserPresenterImpl;
+    use crate::utils::image::{ImageKind, TINY_PNG};
+    use actix_web::body::to_bytes;
+    use chrono::NaiveDateTime;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeAvatarRepository {
+        saved: Mutex<Vec<(ImageKind, Vec<u8>)>>,
+    }
+
+    impl AvatarRepository for FakeAvatarRepository {
+        fn save(&self, user_id: &Uuid, kind: ImageKind, data: &[u8]) -> Result<String, AppError> {
+            self.saved.lock().unwrap().push((kind, data.to_vec()));
+            Ok(format!("{}.{}", user_id, kind.extension()))
+        }
+        fn load(&self, _file_name: &str) -> Result<(ImageKind, Vec<u8>), AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    struct FakeUserRepository;
+
+    impl UserRepository for FakeUserRepository {
+        fn signin(&self, _email: &str, _password: &str) -> Result<(User, String), AppError> {
+            Err(AppError::Internal)
+        }
+        fn signup(
+            &self,
+            _email: &str,
+            _username: &str,
+            _password: &str,
+        ) -> Result<(User, String), AppError> {
+            Err(AppError::Internal)
+        }
+        fn follow_user(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<(Profile, bool), AppError> {
+            Err(AppError::Internal)
+        }
+        fn unfollow_user(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<Profile, AppError> {
+            Err(AppError::Internal)
+        }
+        fn taken_fields(
+            &self,
+            _user_id: Uuid,
+            _changeset: &UpdateUser,
+        ) -> Result<Vec<&'static str>, AppError> {
+            Err(AppError::Internal)
+        }
+        fn update(&self, user_id: Uuid, changeset: UpdateUser) -> Result<(User, String), AppError> {
+            let now = NaiveDateTime::default();
+            let user = User {
+                id: user_id,
+                email: "jake@jake.jake".to_owned(),
+                username: "jake".to_owned(),
+                password: "hashed".to_owned(),
+                bio: None,
+                image: changeset.image,
+                created_at: now,
+                updated_at: now,
+                email_verified: true,
+                role: "user".to_owned(),
+                last_login_at: None,
+            };
+            Ok((user, "token".to_owned()))
+        }
+        fn find(&self, _user_id: Uuid) -> Result<User, AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    fn usecase(avatar_repository: Arc<FakeAvatarRepository>) -> AvatarUsecase {
+        AvatarUsecase::new(
+            avatar_repository,
+            Arc::new(FakeUserRepository),
+            Arc::new(AvatarPresenterImpl::new()),
+            Arc::new(UserPresenterImpl::new(true, 3600)),
+        )
+    }
+
+    #[actix_web::test]
+    async fn upload_image_stores_png_and_sets_user_image() {
+        let avatars = Arc::new(FakeAvatarRepository::default());
+        let user_id = Uuid::new_v4();
+        let res = usecase(avatars.clone())
+            .upload_image(user_id, Some("image/png"), TINY_PNG)
+            .unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            format!("/api/images/{}.png", user_id),
+            body["user"]["image"]
+        );
+        let saved = avatars.saved.lock().unwrap();
+        assert_eq!(vec![(ImageKind::Png, TINY_PNG.to_vec())], *saved);
+    }
+
+    #[test]
+    fn upload_image_rejects_non_image() {
+        let avatars = Arc::new(FakeAvatarRepository::default());
+        let res = usecase(avatars.clone()).upload_image(
+            Uuid::new_v4(),
+            Some("image/png"),
+            b"#!/bin/sh\necho hi\n",
+        );
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        assert!(avatars.saved.lock().unwrap().is_empty());
+    }
+}
@@ -1,19 +1,52 @@
 use super::request;
 use crate::app::drivers::middlewares::auth;
 use crate::app::drivers::middlewares::state::AppState;
-use crate::utils::api::ApiResponse;
-use crate::utils::uuid;
+use crate::utils::api::{self, ApiResponse};
+use crate::utils::pagination::Pagination;
+use crate::utils::{uuid, websocket};
 use actix_web::{web, HttpRequest};
+use serde::Deserialize;
 
 type ArticleIdSlug = String;
 type CommentIdSlug = String;
 
-pub async fn index(state: web::Data<AppState>, req: HttpRequest) -> ApiResponse {
-    let current_user = auth::get_current_user(&req).ok();
-    state
+#[derive(Deserialize)]
+pub struct CommentsListQueryParameter {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+pub async fn index(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleIdSlug>,
+    params: web::Query<CommentsListQueryParameter>,
+) -> ApiResponse {
+    let current_user = auth::get_current_user_optional(&req);
+    let article_title_slug = path.into_inner();
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state.di_container.comment_usecase.fetch_comments(
+        &article_title_slug,
+        &current_user,
+        pagination,
+        &api::page_url(&req),
+    )
+}
+
+// NOTE: browsers can't set headers on a WebSocket, so the token cookie is how a viewer sees drafts.
+pub async fn subscribe(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleIdSlug>,
+    payload: web::Payload,
+) -> ApiResponse {
+    let current_user = auth::get_current_user_optional(&req);
+    let article_title_slug = path.into_inner();
+    let feed = state
         .di_container
         .comment_usecase
-        .fetch_comments(&current_user)
+        .subscribe(&article_title_slug, &current_user)?;
+    Ok(websocket::push(&req, payload, feed))
 }
 
 pub async fn create(
@@ -25,10 +58,13 @@ pub async fn create(
     let current_user = auth::get_current_user(&req)?;
     let article_title_slug = path.into_inner();
     let body = form.comment.body.to_owned();
-    state
-        .di_container
-        .comment_usecase
-        .create_comment(body, article_title_slug, current_user)
+    let parent_id = form.comment.parent_id;
+    state.di_container.comment_usecase.create_comment(
+        body,
+        parent_id,
+        article_title_slug,
+        current_user,
+    )
 }
 
 pub async fn delete(
@@ -42,6 +78,6 @@ pub async fn delete(
     state.di_container.comment_usecase.delete_comment(
         &article_title_slug,
         comment_id,
-        current_user.id,
+        &current_user,
     )
 }
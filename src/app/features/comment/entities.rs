@@ -1,9 +1,9 @@
 use crate::app::features::article::entities::Article;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
-use crate::schema::comments;
+use crate::schema::{comments, users};
 use chrono::NaiveDateTime;
-use diesel::dsl::Eq;
+use diesel::dsl::{Desc, Eq, Filter, InnerJoin, Limit, Offset, Order};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -20,17 +20,45 @@ pub struct Comment {
     pub body: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub parent_id: Option<Uuid>,
+    pub hidden: bool,
 }
 
 type WithId<T> = Eq<comments::id, T>;
-type WithAuthor<T> = Eq<comments::author_id, T>;
+type WithArticleId<T> = Eq<comments::article_id, T>;
+type NotHidden = Eq<comments::hidden, bool>;
+type ByArticleId<T> = Offset<
+    Limit<
+        Order<
+            Filter<Filter<InnerJoin<comments::table, users::table>, WithArticleId<T>>, NotHidden>,
+            (Desc<comments::create_at>, Desc<comments::id>),
+        >,
+    >,
+>;
 
 impl Comment {
     fn with_id(id: &Uuid) -> WithId<&Uuid> {
         comments::id.eq(id)
     }
-    fn with_author(author_id: &Uuid) -> WithAuthor<&Uuid> {
-        comments::author_id.eq(author_id)
+
+    fn with_article_id(article_id: &Uuid) -> WithArticleId<&Uuid> {
+        comments::article_id.eq(article_id)
+    }
+
+    // NOTE: comments hidden by a moderator drop out of the public thread.
+    fn not_hidden() -> NotHidden {
+        comments::hidden.eq(false)
+    }
+
+    // NOTE: newest first; `id` breaks ties so pages don't overlap.
+    fn by_article_id(article_id: &Uuid, offset: i64, limit: i64) -> ByArticleId<&Uuid> {
+        comments::table
+            .inner_join(users::table)
+            .filter(Self::with_article_id(article_id))
+            .filter(Self::not_hidden())
+            .order((comments::create_at.desc(), comments::id.desc()))
+            .limit(limit)
+            .offset(offset)
     }
 }
 
@@ -42,32 +70,135 @@ impl Comment {
         Ok(new_comment)
     }
 
-    pub fn delete(
+    pub fn fetch_by_article_id(
         conn: &mut PgConnection,
-        (comment_id, author_id, slug): (&Uuid, &Uuid, &str),
-    ) -> Result<(), AppError> {
-        let subquery = {
-            use crate::schema::articles;
-            articles::table
-                .filter(articles::slug.eq(slug))
-                .filter(articles::author_id.eq(author_id))
-                .select(articles::id)
-        };
-
-        let query = comments::table
+        article_id: &Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<(Self, User)>, AppError> {
+        let t = Self::by_article_id(article_id, offset, limit);
+        let list = t.get_results::<(Self, User)>(conn)?;
+        Ok(list)
+    }
+
+    pub fn count_by_article_id(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+    ) -> Result<i64, AppError> {
+        let t = comments::table
+            .filter(Self::with_article_id(article_id))
+            .filter(Self::not_hidden());
+        let count = t.count().get_result::<i64>(conn)?;
+        Ok(count)
+    }
+
+    pub fn find_by_article_id(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+        comment_id: &Uuid,
+    ) -> Result<Self, AppError> {
+        let t = comments::table
             .filter(Self::with_id(comment_id))
-            .filter(Self::with_author(author_id))
-            .filter(comments::article_id.eq_any(subquery));
+            .filter(Self::with_article_id(article_id));
+        let comment = t.first::<Self>(conn)?;
+        Ok(comment)
+    }
 
-        diesel::delete(query).execute(conn)?;
+    pub fn set_hidden(
+        conn: &mut PgConnection,
+        comment_id: &Uuid,
+        hidden: bool,
+    ) -> Result<Self, AppError> {
+        let t = comments::table.filter(Self::with_id(comment_id));
+        let comment = diesel::update(t)
+            .set(comments::hidden.eq(hidden))
+            .get_result::<Self>(conn)?;
+        Ok(comment)
+    }
+
+    // NOTE: removes the comment and all of its replies; callers run it in one transaction.
+    pub fn delete_thread(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+        comment_id: &Uuid,
+    ) -> Result<(), AppError> {
+        let edges = comments::table
+            .filter(Self::with_article_id(article_id))
+            .select((comments::id, comments::parent_id))
+            .load::<(Uuid, Option<Uuid>)>(conn)?;
+        let ids = thread_ids(*comment_id, &edges);
+        diesel::delete(comments::table.filter(comments::id.eq_any(ids))).execute(conn)?;
         Ok(())
     }
 }
 
+// NOTE: `edges` are `(id, parent_id)` pairs; the result starts with `root_id` followed by its replies, breadth first.
+fn thread_ids(root_id: Uuid, edges: &[(Uuid, Option<Uuid>)]) -> Vec<Uuid> {
+    let mut ids = vec![root_id];
+    let mut i = 0;
+    while i < ids.len() {
+        let parent_id = ids[i];
+        ids.extend(
+            edges
+                .iter()
+                .filter(|(_, parent)| *parent == Some(parent_id))
+                .map(|(id, _)| *id),
+        );
+        i += 1;
+    }
+    ids
+}
+
 #[derive(Insertable, Clone)]
 #[diesel(table_name = comments)]
 pub struct CreateComment {
     pub body: String,
     pub author_id: Uuid,
     pub article_id: Uuid,
+    pub parent_id: Option<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+
+    #[test]
+    fn by_article_id_lists_only_that_article() {
+        let article_id = Uuid::nil();
+        let query = Comment::by_article_id(&article_id, 0, 20);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql
+            .contains(r#"WHERE (("comments"."article_id" = $1) AND ("comments"."hidden" = $2))"#));
+    }
+
+    #[test]
+    fn by_article_id_orders_newest_first() {
+        let article_id = Uuid::nil();
+        let query = Comment::by_article_id(&article_id, 40, 20);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"ORDER BY "comments"."create_at" DESC, "comments"."id" DESC LIMIT $3 OFFSET $4"#
+        ));
+        assert!(sql.contains("binds: [00000000-0000-0000-0000-000000000000, false, 20, 40]"));
+    }
+
+    #[test]
+    fn thread_ids_collects_nested_replies() {
+        let (root, reply, nested, other) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        let edges = vec![
+            (root, None),
+            (reply, Some(root)),
+            (nested, Some(reply)),
+            (other, None),
+        ];
+        assert_eq!(vec![root, reply, nested], thread_ids(root, &edges));
+        assert_eq!(vec![nested], thread_ids(nested, &edges));
+    }
 }
@@ -0,0 +1,65 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+// NOTE: a subscriber that falls this far behind skips ahead instead of holding up the others.
+const CAPACITY: usize = 64;
+
+// Newly created comments for live article pages, one broadcast channel per article.
+// NOTE: keyed by article id rather than slug, since editing the title changes the slug.
+#[derive(Clone, Default)]
+pub struct CommentFeed {
+    channels: Arc<DashMap<Uuid, broadcast::Sender<String>>>,
+}
+
+impl CommentFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, article_id: Uuid) -> broadcast::Receiver<String> {
+        // NOTE: disconnected subscribers drop their receivers; forget channels nobody listens to.
+        self.channels
+            .retain(|_, sender| sender.receiver_count() > 0);
+        self.channels
+            .entry(article_id)
+            .or_insert_with(|| broadcast::channel(CAPACITY).0)
+            .subscribe()
+    }
+
+    pub fn publish(&self, article_id: Uuid, message: String) {
+        if let Some(sender) = self.channels.get(&article_id) {
+            // NOTE: fails only when every subscriber is gone, which is fine to ignore.
+            let _ = sender.send(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_receive_comments_for_their_article_only() {
+        let feed = CommentFeed::new();
+        let (dragons, rust) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut receiver = feed.subscribe(dragons);
+
+        feed.publish(rust, "rust".to_owned());
+        feed.publish(dragons, "dragons".to_owned());
+        assert_eq!("dragons", receiver.try_recv().unwrap());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn channels_without_subscribers_are_dropped() {
+        let feed = CommentFeed::new();
+        let receiver = feed.subscribe(Uuid::new_v4());
+        assert_eq!(1, feed.channels.len());
+
+        drop(receiver);
+        let _receiver = feed.subscribe(Uuid::new_v4());
+        assert_eq!(1, feed.channels.len());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod controllers;
 pub mod entities;
+pub mod feed;
 pub mod presenters;
 pub mod repositories;
 pub mod request;
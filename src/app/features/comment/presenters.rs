@@ -1,8 +1,10 @@
 use crate::app::features::comment::entities::Comment;
+use crate::app::features::comment::repositories::CommentsList;
 use crate::app::features::profile::entities::Profile;
 use crate::utils::date::Iso8601;
 use actix_web::HttpResponse;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::convert::From;
 use uuid::Uuid;
 
@@ -16,6 +18,7 @@ impl From<(Comment, Profile)> for SingleCommentResponse {
         Self {
             comment: InnerComment {
                 id: comment.id,
+                parent_id: comment.parent_id,
                 body: comment.body,
                 author: InnerAuthor {
                     username: profile.username,
@@ -31,19 +34,23 @@ impl From<(Comment, Profile)> for SingleCommentResponse {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MultipleCommentsResponse {
     pub comments: Vec<InnerComment>,
+    pub comments_count: i64,
 }
 
-impl From<Vec<(Comment, Profile)>> for MultipleCommentsResponse {
-    fn from(list: Vec<(Comment, Profile)>) -> Self {
+impl From<(CommentsList, i64)> for MultipleCommentsResponse {
+    fn from((list, comments_count): (CommentsList, i64)) -> Self {
         Self {
+            comments_count,
             comments: list
                 .into_iter()
                 .map(|item| {
                     let (comment, profile) = item;
                     InnerComment {
                         id: comment.id,
+                        parent_id: comment.parent_id,
                         created_at: Iso8601(comment.created_at),
                         updated_at: Iso8601(comment.updated_at),
                         body: comment.body,
@@ -64,6 +71,7 @@ impl From<Vec<(Comment, Profile)>> for MultipleCommentsResponse {
 #[serde(rename_all = "camelCase")]
 pub struct InnerComment {
     pub id: Uuid,
+    pub parent_id: Option<Uuid>,
     pub created_at: Iso8601,
     pub updated_at: Iso8601,
     pub body: String,
@@ -81,10 +89,15 @@ pub struct InnerAuthor {
 pub trait CommentPresenter: Send + Sync + 'static {
     fn to_http_res(&self) -> HttpResponse;
     fn to_single_json(&self, item: (Comment, Profile)) -> HttpResponse;
-    fn to_multi_json(&self, list: Vec<(Comment, Profile)>) -> HttpResponse;
+    fn to_multi_json(
+        &self,
+        list: CommentsList,
+        count: i64,
+        followed_author_ids: &HashSet<Uuid>,
+    ) -> HttpResponse;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CommentPresenterImpl {}
 impl CommentPresenterImpl {
     pub fn new() -> Self {
@@ -96,8 +109,23 @@ impl CommentPresenter for CommentPresenterImpl {
         HttpResponse::Ok().json("OK")
     }
 
-    fn to_multi_json(&self, list: Vec<(Comment, Profile)>) -> HttpResponse {
-        let res = MultipleCommentsResponse::from(list);
+    fn to_multi_json(
+        &self,
+        list: CommentsList,
+        count: i64,
+        followed_author_ids: &HashSet<Uuid>,
+    ) -> HttpResponse {
+        let list = list
+            .into_iter()
+            .map(|(comment, profile)| {
+                let profile = Profile {
+                    following: followed_author_ids.contains(&comment.author_id),
+                    ..profile
+                };
+                (comment, profile)
+            })
+            .collect();
+        let res = MultipleCommentsResponse::from((list, count));
         HttpResponse::Ok().json(res)
     }
 
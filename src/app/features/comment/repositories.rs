@@ -1,111 +1,158 @@
 use super::entities::{Comment, CreateComment};
 use crate::{
-    app::features::{
-        article::entities::{Article, FetchBySlugAndAuthorId},
-        profile::entities::Profile,
-        user::entities::User,
-    },
+    app::features::{article::entities::Article, profile::entities::Profile, user::entities::User},
     error::AppError,
-    utils::db::DbPool,
+    utils::{
+        db::{self, DbPools},
+        gravatar,
+        pagination::Pagination,
+    },
 };
 use uuid::Uuid;
 
+pub type CommentsList = Vec<(Comment, Profile)>;
+type CommentsCount = i64;
+
+// NOTE: a reply must point at a comment on the same article.
+pub fn invalid_parent() -> AppError {
+    AppError::invalid("parentId", "is invalid")
+}
+
 pub trait CommentRepository: Send + Sync + 'static {
-    fn fetch_comments(
+    fn list_by_article(
         &self,
+        article_title_slug: &str,
         current_user: &Option<User>,
-    ) -> Result<Vec<(Comment, Profile)>, AppError>;
+        pagination: Pagination,
+    ) -> Result<(CommentsList, CommentsCount), AppError>;
 
     fn create_comment(
         &self,
         body: String,
+        parent_id: Option<Uuid>,
         article_title_slug: String,
         author: User,
     ) -> Result<(Comment, Profile), AppError>;
 
-    fn delete_comment(
+    fn find_comment(
         &self,
         article_title_slug: &str,
         comment_id: Uuid,
-        author_id: Uuid,
-    ) -> Result<(), AppError>;
+        current_user: &User,
+    ) -> Result<Comment, AppError>;
+
+    fn delete_comment(&self, article_id: Uuid, comment_id: Uuid) -> Result<(), AppError>;
+
+    fn find_article_id(
+        &self,
+        article_title_slug: &str,
+        current_user: &Option<User>,
+    ) -> Result<Uuid, AppError>;
 }
 
 #[derive(Clone)]
 pub struct CommentRepositoryImpl {
-    pool: DbPool,
+    pools: DbPools,
 }
 
 impl CommentRepositoryImpl {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
     }
 }
 impl CommentRepository for CommentRepositoryImpl {
-    fn fetch_comments(
+    fn list_by_article(
         &self,
+        article_title_slug: &str,
         current_user: &Option<User>,
-    ) -> Result<Vec<(Comment, Profile)>, AppError> {
-        let conn = &mut self.pool.get()?;
-
-        let comments = {
-            use crate::schema::comments;
-            use crate::schema::users;
-            use diesel::prelude::*;
-            comments::table
-                .inner_join(users::table)
-                .get_results::<(Comment, User)>(conn)?
-        };
+        pagination: Pagination,
+    ) -> Result<(CommentsList, CommentsCount), AppError> {
+        let conn = &mut self.pools.read_conn()?;
+
+        let viewer_id = current_user.as_ref().map(|user| &user.id);
+        let (article, _author) =
+            Article::fetch_by_slug_with_author(conn, article_title_slug, viewer_id)?;
+        let comments =
+            Comment::fetch_by_article_id(conn, &article.id, pagination.offset, pagination.limit)?;
+        let count = Comment::count_by_article_id(conn, &article.id)?;
 
         let comments = comments
-            .iter()
+            .into_iter()
             .map(|(comment, user)| {
-                // TODO: avoid N+1. Write one query to fetch all data somehow.
-                let profile = user.to_profile(conn, current_user);
-
-                // TODO: avoid copy
-                (comment.to_owned(), profile)
+                let profile = Profile {
+                    username: user.username,
+                    bio: user.bio,
+                    image: Some(gravatar::image_or_default(user.image, &user.email)),
+                    following: false, // NOTE: resolved in one batch by the usecase
+                };
+                (comment, profile)
             })
-            .collect::<Vec<(Comment, Profile)>>();
+            .collect::<CommentsList>();
 
-        Ok(comments)
+        Ok((comments, count))
     }
 
     fn create_comment(
         &self,
         body: String,
+        parent_id: Option<Uuid>,
         article_title_slug: String,
         author: User,
     ) -> Result<(Comment, Profile), AppError> {
-        let conn = &mut self.pool.get()?;
+        let conn = &mut self.pools.write_conn()?;
 
-        let article = Article::fetch_by_slug_and_author_id(
-            conn,
-            &FetchBySlugAndAuthorId {
-                slug: article_title_slug,
-                author_id: author.id,
-            },
-        )?;
+        // NOTE: anyone may comment on an article they can read, not only its author.
+        let (article, _author) =
+            Article::fetch_by_slug_with_author(conn, &article_title_slug, Some(&author.id))?;
+        if let Some(parent_id) = &parent_id {
+            match Comment::find_by_article_id(conn, &article.id, parent_id) {
+                Ok(_) => {}
+                Err(AppError::NotFound(_)) => return Err(invalid_parent()),
+                Err(err) => return Err(err),
+            }
+        }
         let comment = Comment::create(
             conn,
             &CreateComment {
                 body,
                 author_id: author.id,
-                article_id: article.id.to_owned(),
+                article_id: article.id,
+                parent_id,
             },
         )?;
         let profile = author.fetch_profile(conn, &author.id)?;
         Ok((comment, profile))
     }
 
-    fn delete_comment(
+    fn find_comment(
         &self,
         article_title_slug: &str,
         comment_id: Uuid,
-        author_id: Uuid,
-    ) -> Result<(), AppError> {
-        let conn = &mut self.pool.get()?;
-        let _ = Comment::delete(conn, (&comment_id, &author_id, article_title_slug));
-        Ok(())
+        current_user: &User,
+    ) -> Result<Comment, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let (article, _author) =
+            Article::fetch_by_slug_with_author(conn, article_title_slug, Some(&current_user.id))?;
+        Comment::find_by_article_id(conn, &article.id, &comment_id)
+    }
+
+    fn delete_comment(&self, article_id: Uuid, comment_id: Uuid) -> Result<(), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        // NOTE: the thread is read then deleted; a reply posted in between must not be orphaned.
+        db::with_transaction(conn, |conn| {
+            Comment::delete_thread(conn, &article_id, &comment_id)
+        })
+    }
+
+    fn find_article_id(
+        &self,
+        article_title_slug: &str,
+        current_user: &Option<User>,
+    ) -> Result<Uuid, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let viewer_id = current_user.as_ref().map(|user| &user.id);
+        let (article, _author) =
+            Article::fetch_by_slug_with_author(conn, article_title_slug, viewer_id)?;
+        Ok(article.id)
     }
 }
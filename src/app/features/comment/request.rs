@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize)]
 pub struct CreateCommentRequest {
@@ -6,6 +7,8 @@ pub struct CreateCommentRequest {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct InnerComment {
     pub body: String,
+    pub parent_id: Option<Uuid>,
 }
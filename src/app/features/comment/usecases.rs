@@ -1,57 +1,563 @@
-use super::presenters::CommentPresenter;
+use super::feed::CommentFeed;
+use super::presenters::{CommentPresenter, SingleCommentResponse};
 use super::repositories::CommentRepository;
+use crate::app::features::profile::repositories::ProfileRepository;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
+use crate::utils::api;
+use crate::utils::pagination::Pagination;
 use actix_web::HttpResponse;
+use futures::stream::{self, Stream};
+use std::collections::HashSet;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct CommentUsecase {
     comment_repository: Arc<dyn CommentRepository>,
     comment_presenter: Arc<dyn CommentPresenter>,
+    profile_repository: Arc<dyn ProfileRepository>,
+    comment_feed: CommentFeed,
 }
 
 impl CommentUsecase {
     pub fn new(
         comment_repository: Arc<dyn CommentRepository>,
         comment_presenter: Arc<dyn CommentPresenter>,
+        profile_repository: Arc<dyn ProfileRepository>,
+        comment_feed: CommentFeed,
     ) -> Self {
         Self {
             comment_repository,
             comment_presenter,
+            profile_repository,
+            comment_feed,
         }
     }
 
-    pub fn fetch_comments(&self, user: &Option<User>) -> Result<HttpResponse, AppError> {
-        let result = self.comment_repository.fetch_comments(user)?;
-        let res = self.comment_presenter.to_multi_json(result);
+    pub fn fetch_comments(
+        &self,
+        article_title_slug: &str,
+        user: &Option<User>,
+        pagination: Pagination,
+        page_url: &str,
+    ) -> Result<HttpResponse, AppError> {
+        let (list, count) =
+            self.comment_repository
+                .list_by_article(article_title_slug, user, pagination)?;
+        // NOTE: one query for the whole page instead of one per author.
+        let followed = match user {
+            Some(user) => {
+                let mut author_ids = list
+                    .iter()
+                    .map(|(comment, _)| comment.author_id)
+                    .collect::<Vec<_>>();
+                author_ids.sort();
+                author_ids.dedup();
+                self.profile_repository
+                    .following_map(&user.id, &author_ids)?
+            }
+            None => HashSet::new(),
+        };
+        let mut res = self.comment_presenter.to_multi_json(list, count, &followed);
+        api::set_link_header(
+            &mut res,
+            page_url,
+            pagination.limit,
+            pagination.offset,
+            count,
+        );
         Ok(res)
     }
 
     pub fn create_comment(
         &self,
         body: String,
+        parent_id: Option<Uuid>,
         article_title_slug: String,
         author: User,
     ) -> Result<HttpResponse, AppError> {
-        let result = self
-            .comment_repository
-            .create_comment(body, article_title_slug, author)?;
+        let result =
+            self.comment_repository
+                .create_comment(body, parent_id, article_title_slug, author)?;
+        let article_id = result.0.article_id;
+        match serde_json::to_string(&SingleCommentResponse::from(result.clone())) {
+            Ok(message) => self.comment_feed.publish(article_id, message),
+            Err(err) => error!("Cannot serialize comment for the feed {}", err),
+        }
         let res = self.comment_presenter.to_single_json(result);
         Ok(res)
     }
 
+    // NOTE: yields each new comment on the article as the JSON body `create_comment` returns.
+    pub fn subscribe(
+        &self,
+        article_title_slug: &str,
+        user: &Option<User>,
+    ) -> Result<impl Stream<Item = String> + Unpin, AppError> {
+        let article_id = self
+            .comment_repository
+            .find_article_id(article_title_slug, user)?;
+        let receiver = self.comment_feed.subscribe(article_id);
+        Ok(Box::pin(stream::unfold(receiver, |mut receiver| async {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => return Some((message, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Comment feed subscriber skipped {} comments", skipped)
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })))
+    }
+
     pub fn delete_comment(
         &self,
         article_title_slug: &str,
         comment_id: Uuid,
-        author_id: Uuid,
+        current_user: &User,
     ) -> Result<HttpResponse, AppError> {
-        let _ = self
-            .comment_repository
-            .delete_comment(article_title_slug, comment_id, author_id);
+        let comment =
+            self.comment_repository
+                .find_comment(article_title_slug, comment_id, current_user)?;
+        if comment.author_id != current_user.id {
+            return Err(AppError::Forbidden(
+                "Only the author can delete this comment.".into(),
+            ));
+        }
+        self.comment_repository
+            .delete_comment(comment.article_id, comment.id)?;
         let res = self.comment_presenter.to_http_res();
         Ok(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::features::comment::entities::Comment;
+    use crate::app::features::comment::presenters::CommentPresenterImpl;
+    use crate::app::features::comment::repositories::{invalid_parent, CommentsList};
+    use crate::app::features::profile::entities::{FollowCounts, Profile};
+    use actix_web::body::to_bytes;
+    use actix_web::http::StatusCode;
+    use chrono::NaiveDateTime;
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeCommentRepository {
+        comments: Mutex<Vec<(Comment, User)>>,
+    }
+
+    impl FakeCommentRepository {
+        fn profile(user: &User) -> Profile {
+            Profile {
+                username: user.username.to_owned(),
+                bio: user.bio.to_owned(),
+                image: user.image.to_owned(),
+                following: false,
+            }
+        }
+    }
+
+    impl CommentRepository for FakeCommentRepository {
+        fn list_by_article(
+            &self,
+            _article_title_slug: &str,
+            _current_user: &Option<User>,
+            pagination: Pagination,
+        ) -> Result<(CommentsList, i64), AppError> {
+            let comments = self.comments.lock().unwrap();
+            let list = comments
+                .iter()
+                .rev()
+                .skip(pagination.offset as usize)
+                .take(pagination.limit as usize)
+                .map(|(comment, user)| (comment.clone(), Self::profile(user)))
+                .collect();
+            Ok((list, comments.len() as i64))
+        }
+
+        fn create_comment(
+            &self,
+            body: String,
+            parent_id: Option<Uuid>,
+            _article_title_slug: String,
+            author: User,
+        ) -> Result<(Comment, Profile), AppError> {
+            if let Some(parent_id) = parent_id {
+                self.find_comment("", parent_id, &author)
+                    .map_err(|_| invalid_parent())?;
+            }
+            let now = NaiveDateTime::default();
+            let comment = Comment {
+                id: Uuid::new_v4(),
+                article_id: Uuid::nil(),
+                author_id: author.id,
+                body,
+                created_at: now,
+                updated_at: now,
+                parent_id,
+                hidden: false,
+            };
+            let profile = Self::profile(&author);
+            self.comments
+                .lock()
+                .unwrap()
+                .push((comment.clone(), author));
+            Ok((comment, profile))
+        }
+
+        fn find_comment(
+            &self,
+            _article_title_slug: &str,
+            comment_id: Uuid,
+            _current_user: &User,
+        ) -> Result<Comment, AppError> {
+            self.comments
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(comment, _)| comment.clone())
+                .find(|comment| comment.id == comment_id)
+                .ok_or_else(|| AppError::NotFound("not found".into()))
+        }
+
+        fn delete_comment(&self, _article_id: Uuid, comment_id: Uuid) -> Result<(), AppError> {
+            self.comments.lock().unwrap().retain(|(comment, _)| {
+                comment.id != comment_id && comment.parent_id != Some(comment_id)
+            });
+            Ok(())
+        }
+
+        fn find_article_id(
+            &self,
+            _article_title_slug: &str,
+            _current_user: &Option<User>,
+        ) -> Result<Uuid, AppError> {
+            Ok(Uuid::nil())
+        }
+    }
+
+    // NOTE: the viewer follows every author named "jake".
+    struct FakeProfileRepository {
+        comments: Arc<FakeCommentRepository>,
+        following_queries: Mutex<Vec<Vec<Uuid>>>,
+    }
+
+    impl ProfileRepository for FakeProfileRepository {
+        fn fetch_profile_by_name(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<Profile, AppError> {
+            Err(AppError::Internal)
+        }
+        fn follow_counts(&self, _username: &str) -> Result<FollowCounts, AppError> {
+            Err(AppError::Internal)
+        }
+        fn following_map(
+            &self,
+            _current_user_id: &Uuid,
+            user_ids: &[Uuid],
+        ) -> Result<HashSet<Uuid>, AppError> {
+            self.following_queries
+                .lock()
+                .unwrap()
+                .push(user_ids.to_vec());
+            let comments = self.comments.comments.lock().unwrap();
+            Ok(comments
+                .iter()
+                .filter(|(_, author)| author.username == "jake")
+                .map(|(_, author)| author.id)
+                .collect())
+        }
+        fn profiles_by_usernames(
+            &self,
+            _current_user: &Option<User>,
+            _usernames: &[String],
+        ) -> Result<Vec<Profile>, AppError> {
+            Err(AppError::Internal)
+        }
+        fn search(
+            &self,
+            _current_user: &Option<User>,
+            _query: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn mutuals(
+            &self,
+            _current_user: &User,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn followers(
+            &self,
+            _current_user: &Option<User>,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn following(
+            &self,
+            _current_user: &Option<User>,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    fn user(username: &str) -> User {
+        let now = NaiveDateTime::default();
+        User {
+            id: Uuid::new_v4(),
+            email: format!("{}@example.com", username),
+            username: username.to_owned(),
+            password: "hashed".to_owned(),
+            bio: None,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            email_verified: true,
+            role: "user".to_owned(),
+            last_login_at: None,
+        }
+    }
+
+    const URL: &str = "http://localhost:8080/api/articles/dragons/comments";
+
+    fn usecase_with_profiles(
+        repository: Arc<FakeCommentRepository>,
+    ) -> (CommentUsecase, Arc<FakeProfileRepository>) {
+        let profile_repository = Arc::new(FakeProfileRepository {
+            comments: repository.clone(),
+            following_queries: Mutex::new(vec![]),
+        });
+        let usecase = CommentUsecase::new(
+            repository,
+            Arc::new(CommentPresenterImpl::new()),
+            profile_repository.clone(),
+            CommentFeed::new(),
+        );
+        (usecase, profile_repository)
+    }
+
+    fn usecase(repository: Arc<FakeCommentRepository>) -> CommentUsecase {
+        usecase_with_profiles(repository).0
+    }
+
+    async fn json_body(res: HttpResponse) -> serde_json::Value {
+        let body = to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn create_comment_returns_comment_with_author() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let res = usecase(repository)
+            .create_comment(
+                "Thank you!".to_owned(),
+                None,
+                "dragons".to_owned(),
+                user("jake"),
+            )
+            .unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        let body = json_body(res).await;
+        assert_eq!("Thank you!", body["comment"]["body"]);
+        assert_eq!("jake", body["comment"]["author"]["username"]);
+    }
+
+    #[actix_web::test]
+    async fn fetch_comments_lists_created_comments() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let usecase = usecase(repository);
+        usecase
+            .create_comment("First".to_owned(), None, "dragons".to_owned(), user("jake"))
+            .unwrap();
+        usecase
+            .create_comment(
+                "Second".to_owned(),
+                None,
+                "dragons".to_owned(),
+                user("anna"),
+            )
+            .unwrap();
+
+        let res = usecase
+            .fetch_comments("dragons", &None, Pagination::default(), URL)
+            .unwrap();
+        let body = json_body(res).await;
+        let comments = body["comments"].as_array().unwrap();
+        assert_eq!(2, comments.len());
+        assert_eq!(2, body["commentsCount"]);
+        assert_eq!("Second", comments[0]["body"]);
+        assert_eq!("jake", comments[1]["author"]["username"]);
+    }
+
+    #[actix_web::test]
+    async fn fetch_comments_resolves_following_in_one_query() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let (usecase, profile_repository) = usecase_with_profiles(repository);
+        let (jake, anna) = (user("jake"), user("anna"));
+        for author in [&jake, &anna, &jake] {
+            usecase
+                .create_comment("Hi".to_owned(), None, "dragons".to_owned(), author.clone())
+                .unwrap();
+        }
+
+        let res = usecase
+            .fetch_comments("dragons", &Some(user("viewer")), Pagination::default(), URL)
+            .unwrap();
+        let following_queries = profile_repository.following_queries.lock().unwrap().clone();
+        assert_eq!(1, following_queries.len());
+        assert_eq!(2, following_queries[0].len());
+
+        let body = json_body(res).await;
+        let following = body["comments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|comment| comment["author"]["following"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![json!(true), json!(false), json!(true)], following);
+    }
+
+    #[actix_web::test]
+    async fn fetch_comments_beyond_the_end_is_empty() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let usecase = usecase(repository);
+        usecase
+            .create_comment("First".to_owned(), None, "dragons".to_owned(), user("jake"))
+            .unwrap();
+
+        let res = usecase
+            .fetch_comments(
+                "dragons",
+                &None,
+                Pagination::from_query(None, Some(40)),
+                URL,
+            )
+            .unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let body = json_body(res).await;
+        assert_eq!(0, body["comments"].as_array().unwrap().len());
+        assert_eq!(1, body["commentsCount"]);
+    }
+
+    #[actix_web::test]
+    async fn subscribers_receive_new_comments() {
+        let usecase = usecase(Arc::new(FakeCommentRepository::default()));
+        let mut feed = usecase.subscribe("dragons", &None).unwrap();
+
+        usecase
+            .create_comment(
+                "Thank you!".to_owned(),
+                None,
+                "dragons".to_owned(),
+                user("jake"),
+            )
+            .unwrap();
+
+        let message = feed.next().await.unwrap();
+        let message: serde_json::Value = serde_json::from_str(&message).unwrap();
+        assert_eq!("Thank you!", message["comment"]["body"]);
+        assert_eq!("jake", message["comment"]["author"]["username"]);
+    }
+
+    #[test]
+    fn delete_comment_rejects_other_users() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let usecase = usecase(repository.clone());
+        let author = user("jake");
+        let (comment, _) = repository
+            .create_comment(
+                "Mine".to_owned(),
+                None,
+                "dragons".to_owned(),
+                author.clone(),
+            )
+            .unwrap();
+
+        let res = usecase.delete_comment("dragons", comment.id, &user("anna"));
+        assert!(matches!(res, Err(AppError::Forbidden(_))));
+        assert_eq!(1, repository.comments.lock().unwrap().len());
+
+        usecase
+            .delete_comment("dragons", comment.id, &author)
+            .unwrap();
+        assert!(repository.comments.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn create_comment_as_reply() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let usecase = usecase(repository.clone());
+        let (parent, _) = repository
+            .create_comment(
+                "Question?".to_owned(),
+                None,
+                "dragons".to_owned(),
+                user("jake"),
+            )
+            .unwrap();
+
+        let res = usecase
+            .create_comment(
+                "Answer.".to_owned(),
+                Some(parent.id),
+                "dragons".to_owned(),
+                user("anna"),
+            )
+            .unwrap();
+        let body = json_body(res).await;
+        assert_eq!(parent.id.to_string(), body["comment"]["parentId"]);
+
+        let res = usecase.create_comment(
+            "Orphan".to_owned(),
+            Some(Uuid::new_v4()),
+            "dragons".to_owned(),
+            user("anna"),
+        );
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+    }
+
+    #[test]
+    fn delete_comment_removes_replies() {
+        let repository = Arc::new(FakeCommentRepository::default());
+        let usecase = usecase(repository.clone());
+        let author = user("jake");
+        let (parent, _) = repository
+            .create_comment(
+                "Question?".to_owned(),
+                None,
+                "dragons".to_owned(),
+                author.clone(),
+            )
+            .unwrap();
+        repository
+            .create_comment(
+                "Answer.".to_owned(),
+                Some(parent.id),
+                "dragons".to_owned(),
+                user("anna"),
+            )
+            .unwrap();
+
+        usecase
+            .delete_comment("dragons", parent.id, &author)
+            .unwrap();
+        assert!(repository.comments.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+use crate::app::drivers::middlewares::state::AppState;
+use crate::utils::api::ApiResponse;
+use actix_web::web;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct VerifyQueryParameter {
+    token: String,
+}
+
+pub async fn verify(
+    state: web::Data<AppState>,
+    params: web::Query<VerifyQueryParameter>,
+) -> ApiResponse {
+    state
+        .di_container
+        .email_verification_usecase
+        .verify(&params.token)
+}
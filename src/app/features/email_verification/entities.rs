@@ -0,0 +1,48 @@
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::schema::email_verifications;
+use chrono::NaiveDateTime;
+use diesel::dsl::Eq;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Identifiable, Queryable, Associations, Serialize, Deserialize, Debug, Clone)]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+#[diesel(table_name = email_verifications)]
+pub struct EmailVerification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+type WithToken<T> = Eq<email_verifications::token, T>;
+
+impl EmailVerification {
+    fn with_token(token: &Uuid) -> WithToken<&Uuid> {
+        email_verifications::token.eq(token)
+    }
+}
+
+impl EmailVerification {
+    pub fn create(conn: &mut PgConnection, user_id: &Uuid) -> Result<Self, AppError> {
+        let item = diesel::insert_into(email_verifications::table)
+            .values(email_verifications::user_id.eq(user_id))
+            .get_result::<Self>(conn)?;
+        Ok(item)
+    }
+
+    pub fn find_by_token(conn: &mut PgConnection, token: &Uuid) -> Result<Option<Self>, AppError> {
+        let t = email_verifications::table.filter(Self::with_token(token));
+        let item = t.first::<Self>(conn).optional()?;
+        Ok(item)
+    }
+
+    pub fn delete(&self, conn: &mut PgConnection) -> Result<(), AppError> {
+        diesel::delete(self).execute(conn)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use actix_web::HttpResponse;
+
+pub trait EmailVerificationPresenter: Send + Sync + 'static {
+    fn to_http_res(&self) -> HttpResponse;
+}
+
+#[derive(Clone, Default)]
+pub struct EmailVerificationPresenterImpl {}
+impl EmailVerificationPresenterImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl EmailVerificationPresenter for EmailVerificationPresenterImpl {
+    fn to_http_res(&self) -> HttpResponse {
+        HttpResponse::Ok().json(())
+    }
+}
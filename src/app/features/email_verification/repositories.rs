@@ -0,0 +1,37 @@
+use super::entities::EmailVerification;
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::utils::db::{self, DbPool};
+use uuid::Uuid;
+
+pub trait EmailVerificationRepository: Send + Sync + 'static {
+    fn verify(&self, token: &Uuid) -> Result<User, AppError>;
+}
+
+pub fn invalid_token() -> AppError {
+    AppError::invalid("token", "is invalid")
+}
+
+#[derive(Clone)]
+pub struct EmailVerificationRepositoryImpl {
+    pool: DbPool,
+}
+
+impl EmailVerificationRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl EmailVerificationRepository for EmailVerificationRepositoryImpl {
+    fn verify(&self, token: &Uuid) -> Result<User, AppError> {
+        let conn = &mut self.pool.get()?;
+        db::with_transaction(conn, |conn| {
+            let verification =
+                EmailVerification::find_by_token(conn, token)?.ok_or_else(invalid_token)?;
+            let user = User::mark_email_verified(conn, &verification.user_id)?;
+            verification.delete(conn)?;
+            Ok(user)
+        })
+    }
+}
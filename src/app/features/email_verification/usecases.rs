@@ -0,0 +1,32 @@
+use super::presenters::EmailVerificationPresenter;
+use super::repositories::{invalid_token, EmailVerificationRepository};
+use crate::error::AppError;
+use actix_web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct EmailVerificationUsecase {
+    email_verification_repository: Arc<dyn EmailVerificationRepository>,
+    email_verification_presenter: Arc<dyn EmailVerificationPresenter>,
+}
+
+impl EmailVerificationUsecase {
+    pub fn new(
+        email_verification_repository: Arc<dyn EmailVerificationRepository>,
+        email_verification_presenter: Arc<dyn EmailVerificationPresenter>,
+    ) -> Self {
+        Self {
+            email_verification_repository,
+            email_verification_presenter,
+        }
+    }
+
+    pub fn verify(&self, token: &str) -> Result<HttpResponse, AppError> {
+        let token = Uuid::parse_str(token).map_err(|_err| invalid_token())?;
+        let user = self.email_verification_repository.verify(&token)?;
+        info!("Email verified for user {}", user.id);
+        let res = self.email_verification_presenter.to_http_res();
+        Ok(res)
+    }
+}
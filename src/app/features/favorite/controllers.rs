@@ -1,10 +1,27 @@
 use crate::app::drivers::middlewares::auth;
 use crate::app::drivers::middlewares::state::AppState;
+use crate::app::features::article::presenters::SingleArticleResponse;
+use crate::app::features::favorite::presenters::MultipleProfilesResponse;
 use crate::utils::api::ApiResponse;
+use crate::utils::pagination::Pagination;
 use actix_web::{web, HttpRequest};
+use serde::Deserialize;
+use utoipa::IntoParams;
 
 type ArticleIdSlug = String;
 
+#[utoipa::path(
+    post,
+    path = "/api/articles/{article_title_slug}/favorite",
+    tag = "favorite",
+    params(("article_title_slug" = String, Path)),
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article not found"),
+    ),
+    security(("token" = []))
+)]
 pub async fn favorite(
     state: web::Data<AppState>,
     req: HttpRequest,
@@ -18,6 +35,18 @@ pub async fn favorite(
         .favorite_article(current_user, article_title_slug)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/articles/{article_title_slug}/favorite",
+    tag = "favorite",
+    params(("article_title_slug" = String, Path)),
+    responses(
+        (status = 200, body = SingleArticleResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article not found"),
+    ),
+    security(("token" = []))
+)]
 pub async fn unfavorite(
     state: web::Data<AppState>,
     req: HttpRequest,
@@ -30,3 +59,36 @@ pub async fn unfavorite(
         .favorite_usecase
         .unfavorite_article(current_user, article_title_slug)
 }
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FavoritedByQueryParameter {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/articles/{article_title_slug}/favorited-by",
+    tag = "favorite",
+    params(("article_title_slug" = String, Path), FavoritedByQueryParameter),
+    responses(
+        (status = 200, body = MultipleProfilesResponse),
+        (status = 404, description = "Article not found"),
+    )
+)]
+pub async fn favorited_by(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleIdSlug>,
+    params: web::Query<FavoritedByQueryParameter>,
+) -> ApiResponse {
+    let viewer_id = auth::get_current_user_optional(&req).map(|user| user.id);
+    let article_title_slug = path.into_inner();
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state.di_container.favorite_usecase.fetch_favorited_by(
+        viewer_id,
+        &article_title_slug,
+        pagination,
+    )
+}
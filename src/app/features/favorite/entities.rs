@@ -1,11 +1,13 @@
 use crate::app::features::article::entities::Article;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
-use crate::schema::favorites;
+use crate::schema::{favorites, users};
+use crate::utils::pagination::{Page, Pagination};
 use chrono::NaiveDateTime;
-use diesel::dsl::Eq;
+use diesel::dsl::{Asc, Eq, EqAny, Filter, Limit, Offset, Order, Select};
 use diesel::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Queryable, Identifiable, Associations, Clone, Debug)]
@@ -22,6 +24,23 @@ pub struct Favorite {
 
 type WithUserId<T> = Eq<favorites::user_id, T>;
 type WithArticleId<T> = Eq<favorites::article_id, T>;
+type ArticleIdsByUserId<T> = Select<Filter<favorites::table, WithUserId<T>>, favorites::article_id>;
+type UserIdsByArticleId<T> = Select<Filter<favorites::table, WithArticleId<T>>, favorites::user_id>;
+type FavoritersOf<'a> = Offset<
+    Limit<
+        Order<
+            Filter<users::table, EqAny<users::id, UserIdsByArticleId<&'a Uuid>>>,
+            (Asc<users::username>, Asc<users::id>),
+        >,
+    >,
+>;
+type FavoritedArticleIds<'a> = Select<
+    Filter<
+        Filter<favorites::table, WithUserId<&'a Uuid>>,
+        EqAny<favorites::article_id, &'a [Uuid]>,
+    >,
+    favorites::article_id,
+>;
 
 impl Favorite {
     pub fn with_user_id(user_id: &Uuid) -> WithUserId<&Uuid> {
@@ -31,16 +50,78 @@ impl Favorite {
     pub fn with_article_id(article_id: &Uuid) -> WithArticleId<&Uuid> {
         favorites::article_id.eq_all(article_id)
     }
+
+    // NOTE: meant to be used as a subquery, e.g. `articles::id.eq_any(..)`.
+    pub fn article_ids_by_user_id(user_id: &Uuid) -> ArticleIdsByUserId<&Uuid> {
+        favorites::table
+            .filter(Self::with_user_id(user_id))
+            .select(favorites::article_id)
+    }
+
+    // NOTE: meant to be used as a subquery, e.g. `users::id.eq_any(..)`.
+    pub fn user_ids_by_article_id(article_id: &Uuid) -> UserIdsByArticleId<&Uuid> {
+        favorites::table
+            .filter(Self::with_article_id(article_id))
+            .select(favorites::user_id)
+    }
+
+    fn favoriters_of(article_id: &Uuid, pagination: Pagination) -> FavoritersOf<'_> {
+        users::table
+            .filter(users::id.eq_any(Self::user_ids_by_article_id(article_id)))
+            .order((users::username.asc(), users::id.asc()))
+            .limit(pagination.limit)
+            .offset(pagination.offset)
+    }
+
+    // NOTE: resolves `favorited` for a whole page of articles in a single query.
+    fn favorited_article_ids<'a>(
+        user_id: &'a Uuid,
+        article_ids: &'a [Uuid],
+    ) -> FavoritedArticleIds<'a> {
+        favorites::table
+            .filter(Self::with_user_id(user_id))
+            .filter(favorites::article_id.eq_any(article_ids))
+            .select(favorites::article_id)
+    }
 }
 
 impl Favorite {
+    // NOTE: favoriting twice inserts nothing, so callers can rely on the row count.
     pub fn create(conn: &mut PgConnection, record: &CreateFavorite) -> Result<usize, AppError> {
         let item = diesel::insert_into(favorites::table)
             .values(record)
+            .on_conflict((favorites::article_id, favorites::user_id))
+            .do_nothing()
             .execute(conn)?;
         Ok(item)
     }
 
+    pub fn fetch_favorited_article_ids(
+        conn: &mut PgConnection,
+        user_id: &Uuid,
+        article_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError> {
+        if article_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let t = Self::favorited_article_ids(user_id, article_ids);
+        let ids = t.load::<Uuid>(conn)?;
+        Ok(ids.into_iter().collect())
+    }
+
+    pub fn fetch_favoriters(
+        conn: &mut PgConnection,
+        article_id: &Uuid,
+        pagination: Pagination,
+    ) -> Result<Page<User>, AppError> {
+        let items = Self::favoriters_of(article_id, pagination).load::<User>(conn)?;
+        let total = favorites::table
+            .filter(Self::with_article_id(article_id))
+            .count()
+            .get_result::<i64>(conn)?;
+        Ok(Page { items, total })
+    }
+
     pub fn delete(
         conn: &mut PgConnection,
         DeleteFavorite {
@@ -54,19 +135,6 @@ impl Favorite {
         let item = diesel::delete(t).execute(conn)?;
         Ok(item)
     }
-
-    pub fn fetch_favorited_article_ids_by_username(
-        conn: &mut PgConnection,
-        username: &str,
-    ) -> Result<Vec<Uuid>, AppError> {
-        use crate::schema::users;
-        let t = favorites::table
-            .inner_join(users::table)
-            .filter(User::with_username(username))
-            .select(favorites::article_id);
-        let ids = t.load::<Uuid>(conn)?;
-        Ok(ids)
-    }
 }
 
 #[derive(Insertable)]
@@ -84,5 +152,38 @@ pub struct DeleteFavorite {
 #[derive(Clone)]
 pub struct FavoriteInfo {
     pub is_favorited: bool,
-    pub favorites_count: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+
+    #[test]
+    fn favorited_article_ids_is_one_query_for_many_articles() {
+        let user_id = Uuid::nil();
+        let article_ids = (0..50).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        let query = Favorite::favorited_article_ids(&user_id, &article_ids);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.starts_with(
+            r#"SELECT "favorites"."article_id" FROM "favorites" WHERE (("favorites"."user_id" = $1) AND ("favorites"."article_id" = ANY($2)))"#
+        ));
+        assert!(sql.contains(&article_ids[49].to_string()));
+    }
+
+    #[test]
+    fn favoriters_of_pages_by_username() {
+        let article_id = Uuid::nil();
+        let pagination = Pagination::from_query(Some(20), Some(40));
+        let query = Favorite::favoriters_of(&article_id, pagination);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"WHERE ("users"."id" = ANY(SELECT "favorites"."user_id" FROM "favorites" WHERE ("favorites"."article_id" = $1)))"#
+        ));
+        assert!(
+            sql.contains(r#"ORDER BY "users"."username" ASC, "users"."id" ASC LIMIT $2 OFFSET $3"#)
+        );
+        assert!(sql.contains("binds: [00000000-0000-0000-0000-000000000000, 20, 40]"));
+    }
 }
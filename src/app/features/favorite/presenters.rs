@@ -2,14 +2,17 @@ use super::entities::FavoriteInfo;
 use crate::app::features::article::entities::Article;
 pub use crate::app::features::article::presenters::SingleArticleResponse;
 use crate::app::features::profile::entities::Profile;
+pub use crate::app::features::profile::presenters::MultipleProfilesResponse;
 use crate::app::features::tag::entities::Tag;
+use crate::utils::pagination::Page;
 use actix_web::HttpResponse;
 
 pub trait FavoritePresenter: Send + Sync + 'static {
     fn to_single_json(&self, item: (Article, Profile, FavoriteInfo, Vec<Tag>)) -> HttpResponse;
+    fn to_profiles_json(&self, page: Page<Profile>) -> HttpResponse;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct FavoritePresenterImpl {}
 impl FavoritePresenterImpl {
     pub fn new() -> Self {
@@ -24,4 +27,9 @@ impl FavoritePresenter for FavoritePresenterImpl {
         let res_model = SingleArticleResponse::from((article, profile, favorite_info, tags_list));
         HttpResponse::Ok().json(res_model)
     }
+
+    fn to_profiles_json(&self, page: Page<Profile>) -> HttpResponse {
+        let res_model = MultipleProfilesResponse::from((page.items, page.total));
+        HttpResponse::Ok().json(res_model)
+    }
 }
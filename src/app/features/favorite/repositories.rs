@@ -1,27 +1,51 @@
 use super::entities::{CreateFavorite, DeleteFavorite, Favorite};
-use crate::app::features::article::entities::{Article, FetchBySlugAndAuthorId};
+use crate::app::features::article::entities::Article;
+use crate::app::features::follow::entities::Follow;
+use crate::app::features::profile::entities::Profile;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
-use crate::utils::db::DbPool;
+use crate::utils::db::{self, DbPools};
+use crate::utils::gravatar;
+use crate::utils::pagination::{Page, Pagination};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+type Inserted = bool;
 
 pub trait FavoriteRepository: Send + Sync + 'static {
-    fn favorite_article(&self, user: User, article_title_slug: String)
-        -> Result<Article, AppError>;
+    // NOTE: `Inserted` is false when the user had already favorited the article.
+    fn favorite_article(
+        &self,
+        user: User,
+        article_title_slug: String,
+    ) -> Result<(Article, Inserted), AppError>;
     fn unfavorite_article(
         &self,
         user: User,
         article_title_slug: String,
     ) -> Result<Article, AppError>;
+    fn favorited_article_ids(
+        &self,
+        user_id: &Uuid,
+        article_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError>;
+    // NOTE: `following` on each profile is relative to the viewer, not to the article's author.
+    fn users_for_article(
+        &self,
+        article_id: &Uuid,
+        viewer_id: Option<&Uuid>,
+        pagination: Pagination,
+    ) -> Result<Page<Profile>, AppError>;
 }
 
 #[derive(Clone)]
 pub struct FavoriteRepositoryImpl {
-    pool: DbPool,
+    pools: DbPools,
 }
 
 impl FavoriteRepositoryImpl {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
     }
 }
 impl FavoriteRepository for FavoriteRepositoryImpl {
@@ -29,25 +53,23 @@ impl FavoriteRepository for FavoriteRepositoryImpl {
         &self,
         user: User,
         article_title_slug: String,
-    ) -> Result<Article, AppError> {
-        let conn = &mut self.pool.get()?;
-
-        let article = Article::fetch_by_slug_and_author_id(
-            conn,
-            &FetchBySlugAndAuthorId {
-                slug: article_title_slug,
-                author_id: user.id,
-            },
-        )?;
-        Favorite::create(
-            conn,
-            &CreateFavorite {
-                user_id: user.id,
-                article_id: article.id,
-            },
-        )?;
-
-        Ok(article)
+    ) -> Result<(Article, Inserted), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        // NOTE: the counter moves only by the rows actually written, so repeats leave it as is.
+        db::with_transaction(conn, |conn| {
+            // NOTE: any article the user can read may be favorited, not only their own.
+            let (article, _author) =
+                Article::fetch_by_slug_with_author(conn, &article_title_slug, Some(&user.id))?;
+            let inserted = Favorite::create(
+                conn,
+                &CreateFavorite {
+                    user_id: user.id,
+                    article_id: article.id,
+                },
+            )?;
+            let article = Article::adjust_favorites_count(conn, &article.id, inserted as i32)?;
+            Ok((article, inserted > 0))
+        })
     }
 
     fn unfavorite_article(
@@ -55,21 +77,59 @@ impl FavoriteRepository for FavoriteRepositoryImpl {
         user: User,
         article_title_slug: String,
     ) -> Result<Article, AppError> {
-        let conn = &mut self.pool.get()?;
-        let article = Article::fetch_by_slug_and_author_id(
-            conn,
-            &FetchBySlugAndAuthorId {
-                slug: article_title_slug,
-                author_id: user.id,
-            },
-        )?;
-        Favorite::delete(
-            conn,
-            &DeleteFavorite {
-                user_id: user.id,
-                article_id: article.id,
-            },
-        )?;
-        Ok(article)
+        let conn = &mut self.pools.write_conn()?;
+        db::with_transaction(conn, |conn| {
+            // NOTE: any article the user can read may be favorited, not only their own.
+            let (article, _author) =
+                Article::fetch_by_slug_with_author(conn, &article_title_slug, Some(&user.id))?;
+            let deleted = Favorite::delete(
+                conn,
+                &DeleteFavorite {
+                    user_id: user.id,
+                    article_id: article.id,
+                },
+            )?;
+            Article::adjust_favorites_count(conn, &article.id, -(deleted as i32))
+        })
+    }
+
+    fn favorited_article_ids(
+        &self,
+        user_id: &Uuid,
+        article_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        Favorite::fetch_favorited_article_ids(conn, user_id, article_ids)
+    }
+
+    fn users_for_article(
+        &self,
+        article_id: &Uuid,
+        viewer_id: Option<&Uuid>,
+        pagination: Pagination,
+    ) -> Result<Page<Profile>, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let page = Favorite::fetch_favoriters(conn, article_id, pagination)?;
+        let followee_ids = match viewer_id {
+            Some(viewer_id) => {
+                let user_ids = page.items.iter().map(|user| user.id).collect::<Vec<_>>();
+                Follow::fetch_followee_ids(conn, viewer_id, &user_ids)?
+            }
+            None => HashSet::new(),
+        };
+        let items = page
+            .items
+            .into_iter()
+            .map(|user| Profile {
+                following: followee_ids.contains(&user.id),
+                username: user.username,
+                bio: user.bio,
+                image: Some(gravatar::image_or_default(user.image, &user.email)),
+            })
+            .collect();
+        Ok(Page {
+            items,
+            total: page.total,
+        })
     }
 }
@@ -1,16 +1,20 @@
 use super::presenters::FavoritePresenter;
 use super::repositories::FavoriteRepository;
+use crate::app::features::activity::feed::{Activity, ActivityFeed};
 use crate::app::features::article::repositories::{ArticleRepository, FetchArticleRepositoryInput};
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
+use crate::utils::pagination::Pagination;
 use actix_web::HttpResponse;
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct FavoriteUsecase {
     favorite_repository: Arc<dyn FavoriteRepository>,
     favorite_presenter: Arc<dyn FavoritePresenter>,
     article_repository: Arc<dyn ArticleRepository>,
+    activity_feed: ActivityFeed,
 }
 
 impl FavoriteUsecase {
@@ -18,11 +22,13 @@ impl FavoriteUsecase {
         favorite_repository: Arc<dyn FavoriteRepository>,
         favorite_presenter: Arc<dyn FavoritePresenter>,
         article_repository: Arc<dyn ArticleRepository>,
+        activity_feed: ActivityFeed,
     ) -> Self {
         Self {
             favorite_repository,
             favorite_presenter,
             article_repository,
+            activity_feed,
         }
     }
 
@@ -31,9 +37,16 @@ impl FavoriteUsecase {
         user: User,
         article_title_slug: String,
     ) -> Result<HttpResponse, AppError> {
-        let article = self
+        let (article, inserted) = self
             .favorite_repository
             .favorite_article(user.clone(), article_title_slug)?;
+        // NOTE: a repeat changes nothing, and activity on an article nobody else can see stays private.
+        if inserted && article.is_listed() {
+            self.activity_feed.publish(Activity::Favorite {
+                slug: article.slug.clone(),
+                username: user.username.clone(),
+            });
+        }
 
         let result = self
             .article_repository
@@ -64,4 +77,22 @@ impl FavoriteUsecase {
         let res = self.favorite_presenter.to_single_json(result);
         Ok(res)
     }
+
+    pub fn fetch_favorited_by(
+        &self,
+        viewer_id: Option<Uuid>,
+        article_title_slug: &str,
+        pagination: Pagination,
+    ) -> Result<HttpResponse, AppError> {
+        // NOTE: drafts stay hidden from everyone but their author, and so do their fans.
+        let (article, _, _, _) = self
+            .article_repository
+            .find_by_slug_or_id(article_title_slug, viewer_id)?;
+        let page = self.favorite_repository.users_for_article(
+            &article.id,
+            viewer_id.as_ref(),
+            pagination,
+        )?;
+        Ok(self.favorite_presenter.to_profiles_json(page))
+    }
 }
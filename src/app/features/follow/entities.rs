@@ -2,10 +2,11 @@ use crate::app::features::user::entities::User;
 use crate::error::AppError;
 use crate::schema::follows;
 use chrono::NaiveDateTime;
-use diesel::dsl::Eq;
+use diesel::dsl::{Eq, Filter, Select};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use uuid::Uuid;
 
 #[derive(Queryable, Associations, Clone, Serialize, Deserialize)]
@@ -20,6 +21,10 @@ pub struct Follow {
 
 type WithFollowee<T> = Eq<follows::followee_id, T>;
 type WithFollower<T> = Eq<follows::follower_id, T>;
+pub type FolloweeIdsByFollowerId<T> =
+    Select<Filter<follows::table, WithFollower<T>>, follows::followee_id>;
+pub type FollowerIdsByFolloweeId<T> =
+    Select<Filter<follows::table, WithFollowee<T>>, follows::follower_id>;
 
 impl Follow {
     pub fn with_followee(followee_id: &Uuid) -> WithFollowee<&Uuid> {
@@ -29,33 +34,69 @@ impl Follow {
     pub fn with_follower(follower_id: &Uuid) -> WithFollower<&Uuid> {
         follows::follower_id.eq(follower_id)
     }
+
+    // NOTE: meant to be used as a subquery, e.g. `articles::author_id.eq_any(..)`.
+    pub fn followee_ids_by_follower_id(follower_id: &Uuid) -> FolloweeIdsByFollowerId<&Uuid> {
+        follows::table
+            .filter(Self::with_follower(follower_id))
+            .select(follows::followee_id)
+    }
+
+    // NOTE: meant to be used as a subquery, e.g. `users::id.eq_any(..)`.
+    pub fn follower_ids_by_followee_id(followee_id: &Uuid) -> FollowerIdsByFolloweeId<&Uuid> {
+        follows::table
+            .filter(Self::with_followee(followee_id))
+            .select(follows::follower_id)
+    }
 }
 
 impl Follow {
-    pub fn create(conn: &mut PgConnection, params: &CreateFollow) -> Result<(), AppError> {
-        diesel::insert_into(follows::table)
+    // NOTE: following someone twice is a no-op; returns how many rows were inserted (0 or 1).
+    pub fn create(conn: &mut PgConnection, params: &CreateFollow) -> Result<usize, AppError> {
+        let inserted = diesel::insert_into(follows::table)
             .values(params)
+            .on_conflict((follows::follower_id, follows::followee_id))
+            .do_nothing()
             .execute(conn)?;
-        Ok(())
-    }
-
-    pub fn delete(conn: &mut PgConnection, params: &DeleteFollow) -> Result<(), AppError> {
-        let t = follows::table
-            .filter(Follow::with_followee(&params.followee_id))
-            .filter(Follow::with_follower(&params.follower_id));
-        diesel::delete(t).execute(conn)?;
-        Ok(())
+        Ok(inserted)
     }
 
-    pub fn fetch_folowee_ids_by_follower_id(
+    // NOTE: answers `following` for a whole page of users in a single query.
+    pub fn fetch_followee_ids(
         conn: &mut PgConnection,
         follower_id: &Uuid,
-    ) -> Result<Vec<Uuid>, AppError> {
+        user_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError> {
+        if user_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
         let t = follows::table
-            .filter(Follow::with_follower(follower_id))
+            .filter(Self::with_follower(follower_id))
+            .filter(follows::followee_id.eq_any(user_ids))
             .select(follows::followee_id);
-        let result = t.get_results::<Uuid>(conn)?;
-        Ok(result)
+        let ids = t.load::<Uuid>(conn)?;
+        Ok(ids.into_iter().collect())
+    }
+
+    pub fn count_followers(conn: &mut PgConnection, followee_id: &Uuid) -> Result<i64, AppError> {
+        let t = follows::table.filter(Self::with_followee(followee_id));
+        let count = t.count().get_result::<i64>(conn)?;
+        Ok(count)
+    }
+
+    pub fn count_followees(conn: &mut PgConnection, follower_id: &Uuid) -> Result<i64, AppError> {
+        let t = follows::table.filter(Self::with_follower(follower_id));
+        let count = t.count().get_result::<i64>(conn)?;
+        Ok(count)
+    }
+
+    // NOTE: unfollowing someone not followed is a no-op; returns how many rows were deleted (0 or 1).
+    pub fn delete(conn: &mut PgConnection, params: &DeleteFollow) -> Result<usize, AppError> {
+        let t = follows::table
+            .filter(Follow::with_followee(&params.followee_id))
+            .filter(Follow::with_follower(&params.follower_id));
+        let deleted = diesel::delete(t).execute(conn)?;
+        Ok(deleted)
     }
 }
 
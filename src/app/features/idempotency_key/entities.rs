@@ -0,0 +1,73 @@
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::schema::idempotency_keys;
+use chrono::{Duration, NaiveDateTime};
+use diesel::dsl::{And, Eq, Lt};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+// NOTE: long enough to cover client retries, short enough that a key can be reused the next day.
+pub const TTL_HOURS: i64 = 24;
+
+#[derive(Identifiable, Queryable, Associations, Debug, Clone)]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+#[diesel(table_name = idempotency_keys, primary_key(user_id, key))]
+pub struct IdempotencyKey {
+    pub user_id: Uuid,
+    pub key: String,
+    pub article_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+type WithKey<'a> = And<Eq<idempotency_keys::user_id, &'a Uuid>, Eq<idempotency_keys::key, &'a str>>;
+type ExpiredBefore<T> = Lt<idempotency_keys::created_at, T>;
+
+impl IdempotencyKey {
+    fn with_key<'a>(user_id: &'a Uuid, key: &'a str) -> WithKey<'a> {
+        idempotency_keys::user_id
+            .eq(user_id)
+            .and(idempotency_keys::key.eq(key))
+    }
+
+    fn expired_before(now: NaiveDateTime) -> ExpiredBefore<NaiveDateTime> {
+        idempotency_keys::created_at.lt(now - Duration::hours(TTL_HOURS))
+    }
+}
+
+impl IdempotencyKey {
+    pub fn create(conn: &mut PgConnection, record: &CreateIdempotencyKey) -> Result<(), AppError> {
+        diesel::insert_into(idempotency_keys::table)
+            .values(record)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find_article_id(
+        conn: &mut PgConnection,
+        user_id: &Uuid,
+        key: &str,
+    ) -> Result<Option<Uuid>, AppError> {
+        let article_id = idempotency_keys::table
+            .filter(Self::with_key(user_id, key))
+            .select(idempotency_keys::article_id)
+            .first::<Uuid>(conn)
+            .optional()?;
+        Ok(article_id)
+    }
+
+    // NOTE: run before a lookup so an expired key is free to start a new create.
+    pub fn purge_expired(conn: &mut PgConnection, now: NaiveDateTime) -> Result<usize, AppError> {
+        let t = idempotency_keys::table.filter(Self::expired_before(now));
+        let count = diesel::delete(t).execute(conn)?;
+        Ok(count)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = idempotency_keys)]
+pub struct CreateIdempotencyKey {
+    pub user_id: Uuid,
+    pub key: String,
+    pub article_id: Uuid,
+}
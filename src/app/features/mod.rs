@@ -1,8 +1,16 @@
+pub mod activity;
 pub mod article;
+pub mod avatar;
 pub mod comment;
+pub mod email_verification;
 pub mod favorite;
 pub mod follow;
 pub mod healthcheck;
+pub mod idempotency_key;
+pub mod password_reset;
 pub mod profile;
+pub mod report;
 pub mod tag;
+pub mod token_blacklist;
 pub mod user;
+pub mod webhook;
@@ -0,0 +1,24 @@
+use super::requests;
+use crate::app::drivers::middlewares::state::AppState;
+use crate::utils::api::ApiResponse;
+use actix_web::web;
+
+pub async fn request(
+    state: web::Data<AppState>,
+    form: web::Json<requests::RequestPasswordReset>,
+) -> ApiResponse {
+    state
+        .di_container
+        .password_reset_usecase
+        .request_reset(&form.user.email)
+}
+
+pub async fn confirm(
+    state: web::Data<AppState>,
+    form: web::Json<requests::ConfirmPasswordReset>,
+) -> ApiResponse {
+    state
+        .di_container
+        .password_reset_usecase
+        .confirm_reset(&form.user.token, &form.user.password)
+}
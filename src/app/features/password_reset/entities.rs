@@ -0,0 +1,78 @@
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::schema::password_resets;
+use chrono::{Duration, NaiveDateTime};
+use diesel::dsl::{Eq, Gt, IsNull};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Identifiable, Queryable, Associations, Serialize, Deserialize, Debug, Clone)]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+#[diesel(table_name = password_resets)]
+pub struct PasswordReset {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+type WithToken<T> = Eq<password_resets::token, T>;
+type ExpiresAfter<T> = Gt<password_resets::expires_at, T>;
+type Unused = IsNull<password_resets::used_at>;
+
+impl PasswordReset {
+    pub fn ttl() -> Duration {
+        Duration::hours(1)
+    }
+
+    fn with_token(token: &Uuid) -> WithToken<&Uuid> {
+        password_resets::token.eq(token)
+    }
+
+    fn expires_after(now: NaiveDateTime) -> ExpiresAfter<NaiveDateTime> {
+        password_resets::expires_at.gt(now)
+    }
+
+    fn unused() -> Unused {
+        password_resets::used_at.is_null()
+    }
+}
+
+impl PasswordReset {
+    pub fn create(conn: &mut PgConnection, record: &CreatePasswordReset) -> Result<Self, AppError> {
+        let item = diesel::insert_into(password_resets::table)
+            .values(record)
+            .get_result::<Self>(conn)?;
+        Ok(item)
+    }
+
+    // Marks an unused, unexpired token as used and returns it, or `None` if there is no such token.
+    // NOTE: one conditional UPDATE, so of two concurrent confirms only one gets the row back.
+    pub fn consume(
+        conn: &mut PgConnection,
+        token: &Uuid,
+        now: NaiveDateTime,
+    ) -> Result<Option<Self>, AppError> {
+        let t = password_resets::table
+            .filter(Self::with_token(token))
+            .filter(Self::unused())
+            .filter(Self::expires_after(now));
+        let item = diesel::update(t)
+            .set(password_resets::used_at.eq(now))
+            .get_result::<Self>(conn)
+            .optional()?;
+        Ok(item)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = password_resets)]
+pub struct CreatePasswordReset {
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,18 @@
+use actix_web::HttpResponse;
+
+pub trait PasswordResetPresenter: Send + Sync + 'static {
+    fn to_http_res(&self) -> HttpResponse;
+}
+
+#[derive(Clone, Default)]
+pub struct PasswordResetPresenterImpl {}
+impl PasswordResetPresenterImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl PasswordResetPresenter for PasswordResetPresenterImpl {
+    fn to_http_res(&self) -> HttpResponse {
+        HttpResponse::Ok().json(())
+    }
+}
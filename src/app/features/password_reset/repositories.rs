@@ -0,0 +1,65 @@
+use super::entities::{CreatePasswordReset, PasswordReset};
+use crate::app::features::user::entities::{UpdateUser, User};
+use crate::error::AppError;
+use crate::utils::db::{self, DbPool};
+use crate::utils::hasher::{self, HashConfig};
+use chrono::Utc;
+use uuid::Uuid;
+
+pub trait PasswordResetRepository: Send + Sync + 'static {
+    fn create(&self, email: &str) -> Result<Option<PasswordReset>, AppError>;
+    fn confirm(&self, token: &Uuid, naive_password: &str) -> Result<(), AppError>;
+}
+
+#[derive(Clone)]
+pub struct PasswordResetRepositoryImpl {
+    pool: DbPool,
+    hash: HashConfig,
+}
+
+impl PasswordResetRepositoryImpl {
+    pub fn new(pool: DbPool, hash: HashConfig) -> Self {
+        Self { pool, hash }
+    }
+}
+
+impl PasswordResetRepository for PasswordResetRepositoryImpl {
+    fn create(&self, email: &str) -> Result<Option<PasswordReset>, AppError> {
+        let conn = &mut self.pool.get()?;
+        let user = match User::find_by_email(conn, email)? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+        let expires_at = Utc::now().naive_utc() + PasswordReset::ttl();
+        let item = PasswordReset::create(
+            conn,
+            &CreatePasswordReset {
+                user_id: user.id,
+                expires_at,
+            },
+        )?;
+        Ok(Some(item))
+    }
+
+    fn confirm(&self, token: &Uuid, naive_password: &str) -> Result<(), AppError> {
+        // NOTE: hashed up front so the token's row is not kept locked while argon2 runs.
+        let password = hasher::hash_password(&self.hash, naive_password)?;
+        let conn = &mut self.pool.get()?;
+        db::with_transaction(conn, |conn| {
+            let reset = PasswordReset::consume(conn, token, Utc::now().naive_utc())?
+                .ok_or_else(|| AppError::invalid("token", "is invalid or expired"))?;
+            User::update(
+                conn,
+                reset.user_id,
+                UpdateUser {
+                    email: None,
+                    username: None,
+                    password: Some(password),
+                    image: None,
+                    bio: None,
+                },
+            )?;
+            Ok(())
+        })
+    }
+}
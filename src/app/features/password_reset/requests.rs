@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RequestPasswordReset {
+    pub user: RequestPasswordResetUser,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RequestPasswordResetUser {
+    pub email: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ConfirmPasswordReset {
+    pub user: ConfirmPasswordResetUser,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ConfirmPasswordResetUser {
+    pub token: String,
+    pub password: String,
+}
@@ -0,0 +1,50 @@
+use super::presenters::PasswordResetPresenter;
+use super::repositories::PasswordResetRepository;
+use crate::error::AppError;
+use crate::utils::password_policy::PasswordPolicy;
+use actix_web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct PasswordResetUsecase {
+    password_reset_repository: Arc<dyn PasswordResetRepository>,
+    password_reset_presenter: Arc<dyn PasswordResetPresenter>,
+    password_policy: PasswordPolicy,
+}
+
+impl PasswordResetUsecase {
+    pub fn new(
+        password_reset_repository: Arc<dyn PasswordResetRepository>,
+        password_reset_presenter: Arc<dyn PasswordResetPresenter>,
+        password_policy: PasswordPolicy,
+    ) -> Self {
+        Self {
+            password_reset_repository,
+            password_reset_presenter,
+            password_policy,
+        }
+    }
+
+    pub fn request_reset(&self, email: &str) -> Result<HttpResponse, AppError> {
+        // NOTE: respond the same way for unknown emails so accounts can't be enumerated.
+        if let Some(reset) = self.password_reset_repository.create(email)? {
+            // TODO: deliver the token by email instead of logging it.
+            info!(
+                "Password reset token issued for user {}: {}",
+                reset.user_id, reset.token
+            );
+        }
+        let res = self.password_reset_presenter.to_http_res();
+        Ok(res)
+    }
+
+    pub fn confirm_reset(&self, token: &str, password: &str) -> Result<HttpResponse, AppError> {
+        let token = Uuid::parse_str(token)
+            .map_err(|_err| AppError::invalid("token", "is invalid or expired"))?;
+        self.password_policy.check_field(password)?;
+        self.password_reset_repository.confirm(&token, password)?;
+        let res = self.password_reset_presenter.to_http_res();
+        Ok(res)
+    }
+}
@@ -1,9 +1,122 @@
+use super::presenters::{MultipleProfilesResponse, ProfileResponse};
 use crate::app::drivers::middlewares::{auth, state::AppState};
+use crate::error::AppError;
 use crate::utils::api::ApiResponse;
+use crate::utils::pagination::Pagination;
 use actix_web::{web, HttpRequest};
+use serde::Deserialize;
+use utoipa::IntoParams;
 
 type UsernameSlug = String;
 
+pub const MAX_BATCH_USERNAMES: usize = 50;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProfilesBatchQueryParameter {
+    /// Comma-separated, at most 50, e.g. `jake,celeb`.
+    usernames: Option<String>,
+}
+
+// NOTE: blanks and repeats (case-insensitively) are dropped before the cap is applied.
+fn usernames(value: Option<&str>) -> Result<Vec<String>, AppError> {
+    let mut list: Vec<String> = vec![];
+    for username in value.unwrap_or_default().split(',').map(str::trim) {
+        if !username.is_empty() && !list.iter().any(|seen| seen.eq_ignore_ascii_case(username)) {
+            list.push(username.to_owned());
+        }
+    }
+    if list.len() > MAX_BATCH_USERNAMES {
+        return Err(AppError::invalid(
+            "usernames",
+            &format!("must list at most {} usernames", MAX_BATCH_USERNAMES),
+        ));
+    }
+    Ok(list)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/profiles",
+    tag = "profile",
+    params(ProfilesBatchQueryParameter),
+    responses(
+        (status = 200, body = MultipleProfilesResponse, description = "Known users only, in the order asked"),
+        (status = 422, description = "More than 50 usernames"),
+    )
+)]
+pub async fn index(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    params: web::Query<ProfilesBatchQueryParameter>,
+) -> ApiResponse {
+    let usernames = usernames(params.usernames.as_deref())?;
+    let current_user = auth::get_current_user_optional(&req);
+    state
+        .di_container
+        .profile_usecase
+        .fetch_profiles_by_usernames(&current_user, &usernames)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProfilesSearchQueryParameter {
+    /// Matched case-insensitively anywhere in the username, e.g. `ja`.
+    q: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+fn search_query(value: Option<&str>) -> Result<&str, AppError> {
+    match value.map(str::trim) {
+        Some(query) if !query.is_empty() => Ok(query),
+        _ => Err(AppError::invalid("q", "can't be blank")),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/profiles/search",
+    tag = "profile",
+    params(ProfilesSearchQueryParameter),
+    responses(
+        (status = 200, body = MultipleProfilesResponse, description = "Ordered by username"),
+        (status = 422, description = "Missing or blank `q`"),
+    )
+)]
+pub async fn search(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    params: web::Query<ProfilesSearchQueryParameter>,
+) -> ApiResponse {
+    let query = search_query(params.q.as_deref())?;
+    let current_user = auth::get_current_user_optional(&req);
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state
+        .di_container
+        .profile_usecase
+        .search_profiles(&current_user, query, pagination)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProfilesListQueryParameter {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{username}",
+    tag = "profile",
+    params(("username" = String, Path)),
+    responses(
+        (status = 200, body = ProfileResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Profile not found"),
+    ),
+    security(("token" = []))
+)]
 pub async fn show(
     state: web::Data<AppState>,
     req: HttpRequest,
@@ -17,6 +130,95 @@ pub async fn show(
         .fetch_profile_by_name(&current_user, &username)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{username}/followers",
+    tag = "profile",
+    params(("username" = String, Path), ProfilesListQueryParameter),
+    responses(
+        (status = 200, body = MultipleProfilesResponse),
+        (status = 404, description = "Profile not found"),
+    )
+)]
+pub async fn followers(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<UsernameSlug>,
+    params: web::Query<ProfilesListQueryParameter>,
+) -> ApiResponse {
+    let current_user = auth::get_current_user_optional(&req);
+    let username = path.into_inner();
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state
+        .di_container
+        .profile_usecase
+        .fetch_followers(&current_user, &username, pagination)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{username}/following",
+    tag = "profile",
+    params(("username" = String, Path), ProfilesListQueryParameter),
+    responses(
+        (status = 200, body = MultipleProfilesResponse),
+        (status = 404, description = "Profile not found"),
+    )
+)]
+pub async fn following(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<UsernameSlug>,
+    params: web::Query<ProfilesListQueryParameter>,
+) -> ApiResponse {
+    let current_user = auth::get_current_user_optional(&req);
+    let username = path.into_inner();
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state
+        .di_container
+        .profile_usecase
+        .fetch_following(&current_user, &username, pagination)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{username}/mutuals",
+    tag = "profile",
+    params(("username" = String, Path), ProfilesListQueryParameter),
+    responses(
+        (status = 200, body = MultipleProfilesResponse, description = "Users both you and `username` follow"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Profile not found"),
+    ),
+    security(("token" = []))
+)]
+pub async fn mutuals(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<UsernameSlug>,
+    params: web::Query<ProfilesListQueryParameter>,
+) -> ApiResponse {
+    let current_user = auth::get_current_user(&req)?;
+    let username = path.into_inner();
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state
+        .di_container
+        .profile_usecase
+        .fetch_mutuals(&current_user, &username, pagination)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/profiles/{username}/follow",
+    tag = "profile",
+    params(("username" = String, Path)),
+    responses(
+        (status = 200, body = ProfileResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Profile not found"),
+    ),
+    security(("token" = []))
+)]
 pub async fn follow(
     state: web::Data<AppState>,
     req: HttpRequest,
@@ -30,6 +232,18 @@ pub async fn follow(
         .follow_user(&current_user, &target_username)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/profiles/{username}/follow",
+    tag = "profile",
+    params(("username" = String, Path)),
+    responses(
+        (status = 200, body = ProfileResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Profile not found"),
+    ),
+    security(("token" = []))
+)]
 pub async fn unfollow(
     state: web::Data<AppState>,
     req: HttpRequest,
@@ -42,3 +256,52 @@ pub async fn unfollow(
         .profile_usecase
         .unfollow_user(&current_user, &target_username)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn usernames_are_split_trimmed_and_deduplicated() {
+        assert_eq!(
+            vec!["jake", "celeb"],
+            usernames(Some(" jake,celeb,,Jake ")).unwrap()
+        );
+        assert!(usernames(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn usernames_are_capped() {
+        let names = (0..MAX_BATCH_USERNAMES)
+            .map(|n| format!("user{}", n))
+            .collect::<Vec<_>>();
+        assert_eq!(50, usernames(Some(&names.join(","))).unwrap().len());
+
+        let too_many = format!("{},one-more", names.join(","));
+        match usernames(Some(&too_many)) {
+            Err(err @ AppError::UnprocessableEntity(_)) => assert_eq!(
+                json!({"errors": {"usernames": ["must list at most 50 usernames"]}}),
+                err.body()
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn search_query_is_trimmed() {
+        assert_eq!("ja", search_query(Some(" ja ")).unwrap());
+    }
+
+    #[test]
+    fn search_query_must_not_be_blank() {
+        for value in [None, Some(""), Some("  ")] {
+            match search_query(value) {
+                Err(err @ AppError::UnprocessableEntity(_)) => {
+                    assert_eq!(json!({"errors": {"q": ["can't be blank"]}}), err.body())
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+}
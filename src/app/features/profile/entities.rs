@@ -7,3 +7,9 @@ pub struct Profile {
     pub image: Option<String>,
     pub following: bool,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct FollowCounts {
+    pub followers_count: i64,
+    pub following_count: i64,
+}
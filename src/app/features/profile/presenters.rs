@@ -1,14 +1,15 @@
-use super::entities::Profile as ProfileModel;
+use super::entities::{FollowCounts, Profile as ProfileModel};
 use actix_web::HttpResponse;
 use serde::{Deserialize, Serialize};
 use std::convert::From;
+use utoipa::ToSchema;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct ProfileResponse {
-    pub profile: ProfileContent,
+    pub profile: ProfileDetailContent,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct ProfileContent {
     pub username: String,
     pub bio: Option<String>,
@@ -16,23 +17,60 @@ pub struct ProfileContent {
     pub following: bool,
 }
 
-impl From<ProfileModel> for ProfileResponse {
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileDetailContent {
+    #[serde(flatten)]
+    pub content: ProfileContent,
+    pub followers_count: i64,
+    pub following_count: i64,
+}
+
+impl From<ProfileModel> for ProfileContent {
     fn from(profile_model: ProfileModel) -> Self {
-        let profile = ProfileContent {
+        ProfileContent {
             username: profile_model.username,
             bio: profile_model.bio,
             image: profile_model.image,
             following: profile_model.following,
+        }
+    }
+}
+
+impl From<(ProfileModel, FollowCounts)> for ProfileResponse {
+    fn from((profile_model, counts): (ProfileModel, FollowCounts)) -> Self {
+        let profile = ProfileDetailContent {
+            content: ProfileContent::from(profile_model),
+            followers_count: counts.followers_count,
+            following_count: counts.following_count,
         };
         ProfileResponse { profile }
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipleProfilesResponse {
+    pub profiles: Vec<ProfileContent>,
+    pub profiles_count: i64,
+}
+
+impl From<(Vec<ProfileModel>, i64)> for MultipleProfilesResponse {
+    fn from((list, profiles_count): (Vec<ProfileModel>, i64)) -> Self {
+        let profiles = list.into_iter().map(ProfileContent::from).collect();
+        MultipleProfilesResponse {
+            profiles,
+            profiles_count,
+        }
+    }
+}
+
 pub trait ProfilePresenter: Send + Sync + 'static {
-    fn to_json(&self, model: ProfileModel) -> HttpResponse;
+    fn to_json(&self, model: ProfileModel, counts: FollowCounts) -> HttpResponse;
+    fn to_multi_json(&self, list: Vec<ProfileModel>, count: i64) -> HttpResponse;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ProfilePresenterImpl {}
 impl ProfilePresenterImpl {
     pub fn new() -> Self {
@@ -40,8 +78,51 @@ impl ProfilePresenterImpl {
     }
 }
 impl ProfilePresenter for ProfilePresenterImpl {
-    fn to_json(&self, model: ProfileModel) -> HttpResponse {
-        let res_model = ProfileResponse::from(model);
+    fn to_json(&self, model: ProfileModel, counts: FollowCounts) -> HttpResponse {
+        let res_model = ProfileResponse::from((model, counts));
         HttpResponse::Ok().json(res_model)
     }
+
+    fn to_multi_json(&self, list: Vec<ProfileModel>, count: i64) -> HttpResponse {
+        let res_model = MultipleProfilesResponse::from((list, count));
+        HttpResponse::Ok().json(res_model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_response_includes_follow_counts() {
+        let model = ProfileModel {
+            username: "jake".to_owned(),
+            bio: None,
+            image: None,
+            following: false,
+        };
+        let counts = FollowCounts {
+            followers_count: 2,
+            following_count: 5,
+        };
+        let res = serde_json::to_value(ProfileResponse::from((model, counts))).unwrap();
+        assert_eq!("jake", res["profile"]["username"]);
+        assert_eq!(false, res["profile"]["following"]);
+        assert_eq!(2, res["profile"]["followersCount"]);
+        assert_eq!(5, res["profile"]["followingCount"]);
+    }
+
+    #[test]
+    fn multiple_profiles_response_shape() {
+        let list = vec![ProfileModel {
+            username: "jake".to_owned(),
+            bio: None,
+            image: None,
+            following: true,
+        }];
+        let res = serde_json::to_value(MultipleProfilesResponse::from((list, 3))).unwrap();
+        assert_eq!("jake", res["profiles"][0]["username"]);
+        assert_eq!(true, res["profiles"][0]["following"]);
+        assert_eq!(3, res["profilesCount"]);
+    }
 }
@@ -1,7 +1,15 @@
-use super::entities::Profile;
+use super::entities::{FollowCounts, Profile};
+use crate::app::features::follow::entities::Follow;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
-use crate::utils::db::DbPool;
+use crate::utils::db::DbPools;
+use crate::utils::gravatar;
+use crate::utils::pagination::Pagination;
+use diesel::pg::PgConnection;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+type ProfilesCount = i64;
 
 pub trait ProfileRepository: Send + Sync + 'static {
     fn fetch_profile_by_name(
@@ -9,16 +17,89 @@ pub trait ProfileRepository: Send + Sync + 'static {
         current_user: &User,
         username: &str,
     ) -> Result<Profile, AppError>;
+
+    fn follow_counts(&self, username: &str) -> Result<FollowCounts, AppError>;
+
+    // NOTE: which of `user_ids` the viewer follows, resolved in one query for a whole list.
+    fn following_map(
+        &self,
+        current_user_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError>;
+
+    // NOTE: unknown usernames are left out; `following` is resolved in one query for all of them.
+    fn profiles_by_usernames(
+        &self,
+        current_user: &Option<User>,
+        usernames: &[String],
+    ) -> Result<Vec<Profile>, AppError>;
+
+    fn search(
+        &self,
+        current_user: &Option<User>,
+        query: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError>;
+
+    fn followers(
+        &self,
+        current_user: &Option<User>,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError>;
+
+    fn following(
+        &self,
+        current_user: &Option<User>,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError>;
+
+    // NOTE: users both `current_user` and `username` follow, so every profile has `following: true`.
+    fn mutuals(
+        &self,
+        current_user: &User,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError>;
 }
 
 #[derive(Clone)]
 pub struct ProfileRepositoryImpl {
-    pool: DbPool,
+    pools: DbPools,
 }
 
 impl ProfileRepositoryImpl {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
+    }
+
+    fn fetch_profiles(
+        conn: &mut PgConnection,
+        current_user: &Option<User>,
+        users: Vec<User>,
+    ) -> Result<Vec<Profile>, AppError> {
+        let followee_ids = match current_user {
+            Some(current_user) => {
+                let user_ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+                Follow::fetch_followee_ids(conn, &current_user.id, &user_ids)?
+            }
+            None => HashSet::new(),
+        };
+        Ok(Self::to_profiles(users, &followee_ids))
+    }
+
+    // NOTE: `following` is relative to the viewer, not to the user whose list this is.
+    fn to_profiles(users: Vec<User>, followee_ids: &HashSet<Uuid>) -> Vec<Profile> {
+        users
+            .into_iter()
+            .map(|user| Profile {
+                following: followee_ids.contains(&user.id),
+                username: user.username,
+                bio: user.bio,
+                image: Some(gravatar::image_or_default(user.image, &user.email)),
+            })
+            .collect()
     }
 }
 
@@ -28,11 +109,154 @@ impl ProfileRepository for ProfileRepositoryImpl {
         current_user: &User,
         username: &str,
     ) -> Result<Profile, AppError> {
-        let conn = &mut self.pool.get()?;
-        let profile = {
-            let followee = User::find_by_username(conn, username)?;
-            current_user.fetch_profile(conn, &followee.id)?
-        };
+        let conn = &mut self.pools.read_conn()?;
+        let followee = User::find_by_username(conn, username)?;
+        let followee_ids = Follow::fetch_followee_ids(conn, &current_user.id, &[followee.id])?;
+        let profile = Self::to_profiles(vec![followee], &followee_ids).remove(0);
         Ok(profile)
     }
+
+    fn follow_counts(&self, username: &str) -> Result<FollowCounts, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let user = User::find_by_username(conn, username)?;
+        let followers_count = Follow::count_followers(conn, &user.id)?;
+        let following_count = Follow::count_followees(conn, &user.id)?;
+        Ok(FollowCounts {
+            followers_count,
+            following_count,
+        })
+    }
+
+    fn following_map(
+        &self,
+        current_user_id: &Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        Follow::fetch_followee_ids(conn, current_user_id, user_ids)
+    }
+
+    fn profiles_by_usernames(
+        &self,
+        current_user: &Option<User>,
+        usernames: &[String],
+    ) -> Result<Vec<Profile>, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let list = User::fetch_by_usernames(conn, usernames)?;
+        Self::fetch_profiles(conn, current_user, list)
+    }
+
+    fn search(
+        &self,
+        current_user: &Option<User>,
+        query: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let (list, count) = User::search(conn, query, pagination.offset, pagination.limit)?;
+        let profiles = Self::fetch_profiles(conn, current_user, list)?;
+        Ok((profiles, count))
+    }
+
+    fn followers(
+        &self,
+        current_user: &Option<User>,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let user = User::find_by_username(conn, username)?;
+        let (list, count) =
+            User::fetch_followers(conn, &user.id, pagination.offset, pagination.limit)?;
+        let profiles = Self::fetch_profiles(conn, current_user, list)?;
+        Ok((profiles, count))
+    }
+
+    fn following(
+        &self,
+        current_user: &Option<User>,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let user = User::find_by_username(conn, username)?;
+        let (list, count) =
+            User::fetch_followees(conn, &user.id, pagination.offset, pagination.limit)?;
+        let profiles = Self::fetch_profiles(conn, current_user, list)?;
+        Ok((profiles, count))
+    }
+
+    fn mutuals(
+        &self,
+        current_user: &User,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<(Vec<Profile>, ProfilesCount), AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let user = User::find_by_username(conn, username)?;
+        let (list, count) = User::fetch_mutual_followees(
+            conn,
+            &current_user.id,
+            &user.id,
+            pagination.offset,
+            pagination.limit,
+        )?;
+        let followee_ids = list.iter().map(|user| user.id).collect::<HashSet<_>>();
+        Ok((Self::to_profiles(list, &followee_ids), count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn user(username: &str) -> User {
+        let now = NaiveDateTime::default();
+        User {
+            id: Uuid::new_v4(),
+            email: format!("{}@example.com", username),
+            username: username.to_owned(),
+            password: "hashed".to_owned(),
+            bio: None,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            email_verified: true,
+            role: "user".to_owned(),
+            last_login_at: None,
+        }
+    }
+
+    #[test]
+    fn to_profiles_marks_users_the_viewer_follows() {
+        let (jake, anna) = (user("jake"), user("anna"));
+        let followee_ids = [anna.id].into_iter().collect::<HashSet<_>>();
+        let profiles = ProfileRepositoryImpl::to_profiles(vec![jake, anna], &followee_ids);
+        assert_eq!("jake", profiles[0].username);
+        assert!(!profiles[0].following);
+        assert_eq!("anna", profiles[1].username);
+        assert!(profiles[1].following);
+    }
+
+    #[test]
+    fn to_profiles_for_anonymous_viewer() {
+        let profiles = ProfileRepositoryImpl::to_profiles(vec![user("jake")], &HashSet::new());
+        assert!(!profiles[0].following);
+    }
+
+    #[test]
+    fn to_profiles_falls_back_to_gravatar() {
+        let anna = User {
+            image: Some("https://example.com/anna.png".to_owned()),
+            ..user("anna")
+        };
+        let profiles =
+            ProfileRepositoryImpl::to_profiles(vec![user("jake"), anna], &HashSet::new());
+        assert_eq!(Some(gravatar::url("jake@example.com")), profiles[0].image);
+        assert_eq!(
+            Some("https://example.com/anna.png".to_owned()),
+            profiles[1].image
+        );
+    }
 }
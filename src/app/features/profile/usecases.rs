@@ -1,8 +1,10 @@
 use super::presenters::ProfilePresenter;
 use super::repositories::ProfileRepository;
+use crate::app::features::activity::feed::{Activity, ActivityFeed};
 use crate::app::features::user::entities::User;
 use crate::app::features::user::repositories::UserRepository;
 use crate::error::AppError;
+use crate::utils::pagination::Pagination;
 use actix_web::HttpResponse;
 use std::sync::Arc;
 
@@ -11,6 +13,7 @@ pub struct ProfileUsecase {
     user_repository: Arc<dyn UserRepository>,
     profile_repository: Arc<dyn ProfileRepository>,
     presenter: Arc<dyn ProfilePresenter>,
+    activity_feed: ActivityFeed,
 }
 
 impl ProfileUsecase {
@@ -18,11 +21,13 @@ impl ProfileUsecase {
         profile_repository: Arc<dyn ProfileRepository>,
         user_repository: Arc<dyn UserRepository>,
         presenter: Arc<dyn ProfilePresenter>,
+        activity_feed: ActivityFeed,
     ) -> Self {
         Self {
             profile_repository,
             user_repository,
             presenter,
+            activity_feed,
         }
     }
 
@@ -34,7 +39,68 @@ impl ProfileUsecase {
         let profile = self
             .profile_repository
             .fetch_profile_by_name(current_user, username)?;
-        Ok(self.presenter.to_json(profile))
+        let counts = self.profile_repository.follow_counts(username)?;
+        Ok(self.presenter.to_json(profile, counts))
+    }
+
+    pub fn fetch_profiles_by_usernames(
+        &self,
+        current_user: &Option<User>,
+        usernames: &[String],
+    ) -> Result<HttpResponse, AppError> {
+        let list = self
+            .profile_repository
+            .profiles_by_usernames(current_user, usernames)?;
+        let count = list.len() as i64;
+        Ok(self.presenter.to_multi_json(list, count))
+    }
+
+    pub fn search_profiles(
+        &self,
+        current_user: &Option<User>,
+        query: &str,
+        pagination: Pagination,
+    ) -> Result<HttpResponse, AppError> {
+        let (list, count) = self
+            .profile_repository
+            .search(current_user, query, pagination)?;
+        Ok(self.presenter.to_multi_json(list, count))
+    }
+
+    pub fn fetch_followers(
+        &self,
+        current_user: &Option<User>,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<HttpResponse, AppError> {
+        let (list, count) =
+            self.profile_repository
+                .followers(current_user, username, pagination)?;
+        Ok(self.presenter.to_multi_json(list, count))
+    }
+
+    pub fn fetch_following(
+        &self,
+        current_user: &Option<User>,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<HttpResponse, AppError> {
+        let (list, count) =
+            self.profile_repository
+                .following(current_user, username, pagination)?;
+        Ok(self.presenter.to_multi_json(list, count))
+    }
+
+    pub fn fetch_mutuals(
+        &self,
+        current_user: &User,
+        username: &str,
+        pagination: Pagination,
+    ) -> Result<HttpResponse, AppError> {
+        let (list, count) = self
+            .profile_repository
+            .mutuals(current_user, username, pagination)?;
+        Ok(self.presenter.to_multi_json(list, count))
     }
 
     pub fn follow_user(
@@ -42,10 +108,18 @@ impl ProfileUsecase {
         current_user: &User,
         target_username: &str,
     ) -> Result<HttpResponse, AppError> {
-        let profile = self
+        let (profile, inserted) = self
             .user_repository
             .follow_user(current_user, target_username)?;
-        Ok(self.presenter.to_json(profile))
+        // NOTE: following again changes nothing, so there is nothing to announce.
+        if inserted {
+            self.activity_feed.publish(Activity::Follow {
+                follower: current_user.username.clone(),
+                followee: profile.username.clone(),
+            });
+        }
+        let counts = self.profile_repository.follow_counts(target_username)?;
+        Ok(self.presenter.to_json(profile, counts))
     }
 
     pub fn unfollow_user(
@@ -56,6 +130,245 @@ impl ProfileUsecase {
         let profile = self
             .user_repository
             .unfollow_user(current_user, target_username)?;
-        Ok(self.presenter.to_json(profile))
+        let counts = self.profile_repository.follow_counts(target_username)?;
+        Ok(self.presenter.to_json(profile, counts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::features::profile::entities::{FollowCounts, Profile};
+    use crate::app::features::profile::presenters::ProfilePresenterImpl;
+    use crate::app::features::user::entities::UpdateUser;
+    use actix_web::body::to_bytes;
+    use actix_web::ResponseError;
+    use chrono::NaiveDateTime;
+    use futures::{FutureExt, StreamExt};
+    use serde_json::json;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    // NOTE: `(follower, followee)` username pairs shared by both fakes.
+    type Follows = Arc<Mutex<HashSet<(String, String)>>>;
+
+    struct FakeUserRepository {
+        follows: Follows,
+    }
+
+    impl FakeUserRepository {
+        fn profile(username: &str, following: bool) -> Profile {
+            Profile {
+                username: username.to_owned(),
+                bio: None,
+                image: None,
+                following,
+            }
+        }
+    }
+
+    impl UserRepository for FakeUserRepository {
+        fn signin(&self, _email: &str, _password: &str) -> Result<(User, String), AppError> {
+            Err(AppError::Internal)
+        }
+        fn signup(
+            &self,
+            _email: &str,
+            _username: &str,
+            _password: &str,
+        ) -> Result<(User, String), AppError> {
+            Err(AppError::Internal)
+        }
+        fn follow_user(
+            &self,
+            current_user: &User,
+            username: &str,
+        ) -> Result<(Profile, bool), AppError> {
+            let inserted = self
+                .follows
+                .lock()
+                .unwrap()
+                .insert((current_user.username.to_owned(), username.to_owned()));
+            Ok((Self::profile(username, true), inserted))
+        }
+        fn unfollow_user(&self, current_user: &User, username: &str) -> Result<Profile, AppError> {
+            self.follows
+                .lock()
+                .unwrap()
+                .remove(&(current_user.username.to_owned(), username.to_owned()));
+            Ok(Self::profile(username, false))
+        }
+        fn taken_fields(
+            &self,
+            _user_id: Uuid,
+            _changeset: &UpdateUser,
+        ) -> Result<Vec<&'static str>, AppError> {
+            Err(AppError::Internal)
+        }
+        fn update(
+            &self,
+            _user_id: Uuid,
+            _changeset: UpdateUser,
+        ) -> Result<(User, String), AppError> {
+            Err(AppError::Internal)
+        }
+        fn find(&self, _user_id: Uuid) -> Result<User, AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    struct FakeProfileRepository {
+        follows: Follows,
+    }
+
+    impl ProfileRepository for FakeProfileRepository {
+        fn fetch_profile_by_name(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<Profile, AppError> {
+            // NOTE: what `User::find_by_username` returns when no row matches.
+            Err(diesel::result::Error::NotFound.into())
+        }
+        fn follow_counts(&self, username: &str) -> Result<FollowCounts, AppError> {
+            let follows = self.follows.lock().unwrap();
+            let count = |f: fn(&(String, String)) -> &String| {
+                follows.iter().filter(|pair| f(pair) == username).count() as i64
+            };
+            Ok(FollowCounts {
+                followers_count: count(|(_, followee)| followee),
+                following_count: count(|(follower, _)| follower),
+            })
+        }
+        fn following_map(
+            &self,
+            _current_user_id: &Uuid,
+            _user_ids: &[Uuid],
+        ) -> Result<HashSet<Uuid>, AppError> {
+            Err(AppError::Internal)
+        }
+        fn profiles_by_usernames(
+            &self,
+            _current_user: &Option<User>,
+            _usernames: &[String],
+        ) -> Result<Vec<Profile>, AppError> {
+            Err(AppError::Internal)
+        }
+        fn search(
+            &self,
+            _current_user: &Option<User>,
+            _query: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn mutuals(
+            &self,
+            _current_user: &User,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn followers(
+            &self,
+            _current_user: &Option<User>,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+        fn following(
+            &self,
+            _current_user: &Option<User>,
+            _username: &str,
+            _pagination: Pagination,
+        ) -> Result<(Vec<Profile>, i64), AppError> {
+            Err(AppError::Internal)
+        }
+    }
+
+    fn user(username: &str) -> User {
+        let now = NaiveDateTime::default();
+        User {
+            id: Uuid::new_v4(),
+            email: format!("{}@example.com", username),
+            username: username.to_owned(),
+            password: "hashed".to_owned(),
+            bio: None,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            email_verified: true,
+            role: "user".to_owned(),
+            last_login_at: None,
+        }
+    }
+
+    fn usecase() -> ProfileUsecase {
+        usecase_with_feed(ActivityFeed::new())
+    }
+
+    fn usecase_with_feed(activity_feed: ActivityFeed) -> ProfileUsecase {
+        let follows = Follows::default();
+        ProfileUsecase::new(
+            Arc::new(FakeProfileRepository {
+                follows: follows.clone(),
+            }),
+            Arc::new(FakeUserRepository { follows }),
+            Arc::new(ProfilePresenterImpl::new()),
+            activity_feed,
+        )
+    }
+
+    async fn json_body(res: HttpResponse) -> serde_json::Value {
+        let body = to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn follow_counts_change_immediately() {
+        let usecase = usecase();
+        let (jake, anna) = (user("jake"), user("anna"));
+
+        let body = json_body(usecase.follow_user(&jake, "celeb").unwrap()).await;
+        assert_eq!(true, body["profile"]["following"]);
+        assert_eq!(1, body["profile"]["followersCount"]);
+        assert_eq!(0, body["profile"]["followingCount"]);
+
+        let body = json_body(usecase.follow_user(&anna, "celeb").unwrap()).await;
+        assert_eq!(2, body["profile"]["followersCount"]);
+
+        let body = json_body(usecase.unfollow_user(&jake, "celeb").unwrap()).await;
+        assert_eq!(false, body["profile"]["following"]);
+        assert_eq!(1, body["profile"]["followersCount"]);
+    }
+
+    #[actix_web::test]
+    async fn following_again_is_not_announced() {
+        let activity_feed = ActivityFeed::new();
+        let usecase = usecase_with_feed(activity_feed.clone());
+        let mut events = activity_feed.subscribe(None);
+
+        for _ in 0..2 {
+            usecase.follow_user(&user("jake"), "celeb").unwrap();
+        }
+
+        assert!(events.next().now_or_never().flatten().is_some());
+        assert!(events.next().now_or_never().is_none());
+    }
+
+    #[actix_web::test]
+    async fn missing_username_is_not_found() {
+        let err = usecase()
+            .fetch_profile_by_name(&user("jake"), "nobody")
+            .unwrap_err();
+        let res = err.error_response();
+        assert_eq!(404, res.status().as_u16());
+        assert_eq!(
+            json!({"errors": {"body": ["not found"]}}),
+            json_body(res).await
+        );
     }
 }
@@ -0,0 +1,128 @@
+use super::presenters::{MultipleReportsResponse, ReportResponse};
+use super::requests;
+use crate::app::drivers::middlewares::auth;
+use crate::app::drivers::middlewares::state::AppState;
+use crate::utils::api::ApiResponse;
+use crate::utils::pagination::Pagination;
+use crate::utils::{uuid, validation};
+use actix_web::{web, HttpRequest};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+type ArticleIdSlug = String;
+type CommentIdSlug = String;
+type ReportIdSlug = String;
+
+#[utoipa::path(
+    post,
+    path = "/api/articles/{article_title_slug}/report",
+    tag = "report",
+    params(("article_title_slug" = String, Path)),
+    request_body = requests::CreateReportRequest,
+    responses(
+        (status = 200, body = ReportResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article not found"),
+        (status = 422, description = "Blank reason"),
+    ),
+    security(("token" = []))
+)]
+pub async fn report_article(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ArticleIdSlug>,
+    form: web::Json<requests::CreateReportRequest>,
+) -> ApiResponse {
+    validation::validate(&*form)?;
+    let current_user = auth::get_current_user(&req)?;
+    let article_title_slug = path.into_inner();
+    state.di_container.report_usecase.report_article(
+        &current_user,
+        &article_title_slug,
+        form.report.reason.clone(),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles/{article_title_slug}/comments/{comment_id}/report",
+    tag = "report",
+    params(("article_title_slug" = String, Path), ("comment_id" = String, Path)),
+    request_body = requests::CreateReportRequest,
+    responses(
+        (status = 200, body = ReportResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "Article or comment not found"),
+        (status = 422, description = "Blank reason"),
+    ),
+    security(("token" = []))
+)]
+pub async fn report_comment(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(ArticleIdSlug, CommentIdSlug)>,
+    form: web::Json<requests::CreateReportRequest>,
+) -> ApiResponse {
+    validation::validate(&*form)?;
+    let current_user = auth::get_current_user(&req)?;
+    let (article_title_slug, comment_id) = path.into_inner();
+    let comment_id = uuid::parse(&comment_id)?;
+    state.di_container.report_usecase.report_comment(
+        &current_user,
+        &article_title_slug,
+        comment_id,
+        form.report.reason.clone(),
+    )
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportsListQueryParameter {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/reports",
+    tag = "report",
+    params(ReportsListQueryParameter),
+    responses(
+        (status = 200, body = MultipleReportsResponse, description = "Newest first"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Not an admin"),
+    ),
+    security(("token" = []))
+)]
+pub async fn index(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    params: web::Query<ReportsListQueryParameter>,
+) -> ApiResponse {
+    auth::require_admin(&req)?;
+    let pagination = Pagination::from_query(params.limit, params.offset);
+    state.di_container.report_usecase.fetch_reports(pagination)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/reports/{report_id}/hide",
+    tag = "report",
+    params(("report_id" = String, Path)),
+    responses(
+        (status = 200, body = ReportResponse, description = "The reported article or comment is now hidden from public views"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Not an admin"),
+        (status = 404, description = "Report not found"),
+    ),
+    security(("token" = []))
+)]
+pub async fn hide(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<ReportIdSlug>,
+) -> ApiResponse {
+    auth::require_admin(&req)?;
+    let report_id = uuid::parse(&path.into_inner())?;
+    state.di_container.report_usecase.hide(report_id)
+}
@@ -0,0 +1,69 @@
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::schema::{reports, users};
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+// A user's flag on an article or a comment; exactly one of the two ids is set.
+#[derive(Identifiable, Queryable, Associations, Debug, Clone)]
+#[diesel(belongs_to(User, foreign_key = reporter_id))]
+#[diesel(table_name = reports)]
+pub struct Report {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub article_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub reason: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Report {
+    pub fn create(conn: &mut PgConnection, record: &CreateReport) -> Result<Self, AppError> {
+        let report = diesel::insert_into(reports::table)
+            .values(record)
+            .get_result::<Self>(conn)?;
+        Ok(report)
+    }
+
+    pub fn find_with_reporter(
+        conn: &mut PgConnection,
+        id: &Uuid,
+    ) -> Result<(Self, User), AppError> {
+        let t = reports::table
+            .inner_join(users::table)
+            .filter(reports::id.eq(id));
+        let result = t.get_result::<(Self, User)>(conn)?;
+        Ok(result)
+    }
+
+    // NOTE: newest first; `id` breaks ties so pages don't overlap.
+    pub fn fetch_with_reporter(
+        conn: &mut PgConnection,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<(Self, User)>, AppError> {
+        let t = reports::table
+            .inner_join(users::table)
+            .order((reports::created_at.desc(), reports::id.desc()))
+            .limit(limit)
+            .offset(offset);
+        let list = t.get_results::<(Self, User)>(conn)?;
+        Ok(list)
+    }
+
+    pub fn count(conn: &mut PgConnection) -> Result<i64, AppError> {
+        let count = reports::table.count().get_result::<i64>(conn)?;
+        Ok(count)
+    }
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = reports)]
+pub struct CreateReport {
+    pub reporter_id: Uuid,
+    pub article_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub reason: String,
+}
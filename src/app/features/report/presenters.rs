@@ -0,0 +1,75 @@
+use super::entities::Report;
+use super::repositories::ReportsList;
+use crate::app::features::user::entities::User;
+use crate::utils::date::Iso8601;
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ReportResponse {
+    pub report: ReportContent,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipleReportsResponse {
+    pub reports: Vec<ReportContent>,
+    pub reports_count: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportContent {
+    pub id: Uuid,
+    pub reason: String,
+    pub article_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    // NOTE: the reporter's username.
+    pub reporter: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: Iso8601,
+}
+
+impl From<(Report, User)> for ReportContent {
+    fn from((report, reporter): (Report, User)) -> Self {
+        Self {
+            id: report.id,
+            reason: report.reason,
+            article_id: report.article_id,
+            comment_id: report.comment_id,
+            reporter: reporter.username,
+            created_at: Iso8601(report.created_at),
+        }
+    }
+}
+
+pub trait ReportPresenter: Send + Sync + 'static {
+    fn to_single_json(&self, item: (Report, User)) -> HttpResponse;
+    fn to_multi_json(&self, list: ReportsList, count: i64) -> HttpResponse;
+}
+
+#[derive(Clone, Default)]
+pub struct ReportPresenterImpl {}
+impl ReportPresenterImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+impl ReportPresenter for ReportPresenterImpl {
+    fn to_single_json(&self, item: (Report, User)) -> HttpResponse {
+        let res = ReportResponse {
+            report: ReportContent::from(item),
+        };
+        HttpResponse::Ok().json(res)
+    }
+
+    fn to_multi_json(&self, list: ReportsList, count: i64) -> HttpResponse {
+        let res = MultipleReportsResponse {
+            reports: list.into_iter().map(ReportContent::from).collect(),
+            reports_count: count,
+        };
+        HttpResponse::Ok().json(res)
+    }
+}
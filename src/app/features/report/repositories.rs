@@ -0,0 +1,112 @@
+use super::entities::{CreateReport, Report};
+use crate::app::features::article::entities::Article;
+use crate::app::features::comment::entities::Comment;
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::utils::db::{self, DbPools};
+use crate::utils::pagination::Pagination;
+use uuid::Uuid;
+
+pub type ReportsList = Vec<(Report, User)>;
+type ReportsCount = i64;
+
+pub trait ReportRepository: Send + Sync + 'static {
+    // NOTE: only content the reporter can read may be reported; anything else is a 404.
+    fn report_article(
+        &self,
+        reporter: &User,
+        article_title_slug: &str,
+        reason: String,
+    ) -> Result<(Report, User), AppError>;
+
+    fn report_comment(
+        &self,
+        reporter: &User,
+        article_title_slug: &str,
+        comment_id: Uuid,
+        reason: String,
+    ) -> Result<(Report, User), AppError>;
+
+    fn list(&self, pagination: Pagination) -> Result<(ReportsList, ReportsCount), AppError>;
+
+    // NOTE: hides the reported article or comment from public views; the report itself stays listed.
+    fn hide(&self, report_id: Uuid) -> Result<(Report, User), AppError>;
+}
+
+#[derive(Clone)]
+pub struct ReportRepositoryImpl {
+    pools: DbPools,
+}
+
+impl ReportRepositoryImpl {
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
+    }
+}
+
+impl ReportRepository for ReportRepositoryImpl {
+    fn report_article(
+        &self,
+        reporter: &User,
+        article_title_slug: &str,
+        reason: String,
+    ) -> Result<(Report, User), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        let (article, _author) =
+            Article::fetch_by_slug_with_author(conn, article_title_slug, Some(&reporter.id))?;
+        let report = Report::create(
+            conn,
+            &CreateReport {
+                reporter_id: reporter.id,
+                article_id: Some(article.id),
+                comment_id: None,
+                reason,
+            },
+        )?;
+        Ok((report, reporter.clone()))
+    }
+
+    fn report_comment(
+        &self,
+        reporter: &User,
+        article_title_slug: &str,
+        comment_id: Uuid,
+        reason: String,
+    ) -> Result<(Report, User), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        let (article, _author) =
+            Article::fetch_by_slug_with_author(conn, article_title_slug, Some(&reporter.id))?;
+        let comment = Comment::find_by_article_id(conn, &article.id, &comment_id)?;
+        let report = Report::create(
+            conn,
+            &CreateReport {
+                reporter_id: reporter.id,
+                article_id: None,
+                comment_id: Some(comment.id),
+                reason,
+            },
+        )?;
+        Ok((report, reporter.clone()))
+    }
+
+    fn list(&self, pagination: Pagination) -> Result<(ReportsList, ReportsCount), AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        let list = Report::fetch_with_reporter(conn, pagination.offset, pagination.limit)?;
+        let count = Report::count(conn)?;
+        Ok((list, count))
+    }
+
+    fn hide(&self, report_id: Uuid) -> Result<(Report, User), AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        db::with_transaction(conn, |conn| {
+            let (report, reporter) = Report::find_with_reporter(conn, &report_id)?;
+            if let Some(article_id) = &report.article_id {
+                Article::set_hidden(conn, article_id, true)?;
+            }
+            if let Some(comment_id) = &report.comment_id {
+                Comment::set_hidden(conn, comment_id, true)?;
+            }
+            Ok((report, reporter))
+        })
+    }
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
+pub struct CreateReportRequest {
+    #[validate(nested)]
+    pub report: CreateReportInner,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Validate)]
+pub struct CreateReportInner {
+    #[validate(length(min = 1, message = "can't be blank"))]
+    pub reason: String,
+}
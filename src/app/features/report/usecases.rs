@@ -0,0 +1,64 @@
+use super::presenters::ReportPresenter;
+use super::repositories::ReportRepository;
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::utils::pagination::Pagination;
+use actix_web::HttpResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ReportUsecase {
+    report_repository: Arc<dyn ReportRepository>,
+    report_presenter: Arc<dyn ReportPresenter>,
+}
+
+impl ReportUsecase {
+    pub fn new(
+        report_repository: Arc<dyn ReportRepository>,
+        report_presenter: Arc<dyn ReportPresenter>,
+    ) -> Self {
+        Self {
+            report_repository,
+            report_presenter,
+        }
+    }
+
+    pub fn report_article(
+        &self,
+        reporter: &User,
+        article_title_slug: &str,
+        reason: String,
+    ) -> Result<HttpResponse, AppError> {
+        let item = self
+            .report_repository
+            .report_article(reporter, article_title_slug, reason)?;
+        Ok(self.report_presenter.to_single_json(item))
+    }
+
+    pub fn report_comment(
+        &self,
+        reporter: &User,
+        article_title_slug: &str,
+        comment_id: Uuid,
+        reason: String,
+    ) -> Result<HttpResponse, AppError> {
+        let item = self.report_repository.report_comment(
+            reporter,
+            article_title_slug,
+            comment_id,
+            reason,
+        )?;
+        Ok(self.report_presenter.to_single_json(item))
+    }
+
+    pub fn fetch_reports(&self, pagination: Pagination) -> Result<HttpResponse, AppError> {
+        let (list, count) = self.report_repository.list(pagination)?;
+        Ok(self.report_presenter.to_multi_json(list, count))
+    }
+
+    pub fn hide(&self, report_id: Uuid) -> Result<HttpResponse, AppError> {
+        let item = self.report_repository.hide(report_id)?;
+        Ok(self.report_presenter.to_single_json(item))
+    }
+}
@@ -1,8 +1,44 @@
 extern crate serde_json;
+use crate::app::drivers::middlewares::auth;
 use crate::app::drivers::middlewares::state::AppState;
 use crate::utils::api::ApiResponse;
-use actix_web::web;
+use actix_web::{web, HttpRequest};
+use serde::Deserialize;
+use utoipa::IntoParams;
 
-pub async fn index(state: web::Data<AppState>) -> ApiResponse {
-    state.di_container.tag_usecase.fetch_tags()
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagsQueryParameter {
+    // NOTE: every distinct tag name when absent.
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    tag = "tag",
+    params(TagsQueryParameter),
+    responses((status = 200, body = super::presenters::TagsResponse))
+)]
+pub async fn index(
+    state: web::Data<AppState>,
+    params: web::Query<TagsQueryParameter>,
+) -> ApiResponse {
+    state.di_container.tag_usecase.fetch_tags(params.limit)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tags/consolidate",
+    tag = "tag",
+    responses(
+        (status = 200, body = super::presenters::TagConsolidationResponse, description = "How many tags were renamed and how many duplicates removed"),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 403, description = "Not an admin"),
+    ),
+    security(("token" = []))
+)]
+pub async fn consolidate(state: web::Data<AppState>, req: HttpRequest) -> ApiResponse {
+    auth::require_admin(&req)?;
+    state.di_container.tag_usecase.consolidate().await
 }
@@ -3,11 +3,14 @@ use crate::error::AppError;
 use crate::schema::tags;
 use chrono::NaiveDateTime;
 use diesel::backend::Backend;
-use diesel::dsl::{AsSelect, Eq, Filter, Select};
-use diesel::pg::PgConnection;
+use diesel::dsl::{AsSelect, Asc, Eq, EqAny, Filter, IntoBoxed, Order, Select};
+use diesel::pg::{Pg, PgConnection};
+use diesel::query_builder::QueryFragment;
+use diesel::query_dsl::methods::ExecuteDsl;
 use diesel::Insertable;
 use diesel::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 #[derive(
@@ -34,9 +37,17 @@ pub struct Tag {
 // Tags
 type All<DB> = Select<tags::table, AsSelect<Tag, DB>>;
 type WithName<T> = Eq<tags::name, T>;
-type ByName<T, DB> = Filter<All<DB>, WithName<T>>;
+type ArticleIdsByName<T> = Select<Filter<tags::table, WithName<T>>, tags::article_id>;
 type WithArticleId<T> = Eq<tags::article_id, T>;
 type ByArticleId<T, DB> = Filter<All<DB>, WithArticleId<T>>;
+type NamesByArticleIds<'a> = Order<
+    Select<
+        Filter<tags::table, EqAny<tags::article_id, &'a [Uuid]>>,
+        (tags::article_id, tags::name),
+    >,
+    Asc<tags::name>,
+>;
+type BoxedNames<'a> = IntoBoxed<'a, Select<tags::table, tags::name>, Pg>;
 
 impl Tag {
     fn all<DB>() -> All<DB>
@@ -50,11 +61,11 @@ impl Tag {
         tags::name.eq(name)
     }
 
-    fn by_name<DB>(name: &str) -> ByName<&str, DB>
-    where
-        DB: Backend,
-    {
-        Self::all().filter(Self::with_name(name))
+    // NOTE: meant to be used as a subquery, e.g. `articles::id.eq_any(..)`.
+    pub fn article_ids_by_name(name: &str) -> ArticleIdsByName<&str> {
+        tags::table
+            .filter(Self::with_name(name))
+            .select(tags::article_id)
     }
 
     fn with_article_id(article_id: &Uuid) -> WithArticleId<&Uuid> {
@@ -68,6 +79,29 @@ impl Tag {
         Self::all().filter(Self::with_article_id(article_id))
     }
 
+    // NOTE: tag names for a whole page of articles in a single query.
+    fn names_by_article_ids(article_ids: &[Uuid]) -> NamesByArticleIds<'_> {
+        tags::table
+            .filter(tags::article_id.eq_any(article_ids))
+            .select((tags::article_id, tags::name))
+            .order(tags::name.asc())
+    }
+
+    pub fn fetch_names_by_article_ids(
+        conn: &mut PgConnection,
+        article_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<String>>, AppError> {
+        if article_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let rows = Self::names_by_article_ids(article_ids).load::<(Uuid, String)>(conn)?;
+        let mut names = HashMap::<Uuid, Vec<String>>::new();
+        for (article_id, name) in rows {
+            names.entry(article_id).or_default().push(name);
+        }
+        Ok(names)
+    }
+
     pub fn fetch_by_article_id(
         conn: &mut PgConnection,
         article_id: &Uuid,
@@ -77,32 +111,177 @@ impl Tag {
         Ok(list)
     }
 
-    pub fn fetch(conn: &mut PgConnection) -> Result<Vec<Self>, AppError> {
-        let list = tags::table.load::<Self>(conn)?;
+    // NOTE: distinct names across live articles, so a tag used by many articles is listed once.
+    fn names<'a>(limit: Option<i64>) -> BoxedNames<'a> {
+        use crate::schema::articles;
+        let live_article_ids = articles::table
+            .filter(Article::not_deleted())
+            .filter(Article::listed())
+            .select(articles::id);
+        let t = tags::table
+            .filter(tags::article_id.eq_any(live_article_ids))
+            .select(tags::name)
+            .distinct()
+            .order(tags::name.asc())
+            .into_boxed();
+        match limit {
+            Some(limit) => t.limit(limit.max(0)),
+            None => t,
+        }
+    }
+
+    pub fn list(conn: &mut PgConnection, limit: Option<i64>) -> Result<Vec<String>, AppError> {
+        let list = Self::names(limit).load::<String>(conn)?;
         Ok(list)
     }
 
-    pub fn fetch_article_ids_by_name(
+    pub fn create_list(
         conn: &mut PgConnection,
-        tag_name: &str,
-    ) -> Result<Vec<Uuid>, AppError> {
-        let t = Self::by_name(tag_name);
-        let article_ids = t
-            .load::<Tag>(conn)?
+        article_id: &Uuid,
+        names: &[String],
+    ) -> Result<Vec<Self>, AppError> {
+        let names = Self::normalize_list(names);
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+        let records = names
             .iter()
-            .map(|tag| tag.article_id)
-            .collect();
-        Ok(article_ids)
+            .map(|name| CreateTag { name, article_id })
+            .collect::<Vec<_>>();
+        Self::upsert(&records).execute(conn)?;
+
+        // NOTE: rows that already existed aren't returned by the insert, so read them all back.
+        let tags_list = Self::by_article_id(article_id)
+            .filter(tags::name.eq_any(&names))
+            .get_results::<Self>(conn)?;
+        Ok(Self::sort_by_names(tags_list, &names))
     }
 
-    pub fn create_list(
+    // NOTE: adds the new names, drops the ones no longer listed and leaves the rest untouched.
+    pub fn replace_list(
         conn: &mut PgConnection,
-        records: Vec<CreateTag>,
+        article_id: &Uuid,
+        names: &[String],
     ) -> Result<Vec<Self>, AppError> {
-        let tags_list = diesel::insert_into(tags::table)
+        let names = Self::normalize_list(names);
+        Self::delete_except(article_id, &names).execute(conn)?;
+        Self::create_list(conn, article_id, &names)
+    }
+
+    // NOTE: with no names left this deletes every tag of the article.
+    fn delete_except<'a>(
+        article_id: &'a Uuid,
+        names: &'a [String],
+    ) -> impl RunQueryDsl<PgConnection> + ExecuteDsl<PgConnection> + QueryFragment<Pg> + 'a {
+        diesel::delete(
+            tags::table
+                .filter(tags::article_id.eq(article_id))
+                .filter(tags::name.ne_all(names)),
+        )
+    }
+
+    // NOTE: re-saving a tag the article already has is a no-op thanks to `tags_article_id_name_key`.
+    fn upsert<'a>(
+        records: &'a [CreateTag<'a>],
+    ) -> impl RunQueryDsl<PgConnection> + ExecuteDsl<PgConnection> + QueryFragment<Pg> + 'a {
+        diesel::insert_into(tags::table)
             .values(records)
-            .get_results::<Tag>(conn)?;
-        Ok(tags_list)
+            .on_conflict((tags::article_id, tags::name))
+            .do_nothing()
+    }
+
+    fn sort_by_names(mut list: Vec<Self>, names: &[String]) -> Vec<Self> {
+        list.sort_by_key(|tag| names.iter().position(|name| *name == tag.name));
+        list
+    }
+}
+
+impl Tag {
+    // NOTE: "React", "react" and " re  act " would otherwise be three different tags.
+    pub fn normalize_name(name: &str) -> String {
+        name.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    // NOTE: keeps the first occurrence of each name and drops blank ones.
+    fn normalize_list(names: &[String]) -> Vec<String> {
+        let mut list = Vec::with_capacity(names.len());
+        for name in names.iter().map(|name| Self::normalize_name(name)) {
+            if !name.is_empty() && !list.contains(&name) {
+                list.push(name);
+            }
+        }
+        list
+    }
+}
+
+// What `Tag::consolidate` changed: rows rewritten to their normalized name, and duplicates dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TagConsolidation {
+    pub renamed: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ConsolidationPlan {
+    renamed: Vec<(Uuid, String)>,
+    removed: Vec<Uuid>,
+}
+
+impl Tag {
+    // NOTE: `rows` come oldest first; per article and normalized name, a row already spelled
+    // that way is kept, otherwise the oldest one is renamed. Blank names are dropped.
+    fn consolidation_plan(rows: Vec<(Uuid, Uuid, String)>) -> ConsolidationPlan {
+        let mut groups = BTreeMap::<(Uuid, String), Vec<(Uuid, String)>>::new();
+        for (id, article_id, name) in rows {
+            groups
+                .entry((article_id, Self::normalize_name(&name)))
+                .or_default()
+                .push((id, name));
+        }
+        let mut plan = ConsolidationPlan::default();
+        for ((_, canonical), rows) in groups {
+            if canonical.is_empty() {
+                plan.removed.extend(rows.into_iter().map(|(id, _)| id));
+                continue;
+            }
+            let keep = rows
+                .iter()
+                .position(|(_, name)| *name == canonical)
+                .unwrap_or(0);
+            for (index, (id, name)) in rows.into_iter().enumerate() {
+                if index != keep {
+                    plan.removed.push(id);
+                } else if name != canonical {
+                    plan.renamed.push((id, canonical.clone()));
+                }
+            }
+        }
+        plan
+    }
+
+    // NOTE: tags saved before names were normalized on write can still differ in case or spacing.
+    pub fn consolidate(conn: &mut PgConnection) -> Result<TagConsolidation, AppError> {
+        let rows = tags::table
+            .select((tags::id, tags::article_id, tags::name))
+            .order((tags::created_at.asc(), tags::id.asc()))
+            .load::<(Uuid, Uuid, String)>(conn)?;
+        let plan = Self::consolidation_plan(rows);
+        // NOTE: duplicates go first so no rename collides with `tags_article_id_name_key`.
+        if !plan.removed.is_empty() {
+            diesel::delete(tags::table.filter(tags::id.eq_any(&plan.removed))).execute(conn)?;
+        }
+        for (id, name) in &plan.renamed {
+            diesel::update(tags::table.find(id))
+                .set(tags::name.eq(name))
+                .execute(conn)?;
+        }
+        Ok(TagConsolidation {
+            renamed: plan.renamed.len(),
+            removed: plan.removed.len(),
+        })
     }
 }
 
@@ -112,3 +291,127 @@ pub struct CreateTag<'a> {
     pub name: &'a str,
     pub article_id: &'a Uuid,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_name_trims_lowercases_and_collapses() {
+        assert_eq!("react", Tag::normalize_name("React"));
+        assert_eq!("react", Tag::normalize_name(" react "));
+        assert_eq!("react native", Tag::normalize_name("  React \t Native "));
+    }
+
+    #[test]
+    fn normalize_list_dedups_and_drops_blank() {
+        let names = ["React", "react", " react ", "   ", "Rust", "RUST"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["react", "rust"], Tag::normalize_list(&names));
+    }
+
+    #[test]
+    fn consolidation_plan_merges_variants_per_article() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let ids = (0..6).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        let rows = vec![
+            (ids[0], a, "React".to_owned()),
+            (ids[1], a, " react ".to_owned()),
+            (ids[2], a, "Web  Dev".to_owned()),
+            (ids[3], b, "REACT".to_owned()),
+            (ids[4], b, "react".to_owned()),
+            (ids[5], b, "  ".to_owned()),
+        ];
+        let mut plan = Tag::consolidation_plan(rows);
+        plan.removed.sort();
+        plan.renamed.sort();
+
+        let mut removed = vec![ids[1], ids[3], ids[5]];
+        removed.sort();
+        let mut renamed = vec![(ids[0], "react".to_owned()), (ids[2], "web dev".to_owned())];
+        renamed.sort();
+        assert_eq!(ConsolidationPlan { renamed, removed }, plan);
+    }
+
+    #[test]
+    fn consolidation_plan_leaves_clean_tags_alone() {
+        let rows = vec![(Uuid::new_v4(), Uuid::new_v4(), "rust".to_owned())];
+        assert_eq!(ConsolidationPlan::default(), Tag::consolidation_plan(rows));
+    }
+
+    #[test]
+    fn upsert_ignores_existing_tags() {
+        let article_id = Uuid::nil();
+        let records = [
+            CreateTag {
+                name: "react",
+                article_id: &article_id,
+            },
+            CreateTag {
+                name: "rust",
+                article_id: &article_id,
+            },
+        ];
+        let query = Tag::upsert(&records);
+        let sql = diesel::debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#"ON CONFLICT ("article_id", "name") DO NOTHING"#));
+    }
+
+    #[test]
+    fn delete_except_keeps_the_listed_names() {
+        let article_id = Uuid::nil();
+        let names = vec!["dragons".to_owned()];
+        let query = Tag::delete_except(&article_id, &names);
+        let sql = diesel::debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.starts_with(
+            r#"DELETE FROM "tags" WHERE (("tags"."article_id" = $1) AND ("tags"."name" != ALL($2)))"#
+        ));
+        assert!(sql.contains(r#"["dragons"]"#));
+    }
+
+    #[test]
+    fn names_by_article_ids_is_one_query_for_many_articles() {
+        let article_ids = (0..50).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        let query = Tag::names_by_article_ids(&article_ids);
+        let sql = diesel::debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.starts_with(
+            r#"SELECT "tags"."article_id", "tags"."name" FROM "tags" WHERE ("tags"."article_id" = ANY($1)) ORDER BY "tags"."name" ASC"#
+        ));
+        assert!(sql.contains(&article_ids[49].to_string()));
+    }
+
+    #[test]
+    fn sort_by_names_keeps_requested_order() {
+        let now = NaiveDateTime::default();
+        let tag = |name: &str| Tag {
+            id: Uuid::new_v4(),
+            article_id: Uuid::nil(),
+            name: name.to_owned(),
+            created_at: now,
+            updated_at: now,
+        };
+        let names = vec!["rust".to_owned(), "react".to_owned()];
+        let list = Tag::sort_by_names(vec![tag("react"), tag("rust")], &names);
+        assert_eq!(
+            vec!["rust", "react"],
+            list.iter().map(|tag| tag.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn names_are_distinct_and_unlimited_by_default() {
+        let sql = diesel::debug_query::<Pg, _>(&Tag::names(None)).to_string();
+        assert!(sql.starts_with(r#"SELECT DISTINCT "tags"."name" FROM "tags""#));
+        assert!(sql.contains(r#"ORDER BY "tags"."name" ASC"#));
+        assert!(!sql.contains("LIMIT"));
+    }
+
+    #[test]
+    fn names_take_an_optional_limit() {
+        let sql = diesel::debug_query::<Pg, _>(&Tag::names(Some(5))).to_string();
+        assert!(sql.contains("LIMIT $"));
+        assert!(sql.ends_with("5]"));
+    }
+}
@@ -1,25 +1,41 @@
-use super::entities::Tag;
+use super::entities::TagConsolidation;
 use actix_web::HttpResponse;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct TagsResponse {
     // SPEC: https://gothinkster.github.io/realworld/docs/specs/backend-specs/endpoints#registration
     pub tags: Vec<String>,
 }
 
-impl std::convert::From<Vec<Tag>> for TagsResponse {
-    fn from(tags: Vec<Tag>) -> Self {
-        let list = tags.iter().map(move |tag| tag.name.clone()).collect();
-        TagsResponse { tags: list }
+impl std::convert::From<Vec<String>> for TagsResponse {
+    fn from(tags: Vec<String>) -> Self {
+        TagsResponse { tags }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct TagConsolidationResponse {
+    pub renamed: usize,
+    pub removed: usize,
+}
+
+impl std::convert::From<TagConsolidation> for TagConsolidationResponse {
+    fn from(summary: TagConsolidation) -> Self {
+        TagConsolidationResponse {
+            renamed: summary.renamed,
+            removed: summary.removed,
+        }
     }
 }
 
 pub trait TagPresenter: Send + Sync + 'static {
-    fn to_json(&self, list: Vec<Tag>) -> HttpResponse;
+    fn to_json(&self, list: Vec<String>) -> HttpResponse;
+    fn to_consolidation_json(&self, summary: TagConsolidation) -> HttpResponse;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct TagPresenterImpl {}
 impl TagPresenterImpl {
     pub fn new() -> Self {
@@ -27,8 +43,13 @@ impl TagPresenterImpl {
     }
 }
 impl TagPresenter for TagPresenterImpl {
-    fn to_json(&self, list: Vec<Tag>) -> HttpResponse {
+    fn to_json(&self, list: Vec<String>) -> HttpResponse {
         let res = TagsResponse::from(list);
         HttpResponse::Ok().json(res)
     }
+
+    fn to_consolidation_json(&self, summary: TagConsolidation) -> HttpResponse {
+        let res = TagConsolidationResponse::from(summary);
+        HttpResponse::Ok().json(res)
+    }
 }
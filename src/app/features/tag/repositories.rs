@@ -1,25 +1,34 @@
-use super::entities::Tag;
+use super::entities::{Tag, TagConsolidation};
 use crate::error::AppError;
-use crate::utils::db::DbPool;
+use crate::utils::db::{self, DbPools};
 
 pub trait TagRepository: Send + Sync + 'static {
-    fn fetch_tags(&self) -> Result<Vec<Tag>, AppError>;
+    // NOTE: tags are stored per article; this is the public listing with each name once, A to Z.
+    fn list_distinct_names(&self, limit: Option<i64>) -> Result<Vec<String>, AppError>;
+
+    // NOTE: maintenance only (admin route or `cargo run --bin consolidate_tags`); rewrites the whole table.
+    fn consolidate(&self) -> Result<TagConsolidation, AppError>;
 }
 
 #[derive(Clone)]
 pub struct TagRepositoryImpl {
-    pool: DbPool,
+    pools: DbPools,
 }
 
 impl TagRepositoryImpl {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
     }
 }
 
 impl TagRepository for TagRepositoryImpl {
-    fn fetch_tags(&self) -> Result<Vec<Tag>, AppError> {
-        let conn = &mut self.pool.get()?;
-        Tag::fetch(conn)
+    fn list_distinct_names(&self, limit: Option<i64>) -> Result<Vec<String>, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        Tag::list(conn, limit)
+    }
+
+    fn consolidate(&self) -> Result<TagConsolidation, AppError> {
+        let conn = &mut self.pools.write_conn()?;
+        db::with_transaction(conn, Tag::consolidate)
     }
 }
@@ -1,6 +1,7 @@
 use super::presenters::TagPresenter;
 use super::repositories::TagRepository;
 use crate::error::AppError;
+use crate::utils::db;
 use actix_web::HttpResponse;
 use std::sync::Arc;
 
@@ -21,9 +22,15 @@ impl TagUsecase {
         }
     }
 
-    pub fn fetch_tags(&self) -> Result<HttpResponse, AppError> {
-        let list = self.tag_repository.fetch_tags()?;
+    pub fn fetch_tags(&self, limit: Option<i64>) -> Result<HttpResponse, AppError> {
+        let list = self.tag_repository.list_distinct_names(limit)?;
         let res = self.tag_presenter.to_json(list);
         Ok(res)
     }
+
+    pub async fn consolidate(&self) -> Result<HttpResponse, AppError> {
+        let tag_repository = self.tag_repository.clone();
+        let summary = db::blocking(move || tag_repository.consolidate()).await?;
+        Ok(self.tag_presenter.to_consolidation_json(summary))
+    }
 }
@@ -0,0 +1,68 @@
+use crate::app::features::user::entities::User;
+use crate::error::AppError;
+use crate::schema::revoked_tokens;
+use chrono::NaiveDateTime;
+use diesel::dsl::{Eq, Lt};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Identifiable, Queryable, Associations, Serialize, Deserialize, Debug, Clone)]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+#[diesel(table_name = revoked_tokens, primary_key(jti))]
+pub struct RevokedToken {
+    pub jti: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+type WithJti<T> = Eq<revoked_tokens::jti, T>;
+type ExpiredBefore<T> = Lt<revoked_tokens::expires_at, T>;
+
+impl RevokedToken {
+    fn with_jti(jti: &Uuid) -> WithJti<&Uuid> {
+        revoked_tokens::jti.eq(jti)
+    }
+
+    fn expired_before(now: NaiveDateTime) -> ExpiredBefore<NaiveDateTime> {
+        revoked_tokens::expires_at.lt(now)
+    }
+}
+
+impl RevokedToken {
+    pub fn create(conn: &mut PgConnection, record: &CreateRevokedToken) -> Result<(), AppError> {
+        diesel::insert_into(revoked_tokens::table)
+            .values(record)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn exists(conn: &mut PgConnection, jti: &Uuid) -> Result<bool, AppError> {
+        let t = revoked_tokens::table.filter(Self::with_jti(jti));
+        let exists = diesel::select(diesel::dsl::exists(t)).get_result::<bool>(conn)?;
+        Ok(exists)
+    }
+
+    // NOTE: pass the cut-off, not the current time; a token expired within the refresh grace
+    // can still be swapped for a new one, so its row must outlive `expires_at` by that much.
+    pub fn purge_expired(
+        conn: &mut PgConnection,
+        before: NaiveDateTime,
+    ) -> Result<usize, AppError> {
+        let t = revoked_tokens::table.filter(Self::expired_before(before));
+        let count = diesel::delete(t).execute(conn)?;
+        Ok(count)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = revoked_tokens)]
+pub struct CreateRevokedToken {
+    pub jti: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,52 @@
+use super::entities::{CreateRevokedToken, RevokedToken};
+use crate::error::AppError;
+use crate::utils::db::DbPool;
+use chrono::{Duration, NaiveDateTime, Utc};
+use uuid::Uuid;
+
+pub trait TokenBlacklistRepository: Send + Sync + 'static {
+    fn revoke(&self, jti: Uuid, user_id: Uuid, expires_at: NaiveDateTime) -> Result<(), AppError>;
+    fn is_revoked(&self, jti: &Uuid) -> Result<bool, AppError>;
+    fn purge_expired(&self) -> Result<usize, AppError>;
+}
+
+#[derive(Clone)]
+pub struct TokenBlacklistRepositoryImpl {
+    pool: DbPool,
+    // NOTE: revoked tokens are kept until they can no longer be refreshed either.
+    refresh_grace_seconds: i64,
+}
+
+impl TokenBlacklistRepositoryImpl {
+    pub fn new(pool: DbPool, refresh_grace_seconds: i64) -> Self {
+        Self {
+            pool,
+            refresh_grace_seconds,
+        }
+    }
+}
+
+impl TokenBlacklistRepository for TokenBlacklistRepositoryImpl {
+    fn revoke(&self, jti: Uuid, user_id: Uuid, expires_at: NaiveDateTime) -> Result<(), AppError> {
+        let conn = &mut self.pool.get()?;
+        RevokedToken::create(
+            conn,
+            &CreateRevokedToken {
+                jti,
+                user_id,
+                expires_at,
+            },
+        )
+    }
+
+    fn is_revoked(&self, jti: &Uuid) -> Result<bool, AppError> {
+        let conn = &mut self.pool.get()?;
+        RevokedToken::exists(conn, jti)
+    }
+
+    fn purge_expired(&self) -> Result<usize, AppError> {
+        let conn = &mut self.pool.get()?;
+        let refreshable_since = Utc::now() - Duration::seconds(self.refresh_grace_seconds);
+        RevokedToken::purge_expired(conn, refreshable_since.naive_utc())
+    }
+}
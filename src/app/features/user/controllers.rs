@@ -1,44 +1,164 @@
 use super::entities::UpdateUser;
+use super::presenters::UserResponse;
 use super::requests;
 use crate::app::drivers::middlewares::auth;
 use crate::app::drivers::middlewares::state::AppState;
 use crate::utils::api::ApiResponse;
+use crate::utils::validation;
 use actix_web::{web, HttpRequest};
+use serde::Deserialize;
+use utoipa::IntoParams;
 
-pub async fn signin(state: web::Data<AppState>, form: web::Json<requests::Signin>) -> ApiResponse {
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthQueryParameter {
+    /// Also sets the token as an HttpOnly cookie when true.
+    cookie: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/login",
+    tag = "user",
+    params(AuthQueryParameter),
+    request_body = requests::Signin,
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, description = "Invalid email or password"),
+        (status = 429, description = "Too many failed attempts"),
+    )
+)]
+pub async fn signin(
+    state: web::Data<AppState>,
+    params: web::Query<AuthQueryParameter>,
+    form: web::Json<requests::Signin>,
+) -> ApiResponse {
+    let with_cookie = params.cookie.unwrap_or(false);
     state
         .di_container
         .user_usecase
-        .signin(&form.user.email, &form.user.password)
+        .signin(&form.user.email, &form.user.password, with_cookie)
+        .await
 }
 
-pub async fn signup(state: web::Data<AppState>, form: web::Json<requests::Signup>) -> ApiResponse {
-    state.di_container.user_usecase.signup(
-        &form.user.email,
-        &form.user.username,
-        &form.user.password,
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    tag = "user",
+    params(AuthQueryParameter),
+    request_body = requests::Signup,
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 422, description = "Invalid or already taken fields"),
     )
+)]
+pub async fn signup(
+    state: web::Data<AppState>,
+    params: web::Query<AuthQueryParameter>,
+    form: web::Json<requests::Signup>,
+) -> ApiResponse {
+    validation::validate_with_password(
+        &*form,
+        Some(&form.user.password),
+        &state.config.password.policy,
+    )?;
+    let with_cookie = params.cookie.unwrap_or(false);
+    state
+        .di_container
+        .user_usecase
+        .signup(
+            &form.user.email,
+            &form.user.username,
+            &form.user.password,
+            with_cookie,
+        )
+        .await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/users/refresh",
+    tag = "user",
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, description = "Missing or expired token"),
+    ),
+    security(("token" = []))
+)]
+pub async fn refresh(state: web::Data<AppState>, req: HttpRequest) -> ApiResponse {
+    let token = auth::get_token(&req)?;
+    state.di_container.user_usecase.refresh_token(&token).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/logout",
+    tag = "user",
+    responses(
+        (status = 200, description = "The token has been revoked"),
+        (status = 401, description = "Missing or invalid token"),
+    ),
+    security(("token" = []))
+)]
+pub async fn logout(state: web::Data<AppState>, req: HttpRequest) -> ApiResponse {
+    let token = auth::get_token(&req)?;
+    state.di_container.user_usecase.logout(&token).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/user",
+    tag = "user",
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, description = "Missing or invalid token"),
+    ),
+    security(("token" = []))
+)]
 pub async fn me(state: web::Data<AppState>, req: HttpRequest) -> ApiResponse {
     let current_user = auth::get_current_user(&req)?;
-    state.di_container.user_usecase.get_token(&current_user)
+    state
+        .di_container
+        .user_usecase
+        .get_current_user(current_user.id)
+        .await
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/user",
+    tag = "user",
+    request_body = requests::Update,
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 422, description = "Invalid or already taken fields"),
+    ),
+    security(("token" = []))
+)]
 pub async fn update(
     state: web::Data<AppState>,
     req: HttpRequest,
     form: web::Json<requests::Update>,
 ) -> ApiResponse {
+    validation::validate_with_password(
+        &*form,
+        form.user.password.as_deref(),
+        &state.config.password.policy,
+    )?;
     let current_user = auth::get_current_user(&req)?;
-    state.di_container.user_usecase.update_user(
-        current_user.id,
-        UpdateUser {
-            email: form.user.email.clone(),
-            username: form.user.username.clone(),
-            password: form.user.password.clone(),
-            image: form.user.image.clone(),
-            bio: form.user.bio.clone(),
-        },
-    )
+    state
+        .di_container
+        .user_usecase
+        .update_user(
+            current_user.id,
+            UpdateUser {
+                email: form.user.email.clone(),
+                username: form.user.username.clone(),
+                password: form.user.password.clone(),
+                image: form.user.image.clone(),
+                bio: form.user.bio.clone(),
+            },
+        )
+        .await
 }
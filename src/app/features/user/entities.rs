@@ -1,15 +1,19 @@
-use crate::app::features::favorite::entities::Favorite;
-use crate::app::features::follow::entities::Follow;
+use crate::app::features::follow::entities::{
+    Follow, FolloweeIdsByFollowerId, FollowerIdsByFolloweeId,
+};
 use crate::app::features::profile::entities::Profile;
 use crate::error::AppError;
 use crate::schema::users;
-use crate::utils::{hasher, token};
+use crate::utils::hasher::{self, HashConfig};
+use crate::utils::token::{self, JwtConfig};
+use crate::utils::{converter, gravatar};
 use chrono::prelude::*;
 use chrono::NaiveDateTime;
 use diesel::backend::Backend;
-use diesel::dsl::{AsSelect, Eq, Filter, Select};
+use diesel::dsl::{And, AsSelect, Asc, Eq, EqAny, Filter, ILike, Limit, Offset, Order, Select};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use diesel::sql_types::Text;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -24,15 +28,35 @@ pub struct User {
     pub image: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub email_verified: bool,
+    pub role: String,
+    pub last_login_at: Option<NaiveDateTime>,
 }
 
+// NOTE: the `users.role` check constraint allows exactly these two.
+pub const ROLE_USER: &str = "user";
+pub const ROLE_ADMIN: &str = "admin";
+
 type Token = String;
 
+define_sql_function!(fn lower(x: Text) -> Text);
+
 type All<DB> = Select<users::table, AsSelect<User, DB>>;
-type WithUsername<T> = Eq<users::username, T>;
-type WithEmail<T> = Eq<users::email, T>;
+type Lower<T> = lower<T>;
+type WithUsername<T> = Eq<Lower<users::username>, Lower<T>>;
+type WithEmail<T> = Eq<Lower<users::email>, Lower<T>>;
 type ByUsername<DB, T> = Filter<All<DB>, WithUsername<T>>;
+type ByUsernames<'a, DB> = Filter<All<DB>, EqAny<Lower<users::username>, &'a [String]>>;
 type ByEmail<DB, T> = Filter<All<DB>, WithEmail<T>>;
+type WithFollowerOf<'a> = EqAny<users::id, FollowerIdsByFolloweeId<&'a Uuid>>;
+type Page<Q> = Offset<Limit<Order<Q, (Asc<users::username>, Asc<users::id>)>>>;
+type FollowersOf<'a> = Page<Filter<users::table, WithFollowerOf<'a>>>;
+type WithFolloweeOf<'a> = EqAny<users::id, FolloweeIdsByFollowerId<&'a Uuid>>;
+type FolloweesOf<'a> = Page<Filter<users::table, WithFolloweeOf<'a>>>;
+type WithMutualFolloweeOf<'a> = And<WithFolloweeOf<'a>, WithFolloweeOf<'a>>;
+type MutualFolloweesOf<'a> = Page<Filter<users::table, WithMutualFolloweeOf<'a>>>;
+type WithUsernameLike = ILike<users::username, String>;
+type Matching = Page<Filter<users::table, WithUsernameLike>>;
 
 impl User {
     fn all<DB>() -> All<DB>
@@ -42,8 +66,9 @@ impl User {
         users::table.select(User::as_select())
     }
 
+    // NOTE: usernames keep their display casing but are compared case-insensitively.
     pub fn with_username(username: &str) -> WithUsername<&str> {
-        users::username.eq(username)
+        lower(users::username).eq(lower(username))
     }
 
     pub fn by_username<DB>(username: &str) -> ByUsername<DB, &str>
@@ -53,8 +78,20 @@ impl User {
         Self::all().filter(Self::with_username(username))
     }
 
+    // NOTE: expects names already lowercased; only the column side is lowered in SQL.
+    fn by_usernames<DB>(lowered: &[String]) -> ByUsernames<'_, DB>
+    where
+        DB: Backend,
+    {
+        Self::all().filter(lower(users::username).eq_any(lowered))
+    }
+
     fn with_email(email: &str) -> WithEmail<&str> {
-        users::email.eq(email)
+        lower(users::email).eq(lower(email))
+    }
+
+    pub fn normalize_email(email: &str) -> String {
+        email.to_lowercase()
     }
 
     fn by_email<DB>(email: &str) -> ByEmail<DB, &str>
@@ -63,17 +100,79 @@ impl User {
     {
         Self::all().filter(Self::with_email(email))
     }
+
+    fn with_follower_of(followee_id: &Uuid) -> WithFollowerOf<'_> {
+        users::id.eq_any(Follow::follower_ids_by_followee_id(followee_id))
+    }
+
+    fn followers_of(followee_id: &Uuid, offset: i64, limit: i64) -> FollowersOf<'_> {
+        users::table
+            .filter(Self::with_follower_of(followee_id))
+            .order((users::username.asc(), users::id.asc()))
+            .limit(limit)
+            .offset(offset)
+    }
+
+    fn with_followee_of(follower_id: &Uuid) -> WithFolloweeOf<'_> {
+        users::id.eq_any(Follow::followee_ids_by_follower_id(follower_id))
+    }
+
+    fn followees_of(follower_id: &Uuid, offset: i64, limit: i64) -> FolloweesOf<'_> {
+        users::table
+            .filter(Self::with_followee_of(follower_id))
+            .order((users::username.asc(), users::id.asc()))
+            .limit(limit)
+            .offset(offset)
+    }
+
+    fn with_mutual_followee_of<'a>(
+        follower_id: &'a Uuid,
+        other_follower_id: &'a Uuid,
+    ) -> WithMutualFolloweeOf<'a> {
+        Self::with_followee_of(follower_id).and(Self::with_followee_of(other_follower_id))
+    }
+
+    fn mutual_followees_of<'a>(
+        follower_id: &'a Uuid,
+        other_follower_id: &'a Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> MutualFolloweesOf<'a> {
+        users::table
+            .filter(Self::with_mutual_followee_of(
+                follower_id,
+                other_follower_id,
+            ))
+            .order((users::username.asc(), users::id.asc()))
+            .limit(limit)
+            .offset(offset)
+    }
+
+    fn with_username_like(query: &str) -> WithUsernameLike {
+        users::username.ilike(converter::to_like_pattern(query))
+    }
+
+    fn matching(query: &str, offset: i64, limit: i64) -> Matching {
+        users::table
+            .filter(Self::with_username_like(query))
+            .order((users::username.asc(), users::id.asc()))
+            .limit(limit)
+            .offset(offset)
+    }
 }
 
 impl User {
     pub fn signup<'a>(
         conn: &mut PgConnection,
+        hash: &HashConfig,
+        jwt: &JwtConfig,
         email: &'a str,
         username: &'a str,
         naive_password: &'a str,
     ) -> Result<(User, Token), AppError> {
         use diesel::prelude::*;
-        let hashed_password = hasher::hash_password(naive_password)?;
+        let hashed_password = hasher::hash_password(hash, naive_password)?;
+        let email = &Self::normalize_email(email);
 
         let record = SignupUser {
             email,
@@ -85,19 +184,56 @@ impl User {
             .values(&record)
             .get_result::<User>(conn)?;
 
-        let token = user.generate_token()?;
+        let token = user.generate_token(jwt)?;
         Ok((user, token))
     }
 
     pub fn signin(
         conn: &mut PgConnection,
+        hash: &HashConfig,
+        jwt: &JwtConfig,
         email: &str,
         naive_password: &str,
     ) -> Result<(User, Token), AppError> {
-        let t = Self::by_email(email).limit(1);
-        let user = t.first::<User>(conn)?;
-        hasher::verify(naive_password, &user.password)?;
-        let token = user.generate_token()?;
+        // NOTE: unknown email and wrong password share one error so accounts can't be enumerated.
+        let user = match Self::find_by_email(conn, email)? {
+            Some(user) => user,
+            None => {
+                info!("Signin failed: no user for the given email");
+                // NOTE: hash anyway, or the response time would tell which emails have accounts.
+                hasher::verify_nothing(hash, naive_password)?;
+                return Err(Self::invalid_credentials());
+            }
+        };
+        if !hasher::verify(naive_password, &user.password)? {
+            info!("Signin failed: wrong password for user {}", user.id);
+            return Err(Self::invalid_credentials());
+        }
+        let user = if hasher::needs_rehash(&user.password) {
+            let password = hasher::hash_password(hash, naive_password)?;
+            Self::update(
+                conn,
+                user.id,
+                UpdateUser {
+                    email: None,
+                    username: None,
+                    password: Some(password),
+                    image: None,
+                    bio: None,
+                },
+            )?
+        } else {
+            user
+        };
+        // NOTE: bookkeeping only; a failed write must not turn a valid signin into an error.
+        let user = match Self::record_login(conn, &user.id, Utc::now().naive_utc()) {
+            Ok(user) => user,
+            Err(err) => {
+                warn!("Cannot record login for user {}: {}", user.id, err);
+                user
+            }
+        };
+        let token = user.generate_token(jwt)?;
         Ok((user, token))
     }
 
@@ -112,19 +248,166 @@ impl User {
         user_id: Uuid,
         changeset: UpdateUser,
     ) -> Result<Self, AppError> {
+        let changeset = UserChangeset::from(changeset);
+        if changeset.is_empty() {
+            return Self::find(conn, user_id);
+        }
         let target = users::table.find(user_id);
+        // NOTE: the `set_updated_at` trigger bumps `updated_at`; the returned row already has it.
         let user = diesel::update(target)
             .set(changeset)
             .get_result::<User>(conn)?;
         Ok(user)
     }
 
+    pub fn record_login(
+        conn: &mut PgConnection,
+        user_id: &Uuid,
+        at: NaiveDateTime,
+    ) -> Result<Self, AppError> {
+        let target = users::table.find(user_id);
+        let user = diesel::update(target)
+            .set(users::last_login_at.eq(at))
+            .get_result::<User>(conn)?;
+        Ok(user)
+    }
+
+    pub fn mark_email_verified(conn: &mut PgConnection, user_id: &Uuid) -> Result<Self, AppError> {
+        let target = users::table.find(user_id);
+        let user = diesel::update(target)
+            .set(users::email_verified.eq(true))
+            .get_result::<User>(conn)?;
+        Ok(user)
+    }
+
     pub fn find_by_username(conn: &mut PgConnection, username: &str) -> Result<Self, AppError> {
         let t = Self::by_username(username).limit(1);
         let user = t.first::<User>(conn)?;
         Ok(user)
     }
 
+    // NOTE: unknown names are skipped; the rest come back in the order they were asked for.
+    pub fn fetch_by_usernames(
+        conn: &mut PgConnection,
+        usernames: &[String],
+    ) -> Result<Vec<Self>, AppError> {
+        let lowered = usernames
+            .iter()
+            .map(|username| username.to_lowercase())
+            .collect::<Vec<_>>();
+        let mut list = Self::by_usernames(&lowered).load::<Self>(conn)?;
+        list.sort_by_key(|user| {
+            let username = user.username.to_lowercase();
+            lowered.iter().position(|name| *name == username)
+        });
+        Ok(list)
+    }
+
+    // NOTE: which of the given identity fields another user already holds; the user's own row never counts.
+    pub fn taken_fields(
+        conn: &mut PgConnection,
+        user_id: &Uuid,
+        email: Option<&str>,
+        username: Option<&str>,
+    ) -> Result<Vec<&'static str>, AppError> {
+        use diesel::dsl::exists;
+        let others = || users::table.filter(users::id.ne(user_id));
+        let mut taken = vec![];
+        if let Some(email) = email {
+            let t = others().filter(Self::with_email(email));
+            if diesel::select(exists(t)).get_result::<bool>(conn)? {
+                taken.push("email");
+            }
+        }
+        if let Some(username) = username {
+            let t = others().filter(Self::with_username(username));
+            if diesel::select(exists(t)).get_result::<bool>(conn)? {
+                taken.push("username");
+            }
+        }
+        Ok(taken)
+    }
+
+    pub fn find_id_by_username(
+        conn: &mut PgConnection,
+        username: &str,
+    ) -> Result<Option<Uuid>, AppError> {
+        let t = users::table
+            .filter(Self::with_username(username))
+            .select(users::id);
+        let id = t.first::<Uuid>(conn).optional()?;
+        Ok(id)
+    }
+
+    pub fn fetch_followers(
+        conn: &mut PgConnection,
+        followee_id: &Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<Self>, i64), AppError> {
+        let list = Self::followers_of(followee_id, offset, limit).load::<Self>(conn)?;
+        let count = users::table
+            .filter(Self::with_follower_of(followee_id))
+            .count()
+            .get_result::<i64>(conn)?;
+        Ok((list, count))
+    }
+
+    pub fn fetch_followees(
+        conn: &mut PgConnection,
+        follower_id: &Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<Self>, i64), AppError> {
+        let list = Self::followees_of(follower_id, offset, limit).load::<Self>(conn)?;
+        let count = users::table
+            .filter(Self::with_followee_of(follower_id))
+            .count()
+            .get_result::<i64>(conn)?;
+        Ok((list, count))
+    }
+
+    // NOTE: users followed by both `follower_id` and `other_follower_id`.
+    pub fn fetch_mutual_followees(
+        conn: &mut PgConnection,
+        follower_id: &Uuid,
+        other_follower_id: &Uuid,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<Self>, i64), AppError> {
+        let list = Self::mutual_followees_of(follower_id, other_follower_id, offset, limit)
+            .load::<Self>(conn)?;
+        let count = users::table
+            .filter(Self::with_mutual_followee_of(
+                follower_id,
+                other_follower_id,
+            ))
+            .count()
+            .get_result::<i64>(conn)?;
+        Ok((list, count))
+    }
+
+    // NOTE: `query` matches anywhere in the username, so prefixes are covered too.
+    pub fn search(
+        conn: &mut PgConnection,
+        query: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<Self>, i64), AppError> {
+        let list = Self::matching(query, offset, limit).load::<Self>(conn)?;
+        let count = users::table
+            .filter(Self::with_username_like(query))
+            .count()
+            .get_result::<i64>(conn)?;
+        Ok((list, count))
+    }
+
+    pub fn find_by_email(conn: &mut PgConnection, email: &str) -> Result<Option<Self>, AppError> {
+        let t = Self::by_email(email).limit(1);
+        let user = t.first::<User>(conn).optional()?;
+        Ok(user)
+    }
+
     pub fn is_following(&self, conn: &mut PgConnection, followee_id: &Uuid) -> bool {
         use crate::schema::follows;
         let t = follows::table
@@ -136,22 +419,18 @@ impl User {
 }
 
 impl User {
-    pub fn generate_token(&self) -> Result<String, AppError> {
-        let now = Utc::now().timestamp_nanos() / 1_000_000_000; // nanosecond -> second
-        let token = token::generate(self.id, now)?;
-        Ok(token)
+    fn invalid_credentials() -> AppError {
+        AppError::Unauthorized("email or password is invalid".into())
     }
 
-    pub fn fetch_favorited_article_ids(
-        &self,
-        conn: &mut PgConnection,
-    ) -> Result<Vec<Uuid>, AppError> {
-        use crate::schema::favorites;
-        let t = favorites::table
-            .filter(Favorite::with_user_id(&self.id))
-            .select(favorites::article_id);
-        let favorited_article_ids = t.get_results::<Uuid>(conn)?;
-        Ok(favorited_article_ids)
+    pub fn is_admin(&self) -> bool {
+        self.role == ROLE_ADMIN
+    }
+
+    pub fn generate_token(&self, jwt: &JwtConfig) -> Result<String, AppError> {
+        let now = Utc::now().timestamp(); // in seconds
+        let token = token::generate(jwt, self.id, now)?;
+        Ok(token)
     }
 
     pub fn fetch_profile(
@@ -163,26 +442,14 @@ impl User {
         let profile = Profile {
             username: self.username.to_owned(),
             bio: self.bio.to_owned(),
-            image: self.image.to_owned(),
+            image: Some(gravatar::image_or_default(
+                self.image.to_owned(),
+                &self.email,
+            )),
             following: is_following.to_owned(),
         };
         Ok(profile)
     }
-
-    pub fn to_profile(&self, conn: &mut PgConnection, current_user: &Option<User>) -> Profile {
-        let user = self;
-        let following = match current_user {
-            Some(current_user) => current_user.is_following(conn, &user.id),
-            None => false,
-        };
-
-        Profile {
-            username: user.username.to_owned(),
-            bio: user.bio.to_owned(),
-            image: user.image.to_owned(),
-            following,
-        }
-    }
 }
 
 #[derive(Insertable, Debug, Deserialize)]
@@ -193,8 +460,8 @@ pub struct SignupUser<'a> {
     pub password: &'a str,
 }
 
-#[derive(AsChangeset, Debug, Deserialize, Clone)]
-#[diesel(table_name = users)]
+// NOTE: `None` keeps a field as is; for `image` and `bio`, `Some("")` clears it.
+#[derive(Debug, Deserialize, Clone)]
 pub struct UpdateUser {
     pub email: Option<String>,
     pub username: Option<String>,
@@ -202,3 +469,137 @@ pub struct UpdateUser {
     pub image: Option<String>,
     pub bio: Option<String>,
 }
+
+// NOTE: diesel skips `None` columns and writes NULL for `Some(None)`.
+#[derive(AsChangeset, Debug, PartialEq)]
+#[diesel(table_name = users)]
+struct UserChangeset {
+    email: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    image: Option<Option<String>>,
+    bio: Option<Option<String>>,
+}
+
+impl From<UpdateUser> for UserChangeset {
+    fn from(params: UpdateUser) -> Self {
+        let clearable =
+            |value: Option<String>| value.map(|value| Some(value).filter(|v| !v.is_empty()));
+        Self {
+            email: params.email.map(|email| User::normalize_email(&email)),
+            username: params.username,
+            password: params.password,
+            image: clearable(params.image),
+            bio: clearable(params.bio),
+        }
+    }
+}
+
+impl UserChangeset {
+    fn is_empty(&self) -> bool {
+        self.email.is_none()
+            && self.username.is_none()
+            && self.password.is_none()
+            && self.image.is_none()
+            && self.bio.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+
+    #[test]
+    fn by_email_compares_case_insensitively() {
+        let query = User::by_email("Foo@Example.com");
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#"lower("users"."email") = lower($1)"#));
+        assert!(sql.contains(r#"binds: ["Foo@Example.com"]"#));
+    }
+
+    #[test]
+    fn by_username_compares_case_insensitively() {
+        let query = User::by_username("JakeTheDog");
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#"lower("users"."username") = lower($1)"#));
+        assert!(sql.contains(r#"binds: ["JakeTheDog"]"#));
+    }
+
+    #[test]
+    fn followers_of_pages_by_username() {
+        let followee_id = Uuid::nil();
+        let query = User::followers_of(&followee_id, 40, 20);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"WHERE ("users"."id" = ANY(SELECT "follows"."follower_id" FROM "follows" WHERE ("follows"."followee_id" = $1)))"#
+        ));
+        assert!(
+            sql.contains(r#"ORDER BY "users"."username" ASC, "users"."id" ASC LIMIT $2 OFFSET $3"#)
+        );
+        assert!(sql.contains("binds: [00000000-0000-0000-0000-000000000000, 20, 40]"));
+    }
+
+    #[test]
+    fn followees_of_pages_by_username() {
+        let follower_id = Uuid::nil();
+        let query = User::followees_of(&follower_id, 0, 20);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"WHERE ("users"."id" = ANY(SELECT "follows"."followee_id" FROM "follows" WHERE ("follows"."follower_id" = $1)))"#
+        ));
+        assert!(sql.contains("binds: [00000000-0000-0000-0000-000000000000, 20, 0]"));
+    }
+
+    #[test]
+    fn mutual_followees_of_intersects_both_follow_lists() {
+        let (me, them) = (Uuid::nil(), Uuid::max());
+        let query = User::mutual_followees_of(&me, &them, 0, 20);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"WHERE (("users"."id" = ANY(SELECT "follows"."followee_id" FROM "follows" WHERE ("follows"."follower_id" = $1))) AND ("users"."id" = ANY(SELECT "follows"."followee_id" FROM "follows" WHERE ("follows"."follower_id" = $2))))"#
+        ));
+        assert!(sql.contains(
+            "binds: [00000000-0000-0000-0000-000000000000, ffffffff-ffff-ffff-ffff-ffffffffffff, 20, 0]"
+        ));
+    }
+
+    #[test]
+    fn matching_is_a_case_insensitive_substring_search() {
+        let query = User::matching("ja_", 20, 10);
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#"WHERE ("users"."username" ILIKE $1)"#));
+        assert!(
+            sql.contains(r#"ORDER BY "users"."username" ASC, "users"."id" ASC LIMIT $2 OFFSET $3"#)
+        );
+        assert!(sql.contains(r#"binds: ["%ja\\_%", 10, 20]"#));
+    }
+
+    #[test]
+    fn changeset_clears_empty_fields_and_keeps_absent_ones() {
+        let changeset = UserChangeset::from(UpdateUser {
+            email: Some("Jake@Example.com".to_owned()),
+            username: None,
+            password: None,
+            image: None,
+            bio: Some("".to_owned()),
+        });
+        assert_eq!(
+            UserChangeset {
+                email: Some("jake@example.com".to_owned()),
+                username: None,
+                password: None,
+                image: None,
+                bio: Some(None),
+            },
+            changeset
+        );
+        assert!(!changeset.is_empty());
+    }
+
+    #[test]
+    fn normalize_email_lowercases() {
+        assert_eq!("foo@example.com", User::normalize_email("Foo@Example.COM"));
+    }
+}
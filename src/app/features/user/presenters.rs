@@ -1,9 +1,14 @@
+use crate::constants;
+use crate::utils::date::Iso8601;
+use crate::utils::gravatar;
 use crate::{app::features::user::entities::User, error::AppError};
+use actix_web::cookie::{time::Duration, Cookie, CookieBuilder, SameSite};
 use actix_web::HttpResponse;
 use serde::{Deserialize, Serialize};
 use std::convert::From;
+use utoipa::ToSchema;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct UserResponse {
     pub user: AuthUser,
 }
@@ -11,37 +16,60 @@ pub struct UserResponse {
 impl From<(User, String)> for UserResponse {
     fn from((user, token): (User, String)) -> Self {
         // REF: https://gothinkster.github.io/realworld/docs/specs/backend-specs/api-response-format/#users-for-authentication
+        let image = gravatar::image_or_default(user.image, &user.email);
         Self {
             user: AuthUser {
                 email: user.email,
                 token,
                 username: user.username,
                 bio: user.bio,
-                image: user.image,
+                image: Some(image),
+                last_login_at: user.last_login_at.map(Iso8601),
             },
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct AuthUser {
     pub email: String,
     pub token: String,
     pub username: String,
     pub bio: Option<String>,
     pub image: Option<String>,
+    // NOTE: the most recent successful signin, including the one that issued this token.
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_login_at: Option<Iso8601>,
 }
 
 pub trait UserPresenter: Send + Sync + 'static {
     fn to_json(&self, user: User, token: String) -> HttpResponse;
+    fn to_json_with_cookie(&self, user: User, token: String) -> HttpResponse;
+    fn to_logged_out(&self) -> HttpResponse;
     fn to_auth_middleware(&self, maybe_uesr: Result<User, AppError>) -> Result<User, &str>;
 }
 
 #[derive(Clone)]
-pub struct UserPresenterImpl {}
+pub struct UserPresenterImpl {
+    // NOTE: `false` only for local development over plain http.
+    secure_cookies: bool,
+    token_ttl_seconds: i64,
+}
 impl UserPresenterImpl {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(secure_cookies: bool, token_ttl_seconds: i64) -> Self {
+        Self {
+            secure_cookies,
+            token_ttl_seconds,
+        }
+    }
+
+    fn token_cookie(&self, token: String) -> CookieBuilder<'static> {
+        Cookie::build(constants::TOKEN_COOKIE, token)
+            .path("/")
+            .http_only(true)
+            .secure(self.secure_cookies)
+            .same_site(SameSite::Lax)
     }
 }
 impl UserPresenter for UserPresenterImpl {
@@ -50,6 +78,22 @@ impl UserPresenter for UserPresenterImpl {
         HttpResponse::Ok().json(res_model)
     }
 
+    fn to_json_with_cookie(&self, user: User, token: String) -> HttpResponse {
+        // NOTE: the cookie goes away when the token inside it expires.
+        let cookie = self
+            .token_cookie(token.clone())
+            .max_age(Duration::seconds(self.token_ttl_seconds))
+            .finish();
+        let res_model = UserResponse::from((user, token));
+        HttpResponse::Ok().cookie(cookie).json(res_model)
+    }
+
+    fn to_logged_out(&self) -> HttpResponse {
+        let mut cookie = self.token_cookie(String::new()).finish();
+        cookie.make_removal();
+        HttpResponse::Ok().cookie(cookie).json(())
+    }
+
     fn to_auth_middleware(&self, maybe_user: Result<User, AppError>) -> Result<User, &str> {
         maybe_user.map_err(|_err| "Cannot find auth user")
     }
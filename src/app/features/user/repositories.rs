@@ -1,12 +1,17 @@
 use super::entities::UpdateUser;
+use crate::app::features::email_verification::entities::EmailVerification;
 use crate::app::features::follow::entities::{CreateFollow, DeleteFollow, Follow};
 use crate::app::features::profile::entities::Profile;
 use crate::app::features::user::entities::User;
 use crate::error::AppError;
-use crate::utils::db::DbPool;
+use crate::utils::db::{self, DbPool};
+use crate::utils::gravatar;
+use crate::utils::hasher::HashConfig;
+use crate::utils::token::JwtConfig;
 use uuid::Uuid;
 
 type Token = String;
+type Inserted = bool;
 
 pub trait UserRepository: Send + Sync + 'static {
     fn signin(&self, email: &str, naive_password: &str) -> Result<(User, Token), AppError>;
@@ -16,12 +21,24 @@ pub trait UserRepository: Send + Sync + 'static {
         username: &str,
         naive_password: &str,
     ) -> Result<(User, Token), AppError>;
-    fn follow_user(&self, current_user: &User, target_username: &str) -> Result<Profile, AppError>;
+    // NOTE: both are idempotent: repeating one succeeds and returns the followee's profile
+    // with `following` set to the state the call asked for.
+    // NOTE: `Inserted` is false when the user was already following.
+    fn follow_user(
+        &self,
+        current_user: &User,
+        target_username: &str,
+    ) -> Result<(Profile, Inserted), AppError>;
     fn unfollow_user(
         &self,
         current_user: &User,
         target_username: &str,
     ) -> Result<Profile, AppError>;
+    fn taken_fields(
+        &self,
+        user_id: Uuid,
+        changeset: &UpdateUser,
+    ) -> Result<Vec<&'static str>, AppError>;
     fn update(&self, user_id: Uuid, changeset: UpdateUser) -> Result<(User, Token), AppError>;
     fn find(&self, user_id: Uuid) -> Result<User, AppError>;
 }
@@ -29,20 +46,33 @@ pub trait UserRepository: Send + Sync + 'static {
 #[derive(Clone)]
 pub struct UserRepositoryImpl {
     pool: DbPool,
+    hash: HashConfig,
+    jwt: JwtConfig,
 }
 
 impl UserRepositoryImpl {
-    pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+    pub fn new(pool: DbPool, hash: HashConfig, jwt: JwtConfig) -> Self {
+        Self { pool, hash, jwt }
+    }
+
+    fn followee_profile(followee: User, following: bool) -> Profile {
+        Profile {
+            image: Some(gravatar::image_or_default(followee.image, &followee.email)),
+            username: followee.username,
+            bio: followee.bio,
+            following,
+        }
     }
 }
 
 impl UserRepository for UserRepositoryImpl {
+    #[tracing::instrument(level = "debug", skip_all)]
     fn signin(&self, email: &str, naive_password: &str) -> Result<(User, Token), AppError> {
         let conn = &mut self.pool.get()?;
-        User::signin(conn, email, naive_password)
+        User::signin(conn, &self.hash, &self.jwt, email, naive_password)
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     fn signup(
         &self,
         email: &str,
@@ -50,69 +80,91 @@ impl UserRepository for UserRepositoryImpl {
         naive_password: &str,
     ) -> Result<(User, Token), AppError> {
         let conn = &mut self.pool.get()?;
-        User::signup(conn, email, username, naive_password)
+        db::with_transaction(conn, |conn| {
+            let (user, token) =
+                User::signup(conn, &self.hash, &self.jwt, email, username, naive_password)?;
+            let verification = EmailVerification::create(conn, &user.id)?;
+            // TODO: deliver the token by email instead of logging it.
+            info!(
+                "Email verification token issued for user {}: {}",
+                user.id, verification.token
+            );
+            Ok((user, token))
+        })
     }
 
-    fn follow_user(&self, current_user: &User, target_username: &str) -> Result<Profile, AppError> {
+    #[tracing::instrument(level = "debug", skip_all, fields(user_id = %current_user.id, username = %target_username))]
+    fn follow_user(
+        &self,
+        current_user: &User,
+        target_username: &str,
+    ) -> Result<(Profile, Inserted), AppError> {
         let conn = &mut self.pool.get()?;
-        let t = User::by_username(target_username);
-
-        let followee = {
-            use diesel::prelude::*;
-            t.first::<User>(conn)?
-        };
-
-        Follow::create(
-            conn,
-            &CreateFollow {
-                follower_id: current_user.id,
-                followee_id: followee.id,
-            },
-        )?;
-
-        Ok(Profile {
-            username: current_user.username.clone(),
-            bio: current_user.bio.clone(),
-            image: current_user.image.clone(),
-            following: true,
-        })
+        let (followee, inserted) = db::with_transaction(conn, |conn| {
+            let followee = {
+                use diesel::prelude::*;
+                User::by_username(target_username).first::<User>(conn)?
+            };
+            let inserted = Follow::create(
+                conn,
+                &CreateFollow {
+                    follower_id: current_user.id,
+                    followee_id: followee.id,
+                },
+            )?;
+            Ok((followee, inserted > 0))
+        })?;
+        Ok((Self::followee_profile(followee, true), inserted))
     }
 
+    #[tracing::instrument(level = "debug", skip_all, fields(user_id = %current_user.id, username = %target_username))]
     fn unfollow_user(
         &self,
         current_user: &User,
         target_username: &str,
     ) -> Result<Profile, AppError> {
         let conn = &mut self.pool.get()?;
-        let t = User::by_username(target_username);
-        let followee = {
-            use diesel::prelude::*;
-            t.first::<User>(conn)?
-        };
+        let followee = db::with_transaction(conn, |conn| {
+            let followee = {
+                use diesel::prelude::*;
+                User::by_username(target_username).first::<User>(conn)?
+            };
+            Follow::delete(
+                conn,
+                &DeleteFollow {
+                    followee_id: followee.id,
+                    follower_id: current_user.id,
+                },
+            )?;
+            Ok(followee)
+        })?;
+        Ok(Self::followee_profile(followee, false))
+    }
 
-        Follow::delete(
+    #[tracing::instrument(level = "debug", skip(self, changeset))]
+    fn taken_fields(
+        &self,
+        user_id: Uuid,
+        changeset: &UpdateUser,
+    ) -> Result<Vec<&'static str>, AppError> {
+        let conn = &mut self.pool.get()?;
+        User::taken_fields(
             conn,
-            &DeleteFollow {
-                followee_id: followee.id,
-                follower_id: current_user.id,
-            },
-        )?;
-
-        Ok(Profile {
-            username: current_user.username.clone(),
-            bio: current_user.bio.clone(),
-            image: current_user.image.clone(),
-            following: false,
-        })
+            &user_id,
+            changeset.email.as_deref(),
+            changeset.username.as_deref(),
+        )
     }
 
+    #[tracing::instrument(level = "debug", skip(self, changeset))]
     fn update(&self, user_id: Uuid, changeset: UpdateUser) -> Result<(User, Token), AppError> {
         let conn = &mut self.pool.get()?;
         let new_user = User::update(conn, user_id, changeset)?;
-        let token = &new_user.generate_token()?;
+        let token = &new_user.generate_token(&self.jwt)?;
         Ok((new_user, token.clone()))
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     fn find(&self, user_id: Uuid) -> Result<User, AppError> {
         let conn = &mut self.pool.get()?;
         let user = User::find(conn, user_id)?;
@@ -1,41 +1,52 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema, Validate)]
 pub struct Signup {
     // SPEC: https://gothinkster.github.io/realworld/docs/specs/backend-specs/endpoints#registration
+    #[validate(nested)]
     pub user: SignupUser,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema, Validate)]
 pub struct SignupUser {
+    #[validate(length(min = 1, message = "can't be blank"))]
     pub username: String,
+    #[validate(email(message = "is invalid"))]
     pub email: String,
+    // NOTE: checked against the password policy by `validation::validate_with_password`.
     pub password: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct Signin {
     // SPEC: https://gothinkster.github.io/realworld/docs/specs/backend-specs/endpoints#authentication
     pub user: SigninUser,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct SigninUser {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema, Validate)]
 pub struct Update {
     // SPEC: https://gothinkster.github.io/realworld/docs/specs/backend-specs/endpoints#authentication
+    #[validate(nested)]
     pub user: UpdateUser,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema, Validate)]
 pub struct UpdateUser {
+    #[validate(email(message = "is invalid"))]
     pub email: Option<String>,
+    #[validate(length(min = 1, message = "can't be blank"))]
     pub username: Option<String>,
+    // NOTE: checked against the password policy by `validation::validate_with_password`.
     pub password: Option<String>,
+    // NOTE: "" clears the field; leaving it out (or null) keeps the current value.
     pub image: Option<String>,
     pub bio: Option<String>,
 }
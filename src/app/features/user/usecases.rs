@@ -1,63 +1,537 @@
 use super::entities::{UpdateUser, User};
 use super::presenters::UserPresenter;
 use super::repositories::UserRepository;
+use crate::app::features::token_blacklist::repositories::TokenBlacklistRepository;
+use crate::config::PasswordConfig;
 use crate::error::AppError;
+use crate::utils::login_throttle::LoginThrottle;
+use crate::utils::token::{self, JwtConfig};
+use crate::utils::{db, hasher};
 use actix_web::HttpResponse;
+use chrono::{TimeZone, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct UserUsecase {
     user_repository: Arc<dyn UserRepository>,
+    token_blacklist_repository: Arc<dyn TokenBlacklistRepository>,
     user_presenter: Arc<dyn UserPresenter>,
+    login_throttle: LoginThrottle,
+    jwt: JwtConfig,
+    password: PasswordConfig,
 }
 
 impl UserUsecase {
     pub fn new(
         user_repository: Arc<dyn UserRepository>,
+        token_blacklist_repository: Arc<dyn TokenBlacklistRepository>,
         user_presenter: Arc<dyn UserPresenter>,
+        login_throttle: LoginThrottle,
+        jwt: JwtConfig,
+        password: PasswordConfig,
     ) -> Self {
         Self {
             user_repository,
+            token_blacklist_repository,
             user_presenter,
+            login_throttle,
+            jwt,
+            password,
         }
     }
 
-    pub fn signin(&self, email: &str, password: &str) -> Result<HttpResponse, AppError> {
-        let (user, token) = self.user_repository.signin(email, password)?;
-        let res = self.user_presenter.to_json(user, token);
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn signin(
+        &self,
+        email: &str,
+        password: &str,
+        with_cookie: bool,
+    ) -> Result<HttpResponse, AppError> {
+        if self.login_throttle.is_blocked(email) {
+            return Err(AppError::TooManyRequests(
+                "Too many failed signin attempts. Try again later.".into(),
+            ));
+        }
+        let user_repository = self.user_repository.clone();
+        let (credentials_email, credentials_password) = (email.to_owned(), password.to_owned());
+        let signed_in =
+            db::blocking(move || user_repository.signin(&credentials_email, &credentials_password))
+                .await;
+        let (user, token) = match signed_in {
+            Ok(result) => result,
+            Err(err) => {
+                self.login_throttle.record_failure(email);
+                return Err(err);
+            }
+        };
+        self.login_throttle.reset(email);
+        let res = if with_cookie {
+            self.user_presenter.to_json_with_cookie(user, token)
+        } else {
+            self.user_presenter.to_json(user, token)
+        };
         Ok(res)
     }
 
-    pub fn signup(
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn signup(
         &self,
         email: &str,
         username: &str,
         password: &str,
+        with_cookie: bool,
     ) -> Result<HttpResponse, AppError> {
-        let (user, token) = self.user_repository.signup(email, username, password)?;
+        let user_repository = self.user_repository.clone();
+        let (email, username, password) =
+            (email.to_owned(), username.to_owned(), password.to_owned());
+        let (user, token) =
+            db::blocking(move || user_repository.signup(&email, &username, &password)).await?;
+        let res = if with_cookie {
+            self.user_presenter.to_json_with_cookie(user, token)
+        } else {
+            self.user_presenter.to_json(user, token)
+        };
+        Ok(res)
+    }
+
+    async fn find_token_user(&self, user_id: Uuid) -> Result<User, AppError> {
+        let user_repository = self.user_repository.clone();
+        db::blocking(move || user_repository.find(user_id))
+            .await
+            .map_err(|_err| AppError::Unauthorized("User of this token no longer exists.".into()))
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_current_user(&self, user_id: Uuid) -> Result<HttpResponse, AppError> {
+        // NOTE: re-fetch so the response reflects the latest stored profile.
+        let user = self.find_token_user(user_id).await?;
+        let token = user.generate_token(&self.jwt)?;
         let res = self.user_presenter.to_json(user, token);
         Ok(res)
     }
 
-    pub fn get_token(&self, current_user: &User) -> Result<HttpResponse, AppError> {
-        let token = current_user.generate_token()?;
-        let res = self.user_presenter.to_json(current_user.clone(), token);
+    pub async fn refresh_token(&self, old_token: &str) -> Result<HttpResponse, AppError> {
+        let claims = token::verify_for_refresh(&self.jwt, old_token)?.claims;
+        // NOTE: the refresh route skips the auth middleware, so logout must be honoured here too.
+        let token_blacklist_repository = self.token_blacklist_repository.clone();
+        let jti = claims.jti;
+        if db::blocking(move || token_blacklist_repository.is_revoked(&jti)).await? {
+            return Err(AppError::Unauthorized("Token has been revoked".into()));
+        }
+        let user = self.find_token_user(claims.user_id).await?;
+        let token = user.generate_token(&self.jwt)?;
+        let res = self.user_presenter.to_json(user, token);
+        Ok(res)
+    }
+
+    pub async fn logout(&self, token: &str) -> Result<HttpResponse, AppError> {
+        let claims = token::verify(&self.jwt, token)?.claims;
+        let expires_at = Utc
+            .timestamp_opt(claims.exp, 0)
+            .single()
+            .map(|exp| exp.naive_utc())
+            .ok_or(AppError::Internal)?;
+        let token_blacklist_repository = self.token_blacklist_repository.clone();
+        db::blocking(move || {
+            token_blacklist_repository.revoke(claims.jti, claims.user_id, expires_at)?;
+            if let Err(err) = token_blacklist_repository.purge_expired() {
+                warn!("Cannot purge expired revoked tokens {}", err);
+            }
+            Ok(())
+        })
+        .await?;
+        let res = self.user_presenter.to_logged_out();
         Ok(res)
     }
 
-    pub fn update_user(
+    pub fn is_revoked_token(&self, jti: &Uuid) -> Result<bool, AppError> {
+        self.token_blacklist_repository.is_revoked(jti)
+    }
+
+    #[tracing::instrument(level = "debug", skip(self, changeset))]
+    pub async fn update_user(
         &self,
         user_id: Uuid,
         changeset: UpdateUser,
     ) -> Result<HttpResponse, AppError> {
-        let (new_user, token) = self.user_repository.update(user_id, changeset)?;
+        if let Some(password) = &changeset.password {
+            self.password.policy.check_field(password)?;
+        }
+        let user_repository = self.user_repository.clone();
+        let hash = self.password.hash;
+        let (new_user, token) = db::blocking(move || {
+            // NOTE: the unique indexes still back this up if two updates race.
+            let taken = user_repository.taken_fields(user_id, &changeset)?;
+            if !taken.is_empty() {
+                return Err(Self::already_taken(&taken));
+            }
+            // NOTE: the repository stores what it is given, so only hashes may reach it.
+            let password = changeset
+                .password
+                .as_deref()
+                .map(|password| hasher::hash_password(&hash, password))
+                .transpose()?;
+            user_repository.update(
+                user_id,
+                UpdateUser {
+                    password,
+                    ..changeset
+                },
+            )
+        })
+        .await?;
         let res = self.user_presenter.to_json(new_user, token);
         Ok(res)
     }
 
+    fn already_taken(fields: &[&str]) -> AppError {
+        let errors = fields
+            .iter()
+            .map(|field| (field.to_string(), "has already been taken".to_owned()))
+            .collect();
+        AppError::UnprocessableEntity(errors)
+    }
+
     pub fn find_auth_user(&self, user_id: Uuid) -> Result<User, &str> {
         let maybe_user = self.user_repository.find(user_id);
         self.user_presenter.to_auth_middleware(maybe_user)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::features::profile::entities::Profile;
+    use crate::app::features::user::presenters::UserPresenterImpl;
+    use crate::constants;
+    use crate::utils::gravatar;
+    use crate::utils::hasher::HashConfig;
+    use actix_web::body::to_bytes;
+    use actix_web::cookie::time::Duration as CookieDuration;
+    use chrono::NaiveDateTime;
+    use serde_json::json;
+    use std::sync::{Mutex, OnceLock};
+
+    const PASSWORD: &str = "jakejake42";
+
+    // NOTE: stands in for Postgres, keeping one user whose updates stick.
+    struct FakeUserRepository {
+        user: Mutex<Option<User>>,
+        taken: Vec<&'static str>,
+    }
+
+    impl UserRepository for FakeUserRepository {
+        fn signin(&self, email: &str, password: &str) -> Result<(User, String), AppError> {
+            let user = self.user.lock().unwrap().clone();
+            match user.filter(|user| user.email == email) {
+                Some(user) if hasher::verify(password, &user.password)? => {
+                    Ok((user, "token".to_owned()))
+                }
+                _ => Err(AppError::Unauthorized(
+                    "email or password is invalid".into(),
+                )),
+            }
+        }
+        fn signup(
+            &self,
+            _email: &str,
+            _username: &str,
+            _password: &str,
+        ) -> Result<(User, String), AppError> {
+            Err(AppError::Internal)
+        }
+        fn follow_user(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<(Profile, bool), AppError> {
+            Err(AppError::Internal)
+        }
+        fn unfollow_user(
+            &self,
+            _current_user: &User,
+            _username: &str,
+        ) -> Result<Profile, AppError> {
+            Err(AppError::Internal)
+        }
+        fn taken_fields(
+            &self,
+            _user_id: Uuid,
+            _changeset: &UpdateUser,
+        ) -> Result<Vec<&'static str>, AppError> {
+            Ok(self.taken.clone())
+        }
+        fn update(
+            &self,
+            _user_id: Uuid,
+            changeset: UpdateUser,
+        ) -> Result<(User, String), AppError> {
+            let mut stored = self.user.lock().unwrap();
+            let user = stored.clone().unwrap();
+            let user = User {
+                username: changeset.username.unwrap_or(user.username),
+                password: changeset.password.unwrap_or(user.password),
+                ..user
+            };
+            *stored = Some(user.clone());
+            Ok((user, "token".to_owned()))
+        }
+        fn find(&self, user_id: Uuid) -> Result<User, AppError> {
+            self.user
+                .lock()
+                .unwrap()
+                .clone()
+                .filter(|user| user.id == user_id)
+                .ok_or_else(|| AppError::NotFound("not found".into()))
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeTokenBlacklistRepository {
+        revoked: Mutex<Vec<Uuid>>,
+    }
+
+    impl TokenBlacklistRepository for FakeTokenBlacklistRepository {
+        fn revoke(
+            &self,
+            jti: Uuid,
+            _user_id: Uuid,
+            _expires_at: NaiveDateTime,
+        ) -> Result<(), AppError> {
+            self.revoked.lock().unwrap().push(jti);
+            Ok(())
+        }
+        fn is_revoked(&self, jti: &Uuid) -> Result<bool, AppError> {
+            Ok(self.revoked.lock().unwrap().contains(jti))
+        }
+        fn purge_expired(&self) -> Result<usize, AppError> {
+            Ok(0)
+        }
+    }
+
+    fn user() -> User {
+        static HASH: OnceLock<String> = OnceLock::new();
+        let now = NaiveDateTime::default();
+        User {
+            id: Uuid::new_v4(),
+            email: "jake@jake.jake".to_owned(),
+            username: "jake".to_owned(),
+            password: HASH
+                .get_or_init(|| hasher::hash_password(&HashConfig::default(), PASSWORD).unwrap())
+                .clone(),
+            bio: Some("I work at statefarm".to_owned()),
+            image: None,
+            created_at: now,
+            updated_at: now,
+            email_verified: true,
+            role: "user".to_owned(),
+            last_login_at: None,
+        }
+    }
+
+    fn jwt() -> JwtConfig {
+        JwtConfig::new("0123456789abcdef0123456789abcdef".to_owned())
+    }
+
+    fn usecase(user: Option<User>) -> UserUsecase {
+        usecase_with_taken(user, vec![])
+    }
+
+    fn usecase_with_taken(user: Option<User>, taken: Vec<&'static str>) -> UserUsecase {
+        UserUsecase::new(
+            Arc::new(FakeUserRepository {
+                user: Mutex::new(user),
+                taken,
+            }),
+            Arc::new(FakeTokenBlacklistRepository::default()),
+            Arc::new(UserPresenterImpl::new(true, jwt().ttl_seconds)),
+            LoginThrottle::new(2, std::time::Duration::from_secs(60)),
+            jwt(),
+            PasswordConfig::default(),
+        )
+    }
+
+    #[actix_web::test]
+    async fn signin_returns_user_with_token() {
+        let res = usecase(Some(user()))
+            .signin("jake@jake.jake", PASSWORD, false)
+            .await
+            .unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("jake", body["user"]["username"]);
+        assert_eq!("token", body["user"]["token"]);
+    }
+
+    #[actix_web::test]
+    async fn signin_with_cookie_sets_a_secure_cookie_that_expires_with_the_token() {
+        let res = usecase(Some(user()))
+            .signin("jake@jake.jake", PASSWORD, true)
+            .await
+            .unwrap();
+
+        let cookie = res.cookies().next().unwrap();
+        assert_eq!(constants::TOKEN_COOKIE, cookie.name());
+        assert_eq!("token", cookie.value());
+        assert_eq!(Some(true), cookie.secure());
+        assert_eq!(Some(true), cookie.http_only());
+        assert_eq!(
+            Some(CookieDuration::seconds(jwt().ttl_seconds)),
+            cookie.max_age()
+        );
+    }
+
+    #[actix_web::test]
+    async fn logout_expires_the_token_cookie() {
+        let user = user();
+        let token = user.generate_token(&jwt()).unwrap();
+        let res = usecase(Some(user)).logout(&token).await.unwrap();
+
+        let cookie = res.cookies().next().unwrap();
+        assert_eq!(constants::TOKEN_COOKIE, cookie.name());
+        assert_eq!("", cookie.value());
+        assert_eq!(Some(CookieDuration::ZERO), cookie.max_age());
+        assert_eq!(Some(true), cookie.secure());
+    }
+
+    #[actix_web::test]
+    async fn signin_locks_out_after_repeated_failures() {
+        let usecase = usecase(Some(user()));
+        for _ in 0..2 {
+            let res = usecase.signin("jake@jake.jake", "wrong", false).await;
+            assert!(matches!(res, Err(AppError::Unauthorized(_))));
+        }
+        // NOTE: even the right password is refused until the lockout passes.
+        let res = usecase.signin("jake@jake.jake", PASSWORD, false).await;
+        assert!(matches!(res, Err(AppError::TooManyRequests(_))));
+    }
+
+    #[actix_web::test]
+    async fn get_current_user_returns_user_with_fresh_token() {
+        let user = user();
+        let res = usecase(Some(user.clone()))
+            .get_current_user(user.id)
+            .await
+            .unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("jake@jake.jake", body["user"]["email"]);
+        assert_eq!("jake", body["user"]["username"]);
+        assert_eq!("I work at statefarm", body["user"]["bio"]);
+        assert_eq!(gravatar::url("jake@jake.jake"), body["user"]["image"]);
+        let token = body["user"]["token"].as_str().unwrap();
+        assert_eq!(
+            user.id,
+            token::verify(&jwt(), token).unwrap().claims.user_id
+        );
+    }
+
+    #[actix_web::test]
+    async fn get_current_user_rejects_missing_user() {
+        let res = usecase(None).get_current_user(Uuid::new_v4()).await;
+        assert!(matches!(res, Err(AppError::Unauthorized(_))));
+    }
+
+    fn rename(username: &str) -> UpdateUser {
+        UpdateUser {
+            email: None,
+            username: Some(username.to_owned()),
+            password: None,
+            image: None,
+            bio: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn refresh_token_rejects_a_token_revoked_by_logout() {
+        let user = user();
+        let usecase = usecase(Some(user.clone()));
+        let token = user.generate_token(&jwt()).unwrap();
+
+        let res = usecase.refresh_token(&token).await.unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+
+        usecase.logout(&token).await.unwrap();
+        let res = usecase.refresh_token(&token).await;
+        assert!(matches!(res, Err(AppError::Unauthorized(_))));
+    }
+
+    #[actix_web::test]
+    async fn update_user_rejects_taken_username() {
+        let user = user();
+        let res = usecase_with_taken(Some(user.clone()), vec!["username"])
+            .update_user(user.id, rename("celeb"))
+            .await;
+        let Err(err @ AppError::UnprocessableEntity(_)) = res else {
+            panic!("expected 422");
+        };
+        assert_eq!(
+            json!({"errors": {"username": ["has already been taken"]}}),
+            err.body()
+        );
+    }
+
+    #[actix_web::test]
+    async fn update_user_keeps_own_username() {
+        let user = user();
+        let res = usecase(Some(user.clone()))
+            .update_user(user.id, rename("jake"))
+            .await
+            .unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+    }
+
+    #[actix_web::test]
+    async fn update_user_hashes_the_new_password() {
+        let user = user();
+        let usecase = usecase(Some(user.clone()));
+        let res = usecase
+            .update_user(
+                user.id,
+                UpdateUser {
+                    password: Some("celebceleb42".to_owned()),
+                    ..rename("jake")
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+
+        let res = usecase
+            .signin("jake@jake.jake", "celebceleb42", false)
+            .await
+            .unwrap();
+        assert_eq!(actix_web::http::StatusCode::OK, res.status());
+        let res = usecase.signin("jake@jake.jake", PASSWORD, false).await;
+        assert!(matches!(res, Err(AppError::Unauthorized(_))));
+    }
+
+    #[actix_web::test]
+    async fn update_user_rejects_a_weak_password() {
+        let user = user();
+        let res = usecase(Some(user.clone()))
+            .update_user(
+                user.id,
+                UpdateUser {
+                    password: Some("abc".to_owned()),
+                    ..rename("jake")
+                },
+            )
+            .await;
+        let Err(err @ AppError::UnprocessableEntity(_)) = res else {
+            panic!("expected 422");
+        };
+        assert_eq!(
+            json!({"errors": {"password": [
+                "is too short (minimum is 8 characters)",
+                "must contain at least one digit",
+            ]}}),
+            err.body()
+        );
+    }
+}
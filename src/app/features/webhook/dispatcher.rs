@@ -0,0 +1,309 @@
+use super::entities::Webhook;
+use super::repositories::WebhookRepository;
+use crate::app::features::article::entities::Article;
+use crate::app::features::favorite::entities::FavoriteInfo;
+use crate::app::features::profile::entities::Profile;
+use crate::app::features::tag::entities::Tag;
+use crate::constants;
+use crate::utils::db;
+use crate::utils::http_client::{self, HttpClient};
+use actix_web::rt;
+use ring::hmac;
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const ARTICLE_PUBLISHED: &str = "article.published";
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArticlePublished {
+    pub slug: String,
+    pub title: String,
+    pub author: String,
+    pub tag_list: Vec<String>,
+}
+
+impl From<&(Article, Profile, FavoriteInfo, Vec<Tag>)> for ArticlePublished {
+    fn from((article, author, _, tag_list): &(Article, Profile, FavoriteInfo, Vec<Tag>)) -> Self {
+        Self {
+            slug: article.slug.clone(),
+            title: article.title.clone(),
+            author: author.username.clone(),
+            tag_list: tag_list.iter().map(|tag| tag.name.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'a str,
+    article: &'a ArticlePublished,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    // NOTE: doubled after every failed attempt.
+    pub backoff: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff: Duration::from_secs(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+// NOTE: deliveries run on a spawned task, so the request that published the article never waits
+// on a receiver. They are at-least-once: receivers should de-duplicate by slug.
+pub trait WebhookDispatcher: Send + Sync + 'static {
+    fn article_published(&self, event: ArticlePublished);
+}
+
+#[derive(Clone)]
+pub struct WebhookDispatcherImpl {
+    webhook_repository: Arc<dyn WebhookRepository>,
+    http_client: HttpClient,
+    retry: RetryPolicy,
+}
+
+impl WebhookDispatcherImpl {
+    pub fn new(
+        webhook_repository: Arc<dyn WebhookRepository>,
+        http_client: HttpClient,
+        retry: RetryPolicy,
+    ) -> Self {
+        Self {
+            webhook_repository,
+            http_client,
+            retry,
+        }
+    }
+}
+
+impl WebhookDispatcher for WebhookDispatcherImpl {
+    fn article_published(&self, event: ArticlePublished) {
+        let body = match serde_json::to_vec(&Payload {
+            event: ARTICLE_PUBLISHED,
+            article: &event,
+        }) {
+            Ok(body) => Arc::new(body),
+            Err(err) => {
+                error!("Cannot serialize webhook payload {}", err);
+                return;
+            }
+        };
+        let webhook_repository = self.webhook_repository.clone();
+        let http_client = self.http_client.clone();
+        let retry = self.retry;
+        rt::spawn(async move {
+            let webhooks = match db::blocking(move || webhook_repository.list()).await {
+                Ok(webhooks) => webhooks,
+                Err(err) => {
+                    error!("Cannot load webhooks {:?}", err);
+                    return;
+                }
+            };
+            // NOTE: one task per endpoint so a slow receiver doesn't hold up the others.
+            for webhook in webhooks {
+                rt::spawn(deliver(
+                    http_client.clone(),
+                    webhook,
+                    ARTICLE_PUBLISHED,
+                    body.clone(),
+                    retry,
+                ));
+            }
+        });
+    }
+}
+
+// NOTE: `sha256=<hex HMAC of the raw body>`, keyed by the webhook's secret.
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::sign(&key, body)
+        .as_ref()
+        .iter()
+        .fold(String::from("sha256="), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+async fn deliver(
+    client: HttpClient,
+    webhook: Webhook,
+    event: &'static str,
+    body: Arc<Vec<u8>>,
+    retry: RetryPolicy,
+) {
+    let signature = signature(&webhook.secret, &body);
+    let headers = [
+        (constants::WEBHOOK_EVENT, event),
+        (constants::WEBHOOK_SIGNATURE, signature.as_str()),
+    ];
+    let mut backoff = retry.backoff;
+    for attempt in 1..=retry.attempts {
+        let result = client
+            .post_json(&webhook.url, &headers, body.to_vec(), retry.timeout)
+            .await;
+        match result {
+            Ok(status) if (200..300).contains(&status) => return,
+            Ok(status) => warn!(
+                "Webhook {} answered {} (attempt {}/{})",
+                webhook.id, status, attempt, retry.attempts
+            ),
+            // NOTE: a refused target stays refused, so retrying it is pointless.
+            Err(err @ http_client::Error::Refused(_)) => {
+                error!("Webhook {} {}", webhook.id, err);
+                return;
+            }
+            Err(err) => warn!(
+                "Webhook {} failed {} (attempt {}/{})",
+                webhook.id, err, attempt, retry.attempts
+            ),
+        }
+        if attempt < retry.attempts {
+            rt::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+    error!("Giving up on webhook {} for {}", webhook.id, event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::utils::http_client::Targets;
+    use actix_web::web;
+    use chrono::NaiveDateTime;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use uuid::Uuid;
+
+    struct FakeWebhookRepository {
+        url: String,
+    }
+
+    impl WebhookRepository for FakeWebhookRepository {
+        fn list(&self) -> Result<Vec<Webhook>, AppError> {
+            Ok(vec![Webhook {
+                id: Uuid::new_v4(),
+                url: self.url.clone(),
+                secret: "s3cr3t".to_owned(),
+                created_at: NaiveDateTime::default(),
+            }])
+        }
+    }
+
+    type Delivery = (Vec<String>, Vec<u8>);
+
+    // NOTE: answers each request with the next status in `statuses` and reports what it received.
+    fn mock_server(statuses: Vec<u16>) -> (String, mpsc::Receiver<Delivery>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut headers = vec![];
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    headers.push(line.trim_end().to_lowercase());
+                }
+                let length = headers
+                    .iter()
+                    .find_map(|header| header.strip_prefix("content-length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\n\r\n", status);
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+                tx.send((headers, body)).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    async fn next(rx: Arc<std::sync::Mutex<mpsc::Receiver<Delivery>>>) -> Delivery {
+        web::block(move || rx.lock().unwrap().recv_timeout(Duration::from_secs(5)))
+            .await
+            .unwrap()
+            .expect("webhook was not delivered")
+    }
+
+    #[test]
+    fn signature_is_the_hex_hmac_of_the_body() {
+        // NOTE: RFC 4231 test case 2.
+        assert_eq!(
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            signature("Jefe", b"what do ya want for nothing?")
+        );
+    }
+
+    #[actix_web::test]
+    async fn delivers_a_signed_payload_and_retries_failures() {
+        let (url, rx) = mock_server(vec![503, 200]);
+        let rx = Arc::new(std::sync::Mutex::new(rx));
+        let dispatcher = WebhookDispatcherImpl::new(
+            Arc::new(FakeWebhookRepository { url }),
+            HttpClient::new(Targets::Any),
+            RetryPolicy {
+                attempts: 3,
+                backoff: Duration::from_millis(10),
+                timeout: Duration::from_secs(5),
+            },
+        );
+        dispatcher.article_published(ArticlePublished {
+            slug: "how-to-train-your-dragon".to_owned(),
+            title: "How to train your dragon".to_owned(),
+            author: "jake".to_owned(),
+            tag_list: vec!["dragons".to_owned()],
+        });
+
+        let (_, failed) = next(rx.clone()).await;
+        let (headers, body) = next(rx).await;
+        assert_eq!(failed, body);
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "event": "article.published",
+                "article": {
+                    "slug": "how-to-train-your-dragon",
+                    "title": "How to train your dragon",
+                    "author": "jake",
+                    "tagList": ["dragons"],
+                }
+            }),
+            payload
+        );
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"s3cr3t");
+        let expected = hmac::sign(&key, &body);
+        let sent = headers
+            .iter()
+            .find_map(|header| header.strip_prefix("x-conduit-signature: sha256="))
+            .expect("signature header is missing");
+        let sent = (0..sent.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&sent[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(expected.as_ref(), &sent[..]);
+        assert!(headers.contains(&"x-conduit-event: article.published".to_owned()));
+    }
+}
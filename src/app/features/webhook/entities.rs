@@ -0,0 +1,25 @@
+use crate::error::AppError;
+use crate::schema::webhooks;
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+// An integrator's endpoint; `secret` signs every delivery so the receiver can verify the sender.
+#[derive(Identifiable, Queryable, Debug, Clone)]
+#[diesel(table_name = webhooks)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Webhook {
+    pub fn fetch_all(conn: &mut PgConnection) -> Result<Vec<Self>, AppError> {
+        let list = webhooks::table
+            .order(webhooks::created_at.asc())
+            .load::<Self>(conn)?;
+        Ok(list)
+    }
+}
@@ -0,0 +1,25 @@
+use super::entities::Webhook;
+use crate::error::AppError;
+use crate::utils::db::DbPools;
+
+pub trait WebhookRepository: Send + Sync + 'static {
+    fn list(&self) -> Result<Vec<Webhook>, AppError>;
+}
+
+#[derive(Clone)]
+pub struct WebhookRepositoryImpl {
+    pools: DbPools,
+}
+
+impl WebhookRepositoryImpl {
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
+    }
+}
+
+impl WebhookRepository for WebhookRepositoryImpl {
+    fn list(&self) -> Result<Vec<Webhook>, AppError> {
+        let conn = &mut self.pools.read_conn()?;
+        Webhook::fetch_all(conn)
+    }
+}
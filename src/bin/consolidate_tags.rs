@@ -0,0 +1,27 @@
+use conduit::app::features::tag::repositories::{TagRepository, TagRepositoryImpl};
+use conduit::config::DatabaseConfig;
+use conduit::utils;
+use conduit::utils::db::DbPools;
+use std::process;
+
+// Merges tags that differ only in case or spacing: `cargo run --bin consolidate_tags`.
+fn main() {
+    dotenv::dotenv().ok();
+    utils::logger::init();
+    let config = DatabaseConfig::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let pool = utils::db::establish_connection(&config);
+    let tags = TagRepositoryImpl::new(DbPools::new(pool, None));
+    match tags.consolidate() {
+        Ok(summary) => println!(
+            "renamed {} tags and removed {} duplicates",
+            summary.renamed, summary.removed
+        ),
+        Err(err) => {
+            eprintln!("consolidating tags failed: {}", err);
+            process::exit(1);
+        }
+    }
+}
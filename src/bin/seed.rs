@@ -0,0 +1,37 @@
+use conduit::app::drivers::seed;
+use conduit::config::DatabaseConfig;
+use conduit::utils;
+use conduit::utils::hasher::HashConfig;
+use std::process;
+
+// Populates the database at DATABASE_URL with demo data: `cargo run --bin seed`.
+fn main() {
+    dotenv::dotenv().ok();
+    utils::logger::init();
+    let config = DatabaseConfig::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let hash = HashConfig::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let pool = utils::db::establish_connection(&config);
+    let conn = &mut pool.get().expect("Failed to connect to the database");
+    match seed::run(conn, &hash) {
+        Ok(Some(summary)) => println!(
+            "seeded {} users, {} articles, {} follows, {} favorites and {} comments (password: {})",
+            summary.users,
+            summary.articles,
+            summary.follows,
+            summary.favorites,
+            summary.comments,
+            seed::PASSWORD
+        ),
+        Ok(None) => println!("database already seeded, skipping"),
+        Err(err) => {
+            eprintln!("seeding failed: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use crate::app::drivers::middlewares::cors::CorsConfig;
+use crate::app::features::avatar::repositories::DEFAULT_AVATAR_DIR;
+use crate::constants::{self, env_key};
+use crate::utils::article_limits::ArticleLimits;
+use crate::utils::db::PoolConfig;
+use crate::utils::hasher::HashConfig;
+use crate::utils::http_client::Targets;
+use crate::utils::login_throttle::LoginThrottleConfig;
+use crate::utils::password_policy::PasswordPolicy;
+use crate::utils::rate_limiter::RateLimitConfig;
+use crate::utils::token::JwtConfig;
+use std::env;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+static DEFAULT_VIEW_WINDOW_SECONDS: u64 = 60 * 30;
+
+pub type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Missing(&'static str),
+    Invalid {
+        key: &'static str,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Missing(key) => write!(f, "{} must be set (see .env.example)", key),
+            ConfigError::Invalid { key, expected } => {
+                write!(f, "{} is invalid: expected {}", key, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub replica_url: Option<String>,
+    pub pool: PoolConfig,
+}
+
+impl DatabaseConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::load(&lookup_env)
+    }
+
+    fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        Ok(Self {
+            url: required(lookup, env_key::DATABASE_URL)?,
+            replica_url: optional(lookup, env_key::DATABASE_REPLICA_URL),
+            pool: PoolConfig::load(lookup)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasswordConfig {
+    pub hash: HashConfig,
+    pub policy: PasswordPolicy,
+}
+
+impl PasswordConfig {
+    fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        Ok(Self {
+            hash: HashConfig::load(lookup)?,
+            policy: PasswordPolicy::load(lookup)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticleConfig {
+    pub limits: ArticleLimits,
+    // NOTE: repeat views by the same viewer within this window count once.
+    pub view_window: Duration,
+    pub require_verified_email: bool,
+}
+
+impl Default for ArticleConfig {
+    fn default() -> Self {
+        Self {
+            limits: ArticleLimits::default(),
+            view_window: Duration::from_secs(DEFAULT_VIEW_WINDOW_SECONDS),
+            require_verified_email: true,
+        }
+    }
+}
+
+impl ArticleConfig {
+    fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let default = Self::default();
+        let view_window = parsed::<u64>(
+            lookup,
+            env_key::ARTICLE_VIEW_WINDOW_SECONDS,
+            "a number of seconds",
+            |_| true,
+        )?;
+        Ok(Self {
+            limits: ArticleLimits::load(lookup)?,
+            view_window: view_window
+                .map(Duration::from_secs)
+                .unwrap_or(default.view_window),
+            require_verified_email: flag(
+                lookup,
+                env_key::EMAIL_VERIFICATION_REQUIRED,
+                default.require_verified_email,
+            )?,
+        })
+    }
+}
+
+// Every setting the server runs with, read and checked once at startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub bind: SocketAddr,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub jwt: JwtConfig,
+    pub password: PasswordConfig,
+    pub login_throttle: LoginThrottleConfig,
+    pub rate_limit: RateLimitConfig,
+    pub compression: bool,
+    // NOTE: `Secure` on the session cookie; only turned off for local development over plain http.
+    pub secure_cookies: bool,
+    pub article: ArticleConfig,
+    pub avatar_dir: PathBuf,
+    pub webhook_targets: Targets,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::load(&lookup_env)
+    }
+
+    fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let bind = parsed::<SocketAddr>(
+            lookup,
+            env_key::BIND_ADDRESS,
+            "an address like 0.0.0.0:8080",
+            |_| true,
+        )?;
+        Ok(Self {
+            bind: bind.unwrap_or_else(|| constants::BIND.parse().unwrap()),
+            database: DatabaseConfig::load(lookup)?,
+            cors: CorsConfig::load(lookup),
+            jwt: JwtConfig::load(lookup)?,
+            password: PasswordConfig::load(lookup)?,
+            login_throttle: LoginThrottleConfig::load(lookup)?,
+            rate_limit: RateLimitConfig::load(lookup)?,
+            compression: flag(lookup, env_key::ENABLE_COMPRESSION, true)?,
+            secure_cookies: !flag(lookup, env_key::COOKIE_ALLOW_INSECURE, false)?,
+            article: ArticleConfig::load(lookup)?,
+            avatar_dir: optional(lookup, env_key::AVATAR_DIR)
+                .unwrap_or_else(|| DEFAULT_AVATAR_DIR.to_owned())
+                .into(),
+            webhook_targets: Targets::load(lookup)?,
+        })
+    }
+}
+
+pub(crate) fn lookup_env(key: &str) -> Option<String> {
+    env::var(key).ok()
+}
+
+pub(crate) fn optional(lookup: Lookup, key: &str) -> Option<String> {
+    lookup(key).filter(|value| !value.trim().is_empty())
+}
+
+pub(crate) fn required(lookup: Lookup, key: &'static str) -> Result<String, ConfigError> {
+    optional(lookup, key).ok_or(ConfigError::Missing(key))
+}
+
+pub(crate) fn flag(lookup: Lookup, key: &'static str, default: bool) -> Result<bool, ConfigError> {
+    match optional(lookup, key).map(|value| value.trim().to_lowercase()) {
+        None => Ok(default),
+        Some(value) if value == "true" => Ok(true),
+        Some(value) if value == "false" => Ok(false),
+        Some(_) => Err(ConfigError::Invalid {
+            key,
+            expected: "true or false",
+        }),
+    }
+}
+
+// NOTE: unset is fine and means the default; set but unparsable is a mistake worth stopping for.
+pub(crate) fn parsed<T: FromStr>(
+    lookup: Lookup,
+    key: &'static str,
+    expected: &'static str,
+    is_valid: impl Fn(&T) -> bool,
+) -> Result<Option<T>, ConfigError> {
+    match optional(lookup, key) {
+        None => Ok(None),
+        Some(value) => value
+            .trim()
+            .parse::<T>()
+            .ok()
+            .filter(|value| is_valid(value))
+            .map(Some)
+            .ok_or(ConfigError::Invalid { key, expected }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::drivers::middlewares::cors::AllowedOrigins;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        Config::load(&|key| vars.get(key).cloned())
+    }
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    const REQUIRED: [(&str, &str); 2] = [
+        (env_key::DATABASE_URL, "postgres://localhost/conduit"),
+        (env_key::SECRET_KEY, SECRET),
+    ];
+
+    #[test]
+    fn loads_required_values_and_defaults() {
+        let config = load(&REQUIRED).unwrap();
+        assert_eq!("postgres://localhost/conduit", config.database.url);
+        assert_eq!(None, config.database.replica_url);
+        assert_eq!(constants::BIND, config.bind.to_string());
+        assert!(config.secure_cookies);
+    }
+
+    #[test]
+    fn reads_pool_and_cors_settings_from_the_same_source() {
+        let config = load(&[
+            REQUIRED[0],
+            REQUIRED[1],
+            (env_key::DATABASE_POOL_MAX_SIZE, "4"),
+            (env_key::CORS_ALLOWED_ORIGINS, "http://localhost:3000"),
+        ])
+        .unwrap();
+        assert_eq!(4, config.database.pool.max_size);
+        assert_eq!(
+            AllowedOrigins::List(vec!["http://localhost:3000".to_owned()]),
+            config.cors.origins
+        );
+    }
+
+    #[test]
+    fn missing_database_url_fails_fast() {
+        let err = load(&[(env_key::SECRET_KEY, SECRET)]).unwrap_err();
+        assert_eq!(ConfigError::Missing(env_key::DATABASE_URL), err);
+        assert_eq!(
+            "DATABASE_URL must be set (see .env.example)",
+            err.to_string()
+        );
+        let blank = load(&[(env_key::DATABASE_URL, " "), REQUIRED[1]]).unwrap_err();
+        assert_eq!(ConfigError::Missing(env_key::DATABASE_URL), blank);
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        let err = load(&[REQUIRED[0], REQUIRED[1], (env_key::JWT_TTL_SECONDS, "-5")]).unwrap_err();
+        assert_eq!(
+            "JWT_TTL_SECONDS is invalid: expected a positive number of seconds",
+            err.to_string()
+        );
+        let err = load(&[
+            REQUIRED[0],
+            REQUIRED[1],
+            (env_key::BIND_ADDRESS, "localhost"),
+        ]);
+        assert!(matches!(
+            err,
+            Err(ConfigError::Invalid {
+                key: env_key::BIND_ADDRESS,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn reads_runtime_settings_once() {
+        let config = load(&[
+            REQUIRED[0],
+            REQUIRED[1],
+            (env_key::JWT_TTL_SECONDS, "60"),
+            (env_key::JWT_ISSUER, "conduit"),
+            (env_key::PASSWORD_MIN_LENGTH, "12"),
+            (env_key::RATE_LIMIT_TRUST_FORWARDED_FOR, "TRUE"),
+            (env_key::ENABLE_COMPRESSION, "false"),
+            (env_key::COOKIE_ALLOW_INSECURE, "true"),
+            (env_key::ARTICLE_VIEW_WINDOW_SECONDS, "90"),
+            (env_key::EMAIL_VERIFICATION_REQUIRED, "false"),
+        ])
+        .unwrap();
+        assert_eq!(60, config.jwt.ttl_seconds);
+        assert_eq!(Some("conduit".to_owned()), config.jwt.issuer);
+        assert_eq!(None, config.jwt.audience);
+        assert_eq!(12, config.password.policy.min_length);
+        assert!(config.rate_limit.trust_forwarded_for);
+        assert!(!config.compression);
+        assert!(!config.secure_cookies);
+        assert_eq!(Duration::from_secs(90), config.article.view_window);
+        assert!(!config.article.require_verified_email);
+        assert_eq!(Targets::PublicHttps, config.webhook_targets);
+    }
+
+    #[test]
+    fn rejects_malformed_runtime_settings() {
+        for (key, value) in [
+            (env_key::JWT_REFRESH_GRACE_SECONDS, "-1"),
+            (env_key::PASSWORD_HASH_COST, "1"),
+            (env_key::PASSWORD_REQUIRE_DIGIT, "yes"),
+            (env_key::LOGIN_MAX_FAILED_ATTEMPTS, "0"),
+            (env_key::RATE_LIMIT_PER_SECOND, "fast"),
+            (env_key::ENABLE_COMPRESSION, "1"),
+            (env_key::COOKIE_ALLOW_INSECURE, "yes"),
+            (env_key::ARTICLE_MAX_BODY_LENGTH, "0"),
+            (env_key::SLOW_QUERY_MS, "-5"),
+            (env_key::WEBHOOK_ALLOW_INSECURE_TARGETS, "sure"),
+        ] {
+            let err = load(&[REQUIRED[0], REQUIRED[1], (key, value)]).unwrap_err();
+            assert!(
+                matches!(err, ConfigError::Invalid { key: invalid, .. } if invalid == key),
+                "{}={} gave {:?}",
+                key,
+                value,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn debug_output_hides_the_secret() {
+        let config = load(&REQUIRED).unwrap();
+        assert!(!format!("{:?}", config).contains(SECRET));
+    }
+
+    #[test]
+    fn rejects_a_short_secret() {
+        let err = load(&[REQUIRED[0], (env_key::SECRET_KEY, "0123456789012345")]).unwrap_err();
+        assert_eq!(
+            "SECRET_KEY is invalid: expected at least 32 bytes",
+            err.to_string()
+        );
+        assert!(load(&[REQUIRED[0], (env_key::SECRET_KEY, &SECRET[1..])]).is_err());
+        assert!(load(&REQUIRED).is_ok());
+    }
+}
@@ -1,9 +1,51 @@
 pub const AUTHORIZATION: &str = "Authorization";
 
+// NOTE: lowercase so it can be used as a static `HeaderName`.
+pub const REQUEST_ID: &str = "x-request-id";
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+pub const WEBHOOK_EVENT: &str = "x-conduit-event";
+pub const WEBHOOK_SIGNATURE: &str = "x-conduit-signature";
+pub const LAST_EVENT_ID: &str = "last-event-id";
+
+pub const TOKEN_COOKIE: &str = "conduit_token";
+
 pub const BIND: &str = "0.0.0.0:8080";
 
 pub mod env_key {
+    pub const BIND_ADDRESS: &str = "BIND_ADDRESS";
     pub const DATABASE_URL: &str = "DATABASE_URL";
-    pub const FRONTEND_ORIGIN: &str = "FRONTEND_ORIGIN";
+    pub const DATABASE_REPLICA_URL: &str = "DATABASE_REPLICA_URL";
+    pub const DATABASE_POOL_MAX_SIZE: &str = "DATABASE_POOL_MAX_SIZE";
+    pub const DATABASE_POOL_MIN_IDLE: &str = "DATABASE_POOL_MIN_IDLE";
+    pub const DATABASE_CONNECTION_TIMEOUT_SECS: &str = "DATABASE_CONNECTION_TIMEOUT_SECS";
+    pub const SLOW_QUERY_MS: &str = "SLOW_QUERY_MS";
+    pub const CORS_ALLOWED_ORIGINS: &str = "CORS_ALLOWED_ORIGINS";
+    pub const CORS_ALLOWED_METHODS: &str = "CORS_ALLOWED_METHODS";
+    pub const CORS_MAX_AGE: &str = "CORS_MAX_AGE";
     pub const SECRET_KEY: &str = "SECRET_KEY";
+    pub const JWT_TTL_SECONDS: &str = "JWT_TTL_SECONDS";
+    pub const JWT_REFRESH_GRACE_SECONDS: &str = "JWT_REFRESH_GRACE_SECONDS";
+    pub const JWT_ISSUER: &str = "JWT_ISSUER";
+    pub const JWT_AUDIENCE: &str = "JWT_AUDIENCE";
+    pub const ARGON2_MEMORY_KIB: &str = "ARGON2_MEMORY_KIB";
+    pub const PASSWORD_HASH_COST: &str = "PASSWORD_HASH_COST";
+    pub const ARGON2_PARALLELISM: &str = "ARGON2_PARALLELISM";
+    pub const PASSWORD_MIN_LENGTH: &str = "PASSWORD_MIN_LENGTH";
+    pub const PASSWORD_REQUIRE_LETTER: &str = "PASSWORD_REQUIRE_LETTER";
+    pub const PASSWORD_REQUIRE_DIGIT: &str = "PASSWORD_REQUIRE_DIGIT";
+    pub const PASSWORD_REJECT_COMMON: &str = "PASSWORD_REJECT_COMMON";
+    pub const LOGIN_MAX_FAILED_ATTEMPTS: &str = "LOGIN_MAX_FAILED_ATTEMPTS";
+    pub const LOGIN_LOCKOUT_SECONDS: &str = "LOGIN_LOCKOUT_SECONDS";
+    pub const RATE_LIMIT_BURST: &str = "RATE_LIMIT_BURST";
+    pub const RATE_LIMIT_PER_SECOND: &str = "RATE_LIMIT_PER_SECOND";
+    pub const RATE_LIMIT_TRUST_FORWARDED_FOR: &str = "RATE_LIMIT_TRUST_FORWARDED_FOR";
+    pub const ENABLE_COMPRESSION: &str = "ENABLE_COMPRESSION";
+    pub const COOKIE_ALLOW_INSECURE: &str = "COOKIE_ALLOW_INSECURE";
+    pub const ARTICLE_MAX_TITLE_LENGTH: &str = "ARTICLE_MAX_TITLE_LENGTH";
+    pub const ARTICLE_MAX_BODY_LENGTH: &str = "ARTICLE_MAX_BODY_LENGTH";
+    pub const ARTICLE_VIEW_WINDOW_SECONDS: &str = "ARTICLE_VIEW_WINDOW_SECONDS";
+    pub const EMAIL_VERIFICATION_REQUIRED: &str = "EMAIL_VERIFICATION_REQUIRED";
+    pub const AVATAR_DIR: &str = "AVATAR_DIR";
+    pub const WEBHOOK_ALLOW_INSECURE_TARGETS: &str = "WEBHOOK_ALLOW_INSECURE_TARGETS";
+    pub const LOG_FORMAT: &str = "LOG_FORMAT";
 }
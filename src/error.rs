@@ -1,91 +1,151 @@
+use crate::app::drivers::middlewares::error::ErrorResponse;
 use actix_web::{http::StatusCode, HttpResponse};
+use argon2::password_hash::Error as PasswordHashError;
+use argon2::Error as Argon2Error;
 use bcrypt::BcryptError;
 use diesel::r2d2::{Error as R2D2Error, PoolError};
 use diesel::result::{DatabaseErrorKind, Error as DieselError};
 use jsonwebtoken::errors::{Error as JwtError, ErrorKind as JwtErrorKind};
 use serde_json::json;
 use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
 use std::convert::From;
 use thiserror::Error;
 use uuid::Error as UuidError;
 
+// `(field, message)` pairs, e.g. `("email", "has already been taken")`; a field may repeat.
+pub type FieldErrors = Vec<(String, String)>;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     // 401
     #[error("Unauthorized: {}", _0)]
-    Unauthorized(JsonValue),
+    Unauthorized(String),
 
     // 403
     #[error("Forbidden: {}", _0)]
-    Forbidden(JsonValue),
+    Forbidden(String),
 
     // 404
     #[error("Not Found: {}", _0)]
-    NotFound(JsonValue),
+    NotFound(String),
+
+    // 409
+    #[error("Conflict: {}", _0)]
+    Conflict(String),
 
     // 422
-    #[error("Unprocessable Entity: {}", _0)]
-    UnprocessableEntity(JsonValue),
+    #[error("Unprocessable Entity: {:?}", _0)]
+    UnprocessableEntity(FieldErrors),
+
+    // 429
+    #[error("Too Many Requests: {}", _0)]
+    TooManyRequests(String),
 
     // 500
     #[error("Internal Server Error")]
-    InternalServerError,
+    Internal,
+
+    // 503
+    #[error("Service Unavailable: {}", _0)]
+    ServiceUnavailable(String),
 }
 
-impl actix_web::error::ResponseError for AppError {
-    fn error_response(&self) -> HttpResponse {
-        match self {
-            AppError::Unauthorized(ref msg) => HttpResponse::Unauthorized().json(msg),
-            AppError::Forbidden(ref msg) => HttpResponse::Forbidden().json(msg),
-            AppError::NotFound(ref msg) => HttpResponse::NotFound().json(msg),
-            AppError::UnprocessableEntity(ref msg) => HttpResponse::UnprocessableEntity().json(msg),
-            AppError::InternalServerError => {
-                HttpResponse::InternalServerError().json("Internal Server Error")
-            }
-        }
+impl AppError {
+    // A single failing field, e.g. `AppError::invalid("order", "is invalid")`.
+    pub fn invalid(field: &str, message: &str) -> Self {
+        AppError::UnprocessableEntity(vec![(field.to_owned(), message.to_owned())])
     }
-    fn status_code(&self) -> StatusCode {
-        match *self {
+
+    pub fn status(&self) -> StatusCode {
+        match self {
             AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             AppError::Forbidden(_) => StatusCode::FORBIDDEN,
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
             AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
-            AppError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
+
+    // SPEC: https://realworld-docs.netlify.app/specifications/backend/error-handling/
+    // NOTE: field errors are keyed by field, any other message goes under `body`.
+    pub fn body(&self) -> JsonValue {
+        match self {
+            AppError::UnprocessableEntity(fields) => {
+                let mut errors = BTreeMap::<&str, Vec<&str>>::new();
+                for (field, message) in fields {
+                    errors.entry(field).or_default().push(message);
+                }
+                json!({ "errors": errors })
+            }
+            AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::TooManyRequests(message)
+            | AppError::ServiceUnavailable(message) => json!(ErrorResponse::from(message.as_str())),
+            AppError::Internal => json!(ErrorResponse::from("Internal Server Error")),
+        }
+    }
+
+    pub fn to_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status()).json(self.body())
+    }
 }
 
+impl actix_web::error::ResponseError for AppError {
+    fn error_response(&self) -> HttpResponse {
+        self.to_response()
+    }
+    fn status_code(&self) -> StatusCode {
+        self.status()
+    }
+}
+
+// NOTE: r2d2 only fails `get()` when no connection frees up within the timeout, i.e. overload.
 impl From<PoolError> for AppError {
-    fn from(_err: PoolError) -> Self {
-        AppError::InternalServerError
+    fn from(err: PoolError) -> Self {
+        warn!("Cannot get a database connection {}", err);
+        AppError::ServiceUnavailable("Database is busy, try again later.".into())
     }
 }
 
 impl From<BcryptError> for AppError {
     fn from(_err: BcryptError) -> Self {
-        AppError::InternalServerError
+        AppError::Internal
+    }
+}
+
+impl From<PasswordHashError> for AppError {
+    fn from(_err: PasswordHashError) -> Self {
+        AppError::Internal
+    }
+}
+
+impl From<Argon2Error> for AppError {
+    fn from(_err: Argon2Error) -> Self {
+        AppError::Internal
     }
 }
 
 impl From<JwtError> for AppError {
     fn from(err: JwtError) -> Self {
         match err.kind() {
-            JwtErrorKind::InvalidToken => AppError::Unauthorized(json!({
-                "error": "Token is invalid"
-            })),
-            JwtErrorKind::InvalidIssuer => AppError::Unauthorized(json!({
-                "error": "Issuer is invalid",
-            })),
-            _ => AppError::Unauthorized(json!({
-                "error": "An issue was found with the token provided",
-            })),
+            JwtErrorKind::InvalidToken => AppError::Unauthorized("Token is invalid".into()),
+            JwtErrorKind::InvalidIssuer => AppError::Unauthorized("Issuer is invalid".into()),
+            JwtErrorKind::InvalidAudience => AppError::Unauthorized("Audience is invalid".into()),
+            JwtErrorKind::ExpiredSignature => AppError::Unauthorized("token expired".into()),
+            _ => AppError::Unauthorized("An issue was found with the token provided".into()),
         }
     }
 }
 
 impl From<R2D2Error> for AppError {
     fn from(_err: R2D2Error) -> Self {
-        AppError::InternalServerError
+        AppError::Internal
     }
 }
 
@@ -94,22 +154,237 @@ impl From<DieselError> for AppError {
         match err {
             DieselError::DatabaseError(kind, info) => {
                 if let DatabaseErrorKind::UniqueViolation = kind {
-                    let message = info.details().unwrap_or_else(|| info.message()).to_string();
-                    AppError::UnprocessableEntity(json!({ "error": message }))
+                    let constraint = info.constraint_name();
+                    match constraint.and_then(unique_violation_field) {
+                        Some(field) => AppError::invalid(field, "has already been taken"),
+                        None => {
+                            // NOTE: the database message names tables and values; keep it in the log.
+                            warn!(
+                                "Unique violation on unmapped constraint {:?}: {}",
+                                constraint,
+                                info.message()
+                            );
+                            AppError::invalid("body", "has already been taken")
+                        }
+                    }
                 } else {
-                    AppError::InternalServerError
+                    AppError::Internal
                 }
             }
-            DieselError::NotFound => {
-                AppError::NotFound(json!({ "error": "requested record was not found" }))
-            }
-            _ => AppError::InternalServerError,
+            // NOTE: every `.first()` / `.get_result()` on a missing row lands here.
+            DieselError::NotFound => AppError::NotFound("not found".into()),
+            _ => AppError::Internal,
         }
     }
 }
 
+// NOTE: keep in sync with the unique indexes created in migrations.
+fn unique_violation_field(constraint: &str) -> Option<&'static str> {
+    match constraint {
+        "users_email_lower_key" => Some("email"),
+        "users_username_lower_key" => Some("username"),
+        "articles_slug_key" => Some("slug"),
+        // NOTE: two concurrent first attempts with one key; the loser should simply retry.
+        "idempotency_keys_pkey" => Some("idempotencyKey"),
+        _ => None,
+    }
+}
+
 impl From<UuidError> for AppError {
     fn from(_err: UuidError) -> Self {
-        AppError::NotFound(json!({"error":"Uuid is invalid."}))
+        AppError::NotFound("Uuid is invalid.".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::ResponseError;
+    use diesel::result::DatabaseErrorInformation;
+
+    struct ErrorInfo {
+        constraint: Option<&'static str>,
+    }
+
+    impl DatabaseErrorInformation for ErrorInfo {
+        fn message(&self) -> &str {
+            "duplicate key value violates unique constraint"
+        }
+        fn details(&self) -> Option<&str> {
+            None
+        }
+        fn hint(&self) -> Option<&str> {
+            None
+        }
+        fn table_name(&self) -> Option<&str> {
+            None
+        }
+        fn column_name(&self) -> Option<&str> {
+            None
+        }
+        fn constraint_name(&self) -> Option<&str> {
+            self.constraint
+        }
+        fn statement_position(&self) -> Option<i32> {
+            None
+        }
+    }
+
+    fn database_error(kind: DatabaseErrorKind, constraint: Option<&'static str>) -> AppError {
+        DieselError::DatabaseError(kind, Box::new(ErrorInfo { constraint })).into()
+    }
+
+    #[test]
+    fn unique_email_violation_is_unprocessable() {
+        match database_error(
+            DatabaseErrorKind::UniqueViolation,
+            Some("users_email_lower_key"),
+        ) {
+            AppError::UnprocessableEntity(fields) => assert_eq!(
+                vec![("email".to_owned(), "has already been taken".to_owned())],
+                fields
+            ),
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unique_username_violation_is_unprocessable() {
+        match database_error(
+            DatabaseErrorKind::UniqueViolation,
+            Some("users_username_lower_key"),
+        ) {
+            AppError::UnprocessableEntity(fields) => assert_eq!(
+                vec![("username".to_owned(), "has already been taken".to_owned())],
+                fields
+            ),
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unknown_unique_violation_is_unprocessable_under_body() {
+        for constraint in [None, Some("comments_pkey")] {
+            let err = database_error(DatabaseErrorKind::UniqueViolation, constraint);
+            assert_eq!(
+                json!({ "errors": { "body": ["has already been taken"] } }),
+                err.body()
+            );
+        }
+    }
+
+    #[test]
+    fn other_database_errors_are_internal() {
+        let err = database_error(
+            DatabaseErrorKind::ForeignKeyViolation,
+            Some("users_email_lower_key"),
+        );
+        assert!(matches!(err, AppError::Internal));
+    }
+
+    async fn response(err: AppError) -> (u16, JsonValue) {
+        let res = err.to_response();
+        let status = res.status().as_u16();
+        let body = to_bytes(res.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn body(message: &str) -> JsonValue {
+        json!({ "errors": { "body": [message] } })
+    }
+
+    #[test]
+    fn every_variant_maps_to_its_own_status() {
+        let cases = [
+            (AppError::Unauthorized("".into()), 401),
+            (AppError::Forbidden("".into()), 403),
+            (AppError::NotFound("".into()), 404),
+            (AppError::Conflict("".into()), 409),
+            (AppError::invalid("email", "is invalid"), 422),
+            (AppError::TooManyRequests("".into()), 429),
+            (AppError::Internal, 500),
+            (AppError::ServiceUnavailable("".into()), 503),
+        ];
+        for (err, status) in cases {
+            assert_eq!(status, err.status().as_u16(), "{:?}", err);
+            assert_eq!(err.status(), err.status_code(), "{:?}", err);
+            assert_eq!(err.status(), err.to_response().status(), "{:?}", err);
+        }
+    }
+
+    #[actix_web::test]
+    async fn unauthorized_uses_body_envelope() {
+        let err = AppError::Unauthorized("Token is invalid".into());
+        assert_eq!((401, body("Token is invalid")), response(err).await);
+    }
+
+    #[actix_web::test]
+    async fn forbidden_uses_body_envelope() {
+        let err = AppError::Forbidden("Only the author can delete this comment.".into());
+        assert_eq!(
+            (403, body("Only the author can delete this comment.")),
+            response(err).await
+        );
+    }
+
+    #[actix_web::test]
+    async fn not_found_uses_body_envelope() {
+        let err: AppError = DieselError::NotFound.into();
+        assert_eq!((404, body("not found")), response(err).await);
+    }
+
+    #[actix_web::test]
+    async fn conflict_uses_body_envelope() {
+        let err = AppError::Conflict("Article has been modified.".into());
+        assert_eq!(
+            (409, body("Article has been modified.")),
+            response(err).await
+        );
+    }
+
+    #[actix_web::test]
+    async fn unprocessable_entity_groups_messages_by_field() {
+        let err = AppError::UnprocessableEntity(vec![
+            ("password".to_owned(), "is too short".to_owned()),
+            ("email".to_owned(), "is invalid".to_owned()),
+            (
+                "password".to_owned(),
+                "must contain at least one digit".to_owned(),
+            ),
+        ]);
+        assert_eq!(
+            (
+                422,
+                json!({ "errors": {
+                    "email": ["is invalid"],
+                    "password": ["is too short", "must contain at least one digit"]
+                } })
+            ),
+            response(err).await
+        );
+    }
+
+    #[actix_web::test]
+    async fn too_many_requests_uses_body_envelope() {
+        let err = AppError::TooManyRequests("Try again later.".into());
+        assert_eq!((429, body("Try again later.")), response(err).await);
+    }
+
+    #[actix_web::test]
+    async fn internal_server_error_uses_body_envelope() {
+        assert_eq!(
+            (500, body("Internal Server Error")),
+            response(AppError::Internal).await
+        );
+    }
+
+    #[actix_web::test]
+    async fn service_unavailable_uses_body_envelope() {
+        let err = AppError::ServiceUnavailable("Database is busy, try again later.".into());
+        assert_eq!(
+            (503, body("Database is busy, try again later.")),
+            response(err).await
+        );
     }
 }
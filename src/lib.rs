@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate diesel;
+
+#[macro_use]
+extern crate log;
+
+pub mod app;
+pub mod config;
+pub mod constants;
+pub mod error;
+pub mod schema;
+pub mod utils;
@@ -1,38 +1,39 @@
-#[macro_use]
-extern crate diesel;
-
-#[macro_use]
-extern crate log;
-
-use actix_web::middleware::Logger;
+use actix_web::middleware::{Compress, Condition};
 use actix_web::{App, HttpServer};
-mod app;
-mod constants;
-mod error;
-mod schema;
-mod utils;
+use conduit::{app, config, utils};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     println!("start conduit server...");
-    std::env::set_var("RUST_LOG", "actix_web=trace");
-    env_logger::init();
+    dotenv::dotenv().ok();
+    utils::logger::init();
+    let config = config::Config::from_env().unwrap_or_else(|err| panic!("Invalid config: {}", err));
 
     let state = {
-        let pool = utils::db::establish_connection();
+        let pool = utils::db::establish_connection(&config.database);
+        let replica = utils::db::establish_replica_connection(&config.database);
         use app::drivers::middlewares::state::AppState;
-        AppState::new(pool)
+        AppState::new(config.clone(), pool, replica)
     };
+    let rate_limiter = utils::rate_limiter::RateLimiter::from_config(&config.rate_limit);
+    let compression = config.compression;
 
     HttpServer::new(move || {
         App::new()
-            .wrap(Logger::default())
             .app_data(actix_web::web::Data::new(state.clone()))
-            .wrap(app::drivers::middlewares::cors::cors())
+            .app_data(actix_web::web::Data::new(rate_limiter.clone()))
+            .wrap(app::drivers::middlewares::cors::cors(&state.config.cors))
             .wrap(app::drivers::middlewares::auth::Authentication)
+            .wrap(app::drivers::middlewares::access_log::AccessLog)
+            .wrap(app::drivers::middlewares::request_id::RequestIdentifier)
+            .wrap(Condition::new(
+                compression,
+                app::drivers::middlewares::compress::CompressionThreshold,
+            ))
+            .wrap(Condition::new(compression, Compress::default()))
             .configure(app::drivers::routes::api)
     })
-    .bind(constants::BIND)?
+    .bind(config.bind)?
     .run()
     .await
 }
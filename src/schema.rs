@@ -10,6 +10,13 @@ diesel::table! {
         body -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        published -> Bool,
+        favorites_count -> Int4,
+        version -> Int4,
+        archived -> Bool,
+        hidden -> Bool,
+        view_count -> Int4,
     }
 }
 
@@ -21,6 +28,18 @@ diesel::table! {
         body -> Text,
         create_at -> Timestamp,
         updated_at -> Timestamp,
+        parent_id -> Nullable<Uuid>,
+        hidden -> Bool,
+    }
+}
+
+diesel::table! {
+    email_verifications (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        token -> Uuid,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -43,6 +62,48 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    idempotency_keys (user_id, key) {
+        user_id -> Uuid,
+        key -> Text,
+        article_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    password_resets (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        token -> Uuid,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reports (id) {
+        id -> Uuid,
+        reporter_id -> Uuid,
+        article_id -> Nullable<Uuid>,
+        comment_id -> Nullable<Uuid>,
+        reason -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    revoked_tokens (jti) {
+        jti -> Uuid,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     tags (id) {
         id -> Uuid,
@@ -63,21 +124,47 @@ diesel::table! {
         image -> Nullable<Text>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        email_verified -> Bool,
+        role -> Text,
+        last_login_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Uuid,
+        url -> Text,
+        secret -> Text,
+        created_at -> Timestamp,
     }
 }
 
 diesel::joinable!(articles -> users (author_id));
 diesel::joinable!(comments -> articles (article_id));
 diesel::joinable!(comments -> users (author_id));
+diesel::joinable!(email_verifications -> users (user_id));
 diesel::joinable!(favorites -> articles (article_id));
 diesel::joinable!(favorites -> users (user_id));
+diesel::joinable!(idempotency_keys -> articles (article_id));
+diesel::joinable!(idempotency_keys -> users (user_id));
+diesel::joinable!(password_resets -> users (user_id));
+diesel::joinable!(reports -> articles (article_id));
+diesel::joinable!(reports -> comments (comment_id));
+diesel::joinable!(reports -> users (reporter_id));
+diesel::joinable!(revoked_tokens -> users (user_id));
 diesel::joinable!(tags -> articles (article_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     articles,
     comments,
+    email_verifications,
     favorites,
     follows,
+    idempotency_keys,
+    password_resets,
+    reports,
+    revoked_tokens,
     tags,
     users,
+    webhooks,
 );
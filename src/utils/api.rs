@@ -1,4 +1,176 @@
 use crate::error::AppError;
-use actix_web::HttpResponse;
+use actix_web::http::header::{HeaderValue, ETAG, IF_NONE_MATCH, LINK};
+use actix_web::{HttpRequest, HttpResponse};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 pub type ApiResponse = Result<HttpResponse, AppError>;
+
+const PAGE_PARAMS: [&str; 2] = ["limit", "offset"];
+
+// Absolute URL of the current request without its `limit` / `offset` parameters.
+pub fn page_url(req: &HttpRequest) -> String {
+    let conn = req.connection_info();
+    let query = req
+        .query_string()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !PAGE_PARAMS.contains(&key)
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    let url = format!("{}://{}{}", conn.scheme(), conn.host(), req.path());
+    if query.is_empty() {
+        url
+    } else {
+        format!("{}?{}", url, query)
+    }
+}
+
+// SPEC: https://datatracker.ietf.org/doc/html/rfc5988
+pub fn link_header(url: &str, limit: i64, offset: i64, total: i64) -> Option<String> {
+    if limit <= 0 {
+        return None;
+    }
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let link = |offset: i64, rel: &str| {
+        format!(
+            "<{}{}limit={}&offset={}>; rel=\"{}\"",
+            url, separator, limit, offset, rel
+        )
+    };
+    let last_offset = (total - 1).max(0) / limit * limit;
+    let mut links = vec![link(0, "first")];
+    if offset > 0 {
+        links.push(link((offset - limit).clamp(0, last_offset), "prev"));
+    }
+    if offset + limit < total {
+        links.push(link(offset + limit, "next"));
+    }
+    links.push(link(last_offset, "last"));
+    Some(links.join(", "))
+}
+
+pub fn set_link_header(res: &mut HttpResponse, url: &str, limit: i64, offset: i64, total: i64) {
+    let value =
+        link_header(url, limit, offset, total).and_then(|links| HeaderValue::from_str(&links).ok());
+    if let Some(value) = value {
+        res.headers_mut().insert(LINK, value);
+    }
+}
+
+// SPEC: https://datatracker.ietf.org/doc/html/rfc7232#section-2.3
+// NOTE: weak, because it stands for the resource's state rather than the exact response bytes.
+pub fn weak_etag<T: Hash>(state: &T) -> String {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+pub fn if_none_match(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+// NOTE: `If-None-Match` uses the weak comparison, so `W/` prefixes are ignored on both sides.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    if_none_match.trim() == "*"
+        || if_none_match
+            .split(',')
+            .any(|candidate| opaque(candidate) == opaque(etag))
+}
+
+// Answers 304 when the client already holds `etag`, otherwise tags `res` with it.
+pub fn conditional(if_none_match: Option<&str>, etag: &str, res: HttpResponse) -> HttpResponse {
+    let Ok(value) = HeaderValue::from_str(etag) else {
+        return res;
+    };
+    if if_none_match.is_some_and(|header| etag_matches(header, etag)) {
+        return HttpResponse::NotModified()
+            .insert_header((ETAG, value))
+            .finish();
+    }
+    let mut res = res;
+    res.headers_mut().insert(ETAG, value);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    const URL: &str = "http://localhost:8080/api/articles";
+
+    #[test]
+    fn link_header_first_page_has_no_prev() {
+        assert_eq!(
+            Some(
+                "<http://localhost:8080/api/articles?limit=20&offset=0>; rel=\"first\", \
+<http://localhost:8080/api/articles?limit=20&offset=20>; rel=\"next\", \
+<http://localhost:8080/api/articles?limit=20&offset=40>; rel=\"last\""
+                    .to_owned()
+            ),
+            link_header(URL, 20, 0, 45)
+        );
+    }
+
+    #[test]
+    fn link_header_last_page_has_no_next() {
+        let links = link_header(URL, 20, 40, 45).unwrap();
+        assert!(links.contains("offset=20>; rel=\"prev\""));
+        assert!(links.contains("offset=40>; rel=\"last\""));
+        assert!(!links.contains("rel=\"next\""));
+    }
+
+    #[test]
+    fn link_header_middle_page_has_prev_and_next() {
+        let links = link_header("http://localhost:8080/api/articles?tag=rust", 10, 10, 45).unwrap();
+        assert!(links.contains(
+            "<http://localhost:8080/api/articles?tag=rust&limit=10&offset=0>; rel=\"first\""
+        ));
+        assert!(links.contains("offset=0>; rel=\"prev\""));
+        assert!(links.contains("offset=20>; rel=\"next\""));
+        assert!(links.contains("offset=40>; rel=\"last\""));
+    }
+
+    #[test]
+    fn link_header_without_limit_is_none() {
+        assert_eq!(None, link_header(URL, 0, 0, 45));
+    }
+
+    #[test]
+    fn page_url_drops_page_params() {
+        let req = TestRequest::get()
+            .uri("/api/articles?limit=10&tag=rust&offset=20")
+            .insert_header(("host", "localhost:8080"))
+            .to_http_request();
+        assert_eq!(
+            "http://localhost:8080/api/articles?tag=rust",
+            page_url(&req)
+        );
+    }
+
+    #[test]
+    fn weak_etag_follows_state() {
+        let etag = weak_etag(&("dragon", 1));
+        assert!(etag.starts_with("W/\""));
+        assert_eq!(etag, weak_etag(&("dragon", 1)));
+        assert_ne!(etag, weak_etag(&("dragon", 2)));
+    }
+
+    #[test]
+    fn etag_matches_weakly_and_in_lists() {
+        let etag = "W/\"abc\"";
+        assert!(etag_matches("W/\"abc\"", etag));
+        assert!(etag_matches("\"abc\"", etag));
+        assert!(etag_matches("\"xyz\", W/\"abc\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("W/\"xyz\"", etag));
+    }
+}
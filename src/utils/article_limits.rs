@@ -0,0 +1,114 @@
+use crate::config::{self, ConfigError, Lookup};
+use crate::constants::env_key;
+use crate::error::AppError;
+
+static DEFAULT_MAX_TITLE_LENGTH: usize = 255;
+// NOTE: keeps a single article from dominating a list page or the search index.
+static DEFAULT_MAX_BODY_LENGTH: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArticleLimits {
+    pub max_title_length: usize,
+    pub max_body_length: usize,
+}
+
+impl Default for ArticleLimits {
+    fn default() -> Self {
+        Self {
+            max_title_length: DEFAULT_MAX_TITLE_LENGTH,
+            max_body_length: DEFAULT_MAX_BODY_LENGTH,
+        }
+    }
+}
+
+impl ArticleLimits {
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let default = Self::default();
+        let max_title_length = config::parsed::<usize>(
+            lookup,
+            env_key::ARTICLE_MAX_TITLE_LENGTH,
+            "a positive number of characters",
+            |length| *length > 0,
+        )?;
+        let max_body_length = config::parsed::<usize>(
+            lookup,
+            env_key::ARTICLE_MAX_BODY_LENGTH,
+            "a positive number of characters",
+            |length| *length > 0,
+        )?;
+        Ok(Self {
+            max_title_length: max_title_length.unwrap_or(default.max_title_length),
+            max_body_length: max_body_length.unwrap_or(default.max_body_length),
+        })
+    }
+
+    // NOTE: lengths count characters, not bytes; `None` is a field the update leaves alone.
+    pub fn check(&self, title: Option<&str>, body: Option<&str>) -> Result<(), AppError> {
+        let mut errors = vec![];
+        for (field, value, max) in [
+            ("title", title, self.max_title_length),
+            ("body", body, self.max_body_length),
+        ] {
+            if value.is_some_and(|value| value.chars().count() > max) {
+                errors.push((
+                    field.to_owned(),
+                    format!("is too long (maximum is {} characters)", max),
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::UnprocessableEntity(errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn errors(result: Result<(), AppError>) -> serde_json::Value {
+        match result {
+            Err(err @ AppError::UnprocessableEntity(_)) => err.body()["errors"].clone(),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_values_at_the_limit() {
+        let limits = ArticleLimits::default();
+        let title = "t".repeat(255);
+        let body = "b".repeat(100_000);
+        assert!(limits.check(Some(&title), Some(&body)).is_ok());
+        assert!(limits.check(None, None).is_ok());
+    }
+
+    #[test]
+    fn rejects_values_over_the_limit() {
+        let limits = ArticleLimits::default();
+        let title = "t".repeat(256);
+        let body = "b".repeat(100_001);
+        assert_eq!(
+            json!({
+                "title": ["is too long (maximum is 255 characters)"],
+                "body": ["is too long (maximum is 100000 characters)"]
+            }),
+            errors(limits.check(Some(&title), Some(&body)))
+        );
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        let limits = ArticleLimits {
+            max_title_length: 3,
+            max_body_length: 3,
+        };
+        assert!(limits.check(Some("龍龍龍"), None).is_ok());
+        assert_eq!(
+            json!({"body": ["is too long (maximum is 3 characters)"]}),
+            errors(limits.check(Some("龍龍龍"), Some("龍龍龍龍")))
+        );
+    }
+}
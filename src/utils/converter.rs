@@ -1,7 +1,10 @@
-use convert_case::{Case, Casing};
-
-pub fn to_kebab(text: &str) -> String {
-    text.to_case(Case::Kebab)
+// NOTE: escapes LIKE wildcards so user input only ever matches literally.
+pub fn to_like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
 }
 
 #[cfg(test)]
@@ -9,7 +12,12 @@ mod tests {
     use super::*;
 
     #[test]
-    fn str_to_kebab() {
-        assert_eq!("this-is-blog-title", to_kebab("this is blog title"));
+    fn like_pattern_wraps_text() {
+        assert_eq!("%rust web%", to_like_pattern("rust web"));
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(r"%100\%\_off\\%", to_like_pattern(r"100%_off\"));
     }
 }
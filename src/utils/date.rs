@@ -1,11 +1,54 @@
-use serde::{Deserialize, Serialize, Serializer};
+use chrono::NaiveDateTime;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, PartialEq, Deserialize)]
-pub struct Iso8601(pub chrono::NaiveDateTime);
+// REF: https://gothinkster.github.io/realworld/docs/specs/backend-specs/api-response-format (e.g. 2016-02-18T03:22:56.637Z)
+const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+// A UTC timestamp as the RealWorld API spells it: RFC 3339, milliseconds, `Z` suffix.
+// NOTE: the columns have no zone; sessions run in UTC (see `utils::db`), so the `Z` holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Iso8601(pub NaiveDateTime);
 
 impl Serialize for Iso8601 {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let s = self.0.format("%Y-%m-%dT%H:%M:%S.%3fZ");
-        serializer.serialize_str(&s.to_string())
+        serializer.serialize_str(&self.0.format(FORMAT).to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Iso8601 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        NaiveDateTime::parse_from_str(&s, FORMAT)
+            .map(Iso8601)
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(millis: u32) -> Iso8601 {
+        let date = NaiveDate::from_ymd_opt(2016, 2, 18).unwrap();
+        Iso8601(date.and_hms_milli_opt(3, 22, 56, millis).unwrap())
+    }
+
+    #[test]
+    fn serializes_like_the_spec() {
+        assert_eq!(
+            r#""2016-02-18T03:22:56.637Z""#,
+            serde_json::to_string(&at(637)).unwrap()
+        );
+        assert_eq!(
+            r#""2016-02-18T03:22:56.000Z""#,
+            serde_json::to_string(&at(0)).unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let json = serde_json::to_string(&at(637)).unwrap();
+        assert_eq!(at(637), serde_json::from_str::<Iso8601>(&json).unwrap());
     }
 }
@@ -1,18 +1,302 @@
+use crate::config::{self, ConfigError, DatabaseConfig, Lookup};
 use crate::constants::env_key;
+use crate::error::AppError;
+use crate::utils::slow_query::SlowQueryLog;
+use actix_web::web;
+use diesel::connection::SimpleConnection;
 use diesel::pg::PgConnection;
-use diesel::r2d2::{ConnectionManager, Pool, PoolError};
-use dotenv::dotenv;
-use std::env;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PoolError, PooledConnection};
+use diesel::Connection;
+use std::time::Duration;
+use tracing::{dispatcher, Span};
 
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+pub type DbConn = PooledConnection<ConnectionManager<PgConnection>>;
 
-fn init_pool(database_url: &str) -> Result<DbPool, PoolError> {
+static DEFAULT_POOL_MAX_SIZE: u32 = 10;
+static DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 30;
+static DEFAULT_SLOW_QUERY_MS: u64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolConfig {
+    pub max_size: u32,
+    // NOTE: `None` keeps r2d2's default of holding `max_size` idle connections.
+    pub min_idle: Option<u32>,
+    pub connection_timeout: Duration,
+    // NOTE: `None` (`SLOW_QUERY_MS=0`) turns the slow query log off.
+    pub slow_query_threshold: Option<Duration>,
+}
+
+impl PoolConfig {
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        // NOTE: r2d2 panics on a zero-sized pool, so zero falls back to the default.
+        let max_size = lookup(env_key::DATABASE_POOL_MAX_SIZE)
+            .and_then(|size| size.parse::<u32>().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(DEFAULT_POOL_MAX_SIZE);
+        let min_idle = lookup(env_key::DATABASE_POOL_MIN_IDLE)
+            .and_then(|idle| idle.parse::<u32>().ok())
+            .map(|idle| idle.min(max_size));
+        let timeout_secs = lookup(env_key::DATABASE_CONNECTION_TIMEOUT_SECS)
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_CONNECTION_TIMEOUT_SECS);
+        let slow_query_ms = config::parsed::<u64>(
+            lookup,
+            env_key::SLOW_QUERY_MS,
+            "a number of milliseconds",
+            |_| true,
+        )?
+        .unwrap_or(DEFAULT_SLOW_QUERY_MS);
+        Ok(Self {
+            max_size,
+            min_idle,
+            connection_timeout: Duration::from_secs(timeout_secs),
+            slow_query_threshold: (slow_query_ms > 0).then(|| Duration::from_millis(slow_query_ms)),
+        })
+    }
+}
+
+// NOTE: the timestamp columns carry no zone, so `CURRENT_TIMESTAMP` defaults follow the session's;
+// pinning it to UTC keeps them in line with `Utc::now()` and the `Z` the API puts on them.
+#[derive(Debug, Clone, Copy)]
+struct SessionSetup {
+    slow_query_threshold: Option<Duration>,
+}
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for SessionSetup {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        if let Some(threshold) = self.slow_query_threshold {
+            conn.set_instrumentation(SlowQueryLog::new(threshold));
+        }
+        conn.batch_execute("SET TIME ZONE 'UTC'")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+fn builder(config: &PoolConfig) -> diesel::r2d2::Builder<ConnectionManager<PgConnection>> {
+    Pool::builder()
+        .max_size(config.max_size)
+        .min_idle(config.min_idle)
+        .connection_timeout(config.connection_timeout)
+        .connection_customizer(Box::new(SessionSetup {
+            slow_query_threshold: config.slow_query_threshold,
+        }))
+}
+
+fn init_pool(database_url: &str, config: &PoolConfig) -> Result<DbPool, PoolError> {
     let manager = ConnectionManager::<PgConnection>::new(database_url);
-    Pool::builder().build(manager)
+    builder(config).build(manager)
+}
+
+pub fn establish_connection(config: &DatabaseConfig) -> DbPool {
+    init_pool(&config.url, &config.pool).expect("Failed to create pool")
+}
+
+pub fn establish_replica_connection(config: &DatabaseConfig) -> Option<DbPool> {
+    let replica_url = config.replica_url.as_ref()?;
+    let pool = init_pool(replica_url, &config.pool).expect("Failed to create replica pool");
+    Some(pool)
 }
 
-pub fn establish_connection() -> DbPool {
-    dotenv().ok();
-    let database_url = env::var(env_key::DATABASE_URL).expect("DATABASE_URL must be set");
-    init_pool(&database_url).expect("Failed to create pool")
+// The primary pool plus an optional read replica.
+#[derive(Clone)]
+pub struct DbPools {
+    primary: DbPool,
+    replica: Option<DbPool>,
+}
+
+impl DbPools {
+    pub fn new(primary: DbPool, replica: Option<DbPool>) -> Self {
+        Self { primary, replica }
+    }
+
+    pub fn primary(&self) -> &DbPool {
+        &self.primary
+    }
+
+    fn read_pool(&self) -> &DbPool {
+        self.replica.as_ref().unwrap_or(&self.primary)
+    }
+
+    // NOTE: a replica can lag behind, so reads that must see a write just made use `write_conn`.
+    pub fn read_conn(&self) -> Result<DbConn, AppError> {
+        let conn = self.read_pool().get()?;
+        Ok(conn)
+    }
+
+    pub fn write_conn(&self) -> Result<DbConn, AppError> {
+        let conn = self.primary.get()?;
+        Ok(conn)
+    }
+}
+
+// Runs every statement of `f` in one transaction; any error rolls all of them back.
+pub fn with_transaction<T, F>(conn: &mut PgConnection, f: F) -> Result<T, AppError>
+where
+    F: FnOnce(&mut PgConnection) -> Result<T, AppError>,
+{
+    conn.transaction(f)
+}
+
+// NOTE: diesel is synchronous, so queries run on the blocking pool instead of stalling an actix worker.
+pub async fn blocking<F, T>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    // NOTE: the blocking thread has no span of its own, so repository spans nest under the caller's.
+    let span = Span::current();
+    let dispatch = dispatcher::get_default(|dispatch| dispatch.clone());
+    let f = move || dispatcher::with_default(&dispatch, || span.in_scope(f));
+    web::block(f).await.map_err(|err| {
+        error!("Blocking task failed {}", err);
+        AppError::Internal
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::rt::time::sleep;
+    use std::thread;
+    use std::time::Instant;
+
+    #[actix_web::test]
+    async fn blocking_keeps_worker_responsive_during_slow_query() {
+        let started_at = Instant::now();
+        let slow_query = blocking(|| {
+            thread::sleep(Duration::from_millis(300));
+            Ok(42)
+        });
+        let other_request = async {
+            sleep(Duration::from_millis(10)).await;
+            started_at.elapsed()
+        };
+        let (result, answered_after) = futures::join!(slow_query, other_request);
+        assert_eq!(42, result.unwrap());
+        assert!(answered_after < Duration::from_millis(200));
+    }
+
+    #[test]
+    fn pool_timeout_is_service_unavailable() {
+        let config = PoolConfig {
+            max_size: 1,
+            min_idle: Some(0),
+            connection_timeout: Duration::from_millis(100),
+            slow_query_threshold: None,
+        };
+        // NOTE: nothing listens on port 1, so no connection can ever be handed out.
+        let manager = ConnectionManager::<PgConnection>::new("postgres://localhost:1/conduit");
+        let pool = builder(&config).build_unchecked(manager);
+        let err = pool.get().err().map(AppError::from);
+        assert!(matches!(err, Some(AppError::ServiceUnavailable(_))));
+    }
+
+    // NOTE: pools are told apart by size; `build_unchecked` never connects.
+    fn pool(max_size: u32) -> DbPool {
+        let manager = ConnectionManager::<PgConnection>::new("postgres://localhost:1/conduit");
+        Pool::builder().max_size(max_size).build_unchecked(manager)
+    }
+
+    #[test]
+    fn read_pool_falls_back_to_primary() {
+        let pools = DbPools::new(pool(1), None);
+        assert_eq!(1, pools.read_pool().max_size());
+    }
+
+    #[test]
+    fn read_pool_prefers_replica_and_writes_stay_on_primary() {
+        let pools = DbPools::new(pool(1), Some(pool(2)));
+        assert_eq!(2, pools.read_pool().max_size());
+        assert_eq!(1, pools.primary().max_size());
+    }
+
+    #[test]
+    fn pool_config_ignores_zero_sizes() {
+        let config = PoolConfig::load(&|key| match key {
+            env_key::DATABASE_POOL_MAX_SIZE | env_key::DATABASE_CONNECTION_TIMEOUT_SECS => {
+                Some("0".to_owned())
+            }
+            env_key::DATABASE_POOL_MIN_IDLE => Some("50".to_owned()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(
+            PoolConfig {
+                max_size: DEFAULT_POOL_MAX_SIZE,
+                min_idle: Some(DEFAULT_POOL_MAX_SIZE),
+                connection_timeout: Duration::from_secs(DEFAULT_CONNECTION_TIMEOUT_SECS),
+                slow_query_threshold: Some(Duration::from_millis(DEFAULT_SLOW_QUERY_MS)),
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn zero_slow_query_threshold_disables_the_log() {
+        let load = |millis: &'static str| {
+            PoolConfig::load(&move |key| (key == env_key::SLOW_QUERY_MS).then(|| millis.to_owned()))
+        };
+        assert_eq!(None, load("0").unwrap().slow_query_threshold);
+        assert_eq!(
+            Some(Duration::from_millis(50)),
+            load("50").unwrap().slow_query_threshold
+        );
+        assert!(load("fast").is_err());
+    }
+
+    #[test]
+    #[ignore = "needs a database at DATABASE_URL"]
+    fn failed_transaction_persists_nothing() {
+        use crate::app::features::article::entities::{Article, CreateArticle};
+        use crate::app::features::user::entities::{SignupUser, User};
+        use crate::schema::{articles, users};
+        use diesel::prelude::*;
+
+        dotenv::dotenv().ok();
+        let config = DatabaseConfig::from_env().unwrap();
+        let mut conn = establish_connection(&config).get().unwrap();
+        conn.begin_test_transaction().unwrap();
+        let result = with_transaction::<(), _>(&mut conn, |conn| {
+            let user = diesel::insert_into(users::table)
+                .values(SignupUser {
+                    email: "rollback@example.com",
+                    username: "rollback",
+                    password: "not-a-hash",
+                })
+                .get_result::<User>(conn)?;
+            Article::create(
+                conn,
+                &CreateArticle {
+                    author_id: user.id,
+                    slug: "rollback-slug".to_owned(),
+                    title: "Rollback".to_owned(),
+                    description: "Rollback".to_owned(),
+                    body: "Rollback".to_owned(),
+                    published: true,
+                },
+            )?;
+            // NOTE: fails after both statements succeeded, so both must be undone.
+            Err(AppError::Internal)
+        });
+        assert!(matches!(result, Err(AppError::Internal)));
+        let users = users::table
+            .filter(users::username.eq("rollback"))
+            .count()
+            .get_result::<i64>(&mut conn)
+            .unwrap();
+        let articles = articles::table
+            .filter(articles::slug.eq("rollback-slug"))
+            .count()
+            .get_result::<i64>(&mut conn)
+            .unwrap();
+        assert_eq!((0, 0), (users, articles));
+    }
+
+    #[actix_web::test]
+    async fn blocking_passes_errors_through() {
+        let result = blocking(|| Err::<(), _>(AppError::Internal)).await;
+        assert!(matches!(result, Err(AppError::Internal)));
+    }
 }
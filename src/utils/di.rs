@@ -1,24 +1,44 @@
+use crate::app::features::activity::feed::ActivityFeed;
 use crate::app::features::article::presenters::ArticlePresenterImpl;
 use crate::app::features::article::repositories::ArticleRepositoryImpl;
 use crate::app::features::article::usecases::ArticleUsecase;
+use crate::app::features::avatar::presenters::AvatarPresenterImpl;
+use crate::app::features::avatar::repositories::AvatarRepositoryImpl;
+use crate::app::features::avatar::usecases::AvatarUsecase;
+use crate::app::features::comment::feed::CommentFeed;
 use crate::app::features::comment::presenters::CommentPresenterImpl;
 use crate::app::features::comment::repositories::CommentRepositoryImpl;
 use crate::app::features::comment::usecases::CommentUsecase;
+use crate::app::features::email_verification::presenters::EmailVerificationPresenterImpl;
+use crate::app::features::email_verification::repositories::EmailVerificationRepositoryImpl;
+use crate::app::features::email_verification::usecases::EmailVerificationUsecase;
 use crate::app::features::favorite::presenters::FavoritePresenterImpl;
 use crate::app::features::favorite::repositories::FavoriteRepositoryImpl;
 use crate::app::features::favorite::usecases::FavoriteUsecase;
+use crate::app::features::password_reset::presenters::PasswordResetPresenterImpl;
+use crate::app::features::password_reset::repositories::PasswordResetRepositoryImpl;
+use crate::app::features::password_reset::usecases::PasswordResetUsecase;
 use crate::app::features::profile::presenters::ProfilePresenterImpl;
 use crate::app::features::profile::repositories::ProfileRepositoryImpl;
 use crate::app::features::profile::usecases::ProfileUsecase;
+use crate::app::features::report::presenters::ReportPresenterImpl;
+use crate::app::features::report::repositories::ReportRepositoryImpl;
+use crate::app::features::report::usecases::ReportUsecase;
 use crate::app::features::tag::presenters::TagPresenterImpl;
 use crate::app::features::tag::repositories::TagRepositoryImpl;
 use crate::app::features::tag::usecases::TagUsecase;
+use crate::app::features::token_blacklist::repositories::TokenBlacklistRepositoryImpl;
 use crate::app::features::user::presenters::UserPresenterImpl;
 use crate::app::features::user::repositories::UserRepositoryImpl;
 use crate::app::features::user::usecases::UserUsecase;
+use crate::app::features::webhook::dispatcher::{RetryPolicy, WebhookDispatcherImpl};
+use crate::app::features::webhook::repositories::WebhookRepositoryImpl;
+use crate::utils::http_client::HttpClient;
+use crate::utils::login_throttle::LoginThrottle;
 use std::sync::Arc;
 
-use crate::utils::db::DbPool;
+use crate::config::Config;
+use crate::utils::db::DbPools;
 
 #[derive(Clone)]
 pub struct DiContainer {
@@ -63,44 +83,124 @@ pub struct DiContainer {
     pub comment_repository: CommentRepositoryImpl,
     pub comment_presenter: CommentPresenterImpl,
     pub comment_usecase: CommentUsecase,
+
+    /**
+     * TokenBlacklist
+     */
+    pub token_blacklist_repository: TokenBlacklistRepositoryImpl,
+
+    /**
+     * PasswordReset
+     */
+    pub password_reset_repository: PasswordResetRepositoryImpl,
+    pub password_reset_presenter: PasswordResetPresenterImpl,
+    pub password_reset_usecase: PasswordResetUsecase,
+
+    /**
+     * EmailVerification
+     */
+    pub email_verification_repository: EmailVerificationRepositoryImpl,
+    pub email_verification_presenter: EmailVerificationPresenterImpl,
+    pub email_verification_usecase: EmailVerificationUsecase,
+
+    /**
+     * Avatar
+     */
+    pub avatar_repository: AvatarRepositoryImpl,
+    pub avatar_presenter: AvatarPresenterImpl,
+    pub avatar_usecase: AvatarUsecase,
+
+    /**
+     * Report
+     */
+    pub report_repository: ReportRepositoryImpl,
+    pub report_presenter: ReportPresenterImpl,
+    pub report_usecase: ReportUsecase,
+
+    /**
+     * Webhook
+     */
+    pub webhook_repository: WebhookRepositoryImpl,
+    pub webhook_dispatcher: WebhookDispatcherImpl,
+
+    /**
+     * Activity
+     */
+    pub activity_feed: ActivityFeed,
 }
 
 impl DiContainer {
-    pub fn new(pool: &DbPool) -> Self {
+    pub fn new(config: &Config, pools: &DbPools) -> Self {
+        let pool = pools.primary();
+
         // Repository
-        let user_repository = UserRepositoryImpl::new(pool.clone());
-        let profile_repository = ProfileRepositoryImpl::new(pool.clone());
-        let favorite_repository = FavoriteRepositoryImpl::new(pool.clone());
-        let article_repository = ArticleRepositoryImpl::new(pool.clone());
-        let tag_repository = TagRepositoryImpl::new(pool.clone());
-        let comment_repository = CommentRepositoryImpl::new(pool.clone());
+        let user_repository =
+            UserRepositoryImpl::new(pool.clone(), config.password.hash, config.jwt.clone());
+        let profile_repository = ProfileRepositoryImpl::new(pools.clone());
+        let favorite_repository = FavoriteRepositoryImpl::new(pools.clone());
+        let article_repository = ArticleRepositoryImpl::new(pools.clone());
+        let tag_repository = TagRepositoryImpl::new(pools.clone());
+        let comment_repository = CommentRepositoryImpl::new(pools.clone());
+        let token_blacklist_repository =
+            TokenBlacklistRepositoryImpl::new(pool.clone(), config.jwt.refresh_grace_seconds);
+        let password_reset_repository =
+            PasswordResetRepositoryImpl::new(pool.clone(), config.password.hash);
+        let email_verification_repository = EmailVerificationRepositoryImpl::new(pool.clone());
+        let avatar_repository = AvatarRepositoryImpl::new(config.avatar_dir.clone());
+        let report_repository = ReportRepositoryImpl::new(pools.clone());
+        let webhook_repository = WebhookRepositoryImpl::new(pools.clone());
+
+        // Dispatcher
+        let webhook_dispatcher = WebhookDispatcherImpl::new(
+            Arc::new(webhook_repository.clone()),
+            HttpClient::new(config.webhook_targets),
+            RetryPolicy::default(),
+        );
+
+        // Feed
+        let activity_feed = ActivityFeed::new();
 
         // Presenter
-        let user_presenter = UserPresenterImpl::new();
+        let user_presenter = UserPresenterImpl::new(config.secure_cookies, config.jwt.ttl_seconds);
         let profile_presenter = ProfilePresenterImpl::new();
         let favorite_presenter = FavoritePresenterImpl::new();
         let article_presenter = ArticlePresenterImpl::new();
         let tag_presenter = TagPresenterImpl::new();
         let comment_presenter = CommentPresenterImpl::new();
+        let password_reset_presenter = PasswordResetPresenterImpl::new();
+        let email_verification_presenter = EmailVerificationPresenterImpl::new();
+        let avatar_presenter = AvatarPresenterImpl::new();
+        let report_presenter = ReportPresenterImpl::new();
 
         // Usecase
         let user_usecase = UserUsecase::new(
             Arc::new(user_repository.clone()),
+            Arc::new(token_blacklist_repository.clone()),
             Arc::new(user_presenter.clone()),
+            LoginThrottle::from_config(&config.login_throttle),
+            config.jwt.clone(),
+            config.password.clone(),
         );
         let profile_usecase = ProfileUsecase::new(
             Arc::new(profile_repository.clone()),
             Arc::new(user_repository.clone()),
             Arc::new(profile_presenter.clone()),
+            activity_feed.clone(),
         );
         let favorite_usecase = FavoriteUsecase::new(
             Arc::new(favorite_repository.clone()),
             Arc::new(favorite_presenter.clone()),
             Arc::new(article_repository.clone()),
+            activity_feed.clone(),
         );
         let article_usecase = ArticleUsecase::new(
             Arc::new(article_repository.clone()),
             Arc::new(article_presenter.clone()),
+            Arc::new(favorite_repository.clone()),
+            Arc::new(profile_repository.clone()),
+            Arc::new(webhook_dispatcher.clone()),
+            activity_feed.clone(),
+            config.article.clone(),
         );
         let tag_usecase = TagUsecase::new(
             Arc::new(tag_repository.clone()),
@@ -109,6 +209,27 @@ impl DiContainer {
         let comment_usecase = CommentUsecase::new(
             Arc::new(comment_repository.clone()),
             Arc::new(comment_presenter.clone()),
+            Arc::new(profile_repository.clone()),
+            CommentFeed::new(),
+        );
+        let password_reset_usecase = PasswordResetUsecase::new(
+            Arc::new(password_reset_repository.clone()),
+            Arc::new(password_reset_presenter.clone()),
+            config.password.policy.clone(),
+        );
+        let email_verification_usecase = EmailVerificationUsecase::new(
+            Arc::new(email_verification_repository.clone()),
+            Arc::new(email_verification_presenter.clone()),
+        );
+        let avatar_usecase = AvatarUsecase::new(
+            Arc::new(avatar_repository.clone()),
+            Arc::new(user_repository.clone()),
+            Arc::new(avatar_presenter.clone()),
+            Arc::new(user_presenter.clone()),
+        );
+        let report_usecase = ReportUsecase::new(
+            Arc::new(report_repository.clone()),
+            Arc::new(report_presenter.clone()),
         );
 
         Self {
@@ -141,6 +262,36 @@ impl DiContainer {
             comment_repository,
             comment_presenter,
             comment_usecase,
+
+            // TokenBlacklist
+            token_blacklist_repository,
+
+            // PasswordReset
+            password_reset_repository,
+            password_reset_presenter,
+            password_reset_usecase,
+
+            // EmailVerification
+            email_verification_repository,
+            email_verification_presenter,
+            email_verification_usecase,
+
+            // Avatar
+            avatar_repository,
+            avatar_presenter,
+            avatar_usecase,
+
+            // Report
+            report_repository,
+            report_presenter,
+            report_usecase,
+
+            // Webhook
+            webhook_repository,
+            webhook_dispatcher,
+
+            // Activity
+            activity_feed,
         }
     }
 }
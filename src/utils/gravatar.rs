@@ -0,0 +1,51 @@
+use ring::digest::{digest, SHA256};
+use std::fmt::Write;
+
+const GRAVATAR_URL: &str = "https://www.gravatar.com/avatar";
+
+// NOTE: gravatar accepts SHA-256 of the trimmed, lowercased email; `d=identicon` for unknown ones.
+pub fn url(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+    let hash = digest(&SHA256, email.as_bytes()).as_ref().iter().fold(
+        String::with_capacity(64),
+        |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        },
+    );
+    format!("{}/{}?d=identicon", GRAVATAR_URL, hash)
+}
+
+// NOTE: the stored image stays `None`; only responses fall back to the gravatar.
+pub fn image_or_default(image: Option<String>, email: &str) -> String {
+    image.unwrap_or_else(|| url(email))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_hashes_normalized_email() {
+        assert_eq!(
+            "https://www.gravatar.com/avatar/973dfe463ec85785f5f95af5ba3906eedb2d931c24e69824a89ea65dba4e813b?d=identicon",
+            url("test@example.com")
+        );
+        assert_eq!(url("test@example.com"), url("  Test@Example.COM "));
+    }
+
+    #[test]
+    fn explicit_image_overrides_default() {
+        assert_eq!(
+            "https://example.com/me.png",
+            image_or_default(
+                Some("https://example.com/me.png".to_owned()),
+                "test@example.com"
+            )
+        );
+        assert_eq!(
+            url("test@example.com"),
+            image_or_default(None, "test@example.com")
+        );
+    }
+}
@@ -1,6 +1,192 @@
-pub use bcrypt::verify;
-use bcrypt::{hash, BcryptResult, DEFAULT_COST};
+use crate::config::{self, ConfigError, Lookup};
+use crate::constants::env_key;
+use crate::error::AppError;
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version};
+use std::sync::OnceLock;
 
-pub fn hash_password(naive_pw: &str) -> BcryptResult<String> {
-    hash(naive_pw, DEFAULT_COST)
+// NOTE: bcrypt hashes ($2a$, $2b$, $2y$) created before the argon2id migration.
+const LEGACY_BCRYPT_PREFIX: &str = "$2";
+
+// NOTE: the cost is the argon2id iteration count; below 2 passes hashes get cheap to brute-force.
+pub const MIN_HASH_COST: u32 = 2;
+pub const MAX_HASH_COST: u32 = 10;
+const DEFAULT_HASH_COST: u32 = Params::DEFAULT_T_COST;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashConfig {
+    pub memory_kib: u32,
+    pub cost: u32,
+    pub parallelism: u32,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            cost: DEFAULT_HASH_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl HashConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::load(&config::lookup_env)
+    }
+
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let default = Self::default();
+        let cost = config::parsed::<u32>(
+            lookup,
+            env_key::PASSWORD_HASH_COST,
+            "an integer between 2 and 10",
+            |cost| (MIN_HASH_COST..=MAX_HASH_COST).contains(cost),
+        )?;
+        let memory_kib = config::parsed::<u32>(
+            lookup,
+            env_key::ARGON2_MEMORY_KIB,
+            "a number of KiB",
+            |_| true,
+        )?;
+        let parallelism = config::parsed::<u32>(
+            lookup,
+            env_key::ARGON2_PARALLELISM,
+            "a positive number of lanes",
+            |lanes| *lanes > 0,
+        )?;
+        let config = Self {
+            memory_kib: memory_kib.unwrap_or(default.memory_kib),
+            cost: cost.unwrap_or(default.cost),
+            parallelism: parallelism.unwrap_or(default.parallelism),
+        };
+        // NOTE: argon2 needs at least 8 KiB of memory per lane.
+        config.params().map_err(|_err| ConfigError::Invalid {
+            key: env_key::ARGON2_MEMORY_KIB,
+            expected: "at least 8 KiB per lane of ARGON2_PARALLELISM",
+        })?;
+        Ok(config)
+    }
+
+    fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.memory_kib, self.cost, self.parallelism, None)
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, AppError> {
+        Ok(Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            self.params()?,
+        ))
+    }
+}
+
+pub fn hash_password(config: &HashConfig, naive_pw: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = config.argon2()?.hash_password(naive_pw.as_bytes(), &salt)?;
+    Ok(hash.to_string())
+}
+
+pub fn verify(naive_pw: &str, hash: &str) -> Result<bool, AppError> {
+    if is_legacy(hash) {
+        let is_valid = bcrypt::verify(naive_pw, hash)?;
+        return Ok(is_valid);
+    }
+    let parsed_hash = PasswordHash::new(hash)?;
+    // NOTE: cost params are read from the hash itself, so older params still verify.
+    let is_valid = Argon2::default()
+        .verify_password(naive_pw.as_bytes(), &parsed_hash)
+        .is_ok();
+    Ok(is_valid)
+}
+
+// Does the work of `verify` for a user that does not exist, so a miss takes as long as a wrong password.
+// NOTE: the stand-in hash is made once with the configured params, so it costs what real hashes do.
+pub fn verify_nothing(config: &HashConfig, naive_pw: &str) -> Result<(), AppError> {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = match DUMMY_HASH.get() {
+        Some(hash) => hash,
+        None => {
+            let hash = hash_password(config, "dummy password for unknown users")?;
+            DUMMY_HASH.get_or_init(|| hash)
+        }
+    };
+    verify(naive_pw, hash)?;
+    Ok(())
+}
+
+pub fn needs_rehash(hash: &str) -> bool {
+    is_legacy(hash)
+}
+
+fn is_legacy(hash: &str) -> bool {
+    hash.starts_with(LEGACY_BCRYPT_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_argon2_hash() {
+        let hash = hash_password(&HashConfig::default(), "password123").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify("password123", &hash).unwrap());
+        assert!(!verify("password124", &hash).unwrap());
+        assert!(!needs_rehash(&hash));
+    }
+
+    #[test]
+    fn verify_nothing_runs_argon2() {
+        assert!(verify_nothing(&HashConfig::default(), "password123").is_ok());
+        assert!(verify_nothing(&HashConfig::default(), "password123").is_ok());
+    }
+
+    fn load(vars: &[(&str, &str)]) -> Result<HashConfig, ConfigError> {
+        HashConfig::load(&|key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn hash_cost_defaults_when_unset() {
+        assert_eq!(DEFAULT_HASH_COST, load(&[]).unwrap().cost);
+        assert_eq!(
+            4,
+            load(&[(env_key::PASSWORD_HASH_COST, " 4 ")]).unwrap().cost
+        );
+    }
+
+    #[test]
+    fn hash_cost_out_of_range_is_rejected() {
+        for cost in ["1", "11", "cheap"] {
+            assert!(load(&[(env_key::PASSWORD_HASH_COST, cost)]).is_err());
+        }
+    }
+
+    #[test]
+    fn argon2_params_are_checked_at_load() {
+        let err = load(&[
+            (env_key::ARGON2_MEMORY_KIB, "8"),
+            (env_key::ARGON2_PARALLELISM, "4"),
+        ])
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                key: env_key::ARGON2_MEMORY_KIB,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn verify_legacy_bcrypt_hash() {
+        let hash = bcrypt::hash("password123", 4).unwrap();
+        assert!(verify("password123", &hash).unwrap());
+        assert!(!verify("password124", &hash).unwrap());
+        assert!(needs_rehash(&hash));
+    }
 }
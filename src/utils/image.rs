@@ -0,0 +1,137 @@
+use crate::error::AppError;
+
+pub const MAX_IMAGE_BYTES: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl ImageKind {
+    // NOTE: trusts the file's magic bytes, never the client-declared type alone.
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if data.starts_with(b"\xff\xd8\xff") {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+            Some(Self::Webp)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        match mime.to_ascii_lowercase().as_str() {
+            "image/png" => Some(Self::Png),
+            "image/jpeg" | "image/jpg" => Some(Self::Jpeg),
+            "image/gif" => Some(Self::Gif),
+            "image/webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "png" => Some(Self::Png),
+            "jpg" => Some(Self::Jpeg),
+            "gif" => Some(Self::Gif),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+pub fn invalid(reason: &str) -> AppError {
+    AppError::invalid("image", reason)
+}
+
+pub fn validate(content_type: Option<&str>, data: &[u8]) -> Result<ImageKind, AppError> {
+    if data.is_empty() {
+        return Err(invalid("can't be blank"));
+    }
+    if data.len() > MAX_IMAGE_BYTES {
+        return Err(invalid("is too large (maximum is 2MB)"));
+    }
+    let declared = content_type.and_then(ImageKind::from_content_type);
+    match (declared, ImageKind::sniff(data)) {
+        (Some(declared), Some(sniffed)) if declared == sniffed => Ok(sniffed),
+        _ => Err(invalid("must be a PNG, JPEG, GIF or WebP image")),
+    }
+}
+
+#[cfg(test)]
+pub(crate) const TINY_PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00\x00\x01\x00\x00\x00\x01\x08\x06\x00\x00\x00\x1f\x15\xc4\x89\x00\x00\x00\rIDATx\x9cc\x00\x01\x00\x00\x05\x00\x01\r\n-\xb4\x00\x00\x00\x00IEND\xaeB`\x82";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_accepts_png() {
+        assert_eq!(
+            ImageKind::Png,
+            validate(Some("image/png"), TINY_PNG).unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_rejects_mismatched_content_type() {
+        assert!(matches!(
+            validate(Some("image/jpeg"), TINY_PNG),
+            Err(AppError::UnprocessableEntity(_))
+        ));
+        assert!(matches!(
+            validate(
+                Some("image/png"),
+                b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"
+            ),
+            Err(AppError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_oversized_image() {
+        let mut data = TINY_PNG.to_vec();
+        data.resize(MAX_IMAGE_BYTES + 1, 0);
+        assert!(matches!(
+            validate(Some("image/png"), &data),
+            Err(AppError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn sniff_known_signatures() {
+        assert_eq!(Some(ImageKind::Jpeg), ImageKind::sniff(b"\xff\xd8\xff\xe0"));
+        assert_eq!(Some(ImageKind::Gif), ImageKind::sniff(b"GIF89a\x01\x00"));
+        assert_eq!(
+            Some(ImageKind::Webp),
+            ImageKind::sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 ")
+        );
+        assert_eq!(None, ImageKind::sniff(b"%PDF-1.7"));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::constants::env_key;
+use chrono::Utc;
+use serde_json::json;
+use std::env;
+use std::io::Write;
+
+// NOTE: access log lines are already JSON objects, so the json format writes them as is.
+pub const ACCESS_LOG_TARGET: &str = "conduit::access";
+
+static DEFAULT_FILTER: &str = "actix_web=info,conduit=info";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Json,
+    Pretty,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Self {
+        match env::var(env_key::LOG_FORMAT).as_deref() {
+            Ok("json") => Self::Json,
+            _ => Self::Pretty,
+        }
+    }
+}
+
+// NOTE: with no tracing subscriber installed, spans are bridged to `log` and land here too;
+// `RUST_LOG=conduit=debug` shows usecase and repository spans with their slug / user id fields.
+pub fn init() {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(DEFAULT_FILTER));
+    if LogFormat::from_env() == LogFormat::Json {
+        builder.format(|buf, record| {
+            if record.target() == ACCESS_LOG_TARGET {
+                return writeln!(buf, "{}", record.args());
+            }
+            let line = json!({
+                "time": Utc::now().to_rfc3339(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    }
+    builder.init();
+}
@@ -0,0 +1,217 @@
+use crate::config::{self, ConfigError, Lookup};
+use crate::constants::env_key;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+static DEFAULT_MAX_FAILURES: u32 = 5;
+static DEFAULT_LOCKOUT_SECONDS: u64 = 60 * 15;
+
+// NOTE: failures older than the lockout are only swept once this many emails are tracked, and
+// at most once per `SWEEP_INTERVAL`, so a busy throttle does not walk the whole map on every signin.
+const SWEEP_AT: usize = 10_000;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+// NOTE: emails are chosen by the caller, so past this, failures for new emails are not tracked
+// until a sweep makes room; the per-client rate limit still bounds how fast anyone can guess.
+const MAX_TRACKED_EMAILS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginThrottleConfig {
+    pub max_failures: u32,
+    pub lockout: Duration,
+}
+
+impl LoginThrottleConfig {
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let max_failures = config::parsed::<u32>(
+            lookup,
+            env_key::LOGIN_MAX_FAILED_ATTEMPTS,
+            "a positive number of attempts",
+            |max| *max > 0,
+        )?;
+        let lockout_seconds = config::parsed::<u64>(
+            lookup,
+            env_key::LOGIN_LOCKOUT_SECONDS,
+            "a number of seconds",
+            |_| true,
+        )?;
+        Ok(Self {
+            max_failures: max_failures.unwrap_or(DEFAULT_MAX_FAILURES),
+            lockout: Duration::from_secs(lockout_seconds.unwrap_or(DEFAULT_LOCKOUT_SECONDS)),
+        })
+    }
+}
+
+type FailureCount = u32;
+type LastFailedAt = Instant;
+
+// Blocks signin for an email after too many consecutive failures.
+#[derive(Clone)]
+pub struct LoginThrottle {
+    failures: Arc<DashMap<String, (FailureCount, LastFailedAt)>>,
+    max_failures: u32,
+    lockout: Duration,
+    last_sweep: Arc<Mutex<Option<Instant>>>,
+}
+
+impl LoginThrottle {
+    pub fn new(max_failures: u32, lockout: Duration) -> Self {
+        Self {
+            failures: Arc::new(DashMap::new()),
+            max_failures,
+            lockout,
+            last_sweep: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn from_config(config: &LoginThrottleConfig) -> Self {
+        Self::new(config.max_failures, config.lockout)
+    }
+
+    pub fn is_blocked(&self, email: &str) -> bool {
+        let key = Self::key(email);
+        let (count, last_failed_at) = match self.failures.get(&key) {
+            Some(entry) => *entry,
+            None => return false,
+        };
+        if count < self.max_failures {
+            return false;
+        }
+        if last_failed_at.elapsed() < self.lockout {
+            return true;
+        }
+        // NOTE: lockout is over, so give the email a fresh set of attempts.
+        self.failures.remove(&key);
+        false
+    }
+
+    pub fn record_failure(&self, email: &str) {
+        self.sweep();
+        // NOTE: read before taking the entry, which holds its shard locked.
+        let full = self.failures.len() >= MAX_TRACKED_EMAILS;
+        match self.failures.entry(Self::key(email)) {
+            Entry::Occupied(mut entry) => {
+                let (count, last_failed_at) = *entry.get();
+                // NOTE: failures older than the lockout no longer count, as if they had been swept.
+                let count = if last_failed_at.elapsed() < self.lockout {
+                    count
+                } else {
+                    0
+                };
+                entry.insert((count + 1, Instant::now()));
+            }
+            Entry::Vacant(_) if full => {}
+            Entry::Vacant(entry) => {
+                entry.insert((1, Instant::now()));
+            }
+        }
+    }
+
+    pub fn reset(&self, email: &str) {
+        self.failures.remove(&Self::key(email));
+    }
+
+    fn sweep(&self) {
+        if self.failures.len() < SWEEP_AT {
+            return;
+        }
+        // NOTE: whoever holds the lock is already sweeping, so everyone else moves on.
+        let Ok(mut last_sweep) = self.last_sweep.try_lock() else {
+            return;
+        };
+        if last_sweep.is_some_and(|swept_at| swept_at.elapsed() < SWEEP_INTERVAL) {
+            return;
+        }
+        *last_sweep = Some(Instant::now());
+        self.failures
+            .retain(|_, (_, last_failed_at)| last_failed_at.elapsed() < self.lockout);
+    }
+
+    fn key(email: &str) -> String {
+        email.trim().to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn blocks_after_max_failures() {
+        let throttle = LoginThrottle::new(3, Duration::from_secs(60));
+        for _ in 0..2 {
+            throttle.record_failure("a@a.a");
+        }
+        assert!(!throttle.is_blocked("a@a.a"));
+        throttle.record_failure("a@a.a");
+        assert!(throttle.is_blocked("a@a.a"));
+        assert!(throttle.is_blocked("A@A.A"));
+        assert!(!throttle.is_blocked("b@b.b"));
+    }
+
+    #[test]
+    fn reset_clears_failures() {
+        let throttle = LoginThrottle::new(2, Duration::from_secs(60));
+        throttle.record_failure("a@a.a");
+        throttle.reset("a@a.a");
+        throttle.record_failure("a@a.a");
+        assert!(!throttle.is_blocked("a@a.a"));
+    }
+
+    #[test]
+    fn unblocks_after_lockout() {
+        let throttle = LoginThrottle::new(1, Duration::from_millis(10));
+        throttle.record_failure("a@a.a");
+        assert!(throttle.is_blocked("a@a.a"));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!throttle.is_blocked("a@a.a"));
+        throttle.record_failure("a@a.a");
+        assert!(throttle.is_blocked("a@a.a"));
+    }
+
+    #[test]
+    fn stale_failures_do_not_add_up() {
+        let throttle = LoginThrottle::new(2, Duration::from_millis(10));
+        throttle.record_failure("a@a.a");
+        std::thread::sleep(Duration::from_millis(20));
+        throttle.record_failure("a@a.a");
+        assert!(!throttle.is_blocked("a@a.a"));
+    }
+
+    fn fail(throttle: &LoginThrottle, count: usize) {
+        for _ in 0..count {
+            throttle
+                .failures
+                .insert(format!("{}@a.a", Uuid::new_v4()), (1, Instant::now()));
+        }
+    }
+
+    #[test]
+    fn stale_failures_are_swept_at_most_once_per_interval() {
+        let throttle = LoginThrottle::new(5, Duration::from_millis(1));
+        fail(&throttle, SWEEP_AT);
+        std::thread::sleep(Duration::from_millis(5));
+        throttle.record_failure("a@a.a");
+        assert_eq!(1, throttle.failures.len());
+
+        fail(&throttle, SWEEP_AT);
+        std::thread::sleep(Duration::from_millis(5));
+        throttle.record_failure("b@b.b");
+        assert_eq!(SWEEP_AT + 2, throttle.failures.len());
+    }
+
+    #[test]
+    fn new_emails_are_not_tracked_once_the_throttle_is_full() {
+        let throttle = LoginThrottle::new(1, Duration::from_secs(60));
+        throttle.record_failure("a@a.a");
+        fail(&throttle, MAX_TRACKED_EMAILS - 1);
+
+        throttle.record_failure("b@b.b");
+        assert!(!throttle.is_blocked("b@b.b"));
+        assert_eq!(MAX_TRACKED_EMAILS, throttle.failures.len());
+        throttle.record_failure("a@a.a");
+        assert!(throttle.is_blocked("a@a.a"));
+    }
+}
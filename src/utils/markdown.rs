@@ -0,0 +1,148 @@
+// Markdown to HTML for article bodies, rendered by pulldown-cmark (CommonMark plus tables and
+// strikethrough) and sanitized by ammonia.
+// NOTE: raw HTML in a body (e.g. `<script>`) is turned into text before rendering, so it shows up
+// escaped instead of being dropped, and an HTML block becomes a plain paragraph; ammonia then strips
+// anything else unsafe, and links only keep `SAFE_SCHEMES`. The output can be embedded as is.
+use ammonia::Builder;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+const SAFE_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+// NOTE: pulldown-cmark and ammonia both walk nesting without recursion, so a body of 10k `>` or
+// `[` renders without touching the stack limit (see `deeply_nested_input_renders`).
+pub fn to_html(markdown: &str) -> String {
+    let events = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    )
+    .scan(false, |in_block, event| {
+        Some(match event {
+            Event::Start(Tag::HtmlBlock) => {
+                *in_block = false;
+                [Some(Event::Start(Tag::Paragraph)), None]
+            }
+            Event::End(TagEnd::HtmlBlock) => [Some(Event::End(TagEnd::Paragraph)), None],
+            // NOTE: an HTML block arrives one line at a time, each with its own trailing newline.
+            Event::Html(line) => {
+                let text = CowStr::from(line.trim_end_matches('\n').to_owned());
+                let separator = std::mem::replace(in_block, true).then_some(Event::SoftBreak);
+                [separator, Some(Event::Text(text))]
+            }
+            Event::InlineHtml(raw) => [Some(Event::Text(raw)), None],
+            event => [Some(event), None],
+        })
+    })
+    .flatten()
+    .flatten();
+    let mut unsafe_html = String::with_capacity(markdown.len() * 5 / 4);
+    html::push_html(&mut unsafe_html, events);
+    sanitizer().clean(&unsafe_html).to_string()
+}
+
+fn sanitizer() -> &'static Builder<'static> {
+    static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+    SANITIZER.get_or_init(|| {
+        let mut builder = Builder::default();
+        builder
+            .url_schemes(HashSet::from(SAFE_SCHEMES))
+            .link_rel(Some("nofollow"));
+        builder
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_blocks() {
+        let markdown = "# Dragons\n\nThey *fly*\nand **breathe** fire.\n\n- one\n- two\n\n1. first\n\n> quoted\n\n```\nlet x = 1 < 2;\n```\n\n---";
+        assert_eq!(
+            "<h1>Dragons</h1>\n\
+             <p>They <em>fly</em>\nand <strong>breathe</strong> fire.</p>\n\
+             <ul>\n<li>one</li>\n<li>two</li>\n</ul>\n\
+             <ol>\n<li>first</li>\n</ol>\n\
+             <blockquote>\n<p>quoted</p>\n</blockquote>\n\
+             <pre><code>let x = 1 &lt; 2;\n</code></pre>\n\
+             <hr>\n",
+            to_html(markdown)
+        );
+    }
+
+    #[test]
+    fn script_tags_render_as_text() {
+        let html = to_html("Hello <script>alert('pwned')</script> world");
+        assert_eq!(
+            "<p>Hello &lt;script&gt;alert('pwned')&lt;/script&gt; world</p>\n",
+            html
+        );
+        assert_eq!(
+            "<p>&lt;img src=x onerror=alert(1)&gt;\n`&lt;script&gt;`</p>\n",
+            to_html("<img src=x onerror=alert(1)>\n`<script>`")
+        );
+        assert_eq!(
+            "<p><code>&lt;script&gt;</code> and &lt;b onclick=x&gt;bold&lt;/b&gt;</p>\n",
+            to_html("`<script>` and <b onclick=x>bold</b>")
+        );
+        assert_eq!(
+            "<h2>&lt;script&gt;</h2>\n<pre><code>&lt;/code&gt;&lt;script&gt;\n</code></pre>\n",
+            to_html("## <script>\n```\n</code><script>\n```")
+        );
+    }
+
+    #[test]
+    fn links_keep_safe_schemes_only() {
+        assert_eq!(
+            "<p><a href=\"https://example.com/Rust_(language)\" rel=\"nofollow\">Rust</a></p>\n",
+            to_html("[Rust](https://example.com/Rust_(language))")
+        );
+        assert_eq!(
+            "<p><a href=\"/articles?tag=a&amp;b\" rel=\"nofollow\">tag</a></p>\n",
+            to_html("[tag](/articles?tag=a&b)")
+        );
+        for url in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            "data:text/html,x",
+        ] {
+            assert_eq!(
+                "<p><a rel=\"nofollow\">click</a></p>\n",
+                to_html(&format!("[click]({})", url))
+            );
+        }
+        assert_eq!(
+            "<p><a href=\"https://example.com/%22onmouseover=%22x\" rel=\"nofollow\">q</a></p>\n",
+            to_html("[q](<https://example.com/\"onmouseover=\"x>)")
+        );
+    }
+
+    #[test]
+    fn underscores_inside_words_are_not_emphasis() {
+        assert_eq!(
+            "<p>snake_case_name and <em>this</em></p>\n",
+            to_html("snake_case_name and _this_")
+        );
+    }
+
+    // NOTE: run on a small stack so a recursive renderer would overflow here long before it
+    // would in production.
+    #[test]
+    fn deeply_nested_input_renders() {
+        let render = |markdown: String| {
+            std::thread::Builder::new()
+                .stack_size(256 * 1024)
+                .spawn(move || to_html(&markdown))
+                .unwrap()
+                .join()
+                .expect("rendering overflowed the stack")
+        };
+        let quotes = render(">".repeat(10_000) + " deep");
+        assert!(quotes.contains("deep"));
+        assert!(quotes.starts_with("<blockquote>"));
+        for markdown in ["[".repeat(50_000), "*".repeat(50_000), "*a ".repeat(20_000)] {
+            render(markdown);
+        }
+    }
+}
@@ -1,8 +1,24 @@
 pub mod api;
+pub mod article_limits;
 pub mod converter;
 pub mod date;
 pub mod db;
 pub mod di;
+pub mod gravatar;
 pub mod hasher;
+pub mod http_client;
+pub mod image;
+pub mod logger;
+pub mod login_throttle;
+pub mod markdown;
+pub mod multipart;
+pub mod pagination;
+pub mod password_policy;
+pub mod rate_limiter;
+pub mod slow_query;
+pub mod slug;
+pub mod sse;
 pub mod token;
 pub mod uuid;
+pub mod validation;
+pub mod websocket;
@@ -0,0 +1,134 @@
+// Minimal `multipart/form-data` reader for small, fully buffered request bodies.
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Part<'a> {
+    pub name: Option<&'a str>,
+    pub file_name: Option<&'a str>,
+    pub content_type: Option<&'a str>,
+    pub data: &'a [u8],
+}
+
+pub fn boundary(content_type: &str) -> Option<&str> {
+    let (mime, params) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim_matches('"'))
+        .filter(|value| !value.is_empty())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn header_param<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    header
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value.trim_matches('"'))
+}
+
+fn parse_part(section: &[u8]) -> Option<Part<'_>> {
+    let section = section.strip_prefix(b"\r\n")?;
+    let header_end = find(section, b"\r\n\r\n")?;
+    let headers = std::str::from_utf8(&section[..header_end]).ok()?;
+    let data = &section[header_end + 4..];
+    let data = data.strip_suffix(b"\r\n").unwrap_or(data);
+
+    let mut part = Part {
+        name: None,
+        file_name: None,
+        content_type: None,
+        data,
+    };
+    for line in headers.split("\r\n") {
+        let (key, value) = match line.split_once(':') {
+            Some(header) => header,
+            None => continue,
+        };
+        if key.trim().eq_ignore_ascii_case("content-disposition") {
+            part.name = header_param(value, "name");
+            part.file_name = header_param(value, "filename");
+        } else if key.trim().eq_ignore_ascii_case("content-type") {
+            part.content_type = Some(value.trim());
+        }
+    }
+    Some(part)
+}
+
+// NOTE: malformed sections are skipped rather than failing the whole body.
+pub fn parse<'a>(body: &'a [u8], boundary: &str) -> Vec<Part<'a>> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+    let mut parts = vec![];
+    let mut rest = match find(body, delimiter) {
+        Some(start) => &body[start + delimiter.len()..],
+        None => return parts,
+    };
+    while !rest.starts_with(b"--") {
+        let end = match find(rest, delimiter) {
+            Some(end) => end,
+            None => break,
+        };
+        if let Some(part) = parse_part(&rest[..end]) {
+            parts.push(part);
+        }
+        rest = &rest[end + delimiter.len()..];
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundary_from_content_type() {
+        assert_eq!(Some("XyZ"), boundary("multipart/form-data; boundary=XyZ"));
+        assert_eq!(
+            Some("a b"),
+            boundary(r#"multipart/form-data; charset=utf-8; boundary="a b""#)
+        );
+        assert_eq!(None, boundary("application/json"));
+        assert_eq!(None, boundary("multipart/form-data"));
+    }
+
+    #[test]
+    fn parse_reads_named_parts() {
+        let body = b"--XyZ\r\n\
+Content-Disposition: form-data; name=\"caption\"\r\n\
+\r\n\
+hello\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"image\"; filename=\"me.png\"\r\n\
+Content-Type: image/png\r\n\
+\r\n\
+\x89PNG\r\n\
+--XyZ--\r\n";
+        let parts = parse(body, "XyZ");
+        assert_eq!(2, parts.len());
+        assert_eq!(Some("caption"), parts[0].name);
+        assert_eq!(b"hello", parts[0].data);
+        assert_eq!(
+            Part {
+                name: Some("image"),
+                file_name: Some("me.png"),
+                content_type: Some("image/png"),
+                data: b"\x89PNG",
+            },
+            parts[1]
+        );
+    }
+
+    #[test]
+    fn parse_without_boundary_is_empty() {
+        assert!(parse(b"just some bytes", "XyZ").is_empty());
+    }
+}
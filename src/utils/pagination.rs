@@ -0,0 +1,128 @@
+use chrono::{DateTime, NaiveDateTime};
+use std::fmt;
+use uuid::Uuid;
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Pagination {
+    // NOTE: out-of-range values are clamped instead of rejected.
+    pub fn from_query(limit: Option<i64>, offset: Option<i64>) -> Self {
+        Self {
+            limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(0, MAX_LIMIT),
+            offset: offset.unwrap_or(0).max(0),
+        }
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self::from_query(None, None)
+    }
+}
+
+// Keyset position after a row ordered by `(created_at, id)`; clients treat it as opaque.
+// NOTE: microseconds, since that is what postgres keeps and `createdAt` is only rendered in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (micros, id) = value.split_once('_')?;
+        let created_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc();
+        let id = Uuid::parse_str(id).ok()?;
+        Some(Self { created_at, id })
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}_{}",
+            self.created_at.and_utc().timestamp_micros(),
+            self.id
+        )
+    }
+}
+
+// One page of a list together with the size of the whole list.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
+
+impl<T> Page<T> {
+    pub fn empty() -> Self {
+        Self {
+            items: vec![],
+            total: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
+        let pagination = Pagination::from_query(limit, offset);
+        (pagination.limit, pagination.offset)
+    }
+
+    #[test]
+    fn from_query_defaults() {
+        assert_eq!((DEFAULT_LIMIT, 0), page(None, None));
+        assert_eq!(Pagination::from_query(None, None), Pagination::default());
+    }
+
+    #[test]
+    fn from_query_clamps_limit() {
+        assert_eq!((MAX_LIMIT, 0), page(Some(1000), None));
+        assert_eq!((10, 240), page(Some(10), Some(240)));
+    }
+
+    #[test]
+    fn from_query_rejects_negative_values() {
+        assert_eq!((0, 0), page(Some(-1), None));
+        assert_eq!((10, 0), page(Some(10), Some(-5)));
+    }
+
+    #[test]
+    fn cursor_round_trips_with_microseconds() {
+        let created_at = chrono::NaiveDate::from_ymd_opt(2016, 2, 18)
+            .unwrap()
+            .and_hms_micro_opt(3, 22, 56, 637_123)
+            .unwrap();
+        let cursor = Cursor {
+            created_at,
+            id: Uuid::nil(),
+        };
+        assert_eq!(
+            "1455765776637123_00000000-0000-0000-0000-000000000000",
+            cursor.to_string()
+        );
+        assert_eq!(Some(cursor), Cursor::parse(&cursor.to_string()));
+    }
+
+    #[test]
+    fn cursor_rejects_malformed_values() {
+        assert_eq!(None, Cursor::parse(""));
+        assert_eq!(None, Cursor::parse("1455765776637123"));
+        assert_eq!(
+            None,
+            Cursor::parse("yesterday_00000000-0000-0000-0000-000000000000")
+        );
+        assert_eq!(None, Cursor::parse("1455765776637123_jake"));
+    }
+}
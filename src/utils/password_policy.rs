@@ -0,0 +1,170 @@
+use crate::config::{self, ConfigError, Lookup};
+use crate::constants::env_key;
+use crate::error::AppError;
+
+static DEFAULT_MIN_LENGTH: usize = 8;
+
+const COMMON_PASSWORDS: [&str; 20] = [
+    "password",
+    "password1",
+    "password123",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "qwerty123",
+    "qwertyuiop",
+    "iloveyou1",
+    "abc12345",
+    "letmein1",
+    "welcome1",
+    "admin123",
+    "monkey123",
+    "dragon123",
+    "sunshine1",
+    "football1",
+    "baseball1",
+    "trustno1",
+    "passw0rd",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_letter: bool,
+    pub require_digit: bool,
+    pub reject_common: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: DEFAULT_MIN_LENGTH,
+            require_letter: true,
+            require_digit: true,
+            reject_common: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let default = Self::default();
+        let min_length = config::parsed::<usize>(
+            lookup,
+            env_key::PASSWORD_MIN_LENGTH,
+            "a number of characters",
+            |_| true,
+        )?;
+        Ok(Self {
+            min_length: min_length.unwrap_or(default.min_length),
+            require_letter: config::flag(
+                lookup,
+                env_key::PASSWORD_REQUIRE_LETTER,
+                default.require_letter,
+            )?,
+            require_digit: config::flag(
+                lookup,
+                env_key::PASSWORD_REQUIRE_DIGIT,
+                default.require_digit,
+            )?,
+            reject_common: config::flag(
+                lookup,
+                env_key::PASSWORD_REJECT_COMMON,
+                default.reject_common,
+            )?,
+        })
+    }
+
+    pub fn check(&self, password: &str) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        if password.chars().count() < self.min_length {
+            errors.push(format!(
+                "is too short (minimum is {} characters)",
+                self.min_length
+            ));
+        }
+        if self.require_letter && !password.chars().any(|c| c.is_alphabetic()) {
+            errors.push("must contain at least one letter".to_owned());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            errors.push("must contain at least one digit".to_owned());
+        }
+        if self.reject_common && is_common(password) {
+            errors.push("is too common".to_owned());
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Same as `check`, reported as errors on the `password` field.
+    pub fn check_field(&self, password: &str) -> Result<(), AppError> {
+        self.check(password).map_err(|errors| {
+            AppError::UnprocessableEntity(
+                errors
+                    .into_iter()
+                    .map(|message| ("password".to_owned(), message))
+                    .collect(),
+            )
+        })
+    }
+}
+
+fn is_common(password: &str) -> bool {
+    let password = password.to_lowercase();
+    COMMON_PASSWORDS.iter().any(|common| *common == password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_strong_password() {
+        assert!(PasswordPolicy::default().check("correct horse 42").is_ok());
+    }
+
+    #[test]
+    fn rejects_short_password() {
+        let errors = PasswordPolicy::default().check("abc12").unwrap_err();
+        assert_eq!(vec!["is too short (minimum is 8 characters)"], errors);
+    }
+
+    #[test]
+    fn rejects_password_without_letter_or_digit() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            vec!["must contain at least one letter"],
+            policy.check("1234567890123").unwrap_err()
+        );
+        assert_eq!(
+            vec!["must contain at least one digit"],
+            policy.check("onlyletters").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn rejects_common_password() {
+        let errors = PasswordPolicy::default().check("Password123").unwrap_err();
+        assert_eq!(vec!["is too common"], errors);
+    }
+
+    #[test]
+    fn reports_every_failure() {
+        let errors = PasswordPolicy::default().check("").unwrap_err();
+        assert_eq!(3, errors.len());
+    }
+
+    #[test]
+    fn rules_can_be_relaxed() {
+        let policy = PasswordPolicy {
+            min_length: 1,
+            require_letter: false,
+            require_digit: false,
+            reject_common: false,
+        };
+        assert!(policy.check("a").is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+use crate::config::{self, ConfigError, Lookup};
+use crate::constants::env_key;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+static DEFAULT_BURST: u32 = 100;
+static DEFAULT_PER_SECOND: f64 = 10.0;
+// NOTE: once this many clients are tracked, idle buckets are swept at most once per
+// `SWEEP_INTERVAL`, and new clients are refused until a sweep makes room.
+static MAX_TRACKED_CLIENTS: usize = 10_000;
+static SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    pub burst: u32,
+    pub per_second: f64,
+    pub trust_forwarded_for: bool,
+}
+
+impl RateLimitConfig {
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let burst = config::parsed::<u32>(
+            lookup,
+            env_key::RATE_LIMIT_BURST,
+            "a positive number of requests",
+            |burst| *burst > 0,
+        )?;
+        let per_second = config::parsed::<f64>(
+            lookup,
+            env_key::RATE_LIMIT_PER_SECOND,
+            "a positive number of requests",
+            |rate| rate.is_finite() && *rate > 0.0,
+        )?;
+        Ok(Self {
+            burst: burst.unwrap_or(DEFAULT_BURST),
+            per_second: per_second.unwrap_or(DEFAULT_PER_SECOND),
+            trust_forwarded_for: config::flag(
+                lookup,
+                env_key::RATE_LIMIT_TRUST_FORWARDED_FOR,
+                false,
+            )?,
+        })
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+// Token bucket per client: `burst` requests at once, refilled at `per_second`.
+#[derive(Clone)]
+pub struct RateLimiter {
+    buckets: Arc<DashMap<String, Bucket>>,
+    burst: f64,
+    per_second: f64,
+    trust_forwarded_for: bool,
+    last_sweep: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, per_second: f64, trust_forwarded_for: bool) -> Self {
+        Self {
+            buckets: Arc::new(DashMap::new()),
+            burst: burst as f64,
+            per_second,
+            trust_forwarded_for,
+            last_sweep: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self::new(config.burst, config.per_second, config.trust_forwarded_for)
+    }
+
+    pub fn trust_forwarded_for(&self) -> bool {
+        self.trust_forwarded_for
+    }
+
+    // Takes one token for `client`, or returns how long until the next one is available.
+    pub fn acquire(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        self.sweep(now);
+        // NOTE: read before taking the entry, which holds its shard locked.
+        let full = self.buckets.len() >= MAX_TRACKED_CLIENTS;
+        let mut bucket = match self.buckets.entry(client.to_owned()) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            // NOTE: a client without a bucket would be unlimited, so refuse it instead.
+            Entry::Vacant(_) if full => return Err(SWEEP_INTERVAL),
+            Entry::Vacant(entry) => entry.insert(Bucket {
+                tokens: self.burst,
+                refilled_at: now,
+            }),
+        };
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.per_second).min(self.burst);
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.per_second,
+            ))
+        }
+    }
+
+    fn sweep(&self, now: Instant) {
+        if self.buckets.len() < MAX_TRACKED_CLIENTS {
+            return;
+        }
+        // NOTE: whoever holds the lock is already sweeping, so everyone else moves on.
+        let Ok(mut last_sweep) = self.last_sweep.try_lock() else {
+            return;
+        };
+        if last_sweep
+            .is_some_and(|swept_at| now.saturating_duration_since(swept_at) < SWEEP_INTERVAL)
+        {
+            return;
+        }
+        *last_sweep = Some(now);
+        self.purge_idle(now);
+    }
+
+    // NOTE: a bucket that would be full again by now is the same as no bucket at all.
+    fn purge_idle(&self, now: Instant) {
+        let (burst, per_second) = (self.burst, self.per_second);
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.refilled_at);
+            bucket.tokens + elapsed.as_secs_f64() * per_second < burst
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_burst_then_limits() {
+        let limiter = RateLimiter::new(3, 1.0, false);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.acquire("1.1.1.1", now).is_ok());
+        }
+        assert_eq!(Err(Duration::from_secs(1)), limiter.acquire("1.1.1.1", now));
+        assert!(limiter.acquire("2.2.2.2", now).is_ok());
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = RateLimiter::new(1, 2.0, false);
+        let now = Instant::now();
+        assert!(limiter.acquire("1.1.1.1", now).is_ok());
+        assert!(limiter.acquire("1.1.1.1", now).is_err());
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.acquire("1.1.1.1", later).is_ok());
+    }
+
+    #[test]
+    fn purge_idle_drops_full_buckets_only() {
+        let limiter = RateLimiter::new(2, 1.0, false);
+        let now = Instant::now();
+        limiter.acquire("idle", now).unwrap();
+        limiter
+            .acquire("busy", now + Duration::from_secs(5))
+            .unwrap();
+        limiter.purge_idle(now + Duration::from_secs(5));
+        assert!(!limiter.buckets.contains_key("idle"));
+        assert!(limiter.buckets.contains_key("busy"));
+    }
+
+    fn track_idle_clients(limiter: &RateLimiter, count: usize, now: Instant) {
+        for n in 0..count {
+            limiter.buckets.insert(
+                format!("10.0.{}.{}", n / 256, n % 256),
+                Bucket {
+                    tokens: 0.0,
+                    refilled_at: now,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn idle_buckets_are_swept_at_most_once_per_interval() {
+        let limiter = RateLimiter::new(1, 1.0, false);
+        let now = Instant::now();
+        track_idle_clients(&limiter, MAX_TRACKED_CLIENTS, now);
+        let later = now + Duration::from_secs(2);
+        assert!(limiter.acquire("1.1.1.1", later).is_ok());
+        assert_eq!(1, limiter.buckets.len());
+
+        track_idle_clients(&limiter, MAX_TRACKED_CLIENTS - 1, later);
+        let soon_after = later + Duration::from_secs(2);
+        assert!(limiter.acquire("1.1.1.1", soon_after).is_ok());
+        assert_eq!(MAX_TRACKED_CLIENTS, limiter.buckets.len());
+    }
+
+    #[test]
+    fn new_clients_are_refused_once_the_map_is_full() {
+        let limiter = RateLimiter::new(2, 1.0, false);
+        let now = Instant::now();
+        assert!(limiter.acquire("1.1.1.1", now).is_ok());
+        track_idle_clients(&limiter, MAX_TRACKED_CLIENTS - 1, now);
+        let later = now + Duration::from_millis(500);
+
+        assert_eq!(Err(SWEEP_INTERVAL), limiter.acquire("2.2.2.2", later));
+        assert!(limiter.acquire("1.1.1.1", later).is_ok());
+        assert_eq!(MAX_TRACKED_CLIENTS, limiter.buckets.len());
+    }
+}
@@ -0,0 +1,97 @@
+use diesel::connection::{DebugQuery, Instrumentation, InstrumentationEvent};
+use std::time::{Duration, Instant};
+
+// Times every query on a connection and warns about the ones reaching `threshold`.
+// NOTE: the warning is emitted inside the caller's span, so it nests under the repository span.
+pub struct SlowQueryLog {
+    threshold: Duration,
+    started_at: Option<Instant>,
+}
+
+impl SlowQueryLog {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            started_at: None,
+        }
+    }
+
+    fn start(&mut self, now: Instant) {
+        self.started_at = Some(now);
+    }
+
+    // Returns how long the query took if that reached the threshold.
+    fn finish(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.started_at.take()?);
+        (elapsed >= self.threshold).then_some(elapsed)
+    }
+}
+
+// NOTE: the SQL keeps its `$1` placeholders; bound values may be passwords or emails and are cut off.
+fn statement(query: &dyn DebugQuery) -> String {
+    let text = query.to_string();
+    match text.split_once(" -- binds:") {
+        Some((sql, _binds)) => sql.to_owned(),
+        None => text,
+    }
+}
+
+impl Instrumentation for SlowQueryLog {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartQuery { .. } => self.start(Instant::now()),
+            InstrumentationEvent::FinishQuery { query, .. } => {
+                if let Some(elapsed) = self.finish(Instant::now()) {
+                    tracing::warn!(
+                        elapsed_ms = elapsed.as_millis() as u64,
+                        "Slow query ({} ms): {}",
+                        elapsed.as_millis(),
+                        statement(query)
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::users;
+    use diesel::pg::Pg;
+    use diesel::prelude::*;
+    use std::thread;
+
+    #[test]
+    fn slow_operation_is_reported() {
+        let mut log = SlowQueryLog::new(Duration::from_millis(10));
+        log.start(Instant::now());
+        thread::sleep(Duration::from_millis(20));
+        let elapsed = log.finish(Instant::now());
+        assert!(elapsed.is_some_and(|elapsed| elapsed >= Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn fast_query_is_not_reported() {
+        let mut log = SlowQueryLog::new(Duration::from_millis(200));
+        let now = Instant::now();
+        log.start(now);
+        assert_eq!(None, log.finish(now + Duration::from_millis(5)));
+        // NOTE: a finish without a matching start has nothing to time.
+        assert_eq!(None, log.finish(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn statement_leaves_out_bound_values() {
+        let query = users::table
+            .filter(users::email.eq("jake@example.com"))
+            .select(users::id);
+        let debug = diesel::debug_query::<Pg, _>(&query);
+        let sql = statement(&debug);
+        assert_eq!(
+            r#"SELECT "users"."id" FROM "users" WHERE ("users"."email" = $1)"#,
+            sql
+        );
+    }
+}
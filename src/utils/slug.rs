@@ -0,0 +1,100 @@
+use crate::error::AppError;
+use crate::schema::articles;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::collections::HashSet;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+
+// NOTE: lowercase ASCII words joined by hyphens, e.g. "Crème Brûlée!" -> "creme-brulee".
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.nfkd() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if is_combining_mark(c) {
+            continue;
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        // NOTE: titles without any ASCII letters or digits still need an addressable slug.
+        Uuid::new_v4().to_string()
+    } else {
+        slug.to_owned()
+    }
+}
+
+fn pick_unique(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_owned();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|slug| !taken.contains(slug))
+        .expect("an unused suffix always exists")
+}
+
+pub fn generate(title: &str, conn: &mut PgConnection) -> Result<String, AppError> {
+    generate_except(title, conn, None)
+}
+
+// NOTE: `except` is the slug the article already owns, so retitling doesn't bump its own suffix.
+pub fn generate_except(
+    title: &str,
+    conn: &mut PgConnection,
+    except: Option<&str>,
+) -> Result<String, AppError> {
+    let base = slugify(title);
+    let taken = articles::table
+        .filter(
+            articles::slug
+                .eq(&base)
+                .or(articles::slug.like(format!("{}-%", base))),
+        )
+        .select(articles::slug)
+        .load::<String>(conn)?
+        .into_iter()
+        .filter(|slug| Some(slug.as_str()) != except)
+        .collect::<HashSet<_>>();
+    Ok(pick_unique(&base, &taken))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_ascii_title() {
+        assert_eq!("this-is-blog-title", slugify("this is blog title"));
+        assert_eq!("hello-world", slugify("  Hello,   World! "));
+    }
+
+    #[test]
+    fn slugify_unicode_title() {
+        assert_eq!("creme-brulee-recipe", slugify("Crème Brûlée Recipe"));
+        assert_eq!("uber-cafe", slugify("Über café"));
+    }
+
+    #[test]
+    fn slugify_falls_back_to_uuid() {
+        let slug = slugify("こんにちは");
+        assert!(Uuid::parse_str(&slug).is_ok());
+        assert!(Uuid::parse_str(&slugify("!!!")).is_ok());
+    }
+
+    #[test]
+    fn pick_unique_appends_suffix_on_collision() {
+        let taken = HashSet::new();
+        assert_eq!("hello-world", pick_unique("hello-world", &taken));
+
+        let taken = ["hello-world", "hello-world-2"]
+            .iter()
+            .map(|slug| slug.to_string())
+            .collect::<HashSet<_>>();
+        assert_eq!("hello-world-3", pick_unique("hello-world", &taken));
+    }
+}
@@ -0,0 +1,103 @@
+// Server-sent event responses: one message per item of a stream, plus periodic heartbeats.
+use actix_web::http::header::{CacheControl, CacheDirective, ContentEncoding, CONTENT_ENCODING};
+use actix_web::rt::time::{self, Instant};
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use futures::stream::{self, Stream, StreamExt};
+use std::convert::Infallible;
+use std::future::ready;
+use std::time::Duration;
+
+pub const CONTENT_TYPE: &str = "text/event-stream";
+
+// NOTE: a comment line; clients ignore it, but proxies see traffic and keep the connection open.
+const HEARTBEAT: &str = ": heartbeat\n\n";
+
+// NOTE: multi-line `data` is sent as several `data:` lines, which clients join back with `\n`.
+pub fn message(id: u64, event: &str, data: &str) -> String {
+    let mut message = format!("id: {}\nevent: {}\n", id, event);
+    for line in data.lines() {
+        message.push_str("data: ");
+        message.push_str(line);
+        message.push('\n');
+    }
+    message.push('\n');
+    message
+}
+
+// Streams every item of `messages` as is and a heartbeat whenever `heartbeat` passes. The response
+// ends with `messages`, which is dropped once the client disconnects.
+pub fn stream<S>(messages: S, heartbeat: Duration) -> HttpResponse
+where
+    S: Stream<Item = String> + 'static,
+{
+    let heartbeats = stream::unfold(
+        time::interval_at(Instant::now() + heartbeat, heartbeat),
+        |mut interval| async move {
+            interval.tick().await;
+            Some((HEARTBEAT.to_owned(), interval))
+        },
+    );
+    // NOTE: the trailing `None` marks the end of `messages`, since heartbeats never run out.
+    let messages = messages.map(Some).chain(stream::once(ready(None)));
+    let body = stream::select(messages, heartbeats.map(Some))
+        .take_while(|message| ready(message.is_some()))
+        .filter_map(ready)
+        .map(|message| Ok::<_, Infallible>(Bytes::from(message)));
+    HttpResponse::Ok()
+        .content_type(CONTENT_TYPE)
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        // NOTE: compressors buffer output, which would hold events back.
+        .insert_header((
+            CONTENT_ENCODING,
+            ContentEncoding::Identity.to_header_value(),
+        ))
+        // NOTE: likewise for nginx, which buffers proxied responses unless told otherwise.
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[test]
+    fn messages_carry_an_id_event_and_data() {
+        assert_eq!(
+            "id: 7\nevent: article\ndata: {\"slug\":\"dragons\"}\n\n",
+            message(7, "article", r#"{"slug":"dragons"}"#)
+        );
+        assert_eq!(
+            "id: 8\nevent: note\ndata: first\ndata: second\n\n",
+            message(8, "note", "first\nsecond")
+        );
+    }
+
+    #[actix_web::test]
+    async fn streams_messages_and_heartbeats_until_the_messages_end() {
+        let messages = stream::unfold(0, |sent| async move {
+            time::sleep(Duration::from_millis(30)).await;
+            (sent < 2).then(|| (message(sent, "tick", "{}"), sent + 1))
+        });
+        let res = stream(messages, Duration::from_millis(20));
+        assert_eq!(
+            CONTENT_TYPE,
+            res.headers().get("content-type").unwrap().to_str().unwrap()
+        );
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.starts_with(HEARTBEAT), "{}", body);
+        assert!(
+            body.contains("id: 0\nevent: tick\ndata: {}\n\n"),
+            "{}",
+            body
+        );
+        assert!(
+            body.contains("id: 1\nevent: tick\ndata: {}\n\n"),
+            "{}",
+            body
+        );
+    }
+}
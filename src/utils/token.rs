@@ -1,54 +1,278 @@
+use crate::config::{self, ConfigError, Lookup};
 use crate::constants::env_key;
+use crate::error::AppError;
 use jsonwebtoken::{errors::Error, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::fmt;
 use uuid::Uuid;
 
 static ONE_DAY: i64 = 60 * 60 * 24; // in seconds
+static ONE_HOUR: i64 = 60 * 60; // in seconds
 
-fn get_secret_key() -> String {
-    env::var(env_key::SECRET_KEY).expect("SECRET_KEY must be set")
+// NOTE: HS256 keys shorter than the hash output (32 bytes) are brute-forceable.
+pub const MIN_SECRET_KEY_BYTES: usize = 32;
+
+#[derive(Clone, PartialEq)]
+pub struct JwtConfig {
+    pub secret_key: String,
+    pub ttl_seconds: i64,
+    // NOTE: how long an expired token can still be exchanged for a new one.
+    pub refresh_grace_seconds: i64,
+    // NOTE: `iss` and `aud` are only emitted and validated when configured.
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
 }
 
-pub fn decode(token: &str) -> jsonwebtoken::errors::Result<TokenData<Claims>> {
-    let binding = get_secret_key();
-    let secret_key = binding.as_bytes();
-    jsonwebtoken::decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret_key),
-        &Validation::default(),
-    )
+impl fmt::Debug for JwtConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtConfig")
+            .field("secret_key", &"[redacted]")
+            .field("ttl_seconds", &self.ttl_seconds)
+            .field("refresh_grace_seconds", &self.refresh_grace_seconds)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .finish()
+    }
+}
+
+impl JwtConfig {
+    pub fn new(secret_key: String) -> Self {
+        Self {
+            secret_key,
+            ttl_seconds: ONE_DAY,
+            refresh_grace_seconds: ONE_HOUR,
+            issuer: None,
+            audience: None,
+        }
+    }
+
+    pub fn load(lookup: Lookup) -> Result<Self, ConfigError> {
+        let secret_key = config::required(lookup, env_key::SECRET_KEY)?;
+        if secret_key.len() < MIN_SECRET_KEY_BYTES {
+            return Err(ConfigError::Invalid {
+                key: env_key::SECRET_KEY,
+                expected: "at least 32 bytes",
+            });
+        }
+        let default = Self::new(secret_key);
+        let ttl_seconds = config::parsed::<i64>(
+            lookup,
+            env_key::JWT_TTL_SECONDS,
+            "a positive number of seconds",
+            |ttl| *ttl > 0,
+        )?;
+        let refresh_grace_seconds = config::parsed::<i64>(
+            lookup,
+            env_key::JWT_REFRESH_GRACE_SECONDS,
+            "a number of seconds",
+            |grace| *grace >= 0,
+        )?;
+        Ok(Self {
+            ttl_seconds: ttl_seconds.unwrap_or(default.ttl_seconds),
+            refresh_grace_seconds: refresh_grace_seconds.unwrap_or(default.refresh_grace_seconds),
+            issuer: config::optional(lookup, env_key::JWT_ISSUER),
+            audience: config::optional(lookup, env_key::JWT_AUDIENCE),
+            ..default
+        })
+    }
+
+    fn validation(&self) -> Validation {
+        validation_with(self.issuer.clone(), self.audience.clone())
+    }
 }
 
-pub fn generate(user_id: Uuid, now: i64) -> Result<String, Error> {
-    let claims = Claims::new(user_id, now);
-    let binding = get_secret_key();
-    let secret_key = binding.as_bytes();
+fn validation_with(issuer: Option<String>, audience: Option<String>) -> Validation {
+    let mut validation = Validation::default();
+    validation.leeway = 0; // NOTE: the default leeway (60s) would keep expired tokens alive.
+    let mut required_claims = vec!["exp"];
+    if let Some(issuer) = issuer {
+        validation.set_issuer(&[issuer]);
+        required_claims.push("iss");
+    }
+    if let Some(audience) = audience {
+        validation.set_audience(&[audience]);
+        required_claims.push("aud");
+    }
+    validation.set_required_spec_claims(&required_claims);
+    validation
+}
+
+fn decode_with(
+    config: &JwtConfig,
+    token: &str,
+    validation: &Validation,
+) -> Result<TokenData<Claims>, AppError> {
+    let secret_key = config.secret_key.as_bytes();
+    let token_data =
+        jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(secret_key), validation)?;
+    Ok(token_data)
+}
+
+pub fn verify(config: &JwtConfig, token: &str) -> Result<TokenData<Claims>, AppError> {
+    decode_with(config, token, &config.validation())
+}
+
+// NOTE: same as `verify` but still accepts tokens expired within the refresh grace period.
+// Refreshing returns the claims rather than a new token, since the caller must check the `jti`
+// against revoked tokens and that the user still exists before minting one (see
+// `UserUsecase::refresh_token`).
+pub fn verify_for_refresh(config: &JwtConfig, token: &str) -> Result<TokenData<Claims>, AppError> {
+    let mut validation = config.validation();
+    validation.leeway = config.refresh_grace_seconds as u64;
+    decode_with(config, token, &validation)
+}
+
+pub fn generate(config: &JwtConfig, user_id: Uuid, now: i64) -> Result<String, Error> {
+    let claims = Claims {
+        iss: config.issuer.clone(),
+        aud: config.audience.clone(),
+        ..Claims::new(user_id, now, config.ttl_seconds)
+    };
+    encode(config, &claims)
+}
+
+fn encode(config: &JwtConfig, claims: &Claims) -> Result<String, Error> {
+    let secret_key = config.secret_key.as_bytes();
     jsonwebtoken::encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(secret_key),
     )
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
-    // aud: String, // Optional. Audience
-    exp: i64, // Required (validate_exp defaults to true in validation). Expiration time (as UTC timestamp)
-    iat: i64, // Optional. Issued at (as UTC timestamp)
-    // iss: String, // Optional. Issuer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aud: Option<String>, // Optional. Audience
+    pub exp: i64, // Required (validate_exp defaults to true in validation). Expiration time (as UTC timestamp)
+    iat: i64,     // Optional. Issued at (as UTC timestamp)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iss: Option<String>, // Optional. Issuer
     // nbf: usize, // Optional. Not Before (as UTC timestamp)
     // sub: String, // Optional. Subject (whom token refers to)
+    pub jti: Uuid, // Optional. JWT ID (used to revoke a single token)
     // ---
     pub user_id: Uuid,
 }
 
 impl Claims {
-    pub fn new(user_id: Uuid, now: i64) -> Self {
+    pub fn new(user_id: Uuid, now: i64, ttl: i64) -> Self {
         Claims {
+            aud: None,
+            iss: None,
             iat: now,
-            exp: now + ONE_DAY,
+            exp: now + ttl,
+            jti: Uuid::new_v4(),
             user_id,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn config() -> JwtConfig {
+        JwtConfig::new("0123456789abcdef0123456789abcdef".to_owned())
+    }
+
+    #[test]
+    fn verify_accepts_fresh_token() {
+        let config = config();
+        let user_id = Uuid::new_v4();
+        let token = encode(&config, &Claims::new(user_id, Utc::now().timestamp(), 60)).unwrap();
+        let token_data = verify(&config, &token).unwrap();
+        assert_eq!(user_id, token_data.claims.user_id);
+    }
+
+    #[test]
+    fn generate_emits_unique_jti() {
+        let config = config();
+        let user_id = Uuid::new_v4();
+        let now = Utc::now().timestamp();
+        let first = verify(&config, &generate(&config, user_id, now).unwrap()).unwrap();
+        let second = verify(&config, &generate(&config, user_id, now).unwrap()).unwrap();
+        assert_ne!(first.claims.jti, second.claims.jti);
+    }
+
+    #[test]
+    fn verify_rejects_expired_token() {
+        let config = config();
+        let token = encode(
+            &config,
+            &Claims::new(Uuid::new_v4(), Utc::now().timestamp(), 1),
+        )
+        .unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(2));
+        match verify(&config, &token) {
+            Err(AppError::Unauthorized(msg)) => {
+                assert_eq!("token expired", msg)
+            }
+            _ => panic!("expired token must be rejected as unauthorized"),
+        }
+    }
+
+    #[test]
+    fn verify_accepts_matching_issuer_and_audience() {
+        let config = config();
+        let claims = Claims {
+            iss: Some("conduit".to_owned()),
+            aud: Some("conduit-web".to_owned()),
+            ..Claims::new(Uuid::new_v4(), Utc::now().timestamp(), 60)
+        };
+        let token = encode(&config, &claims).unwrap();
+        let validation =
+            validation_with(Some("conduit".to_owned()), Some("conduit-web".to_owned()));
+        assert!(decode_with(&config, &token, &validation).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_tampered_audience() {
+        let config = config();
+        let claims = Claims {
+            aud: Some("somebody-else".to_owned()),
+            ..Claims::new(Uuid::new_v4(), Utc::now().timestamp(), 60)
+        };
+        let token = encode(&config, &claims).unwrap();
+        let validation = validation_with(None, Some("conduit-web".to_owned()));
+        assert!(matches!(
+            decode_with(&config, &token, &validation),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_missing_issuer_when_configured() {
+        let config = config();
+        let token = encode(
+            &config,
+            &Claims::new(Uuid::new_v4(), Utc::now().timestamp(), 60),
+        )
+        .unwrap();
+        let validation = validation_with(Some("conduit".to_owned()), None);
+        assert!(matches!(
+            decode_with(&config, &token, &validation),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn verify_for_refresh_accepts_token_within_grace() {
+        let config = config();
+        let user_id = Uuid::new_v4();
+        let now = Utc::now().timestamp();
+        let token = encode(&config, &Claims::new(user_id, now - 120, 60)).unwrap();
+        assert!(verify(&config, &token).is_err());
+        let token_data = verify_for_refresh(&config, &token).unwrap();
+        assert_eq!(user_id, token_data.claims.user_id);
+    }
+
+    #[test]
+    fn verify_for_refresh_rejects_token_past_grace() {
+        let config = config();
+        let now = Utc::now().timestamp();
+        let token = encode(&config, &Claims::new(Uuid::new_v4(), now - ONE_DAY, 60)).unwrap();
+        assert!(verify_for_refresh(&config, &token).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::AppError;
+use crate::utils::password_policy::PasswordPolicy;
+use std::collections::BTreeMap;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+type FieldErrors = BTreeMap<String, Vec<String>>;
+
+// Reports every failing field at once, each with all of its messages.
+pub fn validate<T: Validate>(value: &T) -> Result<(), AppError> {
+    into_result(field_errors(value))
+}
+
+// Same as `validate`, also checking `password` (when given) against the configured policy.
+// NOTE: the policy is runtime config, which a `#[validate]` rule has no way to reach.
+pub fn validate_with_password<T: Validate>(
+    value: &T,
+    password: Option<&str>,
+    policy: &PasswordPolicy,
+) -> Result<(), AppError> {
+    let mut fields = field_errors(value);
+    if let Some(Err(messages)) = password.map(|password| policy.check(password)) {
+        fields
+            .entry("password".to_owned())
+            .or_default()
+            .extend(messages);
+    }
+    into_result(fields)
+}
+
+fn field_errors<T: Validate>(value: &T) -> FieldErrors {
+    let mut fields = FieldErrors::new();
+    if let Err(errors) = value.validate() {
+        collect(&errors, &mut fields);
+    }
+    fields
+}
+
+fn into_result(fields: FieldErrors) -> Result<(), AppError> {
+    if fields.is_empty() {
+        return Ok(());
+    }
+    let fields = fields
+        .into_iter()
+        .flat_map(|(field, messages)| {
+            messages
+                .into_iter()
+                .map(move |message| (field.clone(), message))
+        })
+        .collect();
+    Err(AppError::UnprocessableEntity(fields))
+}
+
+// NOTE: nested DTOs (e.g. `{"user": {..}}`) report their inner field names, as the spec does.
+fn collect(errors: &ValidationErrors, fields: &mut FieldErrors) {
+    for (field, kind) in errors.errors() {
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = fields.entry(field.to_string()).or_default();
+                errors
+                    .iter()
+                    .for_each(|error| messages.extend(to_messages(error)));
+            }
+            ValidationErrorsKind::Struct(errors) => collect(errors, fields),
+            ValidationErrorsKind::List(list) => {
+                list.values().for_each(|errors| collect(errors, fields))
+            }
+        }
+    }
+}
+
+fn to_messages(error: &ValidationError) -> Vec<String> {
+    let message = match &error.message {
+        Some(message) => message.to_string(),
+        None => "is invalid".to_owned(),
+    };
+    vec![message]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::features::article::requests::CreateArticleRequest;
+    use crate::app::features::user::requests::Signup;
+    use serde_json::json;
+
+    fn errors<T: Validate>(value: &T) -> serde_json::Value {
+        into_errors(validate(value))
+    }
+
+    fn into_errors(result: Result<(), AppError>) -> serde_json::Value {
+        match result {
+            Err(err @ AppError::UnprocessableEntity(_)) => err.body()["errors"].clone(),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn signup_reports_every_failing_field() {
+        let form: Signup = serde_json::from_value(json!({
+            "user": {"username": "", "email": "not-an-email", "password": "abc"}
+        }))
+        .unwrap();
+        assert_eq!(
+            json!({
+                "username": ["can't be blank"],
+                "email": ["is invalid"],
+                "password": [
+                    "is too short (minimum is 8 characters)",
+                    "must contain at least one digit"
+                ]
+            }),
+            into_errors(validate_with_password(
+                &form,
+                Some(&form.user.password),
+                &PasswordPolicy::default()
+            ))
+        );
+    }
+
+    #[test]
+    fn signup_accepts_valid_user() {
+        let form: Signup = serde_json::from_value(json!({
+            "user": {"username": "jake", "email": "jake@jake.jake", "password": "jakejake42"}
+        }))
+        .unwrap();
+        assert!(validate_with_password(
+            &form,
+            Some(&form.user.password),
+            &PasswordPolicy::default()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn create_article_reports_blank_fields() {
+        let form: CreateArticleRequest = serde_json::from_value(json!({
+            "article": {"title": "", "description": "", "body": "It takes a Jacobian"}
+        }))
+        .unwrap();
+        assert_eq!(
+            json!({"title": ["can't be blank"], "description": ["can't be blank"]}),
+            errors(&form)
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// Push-only WebSocket responses on top of actix-http's handshake and frame codec.
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{CloseCode, CloseReason, Codec, Frame, Message};
+use actix_web::body::BodyStream;
+use actix_web::web::{Bytes, BytesMut, Payload};
+use actix_web::{HttpRequest, HttpResponse};
+use futures::stream::{self, Stream, StreamExt};
+use std::convert::Infallible;
+
+enum Event {
+    Send(Message),
+    // NOTE: the client closed the socket or went away; nothing more is sent.
+    Closed(Option<Message>),
+}
+
+// Upgrades `req` and streams every text from `outgoing` to the client. Pings are answered and
+// the response ends once the client closes or disconnects, which also drops `outgoing`.
+pub fn push<S>(req: &HttpRequest, payload: Payload, outgoing: S) -> HttpResponse
+where
+    S: Stream<Item = String> + Unpin + 'static,
+{
+    let mut res = match actix_http::ws::handshake(req.head()) {
+        Ok(res) => res,
+        Err(err) => return HttpResponse::from_error(err),
+    };
+    let outgoing = outgoing.map(|text| Event::Send(Message::Text(text.into())));
+    let events = stream::select(incoming(payload).boxed_local(), outgoing);
+    let state = (events, Codec::new(), false);
+    let frames = stream::unfold(state, |(mut events, mut codec, closed)| async move {
+        if closed {
+            return None;
+        }
+        let (message, closed) = match events.next().await? {
+            Event::Send(message) => (Some(message), false),
+            Event::Closed(reply) => (reply, true),
+        };
+        let frame = message
+            .and_then(|message| encode(&mut codec, message))
+            .unwrap_or_default();
+        Some((frame, (events, codec, closed)))
+    })
+    .filter(|frame| std::future::ready(!frame.is_empty()))
+    .map(Ok::<_, Infallible>);
+    HttpResponse::from(res.body(BodyStream::new(frames))).map_into_boxed_body()
+}
+
+fn encode(codec: &mut Codec, message: Message) -> Option<Bytes> {
+    let mut buf = BytesMut::new();
+    codec.encode(message, &mut buf).ok()?;
+    Some(buf.freeze())
+}
+
+// NOTE: clients only talk to keep the socket alive, so anything but ping and close is ignored.
+fn incoming(payload: Payload) -> impl Stream<Item = Event> {
+    let state = (payload, BytesMut::new(), Codec::new(), false);
+    stream::unfold(
+        state,
+        |(mut payload, mut buf, mut codec, done)| async move {
+            if done {
+                return None;
+            }
+            loop {
+                match codec.decode(&mut buf) {
+                    Ok(Some(Frame::Ping(bytes))) => {
+                        let event = Event::Send(Message::Pong(bytes));
+                        return Some((event, (payload, buf, codec, false)));
+                    }
+                    Ok(Some(Frame::Close(reason))) => {
+                        let event = Event::Closed(Some(Message::Close(reason)));
+                        return Some((event, (payload, buf, codec, true)));
+                    }
+                    Ok(Some(_)) => continue,
+                    Ok(None) => {}
+                    Err(_) => {
+                        let reason = CloseReason::from(CloseCode::Protocol);
+                        let event = Event::Closed(Some(Message::Close(Some(reason))));
+                        return Some((event, (payload, buf, codec, true)));
+                    }
+                }
+                match payload.next().await {
+                    Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                    Some(Err(_)) | None => {
+                        return Some((Event::Closed(None), (payload, buf, codec, true)));
+                    }
+                }
+            }
+        },
+    )
+}
@@ -0,0 +1,712 @@
+mod common;
+
+use actix_web::test::{call_and_read_body_json, call_service, init_service, TestRequest};
+use actix_web::{http::StatusCode, web, App};
+use common::{
+    create_article, create_article_days_later, create_user, favorite, follow, state, TestDb,
+};
+use conduit::app::drivers::middlewares::auth::Authentication;
+use conduit::app::drivers::routes;
+use conduit::app::features::article::repositories::{
+    ArchiveArticleRepositoryInput, ArticleOrder, ArticleRepository, ArticleRepositoryImpl,
+    ArticlesPage, CreateArticleRepositoryInput, FetchArticlesRepositoryInput,
+    FetchFollowingArticlesRepositoryInput, FetchRelatedArticlesRepositoryInput,
+    UpdateArticleRepositoryInput,
+};
+use conduit::app::features::favorite::repositories::{FavoriteRepository, FavoriteRepositoryImpl};
+use conduit::app::features::tag::entities::Tag;
+use conduit::app::features::user::entities::User;
+use conduit::error::AppError;
+use conduit::schema::articles;
+use conduit::utils::pagination::{Cursor, Pagination};
+use diesel::prelude::*;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn find_by_slug_or_id_for_follower() {
+    let db = TestDb::new();
+    let (jake, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(
+            conn,
+            &celeb,
+            "How to train your dragon",
+            &["dragons", "training"],
+        );
+        follow(conn, &jake, &celeb);
+        favorite(conn, &jake, &article);
+        (jake, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let (found, author, favorite_info, tags) = articles
+        .find_by_slug_or_id(&article.slug, Some(jake.id))
+        .unwrap();
+    assert_eq!(article.id, found.id);
+    assert_eq!("celeb", author.username);
+    assert!(author.following);
+    assert!(favorite_info.is_favorited);
+    let mut tags = tags.into_iter().map(|tag| tag.name).collect::<Vec<_>>();
+    tags.sort();
+    assert_eq!(vec!["dragons", "training"], tags);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn find_by_slug_or_id_for_other_viewers() {
+    let db = TestDb::new();
+    let (stranger, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let stranger = create_user(conn, "stranger");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &["dragons"]);
+        follow(conn, &jake, &celeb);
+        favorite(conn, &jake, &article);
+        (stranger, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    for viewer_id in [Some(stranger.id), None] {
+        let (_, author, favorite_info, tags) = articles
+            .find_by_slug_or_id(&article.slug, viewer_id)
+            .unwrap();
+        assert!(!author.following);
+        assert!(!favorite_info.is_favorited);
+        assert_eq!(1, tags.len());
+    }
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn find_by_slug_or_id_accepts_the_article_id() {
+    let db = TestDb::new();
+    let article = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        create_article(conn, &celeb, "How to train your dragon", &[])
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let (by_id, _, _, _) = articles
+        .find_by_slug_or_id(&article.id.to_string(), None)
+        .unwrap();
+    let (by_slug, _, _, _) = articles.find_by_slug_or_id(&article.slug, None).unwrap();
+    assert_eq!(article.id, by_id.id);
+    assert_eq!(article.id, by_slug.id);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn record_view_increments_the_stored_count() {
+    let db = TestDb::new();
+    let article = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        create_article(conn, &celeb, "How to train your dragon", &[])
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    assert_eq!(0, article.view_count);
+
+    assert_eq!(1, articles.record_view(&article.id).unwrap());
+    assert_eq!(2, articles.record_view(&article.id).unwrap());
+    let (found, _, _, _) = articles.find_by_slug_or_id(&article.slug, None).unwrap();
+    assert_eq!(2, found.view_count);
+    assert_eq!(article.updated_at, found.updated_at);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn find_by_slug_or_id_of_unknown_value_is_not_found() {
+    let db = TestDb::new();
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    for slug_or_id in ["no-such-article", "6f1c2e4a-3b5d-4c7e-9f80-1a2b3c4d5e6f"] {
+        let result = articles.find_by_slug_or_id(slug_or_id, None);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+}
+
+fn update(author: &User, slug: &str, version: i32, body: &str) -> UpdateArticleRepositoryInput {
+    UpdateArticleRepositoryInput {
+        current_user: author.clone(),
+        article_title_slug: slug.to_owned(),
+        title: None,
+        description: None,
+        body: Some(body.to_owned()),
+        published: None,
+        tag_name_list: None,
+        version,
+    }
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn stale_update_is_rejected() {
+    let db = TestDb::new();
+    let (celeb, article) = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        (celeb, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    // NOTE: two clients read the same version; the second write must not clobber the first.
+    let (updated, _, _, _) = articles
+        .update_article(update(&celeb, &article.slug, article.version, "First"))
+        .unwrap();
+    assert_eq!(article.version + 1, updated.version);
+    let stale = articles.update_article(update(&celeb, &article.slug, article.version, "Second"));
+    assert!(matches!(stale, Err(AppError::Conflict(_))));
+
+    let (current, _, _, _) = articles.find_by_slug_or_id(&article.slug, None).unwrap();
+    assert_eq!("First", current.body);
+    assert_eq!(updated.version, current.version);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn update_of_missing_article_is_not_found() {
+    let db = TestDb::new();
+    let celeb = create_user(&mut db.conn(), "celeb");
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let result = articles.update_article(update(&celeb, "no-such-article", 1, "Body"));
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn archived_article_is_only_visible_to_its_author() {
+    let db = TestDb::new();
+    let (celeb, stranger, article) = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let stranger = create_user(conn, "stranger");
+        let article = create_article(conn, &celeb, "How to train your dragon", &["dragons"]);
+        (celeb, stranger, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let listed = || {
+        articles
+            .fetch_articles(FetchArticlesRepositoryInput {
+                query: None,
+                tag: None,
+                author: None,
+                favorited: None,
+                drafts_of: None,
+                since: None,
+                order: ArticleOrder::default(),
+                pagination: Pagination::default(),
+                after: None,
+            })
+            .unwrap()
+            .total
+    };
+    assert_eq!(1, listed());
+
+    let (archived, _, _, _) = articles
+        .set_archived(ArchiveArticleRepositoryInput {
+            article_id: article.id,
+            archived: true,
+            current_user: celeb.clone(),
+        })
+        .unwrap();
+    assert!(archived.archived);
+    assert_eq!(0, listed());
+    for viewer_id in [Some(stranger.id), None] {
+        let result = articles.find_by_slug_or_id(&article.slug, viewer_id);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+    let (found, _, _, _) = articles
+        .find_by_slug_or_id(&article.slug, Some(celeb.id))
+        .unwrap();
+    assert!(found.archived);
+
+    articles
+        .set_archived(ArchiveArticleRepositoryInput {
+            article_id: article.id,
+            archived: false,
+            current_user: celeb,
+        })
+        .unwrap();
+    assert_eq!(1, listed());
+    assert!(articles
+        .find_by_slug_or_id(&article.slug, Some(stranger.id))
+        .is_ok());
+}
+
+fn retag(author: &User, slug: &str, version: i32, tags: &[&str]) -> UpdateArticleRepositoryInput {
+    UpdateArticleRepositoryInput {
+        body: None,
+        tag_name_list: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+        ..update(author, slug, version, "")
+    }
+}
+
+fn tag_names(tags: Vec<Tag>) -> Vec<String> {
+    let mut names = tags.into_iter().map(|tag| tag.name).collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn update_adds_and_removes_tags() {
+    let db = TestDb::new();
+    let (celeb, article) = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(
+            conn,
+            &celeb,
+            "How to train your dragon",
+            &["dragons", "training"],
+        );
+        (celeb, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let kept = {
+        let (_, _, _, tags) = articles.find_by_slug_or_id(&article.slug, None).unwrap();
+        tags.into_iter().find(|tag| tag.name == "dragons").unwrap()
+    };
+
+    let (updated, _, _, tags) = articles
+        .update_article(retag(
+            &celeb,
+            &article.slug,
+            article.version,
+            &["Dragons", "flying"],
+        ))
+        .unwrap();
+    assert_eq!(vec!["dragons", "flying"], tag_names(tags));
+
+    // NOTE: an unchanged tag keeps its row rather than being deleted and re-created.
+    let (_, _, _, tags) = articles.find_by_slug_or_id(&updated.slug, None).unwrap();
+    assert!(tags.iter().any(|tag| tag.id == kept.id));
+    assert_eq!(vec!["dragons", "flying"], tag_names(tags));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn update_with_an_empty_tag_list_clears_tags() {
+    let db = TestDb::new();
+    let (celeb, article) = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &["dragons"]);
+        (celeb, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let (updated, _, _, tags) = articles
+        .update_article(update(&celeb, &article.slug, article.version, "Body"))
+        .unwrap();
+    assert_eq!(vec!["dragons"], tag_names(tags));
+
+    let (_, _, _, tags) = articles
+        .update_article(retag(&celeb, &updated.slug, updated.version, &[]))
+        .unwrap();
+    assert!(tags.is_empty());
+    let (_, _, _, tags) = articles.find_by_slug_or_id(&updated.slug, None).unwrap();
+    assert!(tags.is_empty());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn stale_update_leaves_tags_alone() {
+    let db = TestDb::new();
+    let (celeb, article) = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &["dragons"]);
+        (celeb, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let stale = articles.update_article(retag(&celeb, &article.slug, article.version - 1, &[]));
+    assert!(matches!(stale, Err(AppError::Conflict(_))));
+    let (_, _, _, tags) = articles.find_by_slug_or_id(&article.slug, None).unwrap();
+    assert_eq!(vec!["dragons"], tag_names(tags));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn fetch_articles_honors_the_order() {
+    let db = TestDb::new();
+    let (jake, celeb, slugs) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let slugs = [("First", 1), ("Second", 2), ("Third", 3)]
+            .map(|(title, days)| create_article_days_later(conn, &celeb, title, days).slug);
+        (jake, celeb, slugs)
+    };
+    let favorites = FavoriteRepositoryImpl::new(db.pools());
+    favorites
+        .favorite_article(jake.clone(), slugs[1].clone())
+        .unwrap();
+    favorites.favorite_article(celeb, slugs[1].clone()).unwrap();
+    favorites.favorite_article(jake, slugs[0].clone()).unwrap();
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let titles = |order: ArticleOrder| {
+        articles
+            .fetch_articles(FetchArticlesRepositoryInput {
+                query: None,
+                tag: None,
+                author: None,
+                favorited: None,
+                drafts_of: None,
+                since: None,
+                order,
+                pagination: Pagination::default(),
+                after: None,
+            })
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|(article, _, _)| article.title)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec!["Third", "Second", "First"],
+        titles(ArticleOrder::Recent)
+    );
+    assert_eq!(
+        vec!["Second", "First", "Third"],
+        titles(ArticleOrder::Popular)
+    );
+    assert_eq!(
+        vec!["First", "Second", "Third"],
+        titles(ArticleOrder::Oldest)
+    );
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn search_ranks_word_matches_above_substring_matches() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        create_article_days_later(conn, &celeb, "Dragons", 1);
+        create_article_days_later(conn, &celeb, "Dragonsfire", 2);
+        create_article_days_later(conn, &celeb, "Cats", 3);
+    }
+    let page = ArticleRepositoryImpl::new(db.pools())
+        .fetch_articles(FetchArticlesRepositoryInput {
+            query: Some("dragons".to_owned()),
+            tag: None,
+            author: None,
+            favorited: None,
+            drafts_of: None,
+            since: None,
+            order: ArticleOrder::Recent,
+            pagination: Pagination::default(),
+            after: None,
+        })
+        .unwrap();
+
+    assert_eq!(2, page.total);
+    let titles = page
+        .items
+        .into_iter()
+        .map(|(article, _, _)| article.title)
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["Dragons", "Dragonsfire"], titles);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn since_returns_only_newer_articles() {
+    let db = TestDb::new();
+    let (jake, seen) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        follow(conn, &jake, &celeb);
+        let seen = create_article_days_later(conn, &celeb, "Seen", 1);
+        create_article_days_later(conn, &celeb, "Older", 0);
+        create_article_days_later(conn, &celeb, "Newer", 2);
+        create_article_days_later(conn, &celeb, "Newest", 3);
+        (jake, seen)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let titles = |page: ArticlesPage| {
+        let titles = page
+            .items
+            .into_iter()
+            .map(|(article, _, _)| article.title)
+            .collect::<Vec<_>>();
+        (page.total, titles)
+    };
+    let since = Some(seen.created_at);
+
+    let listed = articles
+        .fetch_articles(FetchArticlesRepositoryInput {
+            query: None,
+            tag: None,
+            author: None,
+            favorited: None,
+            drafts_of: None,
+            since,
+            order: ArticleOrder::default(),
+            pagination: Pagination::default(),
+            after: None,
+        })
+        .unwrap();
+    assert_eq!(
+        (2, vec!["Newest".to_owned(), "Newer".to_owned()]),
+        titles(listed)
+    );
+
+    // NOTE: the filter composes with paging: the total counts only the newer articles.
+    let feed = articles
+        .fetch_following_articles(&FetchFollowingArticlesRepositoryInput {
+            current_user: jake,
+            since,
+            pagination: Pagination::from_query(Some(1), Some(1)),
+        })
+        .unwrap();
+    assert_eq!((2, vec!["Newer".to_owned()]), titles(feed));
+}
+
+fn create(author: &User, title: &str, key: &str) -> CreateArticleRepositoryInput {
+    CreateArticleRepositoryInput {
+        title: title.to_owned(),
+        description: "Ever wonder how?".to_owned(),
+        body: "It takes a Jacobian".to_owned(),
+        tag_name_list: Some(vec!["dragons".to_owned()]),
+        published: true,
+        current_user: author.clone(),
+        idempotency_key: Some(key.to_owned()),
+    }
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn retried_create_returns_the_original_article() {
+    let db = TestDb::new();
+    let celeb = create_user(&mut db.conn(), "celeb");
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let ((first, _, _, _), inserted) = articles
+        .create_article(create(&celeb, "How to train your dragon", "retry-1"))
+        .unwrap();
+    assert!(inserted);
+    let ((retried, _, _, tags), inserted) = articles
+        .create_article(create(&celeb, "How to train your dragon", "retry-1"))
+        .unwrap();
+
+    assert!(!inserted);
+    assert_eq!(first.id, retried.id);
+    assert_eq!(first.slug, retried.slug);
+    assert_eq!(vec!["dragons"], tag_names(tags));
+    let listed = articles
+        .fetch_articles(FetchArticlesRepositoryInput {
+            query: None,
+            tag: None,
+            author: Some(celeb.username.clone()),
+            favorited: None,
+            drafts_of: None,
+            since: None,
+            order: ArticleOrder::default(),
+            pagination: Pagination::default(),
+            after: None,
+        })
+        .unwrap();
+    assert_eq!(1, listed.total);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn distinct_idempotency_keys_create_distinct_articles() {
+    let db = TestDb::new();
+    let celeb = create_user(&mut db.conn(), "celeb");
+    let articles = ArticleRepositoryImpl::new(db.pools());
+
+    let ((first, _, _, _), _) = articles
+        .create_article(create(&celeb, "How to train your dragon", "retry-1"))
+        .unwrap();
+    let ((second, _, _, _), inserted) = articles
+        .create_article(create(&celeb, "How to train your dragon", "retry-2"))
+        .unwrap();
+
+    assert!(inserted);
+
+    assert_ne!(first.id, second.id);
+    assert_ne!(first.slug, second.slug);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn keyset_pages_match_offset_pages_across_ties() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        // NOTE: now() is frozen, so these five share one `created_at` and only the id orders them.
+        for n in 0..5 {
+            create_article(conn, &celeb, &format!("Tied {}", n), &[]);
+        }
+        create_article_days_later(conn, &celeb, "Later", 1);
+        create_article_days_later(conn, &celeb, "Latest", 2);
+    }
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let page = |order: ArticleOrder, offset: i64, after: Option<Cursor>| {
+        articles
+            .fetch_articles(FetchArticlesRepositoryInput {
+                query: None,
+                tag: None,
+                author: None,
+                favorited: None,
+                drafts_of: None,
+                since: None,
+                order,
+                pagination: Pagination::from_query(Some(2), Some(offset)),
+                after,
+            })
+            .unwrap()
+    };
+
+    for order in [ArticleOrder::Recent, ArticleOrder::Oldest] {
+        let mut by_offset = vec![];
+        let mut by_keyset = vec![];
+        let mut after = None;
+        for n in 0..4 {
+            let by_offset_page = page(order, n * 2, None).items;
+            by_offset.extend(by_offset_page.into_iter().map(|(article, _, _)| article.id));
+            let keyset = page(order, 0, after);
+            assert_eq!(7, keyset.total);
+            after = keyset.items.last().map(|(article, _, _)| Cursor {
+                created_at: article.created_at,
+                id: article.id,
+            });
+            by_keyset.extend(keyset.items.into_iter().map(|(article, _, _)| article.id));
+        }
+        assert_eq!(7, by_keyset.len());
+        assert_eq!(by_offset, by_keyset);
+        let mut unique = by_keyset.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(7, unique.len());
+    }
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn related_articles_rank_by_shared_tag_count() {
+    let db = TestDb::new();
+    let (celeb, article) = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let jake = create_user(conn, "jake");
+        let article = create_article(
+            conn,
+            &celeb,
+            "How to train your dragon",
+            &["dragons", "fantasy", "vikings"],
+        );
+        create_article(conn, &jake, "Vikings at sea", &["vikings", "sailing"]);
+        create_article(conn, &jake, "Dragon lore", &["dragons", "fantasy", "myths"]);
+        create_article(
+            conn,
+            &celeb,
+            "How to train your dragon 2",
+            &["dragons", "fantasy", "vikings"],
+        );
+        create_article(conn, &jake, "Cooking for vikings", &["cooking"]);
+        let archived = create_article(conn, &jake, "Archived dragons", &["dragons"]);
+        let deleted = create_article(conn, &jake, "Deleted dragons", &["dragons"]);
+        diesel::update(articles::table.find(archived.id))
+            .set(articles::archived.eq(true))
+            .execute(conn)
+            .unwrap();
+        diesel::update(articles::table.find(deleted.id))
+            .set(articles::deleted_at.eq(diesel::dsl::now))
+            .execute(conn)
+            .unwrap();
+        (celeb, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let related = |exclude_author_id, limit| {
+        articles
+            .fetch_related_articles(&FetchRelatedArticlesRepositoryInput {
+                article_id: article.id,
+                exclude_author_id,
+                limit,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|(article, _, _)| article.title)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec![
+            "How to train your dragon 2",
+            "Dragon lore",
+            "Vikings at sea"
+        ],
+        related(None, 5)
+    );
+    assert_eq!(vec!["How to train your dragon 2"], related(None, 1));
+    assert_eq!(
+        vec!["Dragon lore", "Vikings at sea"],
+        related(Some(celeb.id), 5)
+    );
+}
+
+#[actix_web::test]
+#[ignore = "needs a database at DATABASE_URL"]
+async fn popular_and_search_pages_offer_no_cursor_to_come_back_with() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        for n in 0..3 {
+            create_article_days_later(conn, &celeb, &format!("Dragons {}", n), n);
+        }
+    }
+    let app = init_service(
+        App::new()
+            .app_data(web::Data::new(state(&db)))
+            .wrap(Authentication)
+            .configure(routes::api),
+    )
+    .await;
+
+    let recent: serde_json::Value = call_and_read_body_json(
+        &app,
+        TestRequest::get().uri("/api/articles?limit=1").to_request(),
+    )
+    .await;
+    let cursor = recent["nextCursor"].as_str().unwrap().to_owned();
+    for listing in ["order=popular", "query=dragons"] {
+        let page: serde_json::Value = call_and_read_body_json(
+            &app,
+            TestRequest::get()
+                .uri(&format!("/api/articles?limit=1&{}", listing))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(1, page["articles"].as_array().unwrap().len());
+        assert!(page.get("nextCursor").is_none());
+
+        let res = call_service(
+            &app,
+            TestRequest::get()
+                .uri(&format!(
+                    "/api/articles?limit=1&{}&after={}",
+                    listing, cursor
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, res.status());
+    }
+}
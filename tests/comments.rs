@@ -0,0 +1,104 @@
+mod common;
+
+use common::{create_article, create_user, jwt, serve, TestDb};
+use conduit::constants::env_key;
+use conduit::utils::http_client::{HttpClient, Targets};
+use std::env;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+fn read_head(socket: &mut TcpStream) -> String {
+    let mut head = vec![];
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        socket.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).unwrap()
+}
+
+// NOTE: server frames are never masked; comments fit in the 16-bit length form.
+fn read_text_frame(socket: &mut TcpStream) -> String {
+    let mut header = [0; 2];
+    socket.read_exact(&mut header).unwrap();
+    assert_eq!(0x81, header[0], "expected a final text frame");
+    let len = match header[1] {
+        126 => {
+            let mut len = [0; 2];
+            socket.read_exact(&mut len).unwrap();
+            u16::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+    let mut payload = vec![0; len];
+    socket.read_exact(&mut payload).unwrap();
+    String::from_utf8(payload).unwrap()
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn websocket_pushes_comments_created_over_rest() {
+    env::set_var(env_key::SECRET_KEY, "0123456789abcdef0123456789abcdef");
+    let db = TestDb::new();
+    let (token, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let article = create_article(conn, &jake, "How to train your dragon", &[]);
+        (jake.generate_token(&jwt()).unwrap(), article)
+    };
+    let addr = serve(&db);
+
+    let mut socket = TcpStream::connect(addr).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(
+        socket,
+        "GET /api/articles/{}/comments/ws HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+        article.slug, addr
+    )
+    .unwrap();
+    let head = read_head(&mut socket);
+    assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+
+    let client = HttpClient::new(Targets::Any);
+    let url = format!("http://{}/api/articles/{}/comments", addr, article.slug);
+    let auth = format!("Token {}", token);
+    let headers = [("Authorization", auth.as_str())];
+    let request = client.post_json(
+        &url,
+        &headers,
+        br#"{"comment":{"body":"Thank you!"}}"#.to_vec(),
+        Duration::from_secs(5),
+    );
+    let status = actix_web::rt::System::new().block_on(request).unwrap();
+    assert_eq!(200, status);
+
+    let message: serde_json::Value = serde_json::from_str(&read_text_frame(&mut socket)).unwrap();
+    assert_eq!("Thank you!", message["comment"]["body"]);
+    assert_eq!("jake", message["comment"]["author"]["username"]);
+
+    // NOTE: a masked close frame with an empty payload; the server echoes it and hangs up.
+    socket.write_all(&[0x88, 0x80, 0, 0, 0, 0]).unwrap();
+    let mut rest = vec![];
+    socket.read_to_end(&mut rest).unwrap();
+    assert_eq!(vec![0x88, 0x00], rest);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn websocket_for_a_missing_article_is_not_found() {
+    env::set_var(env_key::SECRET_KEY, "0123456789abcdef0123456789abcdef");
+    let db = TestDb::new();
+    let addr = serve(&db);
+
+    let mut socket = TcpStream::connect(addr).unwrap();
+    write!(
+        socket,
+        "GET /api/articles/no-such-article/comments/ws HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+        addr
+    )
+    .unwrap();
+    assert!(read_head(&mut socket).starts_with("HTTP/1.1 404"));
+}
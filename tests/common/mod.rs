@@ -0,0 +1,168 @@
+// Shared scaffolding for the DB-backed integration tests.
+#![allow(dead_code)]
+
+use actix_web::{rt, web, App, HttpServer};
+use conduit::app::drivers::middlewares::auth::Authentication;
+use conduit::app::drivers::middlewares::state::AppState;
+use conduit::app::drivers::routes;
+use conduit::app::features::article::entities::{Article, CreateArticle};
+use conduit::app::features::favorite::entities::{CreateFavorite, Favorite};
+use conduit::app::features::follow::entities::{CreateFollow, Follow};
+use conduit::app::features::tag::entities::Tag;
+use conduit::app::features::user::entities::{SignupUser, User};
+use conduit::app::features::user::repositories::UserRepositoryImpl;
+use conduit::config::Config;
+use conduit::schema::{articles, users};
+use conduit::utils::db::{DbConn, DbPool, DbPools};
+use conduit::utils::hasher::HashConfig;
+use conduit::utils::slug;
+use conduit::utils::token::JwtConfig;
+use diesel::dsl::IntervalDsl;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool, TestCustomizer};
+use std::env;
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+
+// NOTE: a single connection held open in a transaction that is never committed,
+// so every repository call sees the test's rows and nothing outlives the test.
+pub struct TestDb {
+    pool: DbPool,
+}
+
+impl TestDb {
+    pub fn new() -> Self {
+        dotenv::dotenv().ok();
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_customizer(Box::new(TestCustomizer))
+            .build(manager)
+            .expect("Failed to create test pool");
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> DbPool {
+        self.pool.clone()
+    }
+
+    pub fn pools(&self) -> DbPools {
+        DbPools::new(self.pool(), None)
+    }
+
+    pub fn conn(&self) -> DbConn {
+        self.pool.get().expect("Failed to get test connection")
+    }
+}
+
+pub fn jwt() -> JwtConfig {
+    JwtConfig::new("0123456789abcdef0123456789abcdef".to_owned())
+}
+
+pub fn user_repository(db: &TestDb) -> UserRepositoryImpl {
+    UserRepositoryImpl::new(db.pool(), HashConfig::default(), jwt())
+}
+
+pub fn create_user(conn: &mut PgConnection, username: &str) -> User {
+    let email = format!("{}@example.com", username);
+    diesel::insert_into(users::table)
+        .values(SignupUser {
+            email: &email,
+            username,
+            // NOTE: factories skip hashing; sign in through the usecase when a real password matters.
+            password: "not-a-hash",
+        })
+        .get_result::<User>(conn)
+        .expect("Failed to create user")
+}
+
+pub fn create_article(
+    conn: &mut PgConnection,
+    author: &User,
+    title: &str,
+    tags: &[&str],
+) -> Article {
+    let slug = slug::generate(title, conn).expect("Failed to generate slug");
+    let article = Article::create(
+        conn,
+        &CreateArticle {
+            author_id: author.id,
+            slug,
+            title: title.to_owned(),
+            description: format!("About {}", title),
+            body: format!("All about {}", title),
+            published: true,
+        },
+    )
+    .expect("Failed to create article");
+    let tags = tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+    Tag::create_list(conn, &article.id, &tags).expect("Failed to tag article");
+    article
+}
+
+// NOTE: now() is frozen inside the test transaction, so articles that must differ in age
+// are moved `days` ahead of it by hand.
+pub fn create_article_days_later(
+    conn: &mut PgConnection,
+    author: &User,
+    title: &str,
+    days: i32,
+) -> Article {
+    let article = create_article(conn, author, title, &[]);
+    diesel::update(articles::table.find(article.id))
+        .set(articles::created_at.eq(articles::created_at + days.days()))
+        .get_result::<Article>(conn)
+        .expect("Failed to date article")
+}
+
+pub fn follow(conn: &mut PgConnection, follower: &User, followee: &User) {
+    Follow::create(
+        conn,
+        &CreateFollow {
+            follower_id: follower.id,
+            followee_id: followee.id,
+        },
+    )
+    .expect("Failed to follow user");
+}
+
+pub fn favorite(conn: &mut PgConnection, user: &User, article: &Article) {
+    Favorite::create(
+        conn,
+        &CreateFavorite {
+            user_id: user.id,
+            article_id: article.id,
+        },
+    )
+    .expect("Failed to favorite article");
+}
+
+// NOTE: drop every connection taken from `db` first; the app shares its single one.
+pub fn state(db: &TestDb) -> AppState {
+    AppState::new(Config::from_env().unwrap(), db.pool(), None)
+}
+
+// NOTE: a real server on a free port, for WebSockets and streams that need an actual connection.
+pub fn serve(db: &TestDb) -> SocketAddr {
+    let state = state(db);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        rt::System::new().block_on(async move {
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(web::Data::new(state.clone()))
+                    .wrap(Authentication)
+                    .configure(routes::api)
+            })
+            .workers(1)
+            .listen(listener)
+            .unwrap()
+            .run()
+            .await
+        })
+    });
+    addr
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{user_repository, TestDb};
+use conduit::app::features::email_verification::repositories::{
+    EmailVerificationRepository, EmailVerificationRepositoryImpl,
+};
+use conduit::app::features::user::repositories::UserRepository;
+use conduit::error::AppError;
+use conduit::schema::email_verifications;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn verifying_marks_the_email_and_consumes_the_token() {
+    let db = TestDb::new();
+    let users = user_repository(&db);
+    let verifications = EmailVerificationRepositoryImpl::new(db.pool());
+    let (jake, _) = users
+        .signup("jake@example.com", "jake", "jakejakejake")
+        .unwrap();
+    assert!(!jake.email_verified);
+    let token = email_verifications::table
+        .filter(email_verifications::user_id.eq(jake.id))
+        .select(email_verifications::token)
+        .first::<Uuid>(&mut db.conn())
+        .unwrap();
+
+    let user = verifications.verify(&token).unwrap();
+    assert_eq!(jake.id, user.id);
+    assert!(user.email_verified);
+    let (user, _) = users.signin("jake@example.com", "jakejakejake").unwrap();
+    assert!(user.email_verified);
+
+    let remaining = email_verifications::table
+        .filter(email_verifications::user_id.eq(jake.id))
+        .count()
+        .get_result::<i64>(&mut db.conn())
+        .unwrap();
+    assert_eq!(0, remaining);
+    assert!(matches!(
+        verifications.verify(&token),
+        Err(AppError::UnprocessableEntity(_))
+    ));
+}
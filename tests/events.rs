@@ -0,0 +1,82 @@
+mod common;
+
+use common::{create_user, jwt, serve, TestDb};
+use conduit::constants::env_key;
+use conduit::schema::users;
+use conduit::utils::http_client::{HttpClient, Targets};
+use diesel::prelude::*;
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+// NOTE: the response is chunked, so chunk sizes show up as lines of their own and are skipped.
+fn next_event(reader: &mut impl BufRead) -> Vec<String> {
+    let mut event = vec![];
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() && !event.is_empty() {
+            return event;
+        }
+        if line.starts_with("id:") || line.starts_with("event:") || line.starts_with("data:") {
+            event.push(line.to_owned());
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn new_articles_are_streamed_to_event_subscribers() {
+    env::set_var(env_key::SECRET_KEY, "0123456789abcdef0123456789abcdef");
+    let db = TestDb::new();
+    let token = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        diesel::update(users::table.find(jake.id))
+            .set(users::email_verified.eq(true))
+            .execute(conn)
+            .unwrap();
+        jake.generate_token(&jwt()).unwrap()
+    };
+    let addr = serve(&db);
+
+    let mut socket = TcpStream::connect(addr).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(
+        socket,
+        "GET /api/events HTTP/1.1\r\nHost: {}\r\nAccept: text/event-stream\r\n\r\n",
+        addr
+    )
+    .unwrap();
+    let mut reader = BufReader::new(socket);
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        reader.read_line(&mut head).unwrap();
+    }
+    assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+    assert!(head.contains("content-type: text/event-stream"), "{}", head);
+
+    let client = HttpClient::new(Targets::Any);
+    let url = format!("http://{}/api/articles", addr);
+    let auth = format!("Token {}", token);
+    let headers = [("Authorization", auth.as_str())];
+    let request = client.post_json(
+        &url,
+        &headers,
+        br#"{"article":{"title":"How to train your dragon","description":"Ever wonder how?","body":"You have to believe","tagList":[]}}"#.to_vec(),
+        Duration::from_secs(5),
+    );
+    let status = actix_web::rt::System::new().block_on(request).unwrap();
+    assert_eq!(200, status);
+
+    let event = next_event(&mut reader);
+    assert_eq!("id: 1", event[0]);
+    assert_eq!("event: article", event[1]);
+    let data: serde_json::Value = serde_json::from_str(&event[2]["data: ".len()..]).unwrap();
+    assert_eq!("How to train your dragon", data["title"]);
+    assert_eq!("jake", data["author"]);
+}
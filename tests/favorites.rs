@@ -0,0 +1,192 @@
+mod common;
+
+use common::{create_article, create_user, favorite, follow, TestDb};
+use conduit::app::features::activity::feed::{Activity, ActivityFeed};
+use conduit::app::features::article::repositories::{
+    ArchiveArticleRepositoryInput, ArticleRepository, ArticleRepositoryImpl,
+};
+use conduit::app::features::favorite::presenters::FavoritePresenterImpl;
+use conduit::app::features::favorite::repositories::{FavoriteRepository, FavoriteRepositoryImpl};
+use conduit::app::features::favorite::usecases::FavoriteUsecase;
+use conduit::utils::pagination::Pagination;
+use futures::{FutureExt, StreamExt};
+use std::sync::Arc;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn double_favorite_counts_once() {
+    let db = TestDb::new();
+    let (jake, slug) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        (jake, article.slug)
+    };
+    let favorites = FavoriteRepositoryImpl::new(db.pools());
+
+    let (first, inserted) = favorites
+        .favorite_article(jake.clone(), slug.clone())
+        .unwrap();
+    assert!(inserted);
+    let (second, inserted) = favorites
+        .favorite_article(jake.clone(), slug.clone())
+        .unwrap();
+    assert!(!inserted);
+    assert_eq!(1, first.favorites_count);
+    assert_eq!(1, second.favorites_count);
+
+    let (_, _, favorite_info, _) = ArticleRepositoryImpl::new(db.pools())
+        .find_by_slug_or_id(&slug, Some(jake.id))
+        .unwrap();
+    assert!(favorite_info.is_favorited);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn only_new_favorites_of_listed_articles_are_announced() {
+    let db = TestDb::new();
+    let (jake, celeb, listed, archived) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let listed = create_article(conn, &celeb, "How to train your dragon", &[]);
+        let archived = create_article(conn, &celeb, "How to lose your dragon", &[]);
+        (jake, celeb, listed, archived)
+    };
+    let articles = Arc::new(ArticleRepositoryImpl::new(db.pools()));
+    articles
+        .set_archived(ArchiveArticleRepositoryInput {
+            article_id: archived.id,
+            archived: true,
+            current_user: celeb.clone(),
+        })
+        .unwrap();
+    let activity_feed = ActivityFeed::new();
+    let mut events = activity_feed.subscribe(None);
+    let usecase = FavoriteUsecase::new(
+        Arc::new(FavoriteRepositoryImpl::new(db.pools())),
+        Arc::new(FavoritePresenterImpl::new()),
+        articles,
+        activity_feed,
+    );
+
+    usecase.favorite_article(celeb, archived.slug).unwrap();
+    for _ in 0..2 {
+        usecase
+            .favorite_article(jake.clone(), listed.slug.clone())
+            .unwrap();
+    }
+
+    let event = events.next().now_or_never().flatten().unwrap();
+    assert_eq!(
+        Activity::Favorite {
+            slug: listed.slug,
+            username: jake.username,
+        },
+        event.activity
+    );
+    assert!(events.next().now_or_never().is_none());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn double_unfavorite_never_goes_negative() {
+    let db = TestDb::new();
+    let (jake, anna, slug) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let anna = create_user(conn, "anna");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        (jake, anna, article.slug)
+    };
+    let favorites = FavoriteRepositoryImpl::new(db.pools());
+    favorites.favorite_article(anna, slug.clone()).unwrap();
+    favorites
+        .favorite_article(jake.clone(), slug.clone())
+        .unwrap();
+
+    let first = favorites
+        .unfavorite_article(jake.clone(), slug.clone())
+        .unwrap();
+    let second = favorites
+        .unfavorite_article(jake.clone(), slug.clone())
+        .unwrap();
+    assert_eq!(1, first.favorites_count);
+    assert_eq!(1, second.favorites_count);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn users_for_article_pages_by_username() {
+    let db = TestDb::new();
+    let article = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        for username in ["carol", "alice", "bob"] {
+            let fan = create_user(conn, username);
+            favorite(conn, &fan, &article);
+        }
+        article
+    };
+    let favorites = FavoriteRepositoryImpl::new(db.pools());
+    let usernames = |limit, offset| {
+        let page = favorites
+            .users_for_article(
+                &article.id,
+                None,
+                Pagination::from_query(Some(limit), Some(offset)),
+            )
+            .unwrap();
+        let names = page
+            .items
+            .into_iter()
+            .map(|profile| profile.username)
+            .collect::<Vec<_>>();
+        (names, page.total)
+    };
+
+    assert_eq!(
+        (vec!["alice".to_owned(), "bob".to_owned()], 3),
+        usernames(2, 0)
+    );
+    assert_eq!((vec!["carol".to_owned()], 3), usernames(2, 2));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn users_for_article_marks_who_the_viewer_follows() {
+    let db = TestDb::new();
+    let (jake, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let stranger = create_user(conn, "stranger");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        favorite(conn, &celeb, &article);
+        favorite(conn, &stranger, &article);
+        follow(conn, &jake, &celeb);
+        (jake, article)
+    };
+    let favorites = FavoriteRepositoryImpl::new(db.pools());
+    let following = |viewer_id| {
+        favorites
+            .users_for_article(&article.id, viewer_id, Pagination::default())
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|profile| (profile.username, profile.following))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec![("celeb".to_owned(), true), ("stranger".to_owned(), false)],
+        following(Some(&jake.id))
+    );
+    assert_eq!(
+        vec![("celeb".to_owned(), false), ("stranger".to_owned(), false)],
+        following(None)
+    );
+}
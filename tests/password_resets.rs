@@ -0,0 +1,61 @@
+mod common;
+
+use chrono::Duration;
+use common::{create_user, user_repository, TestDb};
+use conduit::app::features::password_reset::repositories::{
+    PasswordResetRepository, PasswordResetRepositoryImpl,
+};
+use conduit::app::features::user::repositories::UserRepository;
+use conduit::error::AppError;
+use conduit::schema::password_resets;
+use conduit::utils::hasher::HashConfig;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+fn is_invalid_token(result: Result<(), AppError>) -> bool {
+    matches!(result, Err(err @ AppError::UnprocessableEntity(_))
+        if err.body() == serde_json::json!({"errors": {"token": ["is invalid or expired"]}}))
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn reset_tokens_work_once() {
+    let db = TestDb::new();
+    create_user(&mut db.conn(), "jake");
+    let resets = PasswordResetRepositoryImpl::new(db.pool(), HashConfig::default());
+    let users = user_repository(&db);
+    let reset = resets.create("jake@example.com").unwrap().unwrap();
+
+    resets.confirm(&reset.token, "jakejake42").unwrap();
+    assert!(users.signin("jake@example.com", "jakejake42").is_ok());
+
+    assert!(is_invalid_token(
+        resets.confirm(&reset.token, "celebceleb42")
+    ));
+    assert!(users.signin("jake@example.com", "jakejake42").is_ok());
+    assert!(users.signin("jake@example.com", "celebceleb42").is_err());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn expired_and_unknown_reset_tokens_are_refused() {
+    let db = TestDb::new();
+    create_user(&mut db.conn(), "jake");
+    let resets = PasswordResetRepositoryImpl::new(db.pool(), HashConfig::default());
+    let reset = resets.create("jake@example.com").unwrap().unwrap();
+    diesel::update(password_resets::table.find(reset.id))
+        .set(password_resets::expires_at.eq(reset.created_at - Duration::minutes(1)))
+        .execute(&mut db.conn())
+        .unwrap();
+
+    assert!(is_invalid_token(resets.confirm(&reset.token, "jakejake42")));
+    assert!(is_invalid_token(
+        resets.confirm(&Uuid::new_v4(), "jakejake42")
+    ));
+    let used_at = password_resets::table
+        .find(reset.id)
+        .select(password_resets::used_at)
+        .first::<Option<chrono::NaiveDateTime>>(&mut db.conn())
+        .unwrap();
+    assert_eq!(None, used_at);
+}
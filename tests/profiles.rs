@@ -0,0 +1,260 @@
+mod common;
+
+use common::{create_user, follow, user_repository, TestDb};
+use conduit::app::features::profile::repositories::{ProfileRepository, ProfileRepositoryImpl};
+use conduit::app::features::user::repositories::UserRepository;
+use conduit::error::AppError;
+use conduit::utils::pagination::Pagination;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn follow_then_unfollow_profile() {
+    let db = TestDb::new();
+    let (jake, celeb) = {
+        let conn = &mut db.conn();
+        (create_user(conn, "jake"), create_user(conn, "celeb"))
+    };
+    let users = user_repository(&db);
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    users.follow_user(&jake, "celeb").unwrap();
+    let profile = profiles.fetch_profile_by_name(&jake, "celeb").unwrap();
+    assert!(profile.following);
+    assert_eq!(1, profiles.follow_counts("celeb").unwrap().followers_count);
+
+    users.unfollow_user(&jake, "celeb").unwrap();
+    let profile = profiles.fetch_profile_by_name(&jake, "celeb").unwrap();
+    assert!(!profile.following);
+    assert_eq!(0, profiles.follow_counts("celeb").unwrap().followers_count);
+    assert!(
+        !profiles
+            .fetch_profile_by_name(&celeb, "jake")
+            .unwrap()
+            .following
+    );
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn followers_lists_followers_only() {
+    let db = TestDb::new();
+    let celeb = {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let jake = create_user(conn, "jake");
+        let _stranger = create_user(conn, "stranger");
+        follow(conn, &jake, &celeb);
+        celeb
+    };
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    let (list, count) = profiles
+        .followers(&Some(celeb), "celeb", Pagination::default())
+        .unwrap();
+    assert_eq!(1, count);
+    assert_eq!("jake", list[0].username);
+    assert!(!list[0].following);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn repeated_follow_and_unfollow_are_safe() {
+    let db = TestDb::new();
+    let jake = {
+        let conn = &mut db.conn();
+        let _celeb = create_user(conn, "celeb");
+        create_user(conn, "jake")
+    };
+    let users = user_repository(&db);
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    for expected_insert in [true, false] {
+        let (profile, inserted) = users.follow_user(&jake, "celeb").unwrap();
+        assert_eq!(expected_insert, inserted);
+        assert_eq!("celeb", profile.username);
+        assert!(profile.following);
+    }
+    assert_eq!(1, profiles.follow_counts("celeb").unwrap().followers_count);
+
+    for _ in 0..2 {
+        let profile = users.unfollow_user(&jake, "celeb").unwrap();
+        assert_eq!("celeb", profile.username);
+        assert!(!profile.following);
+    }
+    assert_eq!(0, profiles.follow_counts("celeb").unwrap().followers_count);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn profiles_by_usernames_skips_unknown_names() {
+    let db = TestDb::new();
+    let jake = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        create_user(conn, "anna");
+        follow(conn, &jake, &celeb);
+        jake
+    };
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+    let usernames = ["Celeb", "nobody", "anna"].map(str::to_owned);
+
+    let list = profiles
+        .profiles_by_usernames(&Some(jake), &usernames)
+        .unwrap();
+    let list = list
+        .iter()
+        .map(|profile| (profile.username.as_str(), profile.following))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![("celeb", true), ("anna", false)], list);
+
+    let list = profiles.profiles_by_usernames(&None, &usernames).unwrap();
+    assert!(list.iter().all(|profile| !profile.following));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn search_matches_usernames_case_insensitively() {
+    let db = TestDb::new();
+    let anna = {
+        let conn = &mut db.conn();
+        let anna = create_user(conn, "anna");
+        let jake = create_user(conn, "Jake");
+        create_user(conn, "ninja");
+        create_user(conn, "celeb");
+        follow(conn, &anna, &jake);
+        anna
+    };
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    let (list, count) = profiles
+        .search(&Some(anna), "JA", Pagination::default())
+        .unwrap();
+    let list = list
+        .iter()
+        .map(|profile| (profile.username.as_str(), profile.following))
+        .collect::<Vec<_>>();
+    assert_eq!(2, count);
+    assert_eq!(vec![("Jake", true), ("ninja", false)], list);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn search_pages_through_matches() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        for username in ["jake", "jane", "janet", "celeb"] {
+            create_user(conn, username);
+        }
+    }
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+    let page = |offset| {
+        let (list, count) = profiles
+            .search(&None, "ja", Pagination::from_query(Some(2), Some(offset)))
+            .unwrap();
+        let names = list
+            .into_iter()
+            .map(|profile| profile.username)
+            .collect::<Vec<_>>();
+        (count, names)
+    };
+
+    assert_eq!((3, vec!["jake".to_owned(), "jane".to_owned()]), page(0));
+    assert_eq!((3, vec!["janet".to_owned()]), page(2));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn mutuals_lists_users_both_follow() {
+    let db = TestDb::new();
+    let jake = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let anna = create_user(conn, "anna");
+        let bob = create_user(conn, "bob");
+        let zoe = create_user(conn, "zoe");
+        for followee in [&anna, &bob, &celeb] {
+            follow(conn, &jake, followee);
+        }
+        for followee in [&bob, &anna, &zoe] {
+            follow(conn, &celeb, followee);
+        }
+        jake
+    };
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    let (list, count) = profiles
+        .mutuals(&jake, "Celeb", Pagination::default())
+        .unwrap();
+    let list = list
+        .iter()
+        .map(|profile| (profile.username.as_str(), profile.following))
+        .collect::<Vec<_>>();
+    assert_eq!(2, count);
+    assert_eq!(vec![("anna", true), ("bob", true)], list);
+
+    let (list, count) = profiles
+        .mutuals(&jake, "celeb", Pagination::from_query(Some(1), Some(1)))
+        .unwrap();
+    assert_eq!(2, count);
+    assert_eq!("bob", list[0].username);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn mutuals_of_disjoint_follow_sets_is_empty() {
+    let db = TestDb::new();
+    let jake = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let anna = create_user(conn, "anna");
+        let bob = create_user(conn, "bob");
+        follow(conn, &jake, &anna);
+        follow(conn, &celeb, &bob);
+        jake
+    };
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    let (list, count) = profiles
+        .mutuals(&jake, "celeb", Pagination::default())
+        .unwrap();
+    assert_eq!(0, count);
+    assert!(list.is_empty());
+    let missing = profiles.mutuals(&jake, "nobody", Pagination::default());
+    assert!(matches!(missing, Err(AppError::NotFound(_))));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn profiles_resolve_usernames_in_any_case() {
+    let db = TestDb::new();
+    let (jake, celeb) = {
+        let conn = &mut db.conn();
+        (create_user(conn, "Jake"), create_user(conn, "celeb"))
+    };
+    let users = user_repository(&db);
+    let profiles = ProfileRepositoryImpl::new(db.pools());
+
+    for username in ["jake", "JAKE", "Jake"] {
+        let profile = profiles.fetch_profile_by_name(&celeb, username).unwrap();
+        assert_eq!("Jake", profile.username, "{}", username);
+    }
+    users.follow_user(&celeb, "jAKE").unwrap();
+    assert!(
+        profiles
+            .fetch_profile_by_name(&celeb, "jake")
+            .unwrap()
+            .following
+    );
+    assert_eq!(1, profiles.follow_counts("JAKE").unwrap().followers_count);
+    assert_eq!(
+        "Jake",
+        profiles
+            .fetch_profile_by_name(&jake, "jake")
+            .unwrap()
+            .username
+    );
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{create_article, create_user, TestDb};
+use conduit::app::features::article::repositories::{
+    ArticleOrder, ArticleRepository, ArticleRepositoryImpl, FetchArticlesRepositoryInput,
+};
+use conduit::app::features::comment::repositories::{CommentRepository, CommentRepositoryImpl};
+use conduit::app::features::report::repositories::{ReportRepository, ReportRepositoryImpl};
+use conduit::error::AppError;
+use conduit::utils::pagination::Pagination;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn filed_report_is_listed_for_moderators() {
+    let db = TestDb::new();
+    let (jake, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        (jake, article)
+    };
+    let reports = ReportRepositoryImpl::new(db.pools());
+
+    let (report, reporter) = reports
+        .report_article(&jake, &article.slug, "Spam".to_owned())
+        .unwrap();
+    assert_eq!(Some(article.id), report.article_id);
+    assert_eq!(None, report.comment_id);
+    assert_eq!("jake", reporter.username);
+
+    let (list, count) = reports.list(Pagination::default()).unwrap();
+    assert_eq!(1, count);
+    assert_eq!(report.id, list[0].0.id);
+    assert_eq!("Spam", list[0].0.reason);
+    assert_eq!("jake", list[0].1.username);
+
+    let result = reports.report_article(&jake, "no-such-article", "Spam".to_owned());
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn hiding_a_reported_article_removes_it_from_public_views() {
+    let db = TestDb::new();
+    let (jake, celeb, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        (jake, celeb, article)
+    };
+    let articles = ArticleRepositoryImpl::new(db.pools());
+    let reports = ReportRepositoryImpl::new(db.pools());
+    let listed = || {
+        articles
+            .fetch_articles(FetchArticlesRepositoryInput {
+                query: None,
+                tag: None,
+                author: None,
+                favorited: None,
+                drafts_of: None,
+                since: None,
+                order: ArticleOrder::default(),
+                pagination: Pagination::default(),
+                after: None,
+            })
+            .unwrap()
+            .total
+    };
+    let (report, _) = reports
+        .report_article(&jake, &article.slug, "Offensive".to_owned())
+        .unwrap();
+    assert_eq!(1, listed());
+
+    reports.hide(report.id).unwrap();
+    assert_eq!(0, listed());
+    for viewer_id in [Some(jake.id), None] {
+        let result = articles.find_by_slug_or_id(&article.slug, viewer_id);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+    let (found, _, _, _) = articles
+        .find_by_slug_or_id(&article.slug, Some(celeb.id))
+        .unwrap();
+    assert!(found.hidden);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn hiding_a_reported_comment_drops_it_from_the_thread() {
+    let db = TestDb::new();
+    let (jake, celeb, article) = {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        let article = create_article(conn, &celeb, "How to train your dragon", &[]);
+        (jake, celeb, article)
+    };
+    let comments = CommentRepositoryImpl::new(db.pools());
+    let reports = ReportRepositoryImpl::new(db.pools());
+    let (spam, _) = comments
+        .create_comment("Buy now".to_owned(), None, article.slug.clone(), celeb)
+        .unwrap();
+    comments
+        .create_comment(
+            "Thank you!".to_owned(),
+            None,
+            article.slug.clone(),
+            jake.clone(),
+        )
+        .unwrap();
+
+    let result = reports.report_comment(&jake, "no-such-article", spam.id, "Spam".to_owned());
+    assert!(matches!(result, Err(AppError::NotFound(_))));
+    let (report, _) = reports
+        .report_comment(&jake, &article.slug, spam.id, "Spam".to_owned())
+        .unwrap();
+    assert_eq!(Some(spam.id), report.comment_id);
+    assert_eq!(None, report.article_id);
+
+    reports.hide(report.id).unwrap();
+    let (thread, count) = comments
+        .list_by_article(&article.slug, &None, Pagination::default())
+        .unwrap();
+    assert_eq!(1, count);
+    assert_eq!("Thank you!", thread[0].0.body);
+    assert_eq!(1, reports.list(Pagination::default()).unwrap().1);
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use common::TestDb;
+use conduit::app::drivers::seed;
+use conduit::schema::users;
+use conduit::utils::hasher::HashConfig;
+use diesel::prelude::*;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn seeding_twice_does_not_duplicate_users() {
+    let db = TestDb::new();
+    let conn = &mut db.conn();
+
+    let summary = seed::run(conn, &HashConfig::default())
+        .unwrap()
+        .expect("first run should seed");
+    assert!(summary.users > 0);
+    assert_eq!(None, seed::run(conn, &HashConfig::default()).unwrap());
+
+    let count = users::table.count().get_result::<i64>(conn).unwrap();
+    assert_eq!(summary.users as i64, count);
+}
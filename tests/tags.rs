@@ -0,0 +1,105 @@
+mod common;
+
+use common::{create_article, create_user, TestDb};
+use conduit::app::features::tag::entities::TagConsolidation;
+use conduit::app::features::tag::repositories::{TagRepository, TagRepositoryImpl};
+use conduit::schema::tags;
+use diesel::prelude::*;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn list_distinct_names_lists_each_name_once() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        create_article(
+            conn,
+            &celeb,
+            "How to train your dragon",
+            &["dragons", "training"],
+        );
+        create_article(conn, &celeb, "How to train your dragon 2", &["dragons"]);
+    }
+    let tags = TagRepositoryImpl::new(db.pools());
+
+    assert_eq!(
+        vec!["dragons", "training"],
+        tags.list_distinct_names(None).unwrap()
+    );
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn tag_shared_by_two_authors_is_listed_once() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let jake = create_user(conn, "jake");
+        let celeb = create_user(conn, "celeb");
+        create_article(conn, &jake, "Rust for dragons", &["rust"]);
+        create_article(conn, &celeb, "Rust for riders", &["Rust"]);
+    }
+    let tags = TagRepositoryImpl::new(db.pools());
+
+    assert_eq!(vec!["rust"], tags.list_distinct_names(None).unwrap());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn list_distinct_names_honors_the_limit() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        create_article(conn, &celeb, "Dragons", &["dragons", "rust", "training"]);
+    }
+    let tags = TagRepositoryImpl::new(db.pools());
+
+    assert_eq!(
+        vec!["dragons", "rust"],
+        tags.list_distinct_names(Some(2)).unwrap()
+    );
+    assert!(tags.list_distinct_names(Some(0)).unwrap().is_empty());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn consolidate_merges_case_and_spacing_variants() {
+    let db = TestDb::new();
+    {
+        let conn = &mut db.conn();
+        let celeb = create_user(conn, "celeb");
+        let dragon = create_article(conn, &celeb, "Dragons", &[]);
+        let rust = create_article(conn, &celeb, "Rust", &["react"]);
+        // NOTE: written around `Tag::create_list`, the way tags were saved before normalization.
+        let messy = [
+            (dragon.id, "React"),
+            (dragon.id, " react "),
+            (dragon.id, "REACT"),
+            (rust.id, "React"),
+        ];
+        for (article_id, name) in messy {
+            diesel::insert_into(tags::table)
+                .values((tags::article_id.eq(article_id), tags::name.eq(name)))
+                .execute(conn)
+                .unwrap();
+        }
+    }
+    let tags = TagRepositoryImpl::new(db.pools());
+
+    assert_eq!(
+        TagConsolidation {
+            renamed: 1,
+            removed: 3,
+        },
+        tags.consolidate().unwrap()
+    );
+    assert_eq!(vec!["react"], tags.list_distinct_names(None).unwrap());
+    let rows = tags::table
+        .count()
+        .get_result::<i64>(&mut db.conn())
+        .unwrap();
+    assert_eq!(2, rows);
+    assert_eq!(TagConsolidation::default(), tags.consolidate().unwrap());
+}
@@ -0,0 +1,313 @@
+mod common;
+
+use chrono::Duration;
+use common::{create_user, user_repository, TestDb};
+use conduit::app::features::token_blacklist::repositories::{
+    TokenBlacklistRepository, TokenBlacklistRepositoryImpl,
+};
+use conduit::app::features::user::entities::{UpdateUser, User};
+use conduit::app::features::user::repositories::UserRepository;
+use conduit::schema::users;
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn profile_edit_bumps_updated_at_only() {
+    let db = TestDb::new();
+    let conn = &mut db.conn();
+    let jake = {
+        let jake = create_user(conn, "jake");
+        // NOTE: `now()` is frozen for the whole test transaction, so move the row into the past.
+        let an_hour_ago = jake.created_at - Duration::hours(1);
+        diesel::update(users::table.find(jake.id))
+            .set((
+                users::created_at.eq(an_hour_ago),
+                users::updated_at.eq(an_hour_ago),
+            ))
+            .get_result::<User>(conn)
+            .unwrap()
+    };
+
+    let updated = User::update(
+        conn,
+        jake.id,
+        UpdateUser {
+            email: None,
+            username: None,
+            password: None,
+            image: None,
+            bio: Some("I work at statefarm".to_owned()),
+        },
+    )
+    .unwrap();
+    assert_eq!(jake.created_at, updated.created_at);
+    assert!(updated.updated_at > jake.updated_at);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn partial_update_clears_keeps_and_changes_fields() {
+    let db = TestDb::new();
+    let conn = &mut db.conn();
+    let jake = create_user(conn, "jake");
+    let jake = User::update(
+        conn,
+        jake.id,
+        UpdateUser {
+            email: None,
+            username: None,
+            password: None,
+            image: Some("https://example.com/jake.png".to_owned()),
+            bio: Some("I work at statefarm".to_owned()),
+        },
+    )
+    .unwrap();
+
+    let updated = User::update(
+        conn,
+        jake.id,
+        UpdateUser {
+            email: None,
+            username: Some("jakejake".to_owned()),
+            password: None,
+            image: None,
+            bio: Some("".to_owned()),
+        },
+    )
+    .unwrap();
+    assert_eq!("jakejake", updated.username);
+    assert_eq!(None, updated.bio);
+    assert_eq!(jake.image, updated.image);
+    assert_eq!(jake.email, updated.email);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn empty_update_leaves_user_untouched() {
+    let db = TestDb::new();
+    let conn = &mut db.conn();
+    let jake = create_user(conn, "jake");
+
+    let updated = User::update(
+        conn,
+        jake.id,
+        UpdateUser {
+            email: None,
+            username: None,
+            password: None,
+            image: None,
+            bio: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(jake.username, updated.username);
+    assert_eq!(jake.updated_at, updated.updated_at);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn taken_fields_ignore_the_users_own_row() {
+    let db = TestDb::new();
+    let (jake, _celeb) = {
+        let conn = &mut db.conn();
+        (create_user(conn, "jake"), create_user(conn, "celeb"))
+    };
+    let users = user_repository(&db);
+    let rename = |username: &str| UpdateUser {
+        email: None,
+        username: Some(username.to_owned()),
+        password: None,
+        image: None,
+        bio: None,
+    };
+
+    assert_eq!(
+        vec!["username"],
+        users.taken_fields(jake.id, &rename("Celeb")).unwrap()
+    );
+    assert!(users
+        .taken_fields(jake.id, &rename("jake"))
+        .unwrap()
+        .is_empty());
+    let updated = User::update(&mut db.conn(), jake.id, rename("jake")).unwrap();
+    assert_eq!("jake", updated.username);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn new_users_are_not_admins() {
+    let db = TestDb::new();
+    let conn = &mut db.conn();
+    let jake = create_user(conn, "jake");
+    assert_eq!("user", jake.role);
+    assert!(!jake.is_admin());
+
+    let promoted = diesel::update(users::table.find(jake.id))
+        .set(users::role.eq("admin"))
+        .get_result::<User>(conn)
+        .unwrap();
+    assert!(promoted.is_admin());
+    let invalid = diesel::update(users::table.find(jake.id))
+        .set(users::role.eq("superuser"))
+        .execute(conn);
+    assert!(invalid.is_err());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn signin_records_the_last_login() {
+    let db = TestDb::new();
+    let users = user_repository(&db);
+    let (jake, _) = users
+        .signup("jake@example.com", "jake", "jakejakejake")
+        .unwrap();
+    assert_eq!(None, jake.last_login_at);
+
+    let (first, _) = users.signin("jake@example.com", "jakejakejake").unwrap();
+    let (second, _) = users.signin("jake@example.com", "jakejakejake").unwrap();
+    assert!(first.last_login_at.is_some());
+    assert!(second.last_login_at > first.last_login_at);
+    assert_eq!(
+        second.last_login_at,
+        users.find(jake.id).unwrap().last_login_at
+    );
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn revoked_tokens_outlive_their_expiry_by_the_refresh_grace() {
+    let db = TestDb::new();
+    let jake = create_user(&mut db.conn(), "jake");
+    let blacklist = TokenBlacklistRepositoryImpl::new(db.pool(), 3600);
+    let now = chrono::Utc::now().naive_utc();
+    let (refreshable, stale) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    blacklist
+        .revoke(refreshable, jake.id, now - Duration::minutes(10))
+        .unwrap();
+    blacklist
+        .revoke(stale, jake.id, now - Duration::hours(2))
+        .unwrap();
+
+    assert_eq!(1, blacklist.purge_expired().unwrap());
+    assert!(blacklist.is_revoked(&refreshable).unwrap());
+    assert!(!blacklist.is_revoked(&stale).unwrap());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn unknown_email_and_wrong_password_fail_alike() {
+    let db = TestDb::new();
+    let users = user_repository(&db);
+    users
+        .signup("jake@example.com", "jake", "jakejakejake")
+        .unwrap();
+
+    let unknown = users
+        .signin("celeb@example.com", "jakejakejake")
+        .unwrap_err();
+    let wrong = users.signin("jake@example.com", "celebceleb").unwrap_err();
+    assert_eq!(401, unknown.status().as_u16());
+    assert_eq!(wrong.status(), unknown.status());
+    assert_eq!(wrong.body(), unknown.body());
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn mixed_case_emails_sign_in() {
+    let db = TestDb::new();
+    let users = user_repository(&db);
+    let (jake, _) = users
+        .signup("Jake@Example.COM", "Jake", "jakejakejake")
+        .unwrap();
+    assert_eq!("jake@example.com", jake.email);
+
+    for email in ["jake@example.com", "JAKE@EXAMPLE.COM", "Jake@Example.COM"] {
+        let (user, _) = users.signin(email, "jakejakejake").unwrap();
+        assert_eq!(jake.id, user.id, "{}", email);
+    }
+    assert!(users
+        .signup("JAKE@example.com", "jake2", "jakejakejake")
+        .is_err());
+}
+
+const IDENTITY_UP: &str =
+    include_str!("../migrations/2026-10-16-000004_case_insensitive_user_identity/up.sql");
+const IDENTITY_DOWN: &str =
+    include_str!("../migrations/2026-10-16-000004_case_insensitive_user_identity/down.sql");
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn identity_migration_lowercases_emails() {
+    let db = TestDb::new();
+    let conn = &mut db.conn();
+    conn.batch_execute(IDENTITY_DOWN).unwrap();
+    conn.batch_execute(
+        "INSERT INTO users (email, username, password) VALUES ('Jake@Example.com', 'Jake', 'x')",
+    )
+    .unwrap();
+
+    conn.batch_execute(IDENTITY_UP).unwrap();
+    let email = users::table
+        .filter(users::username.eq("Jake"))
+        .select(users::email)
+        .first::<String>(conn)
+        .unwrap();
+    assert_eq!("jake@example.com", email);
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn identity_migration_reports_case_variant_duplicates() {
+    for (rows, message) in [
+        (
+            "('jake@example.com', 'jake', 'x'), ('Jake@Example.com', 'celeb', 'x')",
+            "several users have the email jake@example.com in different cases",
+        ),
+        (
+            "('jake@example.com', 'jake', 'x'), ('celeb@example.com', 'Jake', 'x')",
+            "several users have the username jake in different cases",
+        ),
+    ] {
+        let db = TestDb::new();
+        let conn = &mut db.conn();
+        conn.batch_execute(IDENTITY_DOWN).unwrap();
+        conn.batch_execute(&format!(
+            "INSERT INTO users (email, username, password) VALUES {}",
+            rows
+        ))
+        .unwrap();
+
+        let err = conn.batch_execute(IDENTITY_UP).unwrap_err().to_string();
+        assert!(err.contains(message), "{}", err);
+    }
+}
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn signin_rehashes_legacy_bcrypt_passwords() {
+    let db = TestDb::new();
+    let jake = create_user(&mut db.conn(), "jake");
+    let legacy = bcrypt::hash("jakejakejake", 4).unwrap();
+    diesel::update(users::table.find(jake.id))
+        .set(users::password.eq(&legacy))
+        .execute(&mut db.conn())
+        .unwrap();
+    let password = || {
+        users::table
+            .find(jake.id)
+            .select(users::password)
+            .first::<String>(&mut db.conn())
+            .unwrap()
+    };
+    let users = user_repository(&db);
+
+    assert!(users.signin("jake@example.com", "celebceleb").is_err());
+    assert_eq!(legacy, password());
+
+    users.signin("jake@example.com", "jakejakejake").unwrap();
+    let rehashed = password();
+    assert!(rehashed.starts_with("$argon2id$"), "{}", rehashed);
+    users.signin("jake@example.com", "jakejakejake").unwrap();
+    assert_eq!(rehashed, password());
+}
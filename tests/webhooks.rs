@@ -0,0 +1,41 @@
+mod common;
+
+use common::TestDb;
+use conduit::app::features::webhook::repositories::{WebhookRepository, WebhookRepositoryImpl};
+use conduit::schema::webhooks;
+use diesel::prelude::*;
+
+#[test]
+#[ignore = "needs a database at DATABASE_URL"]
+fn list_returns_every_configured_endpoint() {
+    let db = TestDb::new();
+    diesel::insert_into(webhooks::table)
+        .values(&vec![
+            (
+                webhooks::url.eq("http://127.0.0.1:9000/hooks"),
+                webhooks::secret.eq("s3cr3t"),
+            ),
+            (
+                webhooks::url.eq("http://hooks.internal/conduit"),
+                webhooks::secret.eq("0th3r"),
+            ),
+        ])
+        .execute(&mut db.conn())
+        .unwrap();
+    let webhooks = WebhookRepositoryImpl::new(db.pools());
+
+    let mut urls = webhooks
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|webhook| webhook.url)
+        .collect::<Vec<_>>();
+    urls.sort();
+    assert_eq!(
+        vec![
+            "http://127.0.0.1:9000/hooks",
+            "http://hooks.internal/conduit"
+        ],
+        urls
+    );
+}